@@ -0,0 +1,170 @@
+//! age 形式（<https://age-encryption.org/v1>）との相互運用
+//!
+//! パスフレーズ（scrypt 受信者）で暗号化した age ファイルを読み書きします。
+//! 暗号化・復号化そのものは `age` クレートが行い、このモジュールはパスワードの受け渡しと
+//! エラーの変換だけを担当します。キー導出のパラメータは age が決めるため、設定ファイルの
+//! アルゴリズムやパラメータは使われません。
+
+use crate::error::{CryptoError, IoResultExt, Result};
+use crate::file_ops::CompareWriter;
+use age::secrecy::SecretString;
+use log::{debug, info};
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Read, Write},
+    iter,
+    path::Path,
+};
+
+/// age ファイルの先頭にある形式名
+pub const AGE_MAGIC: &[u8] = b"age-encryption.org/v1\n";
+
+/// 読み書きのバッファサイズ（バイト）
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// データが age 形式（バイナリ）で始まっているか
+pub fn is_age(data: &[u8]) -> bool {
+    data.starts_with(AGE_MAGIC)
+}
+
+/// 平文を読み取り、パスフレーズで暗号化した age 形式で書き込む
+pub fn encrypt_age<R: Read, W: Write>(reader: &mut R, writer: W, password: &str) -> Result<()> {
+    let encryptor = age::Encryptor::with_user_passphrase(SecretString::from(password.to_owned()));
+    let mut age_writer = encryptor
+        .wrap_output(writer)
+        .io_context("age ヘッダーの書き込みに失敗")?;
+    copy_plaintext(reader, &mut age_writer, |e| CryptoError::Io {
+        context: "入力データの読み取りに失敗".to_string(),
+        source: e,
+    })?;
+    age_writer
+        .finish()
+        .and_then(|mut writer| writer.flush())
+        .io_context("age データの書き込みに失敗")?;
+    Ok(())
+}
+
+/// パスフレーズで暗号化された age 形式のデータを復号化して書き込む
+///
+/// パスフレーズ以外（X25519 鍵など）の受信者宛てのファイルは `InvalidInput` で拒否します。
+pub fn decrypt_age<R: Read, W: Write>(reader: R, writer: &mut W, password: &str) -> Result<()> {
+    let decryptor = age::Decryptor::new(reader).map_err(age_error)?;
+    if !decryptor.is_scrypt() {
+        return Err(CryptoError::InvalidInput(
+            "パスフレーズで暗号化された age ファイルではありません".to_string(),
+        ));
+    }
+
+    let identity = age::scrypt::Identity::new(SecretString::from(password.to_owned()));
+    let mut age_reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(age_error)?;
+    // 本文の認証に失敗すると InvalidData、途中で切れていると UnexpectedEof になる
+    copy_plaintext(&mut age_reader, writer, |e| match e.kind() {
+        io::ErrorKind::InvalidData => CryptoError::WrongPasswordOrCorrupt,
+        io::ErrorKind::UnexpectedEof => {
+            CryptoError::Truncated("age データが途中で切れています".to_string())
+        }
+        _ => CryptoError::Io {
+            context: "age データの読み取りに失敗".to_string(),
+            source: e,
+        },
+    })?;
+    writer.flush().io_context("出力データの書き込みに失敗")
+}
+
+/// ファイルをパスフレーズで age 形式に暗号化
+pub fn encrypt_file_age(input_path: &Path, output_path: &Path, password: &str) -> Result<()> {
+    info!("=== age 形式で暗号化開始 ===");
+    debug!("入力: {}", input_path.display());
+    debug!("出力: {}", output_path.display());
+
+    let mut input_file =
+        BufReader::new(File::open(input_path).with_io_context(|| {
+            format!("入力ファイルのオープンに失敗: {}", input_path.display())
+        })?);
+    let output_file = BufWriter::new(
+        File::create(output_path)
+            .with_io_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))?,
+    );
+    encrypt_age(&mut input_file, output_file, password)?;
+
+    info!("=== age 形式で暗号化完了 ===");
+    Ok(())
+}
+
+/// パスフレーズで暗号化された age ファイルを復号化
+pub fn decrypt_file_age(input_path: &Path, output_path: &Path, password: &str) -> Result<()> {
+    info!("=== age 形式の復号化開始 ===");
+    debug!("入力: {}", input_path.display());
+    debug!("出力: {}", output_path.display());
+
+    let input_file =
+        BufReader::new(File::open(input_path).with_io_context(|| {
+            format!("入力ファイルのオープンに失敗: {}", input_path.display())
+        })?);
+    let mut output_file = BufWriter::new(
+        File::create(output_path)
+            .with_io_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))?,
+    );
+    decrypt_age(input_file, &mut output_file, password)?;
+
+    info!("=== age 形式の復号化完了 ===");
+    Ok(())
+}
+
+/// age ファイルを復号化し、元のファイルと一致するか確認する（平文はディスクに書き出さない）
+pub fn verify_file_age(encrypted_path: &Path, original_path: &Path, password: &str) -> Result<()> {
+    let open = |path: &Path, name: &str| {
+        File::open(path)
+            .map(BufReader::new)
+            .with_io_context(|| format!("{name}のオープンに失敗: {}", path.display()))
+    };
+    let mut compare = CompareWriter::new(open(original_path, "元ファイル")?);
+    let result = decrypt_age(
+        open(encrypted_path, "入力ファイル")?,
+        &mut compare,
+        password,
+    );
+    compare.finish(result)
+}
+
+/// `reader` の内容を `writer` に書き写す（読み取りエラーは `read_error` で変換する）
+fn copy_plaintext<R: Read + ?Sized, W: Write + ?Sized>(
+    reader: &mut R,
+    writer: &mut W,
+    read_error: impl Fn(io::Error) -> CryptoError,
+) -> Result<()> {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(()),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(read_error(e)),
+        };
+        writer
+            .write_all(&buffer[..n])
+            .io_context("出力データの書き込みに失敗")?;
+    }
+}
+
+/// age の復号化エラーをこのクレートのエラーに変換
+fn age_error(e: age::DecryptError) -> CryptoError {
+    match e {
+        age::DecryptError::DecryptionFailed
+        | age::DecryptError::KeyDecryptionFailed
+        | age::DecryptError::NoMatchingKeys
+        | age::DecryptError::InvalidMac => CryptoError::WrongPasswordOrCorrupt,
+        age::DecryptError::InvalidHeader | age::DecryptError::UnknownFormat => {
+            CryptoError::InvalidFormat("age 形式のファイルではありません".to_string())
+        }
+        age::DecryptError::ExcessiveWork { .. } => {
+            CryptoError::KeyDerivation(format!("age ファイルのキー導出が重すぎます: {e}"))
+        }
+        age::DecryptError::Io(source) => CryptoError::Io {
+            context: "age データの読み取りに失敗".to_string(),
+            source,
+        },
+    }
+}
@@ -0,0 +1,102 @@
+//! 暗号化データをテキストとして扱うためのASCIIアーマー形式
+//!
+//! メールやissueトラッカーに貼り付けられるよう、バイナリの暗号化データをBase64にして
+//! PGPと同様の開始行・終了行で囲みます。
+//!
+//! 形式:
+//! ```text
+//! -----BEGIN MYCRYPT MESSAGE-----
+//! Comment: AES-256-GCM / argon2id (m=65536 KB, t=3, p=4)
+//!
+//! Base64本文（64文字ごとに改行）
+//! -----END MYCRYPT MESSAGE-----
+//! ```
+//!
+//! ヘッダー行は人が確認するためのもので、復号化には使いません（キー導出のパラメータは
+//! 本文の暗号化データ自体に記録されており、そちらが認証されます）。
+
+use crate::envelope::{is_envelope, ENVELOPE_MAGIC};
+use crate::error::{CryptoError, Result};
+use crate::header::{has_magic, FileHeader};
+use crate::key_derivation::KdfParams;
+use crate::{base64_decode, base64_encode};
+
+/// アーマーの開始行
+pub const ARMOR_BEGIN: &str = "-----BEGIN MYCRYPT MESSAGE-----";
+
+/// アーマーの終了行
+pub const ARMOR_END: &str = "-----END MYCRYPT MESSAGE-----";
+
+/// 本文の1行あたりの文字数
+const LINE_WIDTH: usize = 64;
+
+/// データがアーマー形式か（先頭の空白は無視する）
+pub fn is_armored(data: &[u8]) -> bool {
+    data.trim_ascii_start().starts_with(ARMOR_BEGIN.as_bytes())
+}
+
+/// 標準形式またはエンベロープ形式の暗号化データをアーマー形式にする
+///
+/// ストリーミング形式はファイル全体を読み込む必要があるため対象外です。
+pub fn armor(data: &[u8]) -> Result<String> {
+    let params = if has_magic(data) {
+        FileHeader::parse(data)?.0.kdf.params
+    } else if is_envelope(data) {
+        KdfParams::read_from(&mut &data[ENVELOPE_MAGIC.len()..])?
+    } else {
+        return Err(CryptoError::InvalidFormat(
+            "アーマー形式にできるのは標準形式・エンベロープ形式の暗号化データのみです".to_string(),
+        ));
+    };
+
+    let body = base64_encode(data);
+    let mut text = format!("{ARMOR_BEGIN}\nComment: AES-256-GCM / {params}\n\n");
+    for line in body.as_bytes().chunks(LINE_WIDTH) {
+        // Base64はASCIIのみのため、任意の位置で区切っても文字列として有効
+        text.push_str(std::str::from_utf8(line).expect("Base64はASCII"));
+        text.push('\n');
+    }
+    text.push_str(ARMOR_END);
+    text.push('\n');
+    Ok(text)
+}
+
+/// アーマー形式を解除して元の暗号化データを返す
+///
+/// 改行コードはLF・CRLFのどちらも受け付けます。
+pub fn dearmor(data: &[u8]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| CryptoError::InvalidFormat("アーマーがテキストではありません".to_string()))?;
+    let mut lines = text.trim_start().lines().map(str::trim_end);
+
+    if lines.next() != Some(ARMOR_BEGIN) {
+        return Err(CryptoError::InvalidFormat(
+            "アーマーの開始行が見つかりません".to_string(),
+        ));
+    }
+
+    // ヘッダー行（「キー: 値」）は空行まで読み飛ばす
+    let mut body = String::new();
+    let mut in_headers = true;
+    for line in lines {
+        if line == ARMOR_END {
+            return base64_decode(&body);
+        }
+        if in_headers {
+            if line.is_empty() {
+                in_headers = false;
+                continue;
+            }
+            if line.contains(": ") {
+                continue;
+            }
+            // ヘッダーのないアーマーは最初の行から本文
+            in_headers = false;
+        }
+        body.push_str(line.trim());
+    }
+
+    Err(CryptoError::Truncated(
+        "アーマーの終了行が見つかりません".to_string(),
+    ))
+}
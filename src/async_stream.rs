@@ -0,0 +1,102 @@
+//! Tokio 向けの非同期ストリーミング暗号化（`tokio` フィーチャー）
+//!
+//! `tokio::io` の `AsyncRead` / `AsyncWrite` を、ブロッキングスレッド（`spawn_blocking`）上で
+//! 同期の `Read` / `Write` として扱い、`encrypt_from_reader` / `decrypt_to_writer` と同じ処理を
+//! 行います。キー導出とAES-GCMの計算はブロッキングスレッドで行うため、非同期ランタイムの
+//! ワーカースレッドを止めません。出力は同期版と同じストリーミング形式です。
+
+use crate::config::Config;
+use crate::error::{CryptoError, Result};
+use crate::file_ops::{decrypt_to_writer, encrypt_from_reader};
+use std::io::{self, Read, Write};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::runtime::Handle;
+
+/// `reader` の内容をストリーミング形式で暗号化して `writer` に書き込む（`encrypt_from_reader` の非同期版）
+///
+/// Tokio ランタイムの中から呼び出してください。書き込み先はフラッシュして返します（`shutdown` はしません）。
+pub async fn encrypt_stream_async<R, W>(
+    reader: R,
+    writer: W,
+    password: &str,
+    config: &Config,
+) -> Result<W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let password = password.to_string();
+    let config = config.clone();
+    run_blocking(reader, writer, move |reader, writer| {
+        encrypt_from_reader(reader, writer, &password, &config)
+    })
+    .await
+}
+
+/// ストリーミング形式の暗号文を復号化して `writer` に書き込む（`decrypt_to_writer` の非同期版）
+///
+/// 同期版と同じく、チャンクごとに書き込むため、エラーが返った場合でもそれまでのデータは
+/// 書き込み済みです（エラーの場合は出力を破棄してください）。
+pub async fn decrypt_stream_async<R, W>(reader: R, writer: W, password: &str) -> Result<W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let password = password.to_string();
+    run_blocking(reader, writer, move |reader, writer| {
+        decrypt_to_writer(reader, writer, &password)
+    })
+    .await
+}
+
+/// `run` をブロッキングスレッドで実行し、非同期の入出力を同期の `Read` / `Write` として渡す
+async fn run_blocking<R, W, F>(reader: R, writer: W, run: F) -> Result<W>
+where
+    R: AsyncRead + Unpin + Send + 'static,
+    W: AsyncWrite + Unpin + Send + 'static,
+    F: FnOnce(&mut BlockingIo<R>, &mut BlockingIo<W>) -> Result<()> + Send + 'static,
+{
+    let handle = Handle::current();
+    let task = tokio::task::spawn_blocking(move || {
+        let mut reader = BlockingIo {
+            inner: reader,
+            handle: handle.clone(),
+        };
+        let mut writer = BlockingIo {
+            inner: writer,
+            handle,
+        };
+        run(&mut reader, &mut writer).map(|()| writer.inner)
+    });
+
+    match task.await {
+        Ok(result) => result,
+        Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+        Err(e) => Err(CryptoError::Io {
+            context: "暗号化処理のタスクが中断されました".to_string(),
+            source: io::Error::other(e),
+        }),
+    }
+}
+
+/// 非同期の入出力を、ブロッキングスレッドから同期的に読み書きするためのラッパー
+struct BlockingIo<T> {
+    inner: T,
+    handle: Handle,
+}
+
+impl<T: AsyncRead + Unpin> Read for BlockingIo<T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.handle.block_on(self.inner.read(buf))
+    }
+}
+
+impl<T: AsyncWrite + Unpin> Write for BlockingIo<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.handle.block_on(self.inner.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.handle.block_on(self.inner.flush())
+    }
+}
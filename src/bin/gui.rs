@@ -1,19 +1,83 @@
 use eframe::egui;
 use encript_tool::{
-    config::{create_config_file, get_default_config_path, load_config, Config, OutputFormat},
+    config::{
+        get_default_config_path, load_config, resolve_config_path, save_config, Argon2Config,
+        Config, OutputFormat,
+    },
     crypto::{decrypt_string, encrypt_string},
-    file_ops::{
-        decrypt_file_standard, decrypt_file_streaming, determine_output_path,
-        encrypt_file_standard, encrypt_file_streaming,
+    file_ops::{detect_format, determine_output_path, with_output_lock, EncFormat, FileBatch},
+    fonts::install_japanese_font,
+    inspect::{inspect_file, FileInfo, FormatInfo},
+    key_derivation::{
+        derive_key_with_argon2, last_kdf_duration, with_kdf_observer, Argon2Variant, KdfAlgorithm,
+        KdfEvent, SALT_SIZE,
+    },
+    password::{
+        check_password, estimate_entropy, generate_password, password_strength, PasswordCharset,
+        PasswordStrength,
     },
 };
-use std::path::PathBuf;
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant},
+};
+use zeroize::{Zeroize, Zeroizing};
+
+/// 「パスワード生成」で作るパスワードの長さ
+const GENERATED_PASSWORD_LENGTH: usize = 24;
+
+/// Argon2の「テスト実行」で、これより時間がかかる場合に警告する
+const SLOW_ARGON2_THRESHOLD: Duration = Duration::from_secs(2);
+
+/// セッションをまたいで保持するGUIの状態（設定ファイルと同じディレクトリに保存）
+#[derive(Default, Serialize, Deserialize)]
+struct GuiState {
+    /// 最後にファイルを選択したディレクトリ
+    last_directory: Option<PathBuf>,
+}
+
+impl GuiState {
+    fn path() -> Option<PathBuf> {
+        get_default_config_path()
+            .ok()
+            .map(|path| path.with_file_name("gui_state.toml"))
+    }
+
+    /// 状態ファイルを読み込み（存在しない・壊れている場合はデフォルト）
+    fn load() -> Self {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| toml::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 状態ファイルを保存
+    fn save(&self) -> Result<(), String> {
+        let path = Self::path().ok_or("設定ディレクトリが見つかりません")?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("状態ファイルの保存に失敗: {e}"))?;
+        }
+        let content =
+            toml::to_string_pretty(self).map_err(|e| format!("状態ファイルの生成に失敗: {e}"))?;
+        fs::write(&path, content).map_err(|e| format!("状態ファイルの保存に失敗: {e}"))
+    }
+
+    /// 記憶しているディレクトリ（既に存在しない場合は None）
+    fn existing_directory(&self) -> Option<&Path> {
+        self.last_directory.as_deref().filter(|dir| dir.is_dir())
+    }
+}
 
 /// 実用的なGUI暗号化アプリケーション
 pub struct CryptApp {
     // テキスト処理用
     input_text: String,
-    text_password: String,
+    text_password: Zeroizing<String>,
     output_text: String,
     text_password_visible: bool,
     text_use_env_password: bool,
@@ -25,14 +89,18 @@ pub struct CryptApp {
     file_processing_mode: FileProcessingMode,
     use_streaming: bool,
     delete_original: bool,
-    file_password: String,
+    file_password: Zeroizing<String>,
     file_password_visible: bool,
     file_use_env_password: bool,
     file_env_var_name: String,
+    selected_file_info: Option<SelectedFileInfo>,
+    /// 複数選択したファイル（空なら `selected_file_path` の1ファイルを処理する）
+    batch_files: Vec<BatchFile>,
 
     // 設定関連
     config: Config,
     verbose: bool,
+    gui_state: GuiState,
 
     // UI状態
     error_message: String,
@@ -42,6 +110,25 @@ pub struct CryptApp {
 
     // ファイル処理の進捗
     processing: bool,
+    file_task: Option<Receiver<FileTaskMessage>>,
+    file_progress: Option<(u64, u64)>,
+    deriving_key: bool,
+
+    // Argon2のテスト実行（実行中のパラメータと結果の受信側、直前の結果）
+    argon2_test: Option<(Argon2Config, Receiver<Result<Duration, String>>)>,
+    argon2_test_result: Option<(Argon2Config, Result<Duration, String>)>,
+
+    // クリップボード自動消去
+    clipboard_auto_clear: bool,
+    clipboard_clear_secs: u64,
+    clipboard_copied: String,
+    clipboard_timer: Option<Receiver<()>>,
+
+    // 復号結果の自動消去
+    output_is_plaintext: bool,
+    output_auto_clear: bool,
+    output_clear_secs: u64,
+    last_activity: Instant,
 }
 
 #[derive(Clone, PartialEq)]
@@ -52,6 +139,45 @@ enum Tab {
     About,
 }
 
+/// バックグラウンドのファイル処理からUIへの通知
+enum FileTaskMessage {
+    /// 処理済みバイト数と総バイト数
+    Progress(u64, u64),
+    /// キー導出の開始（`true`）・完了（`false`）
+    DerivingKey(bool),
+    /// `index` 番目のファイルの処理を開始
+    FileStarted(usize),
+    /// `index` 番目のファイルの処理が完了
+    FileDone(usize, Result<(), String>),
+    /// 処理完了
+    Done(Result<(), String>),
+}
+
+/// 複数選択したファイルと、その処理状況
+struct BatchFile {
+    path: PathBuf,
+    status: BatchFileStatus,
+}
+
+enum BatchFileStatus {
+    Pending,
+    Processing,
+    Done,
+    Failed(String),
+}
+
+/// 選択中のファイルの情報（ファイルタブの情報欄に表示する）
+enum SelectedFileInfo {
+    /// 暗号化されていないファイル（バイト数）
+    Plain(u64),
+    /// このツールで暗号化されたファイルのヘッダー情報
+    Encrypted(FileInfo),
+    /// 暗号化ファイルと判定したが、ヘッダーを読み取れなかった
+    Damaged(String),
+    /// ファイルを読み込めなかった
+    Unreadable(String),
+}
+
 #[derive(Clone, PartialEq)]
 enum FileProcessingMode {
     Encrypt,
@@ -63,7 +189,7 @@ impl Default for CryptApp {
         Self {
             // テキスト処理用
             input_text: String::new(),
-            text_password: String::new(),
+            text_password: Zeroizing::new(String::new()),
             output_text: String::new(),
             text_password_visible: false,
             text_use_env_password: false,
@@ -75,13 +201,16 @@ impl Default for CryptApp {
             file_processing_mode: FileProcessingMode::Encrypt,
             use_streaming: false,
             delete_original: false,
-            file_password: String::new(),
+            file_password: Zeroizing::new(String::new()),
             file_password_visible: false,
             file_use_env_password: false,
             file_env_var_name: "MYCRYPT_FILE_PASSWORD".to_string(),
+            selected_file_info: None,
+            batch_files: Vec::new(),
 
             config: Config::default(),
             verbose: false,
+            gui_state: GuiState::default(),
 
             error_message: String::new(),
             success_message: String::new(),
@@ -89,6 +218,22 @@ impl Default for CryptApp {
             current_tab: Tab::TextCrypto,
 
             processing: false,
+            file_task: None,
+            file_progress: None,
+            deriving_key: false,
+
+            argon2_test: None,
+            argon2_test_result: None,
+
+            clipboard_auto_clear: false,
+            clipboard_clear_secs: 30,
+            clipboard_copied: String::new(),
+            clipboard_timer: None,
+
+            output_is_plaintext: false,
+            output_auto_clear: true,
+            output_clear_secs: 120,
+            last_activity: Instant::now(),
         }
     }
 }
@@ -100,13 +245,35 @@ impl CryptApp {
         if let Ok(config) = load_config(None) {
             app.config = config;
         }
+        app.gui_state = GuiState::load();
         app
     }
 
+    /// ファイルダイアログを作成（前回のディレクトリがあればそこから開く）
+    fn file_dialog(&self) -> rfd::FileDialog {
+        let dialog = rfd::FileDialog::new();
+        match self.gui_state.existing_directory() {
+            Some(dir) => dialog.set_directory(dir),
+            None => dialog,
+        }
+    }
+
+    /// 選択したファイルのディレクトリを記憶して保存
+    fn remember_directory(&mut self, path: &Path) {
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        self.gui_state.last_directory = Some(dir.to_path_buf());
+        if let Err(e) = self.gui_state.save() {
+            self.error_message = e;
+        }
+    }
+
     /// テキスト処理用のパスワードを取得
-    fn get_text_password(&self) -> Result<String, String> {
+    fn get_text_password(&self) -> Result<Zeroizing<String>, String> {
         if self.text_use_env_password {
             std::env::var(&self.text_env_var_name)
+                .map(Zeroizing::new)
                 .map_err(|_| format!("環境変数 {} が見つかりません", self.text_env_var_name))
         } else if !self.text_password.is_empty() {
             Ok(self.text_password.clone())
@@ -116,9 +283,10 @@ impl CryptApp {
     }
 
     /// ファイル処理用のパスワードを取得
-    fn get_file_password(&self) -> Result<String, String> {
+    fn get_file_password(&self) -> Result<Zeroizing<String>, String> {
         if self.file_use_env_password {
             std::env::var(&self.file_env_var_name)
+                .map(Zeroizing::new)
                 .map_err(|_| format!("環境変数 {} が見つかりません", self.file_env_var_name))
         } else if !self.file_password.is_empty() {
             Ok(self.file_password.clone())
@@ -127,6 +295,22 @@ impl CryptApp {
         }
     }
 
+    /// 暗号化に使うパスワードが空でなく、設定の下限以上の強度があるか確認
+    fn check_encrypt_password(&self, password: &str) -> Result<(), String> {
+        check_password(password, self.config.min_password_entropy).map_err(|issue| {
+            format!("{issue}（設定タブでパスワードのエントロピーの下限を変更できます）")
+        })
+    }
+
+    /// 「詳細出力」の状態をログレベルに反映
+    fn apply_log_level(&self) {
+        log::set_max_level(if self.verbose {
+            LevelFilter::Debug
+        } else {
+            LevelFilter::Warn
+        });
+    }
+
     /// テキスト暗号化処理
     fn encrypt_text(&mut self) -> Result<(), String> {
         if self.input_text.is_empty() {
@@ -134,9 +318,12 @@ impl CryptApp {
         }
 
         let password = self.get_text_password()?;
+        self.check_encrypt_password(&password)?;
+        self.apply_log_level();
 
-        match encrypt_string(&self.input_text, &password, &self.config, self.verbose) {
+        match encrypt_string(&self.input_text, &password, &self.config) {
             Ok(encrypted) => {
+                self.wipe_output();
                 self.output_text = encrypted;
                 Ok(())
             }
@@ -151,10 +338,14 @@ impl CryptApp {
         }
 
         let password = self.get_text_password()?;
+        self.apply_log_level();
 
-        match decrypt_string(&self.input_text, &password, &self.config, self.verbose) {
+        match decrypt_string(&self.input_text, &password, &self.config) {
             Ok(decrypted) => {
+                self.wipe_output();
                 self.output_text = decrypted;
+                self.output_is_plaintext = true;
+                self.last_activity = Instant::now();
                 Ok(())
             }
             Err(e) => Err(format!("復号化エラー: {e}")),
@@ -163,89 +354,303 @@ impl CryptApp {
 
     /// ファイル処理実行
     fn process_file(&mut self) -> Result<(), String> {
-        if self.selected_file_path.is_empty() {
+        // 処理するファイルと出力先（None なら処理時に自動で決める）
+        let jobs: Vec<(PathBuf, Option<PathBuf>)> = if !self.batch_files.is_empty() {
+            self.batch_files
+                .iter()
+                .map(|file| (file.path.clone(), None))
+                .collect()
+        } else if self.selected_file_path.is_empty() {
             return Err("ファイルが選択されていません".to_string());
-        }
+        } else {
+            let input_path = PathBuf::from(&self.selected_file_path);
+            let output_path = if self.output_file_path.is_empty() {
+                determine_output_path(
+                    &input_path,
+                    &None,
+                    &self.config,
+                    matches!(self.file_processing_mode, FileProcessingMode::Encrypt),
+                    false,
+                )
+                .map_err(|e| format!("出力パス決定エラー: {e}"))?
+            } else {
+                PathBuf::from(&self.output_file_path)
+            };
+            vec![(input_path, Some(output_path))]
+        };
 
-        let input_path = PathBuf::from(&self.selected_file_path);
         let password = self.get_file_password()?;
+        if matches!(self.file_processing_mode, FileProcessingMode::Encrypt) {
+            self.check_encrypt_password(&password)?;
+        }
 
-        // 出力パスの決定
-        let output_path = if self.output_file_path.is_empty() {
-            determine_output_path(
-                &input_path,
-                &None,
-                matches!(self.file_processing_mode, FileProcessingMode::Encrypt),
-            )
-            .map_err(|e| format!("出力パス決定エラー: {e}"))?
-        } else {
-            PathBuf::from(&self.output_file_path)
-        };
+        if self.output_file_path.is_empty() || !self.batch_files.is_empty() {
+            if let Some(dir) = &self.config.default_output_dir {
+                fs::create_dir_all(dir)
+                    .map_err(|e| format!("出力ディレクトリの作成に失敗: {}: {e}", dir.display()))?;
+            }
+        }
 
+        self.apply_log_level();
+        for file in &mut self.batch_files {
+            file.status = BatchFileStatus::Pending;
+        }
+
+        // 重い処理はバックグラウンドスレッドで実行し、結果をチャネルで受け取る
+        let mode = self.file_processing_mode.clone();
+        let use_streaming = self.use_streaming;
+        let delete_original = self.delete_original;
+        let config = self.config.clone();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let progress_sender = sender.clone();
+            // キー導出（数百ミリ秒〜数秒）の間は、暗号化の進捗とは別に表示する
+            let kdf_sender = sender.clone();
+            let report_kdf = move |event| {
+                let started = matches!(event, KdfEvent::Started(_));
+                let _ = kdf_sender.send(FileTaskMessage::DerivingKey(started));
+            };
+
+            let result = with_kdf_observer(report_kdf, || {
+                // 暗号化ではキー導出を全ファイルで1回だけ行う
+                let batch = FileBatch::new(&password, &config).with_progress(move |done, total| {
+                    let _ = progress_sender.send(FileTaskMessage::Progress(done, total));
+                });
+
+                let mut failed = 0;
+                let mut last_error = None;
+                for (index, (input_path, output_path)) in jobs.iter().enumerate() {
+                    // キャンセルされて受信側が破棄されていれば、残りのファイルは処理しない
+                    if sender.send(FileTaskMessage::FileStarted(index)).is_err() {
+                        return Ok(());
+                    }
+                    let result = process_one_file(
+                        &batch,
+                        &mode,
+                        input_path,
+                        output_path.as_deref(),
+                        use_streaming,
+                        delete_original,
+                        &config,
+                    );
+                    if let Err(e) = &result {
+                        failed += 1;
+                        last_error = Some(e.clone());
+                    }
+                    let _ = sender.send(FileTaskMessage::FileDone(index, result));
+                }
+
+                match (jobs.len(), last_error) {
+                    (_, None) => Ok(()),
+                    (1, Some(e)) => Err(e),
+                    (total, Some(_)) => Err(format!(
+                        "{total} 件中 {failed} 件のファイルの処理に失敗しました"
+                    )),
+                }
+            });
+
+            // キャンセル済みで受信側が破棄されていても問題ない
+            let _ = sender.send(FileTaskMessage::Done(result));
+        });
+
+        self.file_task = Some(receiver);
+        self.file_progress = None;
+        self.deriving_key = false;
         self.processing = true;
 
-        let result = match self.file_processing_mode {
-            FileProcessingMode::Encrypt => {
-                if self.use_streaming {
-                    encrypt_file_streaming(
-                        &input_path,
-                        &output_path,
-                        &password,
-                        &self.config,
-                        self.verbose,
-                    )
-                } else {
-                    encrypt_file_standard(
-                        &input_path,
-                        &output_path,
-                        &password,
-                        &self.config,
-                        self.verbose,
-                    )
+        Ok(())
+    }
+
+    /// バックグラウンドのファイル処理結果を確認
+    fn poll_file_task(&mut self) {
+        let Some(receiver) = &self.file_task else {
+            return;
+        };
+
+        let result = loop {
+            match receiver.try_recv() {
+                Ok(FileTaskMessage::Progress(done, total)) => {
+                    self.file_progress = Some((done, total));
                 }
-            }
-            FileProcessingMode::Decrypt => {
-                if self.use_streaming {
-                    decrypt_file_streaming(
-                        &input_path,
-                        &output_path,
-                        &password,
-                        &self.config,
-                        self.verbose,
-                    )
-                } else {
-                    decrypt_file_standard(
-                        &input_path,
-                        &output_path,
-                        &password,
-                        &self.config,
-                        self.verbose,
-                    )
+                Ok(FileTaskMessage::DerivingKey(deriving)) => {
+                    self.deriving_key = deriving;
+                }
+                Ok(FileTaskMessage::FileStarted(index)) => {
+                    self.file_progress = None;
+                    if let Some(file) = self.batch_files.get_mut(index) {
+                        file.status = BatchFileStatus::Processing;
+                    }
+                }
+                Ok(FileTaskMessage::FileDone(index, result)) => {
+                    if let Some(file) = self.batch_files.get_mut(index) {
+                        file.status = match result {
+                            Ok(()) => BatchFileStatus::Done,
+                            Err(e) => BatchFileStatus::Failed(e),
+                        };
+                    }
+                }
+                Ok(FileTaskMessage::Done(result)) => break result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    break Err("処理スレッドが異常終了しました".to_string());
                 }
             }
         };
 
+        self.file_task = None;
+        self.file_progress = None;
+        self.deriving_key = false;
         self.processing = false;
 
+        // 元ファイルの削除などで選択中のファイルが変わっている場合がある
+        self.refresh_file_info();
+
         match result {
             Ok(()) => {
-                if self.delete_original {
-                    if let Err(e) = std::fs::remove_file(&input_path) {
-                        return Err(format!("元ファイル削除エラー: {e}"));
-                    }
-                }
-                Ok(())
+                self.error_message.clear();
+                self.success_message = "ファイル処理が完了しました".to_string();
+            }
+            Err(e) => {
+                self.error_message = e;
+                self.success_message.clear();
+            }
+        }
+    }
+
+    /// 実行中のファイル処理の結果待ちをやめる
+    fn cancel_file_task(&mut self) {
+        // 受信側を破棄するだけなので、スレッド自体は完了まで動作する
+        self.file_task = None;
+        self.file_progress = None;
+        self.deriving_key = false;
+        self.processing = false;
+        self.error_message.clear();
+        self.success_message = "ファイル処理の待機をキャンセルしました".to_string();
+    }
+
+    /// 現在のArgon2パラメータでダミーのパスワードからキーを1回導出し、処理時間を計測する（バックグラウンドで実行）
+    fn start_argon2_test(&mut self) {
+        let params = self.config.argon2.clone();
+        let test_params = params.clone();
+        let (sender, receiver) = mpsc::channel();
+
+        thread::spawn(move || {
+            let result = derive_key_with_argon2("test-password", &[0u8; SALT_SIZE], &test_params)
+                .map_err(|e| format!("キー導出エラー: {e}"))
+                .and_then(|_| {
+                    last_kdf_duration().ok_or_else(|| "処理時間を計測できませんでした".to_string())
+                });
+            let _ = sender.send(result);
+        });
+
+        self.argon2_test = Some((params, receiver));
+    }
+
+    /// Argon2のテスト実行の結果を確認
+    fn poll_argon2_test(&mut self) {
+        let Some((_, receiver)) = &self.argon2_test else {
+            return;
+        };
+
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => {
+                Err("テスト実行のスレッドが異常終了しました".to_string())
+            }
+        };
+        if let Some((params, _)) = self.argon2_test.take() {
+            self.argon2_test_result = Some((params, result));
+        }
+    }
+
+    /// コピーした内容を一定時間後に消去するタイマーを開始
+    fn schedule_clipboard_clear(&mut self, ctx: &egui::Context, copied: String) {
+        if !self.clipboard_auto_clear {
+            return;
+        }
+
+        // 新しいタイマーで置き換えると、古いタイマーの通知は受信されない
+        let (sender, receiver) = mpsc::channel();
+        let delay = Duration::from_secs(self.clipboard_clear_secs);
+        let ctx = ctx.clone();
+        thread::spawn(move || {
+            thread::sleep(delay);
+            if sender.send(()).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+
+        self.clipboard_copied = copied;
+        self.clipboard_timer = Some(receiver);
+    }
+
+    /// タイマー満了時、クリップボードがコピーした内容のままなら消去
+    fn poll_clipboard_timer(&mut self) {
+        let Some(receiver) = &self.clipboard_timer else {
+            return;
+        };
+
+        match receiver.try_recv() {
+            Ok(()) | Err(TryRecvError::Disconnected) => {}
+            Err(TryRecvError::Empty) => return,
+        }
+
+        self.clipboard_timer = None;
+        let copied = std::mem::take(&mut self.clipboard_copied);
+
+        let mut clipboard = match arboard::Clipboard::new() {
+            Ok(clipboard) => clipboard,
+            Err(e) => {
+                self.error_message = format!("クリップボードにアクセスできません: {e}");
+                return;
+            }
+        };
+
+        // ユーザーが後から別の内容をコピーしていれば消さない
+        if clipboard.get_text().is_ok_and(|current| current == copied) {
+            match clipboard.set_text(String::new()) {
+                Ok(()) => self.success_message = "クリップボードを消去しました".to_string(),
+                Err(e) => self.error_message = format!("クリップボードの消去に失敗: {e}"),
             }
-            Err(e) => Err(format!("ファイル処理エラー: {e}")),
+        }
+    }
+
+    /// 出力テキストを消去し、文字列のバッファもゼロで上書きする
+    ///
+    /// egui のテキスト欄が内部に持つ取り消し履歴などのコピーまでは消去できません。
+    fn wipe_output(&mut self) {
+        self.output_text.zeroize();
+        self.output_is_plaintext = false;
+    }
+
+    /// 復号結果を表示したまま一定時間操作がなければ消去
+    fn poll_output_timeout(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.events.is_empty() || i.pointer.is_moving()) {
+            self.last_activity = Instant::now();
+        }
+        if !self.output_is_plaintext || !self.output_auto_clear {
+            return;
+        }
+
+        let timeout = Duration::from_secs(self.output_clear_secs);
+        let idle = self.last_activity.elapsed();
+        if idle >= timeout {
+            self.wipe_output();
+            self.success_message = "操作がなかったため復号結果を消去しました".to_string();
+        } else {
+            // 操作がなくても期限に再描画して確認する
+            ctx.request_repaint_after(timeout - idle);
         }
     }
 
     /// 設定の保存
     fn save_config(&mut self) -> Result<(), String> {
-        let config_path =
-            get_default_config_path().map_err(|e| format!("設定パス取得エラー: {e}"))?;
+        let (config_path, _) =
+            resolve_config_path(None).map_err(|e| format!("設定パス取得エラー: {e}"))?;
 
-        create_config_file(&config_path).map_err(|e| format!("設定保存エラー: {e}"))?;
+        save_config(&self.config, &config_path).map_err(|e| format!("設定保存エラー: {e}"))?;
 
         Ok(())
     }
@@ -264,9 +669,9 @@ impl CryptApp {
         ui.horizontal(|ui| {
             ui.label("パスワード:");
             if self.text_password_visible {
-                ui.text_edit_singleline(&mut self.text_password);
+                ui.text_edit_singleline(&mut *self.text_password);
             } else {
-                ui.add(egui::TextEdit::singleline(&mut self.text_password).password(true));
+                ui.add(egui::TextEdit::singleline(&mut *self.text_password).password(true));
             }
             if ui
                 .button(if self.text_password_visible {
@@ -278,8 +683,31 @@ impl CryptApp {
             {
                 self.text_password_visible = !self.text_password_visible;
             }
+            if ui.button("🎲 パスワード生成").clicked() {
+                self.text_password = Zeroizing::new(generate_password(
+                    GENERATED_PASSWORD_LENGTH,
+                    PasswordCharset::AlphanumericSymbols,
+                ));
+                self.text_password_visible = true;
+                self.success_message = "パスワードを生成しました".to_string();
+            }
+            if ui
+                .button("📋")
+                .on_hover_text("パスワードをコピー")
+                .clicked()
+            {
+                let password = self.text_password.to_string();
+                ui.ctx().copy_text(password.clone());
+                self.success_message = "パスワードをクリップボードにコピーしました".to_string();
+                self.schedule_clipboard_clear(ui.ctx(), password);
+            }
         });
 
+        // 環境変数を使う場合は採点対象のパスワードが手元にないので表示しない
+        if !self.text_use_env_password {
+            draw_strength_meter(ui, &self.text_password);
+        }
+
         ui.checkbox(
             &mut self.text_use_env_password,
             "環境変数からパスワードを取得",
@@ -323,7 +751,7 @@ impl CryptApp {
 
             if ui.button("🗑️ クリア").clicked() {
                 self.input_text.clear();
-                self.output_text.clear();
+                self.wipe_output();
                 self.error_message.clear();
                 self.success_message.clear();
             }
@@ -331,6 +759,7 @@ impl CryptApp {
             if ui.button("📋 コピー").clicked() {
                 ui.ctx().copy_text(self.output_text.clone());
                 self.success_message = "クリップボードにコピーしました".to_string();
+                self.schedule_clipboard_clear(ui.ctx(), self.output_text.clone());
             }
         });
 
@@ -342,10 +771,157 @@ impl CryptApp {
         ui.add_space(10.0);
 
         // 出力テキスト
-        ui.label("出力テキスト:");
+        ui.horizontal(|ui| {
+            ui.label("出力テキスト:");
+            if ui
+                .add_enabled(
+                    !self.output_text.is_empty(),
+                    egui::Button::new("🧹 出力を消去").fill(egui::Color32::DARK_RED),
+                )
+                .clicked()
+            {
+                self.wipe_output();
+                self.success_message = "出力を消去しました".to_string();
+            }
+        });
         ui.text_edit_multiline(&mut self.output_text);
     }
 
+    /// ファイル選択ダイアログで選んだファイルを処理対象にする（2つ以上なら一括処理）
+    fn select_files(&mut self, mut paths: Vec<PathBuf>) {
+        let Some(first) = paths.first() else {
+            return;
+        };
+        self.remember_directory(first);
+
+        if paths.len() == 1 {
+            self.batch_files.clear();
+            self.selected_file_path = paths.remove(0).display().to_string();
+            self.auto_select_mode();
+            return;
+        }
+
+        // すべて暗号化ファイルなら復号化、それ以外は暗号化
+        let all_encrypted = paths
+            .iter()
+            .all(|path| matches!(detect_format(path), Ok(Some(_))));
+        self.file_processing_mode = if all_encrypted {
+            FileProcessingMode::Decrypt
+        } else {
+            FileProcessingMode::Encrypt
+        };
+        self.batch_files = paths
+            .into_iter()
+            .map(|path| BatchFile {
+                path,
+                status: BatchFileStatus::Pending,
+            })
+            .collect();
+        self.selected_file_path.clear();
+        self.selected_file_info = None;
+        self.output_file_path.clear();
+    }
+
+    /// 複数選択したファイルの一覧と処理状況を描画
+    fn draw_batch_files(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("選択したファイル: {} 件", self.batch_files.len()));
+            if ui
+                .add_enabled(!self.processing, egui::Button::new("選択を解除"))
+                .clicked()
+            {
+                self.batch_files.clear();
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .max_height(150.0)
+            .show(ui, |ui| {
+                for file in &self.batch_files {
+                    ui.horizontal(|ui| {
+                        match &file.status {
+                            BatchFileStatus::Pending => {
+                                ui.label("⏸");
+                            }
+                            BatchFileStatus::Processing => {
+                                ui.spinner();
+                            }
+                            BatchFileStatus::Done => {
+                                ui.colored_label(egui::Color32::GREEN, "✅");
+                            }
+                            BatchFileStatus::Failed(_) => {
+                                ui.colored_label(egui::Color32::RED, "❌");
+                            }
+                        }
+                        ui.label(file.path.display().to_string());
+                        if let BatchFileStatus::Failed(e) = &file.status {
+                            ui.colored_label(egui::Color32::RED, e);
+                        }
+                    });
+                }
+            });
+    }
+
+    /// 選択したファイルの先頭を調べ、暗号化ファイルなら復号化、それ以外なら暗号化に処理モードを切り替える
+    fn auto_select_mode(&mut self) {
+        self.refresh_file_info();
+        match self.selected_file_info {
+            Some(SelectedFileInfo::Encrypted(_) | SelectedFileInfo::Damaged(_)) => {
+                self.file_processing_mode = FileProcessingMode::Decrypt;
+            }
+            Some(SelectedFileInfo::Plain(_)) => {
+                self.file_processing_mode = FileProcessingMode::Encrypt;
+            }
+            // 読み込めないファイルは処理時にエラーを表示するため、ここでは切り替えない
+            Some(SelectedFileInfo::Unreadable(_)) | None => {}
+        }
+    }
+
+    /// 選択したファイルの形式とヘッダー情報を読み取る（パスワードもキー導出も使わない）
+    fn refresh_file_info(&mut self) {
+        let path = Path::new(&self.selected_file_path);
+        if !path.is_file() {
+            self.selected_file_info = None;
+            return;
+        }
+        self.selected_file_info = Some(match detect_format(path) {
+            Ok(Some(_)) => match inspect_file(path) {
+                Ok(info) => SelectedFileInfo::Encrypted(info),
+                Err(e) => SelectedFileInfo::Damaged(e.to_string()),
+            },
+            Ok(None) => match fs::metadata(path) {
+                Ok(metadata) => SelectedFileInfo::Plain(metadata.len()),
+                Err(e) => SelectedFileInfo::Unreadable(e.to_string()),
+            },
+            Err(e) => SelectedFileInfo::Unreadable(e.to_string()),
+        });
+    }
+
+    /// 選択中のファイルの情報欄を描画
+    fn draw_file_info(&self, ui: &mut egui::Ui) {
+        let Some(info) = &self.selected_file_info else {
+            return;
+        };
+
+        ui.group(|ui| match info {
+            SelectedFileInfo::Plain(size) => {
+                ui.label("🔓 暗号化されていないファイル");
+                ui.label(format!("サイズ: {size} バイト"));
+            }
+            SelectedFileInfo::Encrypted(info) => draw_encrypted_file_info(ui, info),
+            SelectedFileInfo::Damaged(e) => {
+                ui.label("🔒 暗号化ファイル");
+                ui.colored_label(
+                    egui::Color32::from_rgb(230, 160, 0),
+                    format!("ヘッダーを読み取れません: {e}"),
+                );
+            }
+            SelectedFileInfo::Unreadable(e) => {
+                ui.colored_label(egui::Color32::RED, format!("ファイルを読み込めません: {e}"));
+            }
+        });
+    }
+
     /// ファイル暗号化タブの描画
     fn draw_file_crypto_tab(&mut self, ui: &mut egui::Ui) {
         ui.heading("📁 ファイル暗号化");
@@ -354,9 +930,36 @@ impl CryptApp {
         // ファイル選択
         ui.horizontal(|ui| {
             ui.label("ファイルパス:");
-            ui.text_edit_singleline(&mut self.selected_file_path);
+            let hint = self
+                .gui_state
+                .existing_directory()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default();
+            if ui
+                .add(egui::TextEdit::singleline(&mut self.selected_file_path).hint_text(hint))
+                .changed()
+            {
+                self.batch_files.clear();
+                self.auto_select_mode();
+            }
+            if ui.button("📂 参照").clicked() {
+                if let Some(path) = self.file_dialog().pick_file() {
+                    self.select_files(vec![path]);
+                }
+            }
+            if ui.button("📚 複数選択").clicked() {
+                if let Some(paths) = self.file_dialog().pick_files() {
+                    self.select_files(paths);
+                }
+            }
         });
 
+        if self.batch_files.is_empty() {
+            self.draw_file_info(ui);
+        } else {
+            self.draw_batch_files(ui);
+        }
+
         ui.add_space(10.0);
 
         // 処理モード選択
@@ -374,10 +977,20 @@ impl CryptApp {
             );
         });
 
-        // 出力ファイルパス
+        // 出力ファイルパス（複数のファイルではファイルごとに自動で決める）
         ui.horizontal(|ui| {
             ui.label("出力ファイル:");
+            if !self.batch_files.is_empty() {
+                ui.label("自動（ファイルごと）");
+                return;
+            }
             ui.text_edit_singleline(&mut self.output_file_path);
+            if ui.button("📂 参照").clicked() {
+                if let Some(path) = self.file_dialog().save_file() {
+                    self.output_file_path = path.display().to_string();
+                    self.remember_directory(&path);
+                }
+            }
             if ui.button("自動").clicked() {
                 self.output_file_path.clear();
             }
@@ -389,9 +1002,9 @@ impl CryptApp {
         ui.horizontal(|ui| {
             ui.label("ファイルパスワード:");
             if self.file_password_visible {
-                ui.text_edit_singleline(&mut self.file_password);
+                ui.text_edit_singleline(&mut *self.file_password);
             } else {
-                ui.add(egui::TextEdit::singleline(&mut self.file_password).password(true));
+                ui.add(egui::TextEdit::singleline(&mut *self.file_password).password(true));
             }
             if ui
                 .button(if self.file_password_visible {
@@ -403,8 +1016,31 @@ impl CryptApp {
             {
                 self.file_password_visible = !self.file_password_visible;
             }
+            if ui.button("🎲 パスワード生成").clicked() {
+                self.file_password = Zeroizing::new(generate_password(
+                    GENERATED_PASSWORD_LENGTH,
+                    PasswordCharset::AlphanumericSymbols,
+                ));
+                self.file_password_visible = true;
+                self.success_message = "パスワードを生成しました".to_string();
+            }
+            if ui
+                .button("📋")
+                .on_hover_text("パスワードをコピー")
+                .clicked()
+            {
+                let password = self.file_password.to_string();
+                ui.ctx().copy_text(password.clone());
+                self.success_message = "パスワードをクリップボードにコピーしました".to_string();
+                self.schedule_clipboard_clear(ui.ctx(), password);
+            }
         });
 
+        // 環境変数を使う場合は採点対象のパスワードが手元にないので表示しない
+        if !self.file_use_env_password {
+            draw_strength_meter(ui, &self.file_password);
+        }
+
         ui.checkbox(
             &mut self.file_use_env_password,
             "環境変数からパスワードを取得",
@@ -434,7 +1070,7 @@ impl CryptApp {
                 match self.process_file() {
                     Ok(()) => {
                         self.error_message.clear();
-                        self.success_message = "ファイル処理が完了しました".to_string();
+                        self.success_message.clear();
                     }
                     Err(e) => {
                         self.error_message = e;
@@ -445,8 +1081,43 @@ impl CryptApp {
         } else {
             ui.horizontal(|ui| {
                 ui.spinner();
-                ui.label("処理中...");
+                ui.label(if self.deriving_key {
+                    "鍵を導出中..."
+                } else {
+                    "処理中..."
+                });
+                if !self.batch_files.is_empty() {
+                    let finished = self
+                        .batch_files
+                        .iter()
+                        .filter(|file| {
+                            matches!(
+                                file.status,
+                                BatchFileStatus::Done | BatchFileStatus::Failed(_)
+                            )
+                        })
+                        .count();
+                    ui.label(format!("{finished} / {} 件", self.batch_files.len()));
+                }
+                if ui.button("⏹ キャンセル").clicked() {
+                    self.cancel_file_task();
+                }
             });
+
+            // ストリーミング処理の進捗（キー導出中は進まないため表示しない）
+            if let Some((done, total)) = self.file_progress.filter(|_| !self.deriving_key) {
+                let fraction = if total > 0 {
+                    done as f32 / total as f32
+                } else {
+                    1.0
+                };
+                ui.add(egui::ProgressBar::new(fraction).text(format!(
+                    "{:.0}% ({:.1} / {:.1} MB)",
+                    fraction * 100.0,
+                    done as f64 / 1_048_576.0,
+                    total as f64 / 1_048_576.0
+                )));
+            }
         }
     }
 
@@ -455,8 +1126,57 @@ impl CryptApp {
         ui.heading("⚙️ 設定");
         ui.separator();
 
+        // キー導出アルゴリズム
+        ui.horizontal(|ui| {
+            ui.label("キー導出:");
+            ui.radio_value(&mut self.config.kdf, KdfAlgorithm::Argon2id, "Argon2id");
+            ui.radio_value(&mut self.config.kdf, KdfAlgorithm::Pbkdf2, "PBKDF2");
+            ui.radio_value(&mut self.config.kdf, KdfAlgorithm::Scrypt, "scrypt");
+        });
+
+        match self.config.kdf {
+            KdfAlgorithm::Argon2id => {}
+            KdfAlgorithm::Pbkdf2 => {
+                ui.horizontal(|ui| {
+                    ui.label("PBKDF2 反復回数:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.config.pbkdf2.iterations)
+                            .range(1..=10_000_000),
+                    );
+                });
+            }
+            KdfAlgorithm::Scrypt => {
+                ui.collapsing("🔧 scrypt パラメータ", |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("log_n (N = 2^log_n):");
+                        ui.add(egui::DragValue::new(&mut self.config.scrypt.log_n).range(10..=22));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("r:");
+                        ui.add(egui::DragValue::new(&mut self.config.scrypt.r).range(1..=32));
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("p:");
+                        ui.add(egui::DragValue::new(&mut self.config.scrypt.p).range(1..=16));
+                    });
+                });
+            }
+        }
+
+        ui.add_space(10.0);
+
         // Argon2設定
         ui.collapsing("🔧 Argon2 パラメータ", |ui| {
+            ui.horizontal(|ui| {
+                ui.label("種類:");
+                let variant = &mut self.config.argon2.variant;
+                ui.radio_value(variant, Argon2Variant::Argon2id, "Argon2id");
+                ui.radio_value(variant, Argon2Variant::Argon2i, "Argon2i");
+                ui.radio_value(variant, Argon2Variant::Argon2d, "Argon2d");
+            });
+
             ui.horizontal(|ui| {
                 ui.label("メモリ使用量 (KB):");
                 ui.add(
@@ -473,6 +1193,43 @@ impl CryptApp {
                 ui.label("並列度:");
                 ui.add(egui::DragValue::new(&mut self.config.argon2.parallelism).range(1..=16));
             });
+
+            ui.horizontal(|ui| {
+                let running = self.argon2_test.is_some();
+                if ui
+                    .add_enabled(!running, egui::Button::new("テスト実行"))
+                    .on_hover_text("現在のパラメータでキー導出を1回実行し、かかる時間を計測します")
+                    .clicked()
+                {
+                    self.start_argon2_test();
+                }
+
+                if running {
+                    ui.spinner();
+                    ui.label("計測中...");
+                    return;
+                }
+                // パラメータを変更した後は、古い結果を表示しない
+                match &self.argon2_test_result {
+                    Some((params, Ok(elapsed))) if *params == self.config.argon2 => {
+                        let secs = elapsed.as_secs_f64();
+                        if *elapsed > SLOW_ARGON2_THRESHOLD {
+                            ui.colored_label(
+                                egui::Color32::from_rgb(230, 160, 0),
+                                format!(
+                                    "⚠ {secs:.2} 秒（暗号化・復号化のたびにこの時間がかかります）"
+                                ),
+                            );
+                        } else {
+                            ui.label(format!("{secs:.2} 秒"));
+                        }
+                    }
+                    Some((params, Err(e))) if *params == self.config.argon2 => {
+                        ui.colored_label(egui::Color32::RED, e);
+                    }
+                    _ => {}
+                }
+            });
         });
 
         ui.add_space(10.0);
@@ -492,6 +1249,40 @@ impl CryptApp {
 
         // その他の設定
         ui.checkbox(&mut self.config.default_verbose, "デフォルトで詳細出力");
+        ui.checkbox(
+            &mut self.config.preserve_metadata,
+            "元ファイルのパーミッションと更新日時を保持",
+        );
+
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.clipboard_auto_clear, "コピー後");
+            ui.add_enabled(
+                self.clipboard_auto_clear,
+                egui::DragValue::new(&mut self.clipboard_clear_secs).range(1..=600),
+            );
+            ui.label("秒でクリップボードを消去");
+        });
+
+        ui.horizontal(|ui| {
+            ui.checkbox(
+                &mut self.output_auto_clear,
+                "タブの切り替え時と、操作がないまま",
+            );
+            ui.add_enabled(
+                self.output_auto_clear,
+                egui::DragValue::new(&mut self.output_clear_secs).range(10..=3600),
+            );
+            ui.label("秒後に復号結果を消去");
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("暗号化時のパスワードのエントロピーの下限:");
+            ui.add(
+                egui::DragValue::new(&mut self.config.min_password_entropy)
+                    .range(0.0..=128.0)
+                    .suffix(" ビット"),
+            );
+        });
 
         ui.add_space(20.0);
 
@@ -510,8 +1301,8 @@ impl CryptApp {
                         "ファイルパスワードをテキストにコピーしました".to_string();
                 }
                 if ui.button("両方クリア").clicked() {
-                    self.text_password.clear();
-                    self.file_password.clear();
+                    self.text_password.zeroize();
+                    self.file_password.zeroize();
                     self.success_message = "パスワードをクリアしました".to_string();
                 }
             });
@@ -521,7 +1312,7 @@ impl CryptApp {
 
         // 設定ファイル操作
         ui.collapsing("💾 設定ファイル", |ui| {
-            if let Ok(config_path) = get_default_config_path() {
+            if let Ok((config_path, _)) = resolve_config_path(None) {
                 ui.label(format!("設定ファイル: {}", config_path.display()));
                 ui.label(format!(
                     "存在: {}",
@@ -607,120 +1398,136 @@ impl CryptApp {
     }
 }
 
-impl eframe::App for CryptApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // 日本語フォント設定（クロスプラットフォーム対応）
-        if !self.fonts_loaded {
-            let mut fonts = egui::FontDefinitions::default();
-            let mut font_loaded = false;
+/// パスワード強度メーターの描画
+fn draw_strength_meter(ui: &mut egui::Ui, password: &str) {
+    if password.is_empty() {
+        return;
+    }
 
-            // Windows用の日本語フォント設定
-            #[cfg(target_os = "windows")]
-            {
-                let font_paths = [
-                    "C:/Windows/Fonts/msgothic.ttc",            // MS Gothic
-                    "C:/Windows/Fonts/msjh.ttc",                // Microsoft JhengHei
-                    "C:/Windows/Fonts/yugoth.ttf",              // Yu Gothic
-                    "C:/Windows/Fonts/NotoSansCJK-Regular.ttc", // Noto Sans CJK (if installed)
-                ];
-
-                for font_path in &font_paths {
-                    if let Ok(font_data) = std::fs::read(font_path) {
-                        fonts.font_data.insert(
-                            "japanese_font".to_owned(),
-                            egui::FontData::from_owned(font_data).into(),
-                        );
-
-                        fonts
-                            .families
-                            .get_mut(&egui::FontFamily::Proportional)
-                            .unwrap()
-                            .insert(0, "japanese_font".to_owned());
-
-                        font_loaded = true;
-                        break;
-                    }
-                }
-            }
+    let strength = password_strength(password);
+    let fraction = (estimate_entropy(password) / 100.0).min(1.0) as f32;
+    let color = match strength {
+        PasswordStrength::Weak => egui::Color32::RED,
+        PasswordStrength::Fair => egui::Color32::from_rgb(230, 160, 0),
+        PasswordStrength::Strong => egui::Color32::GREEN,
+    };
 
-            // Linux用の日本語フォント設定
-            #[cfg(target_os = "linux")]
-            {
-                let font_paths = [
-                    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
-                    "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
-                    "/usr/share/fonts/truetype/takao-gothic/TakaoGothic.ttf",
-                    "/usr/share/fonts/vl-gothic-fonts/VL-Gothic-Regular.ttf",
-                    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
-                    "/usr/share/fonts/TTF/NotoSansCJK-Regular.ttc",
-                    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
-                ];
-
-                for font_path in &font_paths {
-                    if let Ok(font_data) = std::fs::read(font_path) {
-                        fonts.font_data.insert(
-                            "japanese_font".to_owned(),
-                            egui::FontData::from_owned(font_data).into(),
-                        );
-
-                        fonts
-                            .families
-                            .get_mut(&egui::FontFamily::Proportional)
-                            .unwrap()
-                            .insert(0, "japanese_font".to_owned());
-
-                        font_loaded = true;
-                        break;
-                    }
-                }
-            }
+    ui.horizontal(|ui| {
+        ui.label("強度:");
+        ui.add(
+            egui::ProgressBar::new(fraction)
+                .desired_width(150.0)
+                .fill(color),
+        );
+        ui.colored_label(color, strength.label());
+    });
+}
 
-            // macOS用の日本語フォント設定
-            #[cfg(target_os = "macos")]
-            {
-                let font_paths = [
-                    "/System/Library/Fonts/Hiragino Sans GB.ttc",
-                    "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
-                    "/Library/Fonts/Arial Unicode MS.ttf",
-                ];
-
-                for font_path in &font_paths {
-                    if let Ok(font_data) = std::fs::read(font_path) {
-                        fonts.font_data.insert(
-                            "japanese_font".to_owned(),
-                            egui::FontData::from_owned(font_data).into(),
-                        );
-
-                        fonts
-                            .families
-                            .get_mut(&egui::FontFamily::Proportional)
-                            .unwrap()
-                            .insert(0, "japanese_font".to_owned());
-
-                        font_loaded = true;
-                        break;
-                    }
-                }
-            }
+/// 1つのファイルを暗号化・復号化し、指定されていれば元ファイルを削除する
+///
+/// `output_path` が `None` なら、処理する直前に出力先を決める（一括処理で名前が重ならないように）。
+fn process_one_file(
+    batch: &FileBatch,
+    mode: &FileProcessingMode,
+    input_path: &Path,
+    output_path: Option<&Path>,
+    use_streaming: bool,
+    delete_original: bool,
+    config: &Config,
+) -> Result<(), String> {
+    let encrypt = matches!(mode, FileProcessingMode::Encrypt);
+    let output_path = match output_path {
+        Some(path) => path.to_path_buf(),
+        None => determine_output_path(input_path, &None, config, encrypt, false)
+            .map_err(|e| format!("出力パス決定エラー: {e}"))?,
+    };
+
+    // 他のプロセス（CLIなど）が同じ出力先を処理中なら書き込まない
+    let result = with_output_lock(&output_path, || {
+        if encrypt {
+            batch.encrypt_file(input_path, &output_path, use_streaming)
+        } else {
+            // チェックがなくても、ストリーミング形式のファイルは自動で判別する
+            let streaming = use_streaming
+                || matches!(detect_format(input_path), Ok(Some(EncFormat::Streaming)));
+            batch.decrypt_file(input_path, &output_path, streaming)
+        }
+    });
+    result.map_err(|e| format!("ファイル処理エラー: {e}"))?;
+
+    if delete_original {
+        fs::remove_file(input_path).map_err(|e| format!("元ファイル削除エラー: {e}"))?;
+    }
+    Ok(())
+}
 
-            // フォント設定を適用
-            if font_loaded {
-                ctx.set_fonts(fonts);
+/// 暗号化ファイルのヘッダー情報を表示
+fn draw_encrypted_file_info(ui: &mut egui::Ui, info: &FileInfo) {
+    let format = match &info.format {
+        FormatInfo::Standard { version, .. } => format!("標準（バージョン {version}）"),
+        FormatInfo::Streaming { .. } => "ストリーミング".to_string(),
+        FormatInfo::Envelope { slots } => format!("複数パスワード（{slots} スロット）"),
+    };
+    let encoding = match (&info.encoding, info.armored) {
+        (Some(format), _) => format!("、{format:?}の文字列"),
+        (None, true) => "、ASCIIアーマー".to_string(),
+        (None, false) => String::new(),
+    };
+
+    ui.label("🔒 暗号化ファイル");
+    ui.label(format!("形式: {format}{encoding}"));
+    ui.label("暗号方式: AES-256-GCM");
+    ui.label(format!("キー導出: {}", info.kdf));
+    match &info.format {
+        FormatInfo::Standard { hint, path, .. } => {
+            if let Some(path) = path {
+                ui.label(format!("元のファイル: {path}"));
             }
+            if let Some(hint) = hint {
+                ui.label(format!("ヒント: {hint}"));
+            }
+        }
+        FormatInfo::Streaming { complete, .. } => {
+            if !*complete {
+                ui.colored_label(egui::Color32::from_rgb(230, 160, 0), "途中で切れています");
+            }
+        }
+        FormatInfo::Envelope { .. } => {}
+    }
+    ui.small("※ パスワードを使わずに読み取った情報です");
+}
 
+impl eframe::App for CryptApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // バックグラウンド処理の完了を確認し、処理中は定期的に再描画
+        self.poll_file_task();
+        self.poll_clipboard_timer();
+        self.poll_argon2_test();
+        self.poll_output_timeout(ctx);
+        if self.processing || self.argon2_test.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
+        // 日本語フォント設定（OSごとのフォントを探して登録）
+        if !self.fonts_loaded {
+            install_japanese_font(ctx);
             self.fonts_loaded = true;
         }
 
+        let previous_tab = self.current_tab.clone();
+
         // トップメニューバー
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             egui::MenuBar::new().ui(ui, |ui| {
                 ui.menu_button("ファイル", |ui| {
                     if ui.button("新規").clicked() {
                         self.input_text.clear();
-                        self.output_text.clear();
-                        self.text_password.clear();
-                        self.file_password.clear();
+                        self.wipe_output();
+                        self.text_password.zeroize();
+                        self.file_password.zeroize();
                         self.selected_file_path.clear();
+                        self.selected_file_info = None;
+                        self.batch_files.clear();
                         self.output_file_path.clear();
                         self.error_message.clear();
                         self.success_message.clear();
@@ -766,6 +1573,15 @@ impl eframe::App for CryptApp {
             });
         });
 
+        // テキストタブから離れたら復号結果を消去
+        if previous_tab == Tab::TextCrypto
+            && self.current_tab != Tab::TextCrypto
+            && self.output_is_plaintext
+            && self.output_auto_clear
+        {
+            self.wipe_output();
+        }
+
         // ステータスバー
         egui::TopBottomPanel::bottom("status_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -796,6 +1612,14 @@ impl eframe::App for CryptApp {
 }
 
 fn main() -> eframe::Result<()> {
+    // 詳細ログは「詳細出力」チェック時のみ標準エラーへ出力
+    env_logger::Builder::new()
+        .filter_level(LevelFilter::Debug)
+        .parse_default_env()
+        .format_timestamp(None)
+        .init();
+    log::set_max_level(LevelFilter::Warn);
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
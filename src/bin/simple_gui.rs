@@ -1,5 +1,6 @@
 // src/bin/simple_gui.rs
 use eframe::egui;
+use encript_tool::fonts::install_japanese_font;
 
 struct SimpleApp {
     name: String,
@@ -19,27 +20,7 @@ impl eframe::App for SimpleApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // 日本語フォントを一回だけ設定
         if !self.fonts_loaded {
-            let mut fonts = egui::FontDefinitions::default();
-
-            // VL Gothicフォントを読み込み
-            if let Ok(font_data) =
-                std::fs::read("/usr/share/fonts/vl-gothic-fonts/VL-Gothic-Regular.ttf")
-            {
-                fonts.font_data.insert(
-                    "vl_gothic".to_owned(),
-                    egui::FontData::from_owned(font_data).into(),
-                );
-
-                // フォントファミリーの先頭に追加
-                fonts
-                    .families
-                    .get_mut(&egui::FontFamily::Proportional)
-                    .unwrap()
-                    .insert(0, "vl_gothic".to_owned());
-
-                ctx.set_fonts(fonts);
-            }
-
+            install_japanese_font(ctx);
             self.fonts_loaded = true;
         }
 
@@ -0,0 +1,159 @@
+//! 標準形式の平文の圧縮（Deflate）
+//!
+//! 圧縮するかどうかは暗号化時に決めてヘッダーの圧縮フラグに記録し、復号化ではフラグだけを見て展開します。
+//! 暗号化時の `CompressMode` が何であっても、復号化に指定は要りません。
+//!
+//! 圧縮は暗号化の前に行うため、暗号文の長さから平文の内容が推測されやすくなります
+//! （平文の一部を攻撃者が操作できる場合の CRIME / BREACH のような攻撃）。
+
+use crate::error::{CryptoError, Result};
+use flate2::read::DeflateDecoder;
+use std::fmt;
+use std::io::Read;
+use std::str::FromStr;
+// 暗号化時の圧縮用（ファイルの暗号化でのみ使用）
+#[cfg(feature = "std-fs")]
+use flate2::{write::DeflateEncoder, Compression};
+#[cfg(feature = "std-fs")]
+use std::{io::Write, path::Path};
+
+/// `auto` でエントロピーを調べる先頭のバイト数（ストリーミング処理の既定のチャンクサイズと同じ）
+#[cfg(feature = "std-fs")]
+const SAMPLE_SIZE: usize = 64 * 1024;
+
+/// `auto` で圧縮しない先頭の標本のエントロピー（ビット/バイト、8が最大）
+#[cfg(feature = "std-fs")]
+const MAX_COMPRESSIBLE_ENTROPY: f64 = 7.5;
+
+/// 既に圧縮されているため、`auto` で圧縮しない拡張子
+#[cfg(feature = "std-fs")]
+const COMPRESSED_EXTENSIONS: &[&str] = &[
+    "7z", "avi", "br", "bz2", "docx", "gif", "gz", "heic", "jpeg", "jpg", "m4a", "mkv", "mov",
+    "mp3", "mp4", "ogg", "png", "rar", "tgz", "webm", "webp", "xlsx", "xz", "zip", "zst",
+];
+
+/// 圧縮が効きやすいため、`auto` で標本を調べずに圧縮する拡張子
+#[cfg(feature = "std-fs")]
+const TEXT_EXTENSIONS: &[&str] = &[
+    "csv", "htm", "html", "json", "log", "md", "sql", "svg", "toml", "tsv", "txt", "xml", "yaml",
+    "yml",
+];
+
+/// 暗号化時に平文を圧縮するか
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressMode {
+    /// 入力の拡張子と先頭の標本のエントロピーで決める（圧縮しても小さくならなければ圧縮しない）
+    Auto,
+    /// 常に圧縮する
+    Always,
+    /// 圧縮しない（デフォルト）
+    #[default]
+    Never,
+}
+
+impl fmt::Display for CompressMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CompressMode::Auto => "auto",
+            CompressMode::Always => "always",
+            CompressMode::Never => "never",
+        })
+    }
+}
+
+impl FromStr for CompressMode {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "auto" => Ok(CompressMode::Auto),
+            "always" => Ok(CompressMode::Always),
+            "never" => Ok(CompressMode::Never),
+            _ => Err(CryptoError::InvalidInput(format!(
+                "不明な圧縮の指定です: {s}（auto / always / never）"
+            ))),
+        }
+    }
+}
+
+/// `mode` に従って `data`（`path` から読み込んだ平文）を圧縮し、圧縮した場合だけ圧縮後のデータを返す
+#[cfg(feature = "std-fs")]
+pub(crate) fn compress_with_mode(
+    data: &[u8],
+    mode: CompressMode,
+    path: &Path,
+) -> Result<Option<Vec<u8>>> {
+    match mode {
+        CompressMode::Never => Ok(None),
+        CompressMode::Always => compress(data).map(Some),
+        CompressMode::Auto => {
+            if !looks_compressible(path, data) {
+                return Ok(None);
+            }
+            let compressed = compress(data)?;
+            Ok((compressed.len() < data.len()).then_some(compressed))
+        }
+    }
+}
+
+/// 拡張子で決まらなければ、先頭の標本のエントロピーで圧縮が効きそうか判断する
+#[cfg(feature = "std-fs")]
+fn looks_compressible(path: &Path, data: &[u8]) -> bool {
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some(ext) if COMPRESSED_EXTENSIONS.contains(&ext) => false,
+        Some(ext) if TEXT_EXTENSIONS.contains(&ext) => true,
+        _ => entropy(&data[..data.len().min(SAMPLE_SIZE)]) < MAX_COMPRESSIBLE_ENTROPY,
+    }
+}
+
+/// バイトの出現頻度から求めたシャノンエントロピー（ビット/バイト）
+#[cfg(feature = "std-fs")]
+fn entropy(sample: &[u8]) -> f64 {
+    if sample.is_empty() {
+        return 0.0;
+    }
+    let mut counts = [0usize; 256];
+    for &byte in sample {
+        counts[byte as usize] += 1;
+    }
+    let len = sample.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Deflate で圧縮
+#[cfg(feature = "std-fs")]
+pub(crate) fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(data)
+        .and_then(|()| encoder.finish())
+        .map_err(|_| CryptoError::Encryption)
+}
+
+/// `compress` で圧縮したデータを、ヘッダーに記録された展開後のバイト数 `len` まで展開
+///
+/// `len` を超えて展開しないため、展開後に極端に大きくなるデータでもメモリを使い切りません。
+pub(crate) fn decompress(data: &[u8], len: u64) -> Result<Vec<u8>> {
+    let mut plaintext = Vec::new();
+    DeflateDecoder::new(data)
+        .take(len.saturating_add(1))
+        .read_to_end(&mut plaintext)
+        .map_err(|e| CryptoError::InvalidFormat(format!("圧縮データの展開に失敗しました: {e}")))?;
+    if plaintext.len() as u64 != len {
+        return Err(CryptoError::InvalidFormat(
+            "展開したデータのバイト数がヘッダーの記録と一致しません".to_string(),
+        ));
+    }
+    Ok(plaintext)
+}
@@ -1,12 +1,34 @@
-use anyhow::{anyhow, Context, Result};
+use crate::compress::CompressMode;
+use crate::error::CryptoError;
+#[cfg(feature = "std-fs")]
+use crate::file_ops::write_atomic;
+use crate::header::validate_hint;
+use crate::key_derivation::{Argon2Variant, KdfAlgorithm, Pepper, SALT_SIZE};
+use anyhow::{anyhow, bail, Context, Result};
 use serde::{Deserialize, Serialize};
-use std::{
-    fs,
-    path::{Path, PathBuf},
-};
+use std::path::PathBuf;
+// 設定ファイルの読み書き用
+#[cfg(feature = "std-fs")]
+use std::{fs, path::Path};
+
+/// ストリーミング処理のデフォルトチャンクサイズ（64KB）
+pub const DEFAULT_STREAM_CHUNK_SIZE: usize = 64 * 1024;
+/// 自動でストリーミング処理に切り替える入力ファイルサイズの既定値（100MB）
+pub const DEFAULT_STREAMING_THRESHOLD: u64 = 100 * 1024 * 1024;
+/// チャンクサイズの下限（4KB）
+pub const MIN_STREAM_CHUNK_SIZE: usize = 4 * 1024;
+/// チャンクサイズの上限（16MB）
+pub const MAX_STREAM_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+/// 文字列のパディングのブロックサイズの上限（64KB）
+pub const MAX_PAD_BLOCK: usize = 64 * 1024;
+/// 暗号化時に要求するパスワードのエントロピーの既定の下限（ビット、英小文字6文字程度）
+pub const DEFAULT_MIN_PASSWORD_ENTROPY: f64 = 28.0;
+/// ストリーミング処理のプログレスバーの既定のテンプレート（indicatif の書式）
+pub const DEFAULT_PROGRESS_TEMPLATE: &str =
+    "{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})";
 
 /// 設定ファイルの構造
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// デフォルトの出力形式
     pub default_format: OutputFormat,
@@ -16,12 +38,80 @@ pub struct Config {
     pub default_password_env: Option<String>,
     /// 設定ファイルのバージョン
     pub version: String,
+    /// ストリーミング処理のチャンクサイズ（バイト）
+    #[serde(default = "default_stream_chunk_size")]
+    pub stream_chunk_size: usize,
+    /// `encrypt-file` で `--streaming` / `--no-streaming` を指定しない場合に、
+    /// ストリーミング処理を使う入力ファイルサイズ（このバイト数を超えるファイル）
+    #[serde(default = "default_streaming_threshold")]
+    pub streaming_threshold: u64,
+    /// ファイルの暗号化時にパーミッションと更新日時を記録し、復号化時に復元するか
+    #[serde(default = "default_preserve_metadata")]
+    pub preserve_metadata: bool,
+    /// 文字列の暗号化時に平文をこのバイト数の倍数までパディングし、長さを隠す
+    #[serde(default)]
+    pub pad_block: Option<usize>,
+    /// 暗号化したファイルに付ける拡張子（先頭の `.` は含めない）
+    #[serde(default = "default_encrypted_extension")]
+    pub encrypted_extension: String,
+    /// 標準形式のファイルの暗号化時に、暗号文をこの形式の文字列で書き込む
+    /// （未設定ならバイナリのまま。ストリーミング形式には適用しない）
+    #[serde(default)]
+    pub file_format: Option<OutputFormat>,
+    /// 出力先を指定しない場合に、暗号化・復号化したファイルを置くディレクトリ（未設定なら入力と同じ場所）
+    #[serde(default)]
+    pub default_output_dir: Option<PathBuf>,
+    /// 暗号化時に要求するパスワードの推定エントロピーの下限（ビット、0なら空のパスワードのみ拒否）
+    #[serde(default = "default_min_password_entropy")]
+    pub min_password_entropy: f64,
+    /// ストリーミング処理で端末にプログレスバーを表示するか
+    #[serde(default = "default_show_progress")]
+    pub show_progress: bool,
+    /// プログレスバーのテンプレート（indicatif の書式、未設定なら `DEFAULT_PROGRESS_TEMPLATE`）
+    #[serde(default)]
+    pub progress_template: Option<String>,
+    /// 暗号化時にヘッダーに記録するパスワードのヒント（`--hint` で指定、設定ファイルには保存しない）
+    #[serde(skip)]
+    pub hint: Option<String>,
+    /// 暗号化時に元の平文のSHA-256を記録し、復号化後に検証するか（`--checksum` で指定、設定ファイルには保存しない）
+    #[serde(skip)]
+    pub checksum: bool,
+    /// 標準形式のファイルの暗号化時に平文を圧縮するか（`--compress` で指定、設定ファイルには保存しない）
+    #[serde(skip)]
+    pub compress: CompressMode,
+    /// 暗号化時のキー導出に使うソルト（`--salt-file` で指定、未設定ならランダム。設定ファイルには保存しない）
+    #[serde(skip)]
+    pub salt: Option<[u8; SALT_SIZE]>,
+    /// Argon2に混ぜる秘密の値（環境変数 `MYCRYPT_PEPPER` か `--pepper-file` で指定、設定ファイルには保存しない）
+    #[serde(skip)]
+    pub pepper: Option<Pepper>,
+    /// 読み込んだ設定ファイルにあった、このバージョンが知らない項目（`argon2.xxx` のようにドット区切り）
+    ///
+    /// 新しいバージョンで追加された項目を含む設定ファイルも読み込めるよう、不明な項目は無視してここに記録します。
+    #[serde(skip)]
+    pub ignored_keys: Vec<String>,
+    /// ヘッダーのない旧形式（パスワードから決定的に作ったソルト）のデータの復号化を試みるか
+    /// （`legacy-compat` フィーチャーが有効な場合のみ。暗号化には常にランダムなソルトを使う）
+    #[serde(default)]
+    pub legacy_deterministic_salt: bool,
+    /// 暗号化時に使用するキー導出アルゴリズム
+    #[serde(default)]
+    pub kdf: KdfAlgorithm,
     /// Argon2設定
     pub argon2: Argon2Config,
+    /// PBKDF2設定
+    #[serde(default)]
+    pub pbkdf2: Pbkdf2Config,
+    /// scrypt設定
+    #[serde(default)]
+    pub scrypt: ScryptConfig,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Argon2Config {
+    /// Argon2の種類（既定は Argon2id）
+    #[serde(default)]
+    pub variant: Argon2Variant,
     /// メモリ使用量（KB）
     pub memory_cost: u32,
     /// 時間コスト（繰り返し回数）
@@ -30,8 +120,24 @@ pub struct Argon2Config {
     pub parallelism: u32,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pbkdf2Config {
+    /// 反復回数
+    pub iterations: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScryptConfig {
+    /// コストパラメータ N の2を底とする対数
+    pub log_n: u8,
+    /// ブロックサイズ
+    pub r: u32,
+    /// 並列度
+    pub p: u32,
+}
+
 // PartialEq を追加
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum OutputFormat {
     Base64,
     Hex,
@@ -40,6 +146,7 @@ pub enum OutputFormat {
 impl Default for Argon2Config {
     fn default() -> Self {
         Self {
+            variant: Argon2Variant::default(),
             memory_cost: 65536, // 64MB
             time_cost: 3,       // 3回繰り返し
             parallelism: 4,     // 4並列
@@ -47,6 +154,24 @@ impl Default for Argon2Config {
     }
 }
 
+impl Default for Pbkdf2Config {
+    fn default() -> Self {
+        Self {
+            iterations: 600_000, // OWASP推奨値（PBKDF2-HMAC-SHA256）
+        }
+    }
+}
+
+impl Default for ScryptConfig {
+    fn default() -> Self {
+        Self {
+            log_n: 17, // N = 131072（メモリ使用量 128 × r × N = 128MB）
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -54,17 +179,616 @@ impl Default for Config {
             default_verbose: false,
             default_password_env: Some("MYCRYPT_PASSWORD".to_string()),
             version: "2.0".to_string(),
+            stream_chunk_size: DEFAULT_STREAM_CHUNK_SIZE,
+            streaming_threshold: DEFAULT_STREAMING_THRESHOLD,
+            preserve_metadata: true,
+            pad_block: None,
+            encrypted_extension: default_encrypted_extension(),
+            file_format: None,
+            default_output_dir: None,
+            min_password_entropy: DEFAULT_MIN_PASSWORD_ENTROPY,
+            show_progress: true,
+            progress_template: None,
+            hint: None,
+            checksum: false,
+            compress: CompressMode::Never,
+            salt: None,
+            pepper: None,
+            ignored_keys: Vec::new(),
+            legacy_deterministic_salt: false,
+            kdf: KdfAlgorithm::default(),
             argon2: Argon2Config::default(),
+            pbkdf2: Pbkdf2Config::default(),
+            scrypt: ScryptConfig::default(),
+        }
+    }
+}
+
+/// `config get` / `config set` で指定できるキー
+pub const CONFIG_KEYS: &[&str] = &[
+    "default_format",
+    "default_verbose",
+    "default_password_env",
+    "stream_chunk_size",
+    "streaming_threshold",
+    "preserve_metadata",
+    "pad_block",
+    "encrypted_extension",
+    "file_format",
+    "default_output_dir",
+    "min_password_entropy",
+    "show_progress",
+    "progress_template",
+    "legacy_deterministic_salt",
+    "kdf",
+    "argon2.variant",
+    "argon2.memory_cost",
+    "argon2.time_cost",
+    "argon2.parallelism",
+    "pbkdf2.iterations",
+    "scrypt.log_n",
+    "scrypt.r",
+    "scrypt.p",
+];
+
+impl Config {
+    /// 設定値の妥当性を検証
+    pub fn validate(&self) -> Result<()> {
+        validate_chunk_size(self.stream_chunk_size)?;
+        if let Some(block) = self.pad_block {
+            validate_pad_block(block)?;
+        }
+        validate_extension(&self.encrypted_extension)?;
+        validate_min_password_entropy(self.min_password_entropy)?;
+        #[cfg(feature = "std-fs")]
+        if let Some(template) = &self.progress_template {
+            validate_progress_template(template)?;
+        }
+        if let Some(hint) = &self.hint {
+            validate_hint(hint)?;
+        }
+        argon2::Params::new(
+            self.argon2.memory_cost,
+            self.argon2.time_cost,
+            self.argon2.parallelism,
+            Some(32),
+        )
+        .map_err(|e| anyhow!("Argon2パラメータが不正です: {e}"))?;
+        validate_pbkdf2_iterations(self.pbkdf2.iterations)?;
+        scrypt::Params::new(self.scrypt.log_n, self.scrypt.r, self.scrypt.p, 32)
+            .map_err(|e| anyhow!("scryptパラメータが不正です: {e}"))?;
+        if self.pepper.is_some() && self.kdf != KdfAlgorithm::Argon2id {
+            bail!("ペッパーはキー導出アルゴリズムが argon2id の場合にのみ使用できます");
+        }
+        Ok(())
+    }
+
+    /// 既定値から設定を組み立てるビルダーを作成
+    ///
+    /// ```
+    /// use encript_tool::{Config, OutputFormat};
+    ///
+    /// let config = Config::builder()
+    ///     .argon2_memory(131072)
+    ///     .format(OutputFormat::Hex)
+    ///     .build()?;
+    /// # Ok::<(), anyhow::Error>(())
+    /// ```
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config::default(),
+            error: None,
+        }
+    }
+
+    /// ドット区切りのキーで設定値を文字列として取得
+    pub fn get_value(&self, key: &str) -> Result<String> {
+        let value = match key {
+            "default_format" => match self.default_format {
+                OutputFormat::Base64 => "base64".to_string(),
+                OutputFormat::Hex => "hex".to_string(),
+            },
+            "default_verbose" => self.default_verbose.to_string(),
+            "default_password_env" => self.default_password_env.clone().unwrap_or_default(),
+            "stream_chunk_size" => self.stream_chunk_size.to_string(),
+            "streaming_threshold" => self.streaming_threshold.to_string(),
+            "preserve_metadata" => self.preserve_metadata.to_string(),
+            "pad_block" => self
+                .pad_block
+                .map(|block| block.to_string())
+                .unwrap_or_default(),
+            "encrypted_extension" => self.encrypted_extension.clone(),
+            "file_format" => match self.file_format {
+                None => "binary".to_string(),
+                Some(OutputFormat::Base64) => "base64".to_string(),
+                Some(OutputFormat::Hex) => "hex".to_string(),
+            },
+            "default_output_dir" => self
+                .default_output_dir
+                .as_ref()
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_default(),
+            "min_password_entropy" => self.min_password_entropy.to_string(),
+            "show_progress" => self.show_progress.to_string(),
+            "progress_template" => self.progress_template.clone().unwrap_or_default(),
+            "legacy_deterministic_salt" => self.legacy_deterministic_salt.to_string(),
+            "kdf" => self.kdf.to_string(),
+            "argon2.variant" => self.argon2.variant.to_string(),
+            "argon2.memory_cost" => self.argon2.memory_cost.to_string(),
+            "argon2.time_cost" => self.argon2.time_cost.to_string(),
+            "argon2.parallelism" => self.argon2.parallelism.to_string(),
+            "pbkdf2.iterations" => self.pbkdf2.iterations.to_string(),
+            "scrypt.log_n" => self.scrypt.log_n.to_string(),
+            "scrypt.r" => self.scrypt.r.to_string(),
+            "scrypt.p" => self.scrypt.p.to_string(),
+            _ => return Err(unknown_key(key)),
+        };
+        Ok(value)
+    }
+
+    /// ドット区切りのキーで設定値を変更（値の型と範囲を検証）
+    pub fn set_value(&mut self, key: &str, value: &str) -> Result<()> {
+        match key {
+            "default_format" => {
+                self.default_format = match value.to_ascii_lowercase().as_str() {
+                    "base64" => OutputFormat::Base64,
+                    "hex" => OutputFormat::Hex,
+                    _ => bail!("default_format には base64 または hex を指定してください: {value}"),
+                }
+            }
+            "default_verbose" => self.default_verbose = parse_value(key, value)?,
+            // 空文字列で未設定に戻す
+            "default_password_env" => {
+                self.default_password_env = (!value.is_empty()).then(|| value.to_string())
+            }
+            "stream_chunk_size" => self.stream_chunk_size = parse_value(key, value)?,
+            "streaming_threshold" => self.streaming_threshold = parse_value(key, value)?,
+            "preserve_metadata" => self.preserve_metadata = parse_value(key, value)?,
+            // 空文字列でパディングなしに戻す
+            "pad_block" => {
+                self.pad_block = if value.is_empty() {
+                    None
+                } else {
+                    Some(parse_value(key, value)?)
+                }
+            }
+            "encrypted_extension" => {
+                self.encrypted_extension = value.trim_start_matches('.').to_string()
+            }
+            // 空文字列でもバイナリに戻す
+            "file_format" => {
+                self.file_format = match value.to_ascii_lowercase().as_str() {
+                    "" | "binary" => None,
+                    "base64" => Some(OutputFormat::Base64),
+                    "hex" => Some(OutputFormat::Hex),
+                    _ => {
+                        bail!("file_format には binary / base64 / hex を指定してください: {value}")
+                    }
+                }
+            }
+            // 空文字列で入力と同じ場所に戻す
+            "default_output_dir" => {
+                self.default_output_dir = (!value.is_empty()).then(|| PathBuf::from(value))
+            }
+            "min_password_entropy" => self.min_password_entropy = parse_value(key, value)?,
+            "show_progress" => self.show_progress = parse_value(key, value)?,
+            // 空文字列で既定のテンプレートに戻す
+            "progress_template" => {
+                self.progress_template = (!value.is_empty()).then(|| value.to_string())
+            }
+            "legacy_deterministic_salt" => {
+                self.legacy_deterministic_salt = parse_value(key, value)?
+            }
+            "kdf" => self.kdf = parse_value(key, value)?,
+            "argon2.variant" => self.argon2.variant = parse_value(key, value)?,
+            "argon2.memory_cost" => self.argon2.memory_cost = parse_value(key, value)?,
+            "argon2.time_cost" => self.argon2.time_cost = parse_value(key, value)?,
+            "argon2.parallelism" => self.argon2.parallelism = parse_value(key, value)?,
+            "pbkdf2.iterations" => self.pbkdf2.iterations = parse_value(key, value)?,
+            "scrypt.log_n" => self.scrypt.log_n = parse_value(key, value)?,
+            "scrypt.r" => self.scrypt.r = parse_value(key, value)?,
+            "scrypt.p" => self.scrypt.p = parse_value(key, value)?,
+            _ => return Err(unknown_key(key)),
+        }
+
+        self.validate()
+    }
+
+    /// `MYCRYPT_*` 環境変数で設定値を上書き（未設定・空の変数は無視）
+    pub fn apply_env_overrides(&mut self) -> Result<()> {
+        for (var, key) in ENV_OVERRIDES {
+            let Ok(value) = std::env::var(var) else {
+                continue;
+            };
+            if value.is_empty() {
+                continue;
+            }
+
+            let value = match (*key, value.as_str()) {
+                ("default_verbose", "1") => "true",
+                ("default_verbose", "0") => "false",
+                _ => value.as_str(),
+            };
+            self.set_value(key, value)
+                .with_context(|| format!("環境変数 {var} の値が不正です"))?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_value<T>(key: &str, value: &str) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    value
+        .parse()
+        .map_err(|e| anyhow!("{key} の値が不正です: {value} ({e})"))
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+    anyhow!(
+        "不明な設定キーです: {key}（有効なキー: {}）",
+        CONFIG_KEYS.join(", ")
+    )
+}
+
+fn default_stream_chunk_size() -> usize {
+    DEFAULT_STREAM_CHUNK_SIZE
+}
+
+fn default_streaming_threshold() -> u64 {
+    DEFAULT_STREAMING_THRESHOLD
+}
+
+fn default_preserve_metadata() -> bool {
+    true
+}
+
+fn default_show_progress() -> bool {
+    true
+}
+
+fn default_min_password_entropy() -> f64 {
+    DEFAULT_MIN_PASSWORD_ENTROPY
+}
+
+fn default_encrypted_extension() -> String {
+    "enc".to_string()
+}
+
+fn validate_pad_block(block: usize) -> Result<()> {
+    if !(2..=MAX_PAD_BLOCK).contains(&block) {
+        bail!("パディングのブロックサイズは2〜{MAX_PAD_BLOCK}バイトで指定してください: {block}");
+    }
+    Ok(())
+}
+
+fn validate_min_password_entropy(bits: f64) -> Result<()> {
+    if !(bits.is_finite() && bits >= 0.0) {
+        bail!("パスワードのエントロピーの下限は0以上で指定してください: {bits}");
+    }
+    Ok(())
+}
+
+fn validate_pbkdf2_iterations(iterations: u32) -> Result<()> {
+    if iterations == 0 {
+        bail!("PBKDF2の反復回数は1以上を指定してください");
+    }
+    Ok(())
+}
+
+/// 暗号化ファイルの拡張子として使えるか検証（空、`.` で始まる、パスの区切りを含むものは不可）
+fn validate_extension(extension: &str) -> Result<()> {
+    if extension.is_empty()
+        || extension.starts_with('.')
+        || extension.contains(['/', '\\'])
+        || extension.chars().any(char::is_control)
+    {
+        bail!("暗号化ファイルの拡張子が不正です: {extension:?}");
+    }
+    Ok(())
+}
+
+/// プログレスバーのテンプレートを indicatif が解釈できるか検証（処理の途中ではなく設定の読み込み時に失敗させる）
+#[cfg(feature = "std-fs")]
+fn validate_progress_template(template: &str) -> Result<()> {
+    indicatif::ProgressStyle::default_bar()
+        .template(template)
+        .map_err(|e| anyhow!("プログレスバーのテンプレートが不正です: {e}"))?;
+    Ok(())
+}
+
+/// チャンクサイズが許容範囲内か検証
+pub fn validate_chunk_size(chunk_size: usize) -> Result<(), CryptoError> {
+    if !(MIN_STREAM_CHUNK_SIZE..=MAX_STREAM_CHUNK_SIZE).contains(&chunk_size) {
+        return Err(CryptoError::InvalidInput(format!(
+            "チャンクサイズが範囲外です: {chunk_size} バイト（{} KB〜{} MB）",
+            MIN_STREAM_CHUNK_SIZE / 1024,
+            MAX_STREAM_CHUNK_SIZE / 1_048_576
+        )));
+    }
+    Ok(())
+}
+
+/// `Config` を組み立てるビルダー（`Config::builder()` で作成）
+///
+/// 各メソッドは単独で検証できる値をその場で検証し、最初に見つかったエラーを `build` で返します。
+/// 組み合わせで決まる制約（Argon2 のメモリ使用量と並列度など）は `build` の `Config::validate` で検証します。
+/// 設定しなかった項目は `Config::default()` の値になります。
+#[must_use]
+#[derive(Debug)]
+pub struct ConfigBuilder {
+    config: Config,
+    /// 最初に失敗した検証のエラー
+    error: Option<anyhow::Error>,
+}
+
+impl ConfigBuilder {
+    /// 検証結果を記録し、最初のエラーだけを残す
+    fn check(mut self, result: Result<()>) -> Self {
+        if let (None, Err(e)) = (&self.error, result) {
+            self.error = Some(e);
         }
+        self
+    }
+
+    /// 文字列の暗号文の出力形式
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.config.default_format = format;
+        self
+    }
+
+    /// パスワードを読み取る既定の環境変数（`None` で使わない）
+    pub fn password_env(mut self, env_var: Option<&str>) -> Self {
+        self.config.default_password_env = env_var.map(str::to_string);
+        self
+    }
+
+    /// ストリーミング処理のチャンクサイズ（バイト、4KB〜16MB）
+    pub fn stream_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.config.stream_chunk_size = chunk_size;
+        self.check(validate_chunk_size(chunk_size).map_err(Into::into))
+    }
+
+    /// 自動でストリーミング処理を使う入力ファイルサイズ（このバイト数を超えるファイル）
+    pub fn streaming_threshold(mut self, bytes: u64) -> Self {
+        self.config.streaming_threshold = bytes;
+        self
+    }
+
+    /// 元ファイルのパーミッションと更新日時を記録・復元するか
+    pub fn preserve_metadata(mut self, preserve: bool) -> Self {
+        self.config.preserve_metadata = preserve;
+        self
+    }
+
+    /// 文字列の暗号化時に平文をこのバイト数の倍数までパディングする（2〜65536）
+    pub fn pad_block(mut self, block: usize) -> Self {
+        self.config.pad_block = Some(block);
+        self.check(validate_pad_block(block))
+    }
+
+    /// 暗号化したファイルに付ける拡張子（先頭の `.` は取り除く）
+    pub fn encrypted_extension(mut self, extension: &str) -> Self {
+        let extension = extension.trim_start_matches('.').to_string();
+        let result = validate_extension(&extension);
+        self.config.encrypted_extension = extension;
+        self.check(result)
+    }
+
+    /// 標準形式の暗号化ファイルの書き込み形式（`None` でバイナリ）
+    pub fn file_format(mut self, format: Option<OutputFormat>) -> Self {
+        self.config.file_format = format;
+        self
+    }
+
+    /// 自動で決めた出力ファイルを置くディレクトリ
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.default_output_dir = Some(dir.into());
+        self
+    }
+
+    /// 暗号化時に要求するパスワードの推定エントロピーの下限（ビット）
+    pub fn min_password_entropy(mut self, bits: f64) -> Self {
+        self.config.min_password_entropy = bits;
+        self.check(validate_min_password_entropy(bits))
     }
+
+    /// ストリーミング処理で端末にプログレスバーを表示するか
+    pub fn show_progress(mut self, show: bool) -> Self {
+        self.config.show_progress = show;
+        self
+    }
+
+    /// プログレスバーのテンプレート（indicatif の書式、`build` で検証）
+    pub fn progress_template(mut self, template: &str) -> Self {
+        self.config.progress_template = Some(template.to_string());
+        self
+    }
+
+    /// 暗号化時にヘッダーに記録するパスワードのヒント
+    pub fn hint(mut self, hint: &str) -> Self {
+        self.config.hint = Some(hint.to_string());
+        self.check(validate_hint(hint).map_err(Into::into))
+    }
+
+    /// 暗号化時に元の平文のSHA-256を記録するか
+    pub fn checksum(mut self, checksum: bool) -> Self {
+        self.config.checksum = checksum;
+        self
+    }
+
+    /// 標準形式のファイルの暗号化時に平文を圧縮するか
+    pub fn compress(mut self, mode: CompressMode) -> Self {
+        self.config.compress = mode;
+        self
+    }
+
+    /// 暗号化時のキー導出にランダムなソルトの代わりに使う、外部で管理しているソルト
+    pub fn salt(mut self, salt: [u8; SALT_SIZE]) -> Self {
+        self.config.salt = Some(salt);
+        self
+    }
+
+    /// Argon2に混ぜる秘密の値（ペッパー）。暗号化データには記録しない
+    pub fn pepper(mut self, pepper: Pepper) -> Self {
+        self.config.pepper = Some(pepper);
+        self
+    }
+
+    /// ヘッダーのない旧形式のデータの復号化を試みるか（`legacy-compat` フィーチャーが必要）
+    pub fn legacy_deterministic_salt(mut self, enabled: bool) -> Self {
+        self.config.legacy_deterministic_salt = enabled;
+        self
+    }
+
+    /// 暗号化時のキー導出アルゴリズム
+    pub fn kdf(mut self, kdf: KdfAlgorithm) -> Self {
+        self.config.kdf = kdf;
+        self
+    }
+
+    /// Argon2の種類
+    pub fn argon2_variant(mut self, variant: Argon2Variant) -> Self {
+        self.config.argon2.variant = variant;
+        self
+    }
+
+    /// Argon2のメモリ使用量（KB）
+    pub fn argon2_memory(mut self, memory_cost: u32) -> Self {
+        self.config.argon2.memory_cost = memory_cost;
+        self.check(check_range(
+            "Argon2のメモリ使用量",
+            memory_cost,
+            argon2::Params::MIN_M_COST,
+            argon2::Params::MAX_M_COST,
+        ))
+    }
+
+    /// Argon2の時間コスト（繰り返し回数）
+    pub fn argon2_time(mut self, time_cost: u32) -> Self {
+        self.config.argon2.time_cost = time_cost;
+        self.check(check_range(
+            "Argon2の時間コスト",
+            time_cost,
+            argon2::Params::MIN_T_COST,
+            argon2::Params::MAX_T_COST,
+        ))
+    }
+
+    /// Argon2の並列度
+    pub fn argon2_parallelism(mut self, parallelism: u32) -> Self {
+        self.config.argon2.parallelism = parallelism;
+        self.check(check_range(
+            "Argon2の並列度",
+            parallelism,
+            argon2::Params::MIN_P_COST,
+            argon2::Params::MAX_P_COST,
+        ))
+    }
+
+    /// PBKDF2の反復回数
+    pub fn pbkdf2_iterations(mut self, iterations: u32) -> Self {
+        self.config.pbkdf2.iterations = iterations;
+        self.check(validate_pbkdf2_iterations(iterations))
+    }
+
+    /// scryptのパラメータ（コストは N = 2^log_n）
+    pub fn scrypt(mut self, log_n: u8, r: u32, p: u32) -> Self {
+        self.config.scrypt = ScryptConfig { log_n, r, p };
+        self.check(
+            scrypt::Params::new(log_n, r, p, 32)
+                .map(|_| ())
+                .map_err(|e| anyhow!("scryptパラメータが不正です: {e}")),
+        )
+    }
+
+    /// 設定を検証して `Config` を作成
+    pub fn build(self) -> Result<Config> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// `value` が `min`〜`max` の範囲内か検証
+fn check_range(name: &str, value: u32, min: u32, max: u32) -> Result<()> {
+    if !(min..=max).contains(&value) {
+        bail!("{name}は{min}〜{max}で指定してください: {value}");
+    }
+    Ok(())
+}
+
+/// 設定を上書きする環境変数と、対応する設定キー
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("MYCRYPT_DEFAULT_FORMAT", "default_format"),
+    ("MYCRYPT_DEFAULT_VERBOSE", "default_verbose"),
+    ("MYCRYPT_ARGON2_MEMORY_COST", "argon2.memory_cost"),
+    ("MYCRYPT_ARGON2_TIME_COST", "argon2.time_cost"),
+    ("MYCRYPT_ARGON2_PARALLELISM", "argon2.parallelism"),
+];
+
+/// プロジェクトごとの設定ファイルの名前
+pub const PROJECT_CONFIG_FILE: &str = ".mycrypt.toml";
+
+/// 使用する設定ファイルをどこから決めたか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// `--config` で指定されたパス
+    Explicit,
+    /// カレントディレクトリかその親で見つかった `.mycrypt.toml`
+    Project,
+    /// デフォルトの設定ファイル（`~/.config/mycrypt/config.toml` など）
+    Default,
+}
+
+/// 使用する設定ファイルのパスを決定
+///
+/// `config_path` が指定されていればそのパス、なければカレントディレクトリから親へ（git と同様に）
+/// `.mycrypt.toml` を探し、見つからなければデフォルトの設定ファイルを使います。
+#[cfg(feature = "std-fs")]
+pub fn resolve_config_path(config_path: Option<&Path>) -> Result<(PathBuf, ConfigSource)> {
+    if let Some(path) = config_path {
+        return Ok((path.to_path_buf(), ConfigSource::Explicit));
+    }
+
+    let current_dir =
+        std::env::current_dir().context("カレントディレクトリの取得に失敗しました")?;
+    if let Some(path) = find_project_config(&current_dir) {
+        return Ok((path, ConfigSource::Project));
+    }
+    Ok((get_default_config_path()?, ConfigSource::Default))
+}
+
+/// `start` とその親ディレクトリから、最も近い `.mycrypt.toml` を探す
+#[cfg(feature = "std-fs")]
+pub fn find_project_config(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_FILE))
+        .find(|path| path.is_file())
 }
 
 /// 設定ファイルを読み込み
+///
+/// 優先順位は 環境変数 > 設定ファイル > デフォルト値 です。
+/// 設定ファイルは `resolve_config_path` で決めたものを使います。
+#[cfg(feature = "std-fs")]
 pub fn load_config(config_path: Option<&Path>) -> Result<Config> {
-    let path = match config_path {
-        Some(p) => p.to_path_buf(),
-        None => get_default_config_path()?,
-    };
+    let mut config = load_config_file(config_path)?;
+    config.apply_env_overrides()?;
+    Ok(config)
+}
+
+/// 環境変数による上書きを行わずに設定ファイルだけを読み込み
+///
+/// `config set` のように、読み込んだ内容をそのまま書き戻す場合に使います。
+#[cfg(feature = "std-fs")]
+pub fn load_config_file(config_path: Option<&Path>) -> Result<Config> {
+    let (path, _) = resolve_config_path(config_path)?;
 
     if !path.exists() {
         return Ok(Config::default());
@@ -73,13 +797,59 @@ pub fn load_config(config_path: Option<&Path>) -> Result<Config> {
     let content = fs::read_to_string(&path)
         .with_context(|| format!("設定ファイルの読み取りに失敗: {}", path.display()))?;
 
-    let config: Config = toml::from_str(&content)
-        .with_context(|| format!("設定ファイルの解析に失敗: {}", path.display()))?;
+    let table: toml::Table = content
+        .parse()
+        .map_err(|e| parse_error(e, &path, &content))?;
+    let mut config: Config =
+        toml::from_str(&content).map_err(|e| parse_error(e, &path, &content))?;
+    config.ignored_keys = unknown_keys(&table, "");
+
+    config
+        .validate()
+        .with_context(|| format!("設定値が不正です: {}", path.display()))?;
 
     Ok(config)
 }
 
+/// TOMLの解析エラーに、問題のある行と列を付ける（エラー自体には該当行と位置を示す `^` が含まれる）
+#[cfg(feature = "std-fs")]
+fn parse_error(error: toml::de::Error, path: &Path, content: &str) -> anyhow::Error {
+    let location = error
+        .span()
+        .and_then(|span| content.get(..span.start))
+        .map(|before| {
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let column = before[line_start..].chars().count() + 1;
+            format!("（{line}行目、{column}列目）")
+        })
+        .unwrap_or_default();
+    anyhow::Error::new(error).context(format!(
+        "設定ファイルの解析に失敗: {}{location}",
+        path.display()
+    ))
+}
+
+/// 設定ファイルの項目のうち、このバージョンが知らないもの（`argon2.xxx` のようにドット区切りで返す）
+#[cfg(feature = "std-fs")]
+fn unknown_keys(table: &toml::Table, prefix: &str) -> Vec<String> {
+    let mut unknown = Vec::new();
+    for (key, value) in table {
+        let name = format!("{prefix}{key}");
+        let section = format!("{name}.");
+        match value {
+            toml::Value::Table(table) if CONFIG_KEYS.iter().any(|k| k.starts_with(&section)) => {
+                unknown.extend(unknown_keys(table, &section));
+            }
+            _ if name == "version" || CONFIG_KEYS.contains(&name.as_str()) => {}
+            _ => unknown.push(name),
+        }
+    }
+    unknown
+}
+
 /// デフォルトの設定ファイルパスを取得
+#[cfg(feature = "std-fs")]
 pub fn get_default_config_path() -> Result<PathBuf> {
     let config_dir =
         dirs::config_dir().ok_or_else(|| anyhow!("設定ディレクトリが見つかりません"))?;
@@ -88,26 +858,32 @@ pub fn get_default_config_path() -> Result<PathBuf> {
     Ok(app_config_dir.join("config.toml"))
 }
 
-/// 設定ファイルを作成
-pub fn create_config_file(path: &Path) -> Result<()> {
+/// 設定を設定ファイルに保存（一時ファイル経由で置き換えるため、途中で失敗しても既存の設定は壊れない）
+#[cfg(feature = "std-fs")]
+pub fn save_config(config: &Config, path: &Path) -> Result<()> {
     // ディレクトリを作成
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
             .with_context(|| format!("設定ディレクトリの作成に失敗: {}", parent.display()))?;
     }
 
-    // デフォルト設定を作成
-    let config = Config::default();
     let toml_content =
-        toml::to_string_pretty(&config).context("設定ファイルの生成に失敗しました")?;
+        toml::to_string_pretty(config).context("設定ファイルの生成に失敗しました")?;
 
-    fs::write(path, toml_content)
+    write_atomic(path, toml_content.as_bytes())
         .with_context(|| format!("設定ファイルの書き込みに失敗: {}", path.display()))?;
 
     Ok(())
 }
 
+/// デフォルト設定で設定ファイルを作成
+#[cfg(feature = "std-fs")]
+pub fn create_config_file(path: &Path) -> Result<()> {
+    save_config(&Config::default(), path)
+}
+
 /// 設定ファイルを削除
+#[cfg(feature = "std-fs")]
 pub fn delete_config_file(path: &Path) -> Result<()> {
     if path.exists() {
         fs::remove_file(path)
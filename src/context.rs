@@ -0,0 +1,87 @@
+//! 1つのパスワードで多数の小さなデータを暗号化・復号化するためのコンテキスト
+//!
+//! キー導出を作成時に一度だけ行い、以降の `encrypt` / `decrypt` では導出済みのキーを使います。
+//! 出力は `encrypt_bytes` と同じ標準形式のため、`decrypt_bytes` でも復号化できます。
+//!
+//! 全データで同じキーを共有し、ナンスは呼び出しごとにランダムに生成します。
+//! ランダムなナンスの衝突を避けるため、1つのコンテキストでの暗号化は 2^32 回未満にしてください。
+
+use crate::config::Config;
+use crate::crypto::{open_with_key, seal_with_key, with_checksum};
+use crate::envelope::is_envelope;
+use crate::error::{CryptoError, Result};
+use crate::header::{has_magic, FileHeader, KdfBlock};
+use log::debug;
+use zeroize::Zeroizing;
+
+/// 導出済みのキーとキー導出ブロック（ソルト・パラメータ）を保持する暗号化コンテキスト
+///
+/// キーは破棄時にメモリ上から消去されます。
+pub struct CryptContext {
+    kdf: KdfBlock,
+    /// 暗号化するデータの先頭に付けるヘッダー（追加認証データ）
+    header: Vec<u8>,
+    /// 平文の末尾にSHA-256を付けるか
+    checksum: bool,
+    key: Zeroizing<[u8; 32]>,
+}
+
+impl CryptContext {
+    /// 設定のアルゴリズムとパラメータ、ランダムなソルトでキーを導出してコンテキストを作成
+    pub fn derive(password: &str, config: &Config) -> Result<Self> {
+        let header = FileHeader::new(config);
+        debug!(
+            "コンテキストのキー導出アルゴリズム: {}",
+            header.kdf.params.name()
+        );
+        let key = Zeroizing::new(
+            header
+                .kdf
+                .derive_key_with_pepper(password, config.pepper.as_ref())?,
+        );
+
+        Ok(Self {
+            header: header.to_bytes(),
+            checksum: header.checksum,
+            kdf: header.kdf,
+            key,
+        })
+    }
+
+    /// このコンテキストのキー導出ブロック（ソルトとパラメータ）
+    pub fn kdf_block(&self) -> &KdfBlock {
+        &self.kdf
+    }
+
+    /// バイト列を暗号化（戻り値は ヘッダー + ナンス(12) + 暗号文）
+    pub fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let data = with_checksum(data, self.checksum);
+        Ok(seal_with_key(&data, &self.key, self.header.clone(), &[])?.into_bytes())
+    }
+
+    /// このコンテキストで暗号化したデータを復号化
+    ///
+    /// 別のソルトやパラメータで暗号化されたデータは、キー導出を行わずに `InvalidInput` で拒否します。
+    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        if is_envelope(data) {
+            return Err(CryptoError::InvalidInput(
+                "エンベロープ形式のデータはコンテキストでは復号化できません".to_string(),
+            ));
+        }
+        if !has_magic(data) {
+            return Err(CryptoError::NotEncrypted);
+        }
+
+        open_with_key(data, &[], |kdf| {
+            if *kdf == self.kdf {
+                Ok(*self.key)
+            } else {
+                Err(CryptoError::InvalidInput(
+                    "このコンテキストとは異なるソルトまたはパラメータで暗号化されたデータです"
+                        .to_string(),
+                ))
+            }
+        })
+        .map(|(plaintext, _)| plaintext)
+    }
+}
@@ -1,131 +1,687 @@
-use crate::base64_encode;
-use crate::config::Config;
-use crate::key_derivation::generate_key_from_password;
+use crate::armor::{dearmor, is_armored};
+use crate::compress::decompress;
+use crate::config::{Config, OutputFormat};
+use crate::envelope::{is_envelope, open_envelope};
+use crate::error::{CryptoError, Result};
+use crate::header::{
+    EncryptedFile, EncryptedView, FORMAT_VERSION, FileHeader, FileMetadata, KdfBlock, KeyCache,
+    has_magic,
+};
+use crate::key_derivation::{KdfParams, SALT_SIZE};
+use crate::timing::{Phase, time_phase};
+use crate::{base64_decode, base64_encode, hex_decode, hex_encode};
 use aes_gcm::{
     Aes256Gcm, Nonce,
-    aead::{Aead, KeyInit},
+    aead::{Aead, KeyInit, Payload},
 };
-use anyhow::{Context, Result, anyhow};
-use base64::{Engine as _, engine::general_purpose};
+use log::{debug, info, trace};
 use rand::RngCore;
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 
-/// 文字列をAES-GCMで暗号化
-pub fn encrypt_string(
-    text: &str,
-    password: &str,
-    config: &Config,
-    verbose: bool,
-) -> Result<String> {
-    if verbose {
-        println!("=== AES-GCM 文字列暗号化開始 ===");
-        println!("元のテキスト: {text}");
-        println!("テキスト長: {} 文字", text.chars().count());
+/// 平文の末尾に付けるチェックサム（SHA-256）のサイズ（バイト）
+const CHECKSUM_SIZE: usize = 32;
+
+/// バイト列をAES-GCMで暗号化（戻り値は ヘッダー + ナンス(12) + 暗号文）
+///
+/// ヘッダーにはキー導出のアルゴリズム・パラメータ・ランダムなソルトを記録し、
+/// 追加認証データとして暗号文と一緒に認証します。
+///
+/// `config.checksum` が有効な場合は、元の平文のSHA-256を平文の末尾に付けて一緒に暗号化します。
+pub fn encrypt_bytes(data: &[u8], password: &str, config: &Config) -> Result<Vec<u8>> {
+    let header = FileHeader::new(config);
+    let sealed = seal_with_header(
+        &with_checksum(data, header.checksum),
+        &KeyCache::from_config(password, config),
+        &header,
+        &[],
+    )?;
+    Ok(EncryptedFile {
+        version: FORMAT_VERSION,
+        header,
+        nonce: sealed.nonce,
+        ciphertext: sealed.ciphertext,
     }
+    .to_bytes())
+}
+
+/// 指定したヘッダーでバイト列を暗号化（ファイルのメタデータを記録する場合などに使用）
+///
+/// キーは `keys` から取得するため、同じキー導出ブロックのヘッダーなら導出は一度で済みます。
+/// `aad` はヘッダーの後ろに続けて認証し、出力には含めません。
+pub(crate) fn encrypt_with_header(
+    data: &[u8],
+    keys: &KeyCache,
+    header: &FileHeader,
+    aad: &[u8],
+) -> Result<Vec<u8>> {
+    Ok(seal_with_header(data, keys, header, aad)?.into_bytes())
+}
+
+/// 暗号化結果の各部分（結合せずにそのまま書き出す場合に使用）
+pub(crate) struct Sealed {
+    pub(crate) header: Vec<u8>,
+    pub(crate) nonce: [u8; 12],
+    pub(crate) ciphertext: Vec<u8>,
+}
 
-    // キーを生成（Argon2使用）
-    let key = generate_key_from_password(password, config, verbose)?;
-    if verbose {
-        println!("Argon2キー生成完了 (32バイト)");
+impl Sealed {
+    /// ヘッダー + ナンス + 暗号文を結合
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        let mut result = self.header;
+        result.extend_from_slice(&self.nonce);
+        result.extend_from_slice(&self.ciphertext);
+        debug!(
+            "ヘッダー・ナンス・暗号文を結合。総データ長: {} バイト",
+            result.len()
+        );
+        result
     }
+}
+
+/// 指定したヘッダーでバイト列を暗号化し、ヘッダー・ナンス・暗号文を個別に返す
+pub(crate) fn seal_with_header(
+    data: &[u8],
+    keys: &KeyCache,
+    header: &FileHeader,
+    aad: &[u8],
+) -> Result<Sealed> {
+    debug!("キー導出アルゴリズム: {}", header.kdf.params.name());
 
+    // キーを生成
+    let key = keys.derive(&header.kdf)?;
+    debug!("キー生成完了 (32バイト)");
+
+    seal_with_key(data, &key, header.to_bytes(), aad)
+}
+
+/// 導出済みのキーでバイト列を暗号化（`header_bytes` は追加認証データとしてそのまま出力の先頭になる）
+///
+/// `aad` は `header_bytes` の後ろに続けて認証しますが、出力には含めません。
+pub(crate) fn seal_with_key(
+    data: &[u8],
+    key: &[u8; 32],
+    header_bytes: Vec<u8>,
+    aad: &[u8],
+) -> Result<Sealed> {
     // ランダムナンス生成
     let mut nonce_bytes = [0u8; 12];
     rand::rng().fill_bytes(&mut nonce_bytes);
+    debug!("ナンス生成: {}", base64_encode(&nonce_bytes));
+
+    seal_with_nonce(data, key, header_bytes, nonce_bytes, aad)
+}
+
+/// 導出済みのキーと指定したナンスでバイト列を暗号化
+fn seal_with_nonce(
+    data: &[u8],
+    key: &[u8; 32],
+    header_bytes: Vec<u8>,
+    nonce_bytes: [u8; 12],
+    aad: &[u8],
+) -> Result<Sealed> {
     let nonce = Nonce::from_slice(&nonce_bytes);
-    if verbose {
-        println!("ナンス生成: {}", base64_encode(&nonce_bytes));
-    }
 
     // AES-GCM暗号化エンジンを初期化
+    let cipher = Aes256Gcm::new(key.into());
+    debug!("AES-GCM暗号エンジン初期化完了");
+
+    // 暗号化実行
+    let ciphertext = time_phase(Phase::Cipher, || {
+        cipher.encrypt(
+            nonce,
+            Payload {
+                msg: data,
+                aad: &associated_data(&header_bytes, aad),
+            },
+        )
+    })
+    .map_err(|_| CryptoError::Encryption)?;
+    debug!("暗号化完了。データ長: {} バイト", ciphertext.len());
+
+    Ok(Sealed {
+        header: header_bytes,
+        nonce: nonce_bytes,
+        ciphertext,
+    })
+}
+
+/// ソルトとナンスを指定して `encrypt_bytes` と同じ形式で暗号化（テストベクター・他の実装との相互検証用）
+///
+/// 出力が入力だけで決まるため、暗号文のバイト列をそのまま比較できます。
+///
+/// **本番環境では絶対に使用しないでください。** AES-GCMは同じキーでナンスを再利用すると、
+/// 2つの平文の排他的論理和が漏れるうえ、認証用の鍵が復元されて暗号文を改ざんできるようになります。
+/// 通常の暗号化には、ソルトとナンスを毎回ランダムに生成する `encrypt_bytes` を使ってください。
+#[doc(hidden)]
+pub fn encrypt_with_salt_and_nonce(
+    data: &[u8],
+    password: &str,
+    config: &Config,
+    salt: &[u8; SALT_SIZE],
+    nonce: &[u8; 12],
+) -> Result<Vec<u8>> {
+    let header = FileHeader {
+        kdf: KdfBlock {
+            params: KdfParams::from_config(config),
+            salt: *salt,
+        },
+        ..FileHeader::new(config)
+    };
+    let key = header
+        .kdf
+        .derive_key_with_pepper(password, config.pepper.as_ref())?;
+    let data = with_checksum(data, header.checksum);
+    Ok(seal_with_nonce(&data, &key, header.to_bytes(), *nonce, &[])?.into_bytes())
+}
+
+/// `encrypt_bytes` の出力をAES-GCMで復号化
+///
+/// キー導出はヘッダーに記録されたアルゴリズムとパラメータで行います。
+/// 複数パスワード用のエンベロープ形式も自動で判別して復号化します。
+///
+/// マジックナンバーのないデータは、キー導出の前に `CryptoError::NotEncrypted` で拒否します。
+/// `legacy-compat` フィーチャーが有効で設定の `legacy_deterministic_salt` が `true` の場合は、
+/// ヘッダーのない旧形式として復号化を試みます。
+pub fn decrypt_bytes(data: &[u8], password: &str, config: &Config) -> Result<Vec<u8>> {
+    decrypt_with_metadata(data, &KeyCache::from_config(password, config), config)
+        .map(|(plaintext, _)| plaintext)
+}
+
+/// 暗号化時と同じ追加認証データを指定して `decrypt_bytes` と同じく復号化
+///
+/// `encrypt_to_raw_with_aad` の出力を復号化します。`aad` が暗号化時と異なる場合は認証に失敗します。
+pub fn decrypt_bytes_with_aad(
+    data: &[u8],
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Result<Vec<u8>> {
+    decrypt_with_aad(data, &KeyCache::from_config(password, config), aad, config)
+        .map(|(plaintext, _)| plaintext)
+}
+
+/// `decrypt_bytes` と同じく復号化し、ヘッダーに記録された元ファイルのメタデータも返す
+pub(crate) fn decrypt_with_metadata(
+    data: &[u8],
+    keys: &KeyCache,
+    config: &Config,
+) -> Result<(Vec<u8>, Option<FileMetadata>)> {
+    decrypt_with_aad(data, keys, &[], config)
+}
+
+/// 暗号化時と同じ追加認証データ `aad` を指定して復号化
+///
+/// エンベロープ形式と旧形式は追加認証データに対応していないため、`aad` が空でなければ拒否します。
+fn decrypt_with_aad(
+    data: &[u8],
+    keys: &KeyCache,
+    aad: &[u8],
+    config: &Config,
+) -> Result<(Vec<u8>, Option<FileMetadata>)> {
+    if is_armored(data) {
+        debug!("ASCIIアーマー形式を検出");
+        return decrypt_with_aad(&dearmor(data)?, keys, aad, config);
+    }
+
+    if !has_magic(data) && !is_envelope(data) {
+        if let Some((format, decoded)) = decode_encoded_file(data) {
+            debug!("{format:?}でエンコードされた暗号文を検出");
+            return decrypt_with_aad(&decoded, keys, aad, config);
+        }
+    }
+
+    if is_envelope(data) {
+        debug!("エンベロープ形式を検出");
+        if !aad.is_empty() {
+            return Err(CryptoError::InvalidInput(
+                "エンベロープ形式のデータには追加認証データを指定できません".to_string(),
+            ));
+        }
+        return open_envelope(data, keys.password(), config);
+    }
+
+    if !has_magic(data) {
+        #[cfg(feature = "legacy-compat")]
+        if aad.is_empty() && config.legacy_deterministic_salt {
+            debug!("ヘッダーなし（旧形式）として復号化");
+            return crate::legacy::decrypt_headerless(data, keys.password(), config)
+                .map(|plaintext| (plaintext, None));
+        }
+        return Err(CryptoError::NotEncrypted);
+    }
+
+    open_with_key(data, aad, |kdf| keys.derive(kdf))
+}
+
+/// 標準形式のデータを復号化（キーはヘッダーのキー導出ブロックから `key_for` で取得）
+///
+/// `aad` は暗号化時に指定した追加認証データで、異なる場合は認証に失敗します。
+pub(crate) fn open_with_key(
+    data: &[u8],
+    aad: &[u8],
+    key_for: impl FnOnce(&KdfBlock) -> Result<[u8; 32]>,
+) -> Result<(Vec<u8>, Option<FileMetadata>)> {
+    // ヘッダー・ナンス・暗号文を分離
+    let EncryptedView {
+        header,
+        header_bytes,
+        nonce,
+        ciphertext,
+        ..
+    } = EncryptedView::parse(data)?;
+    debug!("キー導出アルゴリズム: {}", header.kdf.params.name());
+    debug!("ナンス抽出: {}", base64_encode(&nonce));
+    debug!("暗号文長: {} バイト", ciphertext.len());
+
+    // ヘッダーのパラメータでキーを再生成
+    let key = key_for(&header.kdf)?;
     let cipher = Aes256Gcm::new(&key.into());
-    if verbose {
-        println!("AES-GCM暗号エンジン初期化完了");
+    debug!("AES-GCM復号エンジン初期化完了");
+
+    // 復号化実行
+    let plaintext = time_phase(Phase::Cipher, || {
+        cipher.decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: ciphertext,
+                aad: &associated_data(header_bytes, aad),
+            },
+        )
+    })
+    .map_err(|_| CryptoError::WrongPasswordOrCorrupt)?;
+    debug!("復号化完了。データ長: {} バイト", plaintext.len());
+
+    let plaintext = if header.padded {
+        unpad(plaintext)?
+    } else {
+        plaintext
+    };
+    let plaintext = if let Some(len) = header.compressed {
+        let decompressed = decompress(&plaintext, len)?;
+        debug!("展開: {} → {} バイト", plaintext.len(), decompressed.len());
+        decompressed
+    } else {
+        plaintext
+    };
+    let plaintext = if header.checksum {
+        verify_checksum(plaintext)?
+    } else {
+        plaintext
+    };
+
+    Ok((plaintext, header.metadata))
+}
+
+/// AES-GCMで認証する追加認証データ（ヘッダーの後ろに呼び出し側の `aad` を続ける）
+fn associated_data<'a>(header_bytes: &'a [u8], aad: &[u8]) -> Cow<'a, [u8]> {
+    if aad.is_empty() {
+        return Cow::Borrowed(header_bytes);
     }
+    Cow::Owned([header_bytes, aad].concat())
+}
 
-    // 暗号化実行
-    let ciphertext = cipher
-        .encrypt(nonce, text.as_bytes())
-        .map_err(|e| anyhow!("暗号化に失敗: {e}"))?;
-    if verbose {
-        println!("暗号化完了。データ長: {} バイト", ciphertext.len());
+/// `checksum` が有効な場合、平文の末尾に元の平文のSHA-256を付ける
+pub(crate) fn with_checksum(data: &[u8], checksum: bool) -> Cow<'_, [u8]> {
+    if !checksum {
+        return Cow::Borrowed(data);
     }
+    let mut payload = Vec::with_capacity(data.len() + CHECKSUM_SIZE);
+    payload.extend_from_slice(data);
+    payload.extend_from_slice(&Sha256::digest(data));
+    Cow::Owned(payload)
+}
 
-    // ナンス + 暗号文を結合
-    let mut result = nonce_bytes.to_vec();
-    result.extend_from_slice(&ciphertext);
-    if verbose {
-        println!("ナンスと暗号文を結合。総データ長: {} バイト", result.len());
+/// 末尾のSHA-256を取り除き、残りの平文から計算し直した値と一致するか検証
+fn verify_checksum(mut plaintext: Vec<u8>) -> Result<Vec<u8>> {
+    let Some(len) = plaintext.len().checked_sub(CHECKSUM_SIZE) else {
+        return Err(CryptoError::InvalidFormat(
+            "平文のチェックサムが途中で切れています".to_string(),
+        ));
+    };
+    let (data, expected) = plaintext.split_at(len);
+    if Sha256::digest(data).as_slice() != expected {
+        return Err(CryptoError::ChecksumMismatch);
     }
+    debug!("平文のチェックサム検証完了");
 
-    // Base64エンコードして返す
-    let encoded = base64_encode(&result);
-    if verbose {
-        println!("Base64エンコード完了");
-        println!("=== AES-GCM 文字列暗号化完了 ===");
+    plaintext.truncate(len);
+    Ok(plaintext)
+}
+
+/// 0x80 と 0x00 の並びで `block` の倍数の長さにパディング（ISO/IEC 7816-4 方式）
+///
+/// PKCS#7と同じく常に1バイト以上追加するため、ちょうどブロック境界の平文も1ブロック伸びます。
+/// PKCS#7と違い、255バイトを超えるブロックサイズにも対応します。
+fn pad(data: &[u8], block: usize) -> Result<Vec<u8>> {
+    if block == 0 {
+        return Err(CryptoError::InvalidInput(
+            "パディングのブロックサイズは1以上が必要です".to_string(),
+        ));
+    }
+
+    let padded_len = (data.len() / block + 1) * block;
+    let mut padded = Vec::with_capacity(padded_len);
+    padded.extend_from_slice(data);
+    padded.push(0x80);
+    padded.resize(padded_len, 0);
+    Ok(padded)
+}
+
+/// `pad` で付けたパディングを取り除く
+fn unpad(mut data: Vec<u8>) -> Result<Vec<u8>> {
+    let marker = data.iter().rposition(|&b| b != 0);
+    match marker {
+        Some(index) if data[index] == 0x80 => {
+            data.truncate(index);
+            Ok(data)
+        }
+        _ => Err(CryptoError::InvalidFormat(
+            "パディングが不正です".to_string(),
+        )),
     }
+}
+
+/// 文字列をAES-GCMで暗号化
+///
+/// `config.pad_block` が指定されている場合は、暗号文から平文の長さがわからないよう
+/// 平文をブロックサイズの倍数までパディングしてから暗号化します。
+pub fn encrypt_string(text: &str, password: &str, config: &Config) -> Result<String> {
+    trace!("元のテキスト: {text}");
+    debug!("テキスト長: {} 文字", text.chars().count());
+
+    encrypt_to_text(text.as_bytes(), password, config)
+}
+
+/// 追加認証データ（AAD）を指定して文字列を暗号化
+///
+/// `aad` は暗号文と一緒に認証されますが、出力には含まれません。復号化する側は
+/// `decrypt_string_with_aad` に同じ値を渡す必要があり、異なる場合は認証に失敗します。
+/// ユーザーIDやAPIのバージョンなど、暗号文を特定の文脈に結び付ける場合に使います。
+/// 空の `aad` は指定しない場合（`encrypt_string`）と同じです。
+pub fn encrypt_string_with_aad(
+    text: &str,
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Result<String> {
+    trace!("元のテキスト: {text}");
+    debug!("テキスト長: {} 文字", text.chars().count());
+
+    encrypt_to_text_with_aad(text.as_bytes(), password, aad, config)
+}
+
+/// 任意のバイト列を `encrypt_string` と同じ形式（Base64 / 16進数の文字列）に暗号化
+///
+/// UTF-8でないバイナリデータを文字列の暗号文として扱う場合に使い、`decrypt_from_text` で復号化します。
+pub fn encrypt_to_text(data: &[u8], password: &str, config: &Config) -> Result<String> {
+    encrypt_to_text_with_aad(data, password, &[], config)
+}
+
+/// 追加認証データを指定して `encrypt_to_text` と同じ形式に暗号化（`decrypt_from_text_with_aad` で復号化）
+pub fn encrypt_to_text_with_aad(
+    data: &[u8],
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Result<String> {
+    info!("=== AES-GCM 文字列暗号化開始 ===");
+    let result = seal_text(data, password, aad, config)?;
+
+    // 設定の出力形式でエンコードして返す
+    let encoded = encode_text(&result, &config.default_format);
+    debug!("{:?}エンコード完了", config.default_format);
+    info!("=== AES-GCM 文字列暗号化完了 ===");
 
     Ok(encoded)
 }
 
+/// `encrypt_to_text` と同じく暗号化し、Base64 / 16進数にエンコードせずバイナリのまま返す
+///
+/// 出力は標準形式のファイルと同じ形式のため、ファイルに保存すれば `decrypt_file_standard` で
+/// 復号化できます。エンコードしない分、文字列の暗号文より約25%小さくなります。
+pub fn encrypt_to_raw(data: &[u8], password: &str, config: &Config) -> Result<Vec<u8>> {
+    encrypt_to_raw_with_aad(data, password, &[], config)
+}
+
+/// 追加認証データを指定して `encrypt_to_raw` と同じ形式に暗号化（`decrypt_bytes_with_aad` で復号化）
+pub fn encrypt_to_raw_with_aad(
+    data: &[u8],
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Result<Vec<u8>> {
+    info!("=== AES-GCM バイナリ暗号化開始 ===");
+    let result = seal_text(data, password, aad, config)?;
+    info!("=== AES-GCM バイナリ暗号化完了 ===");
+    Ok(result)
+}
+
+/// 文字列の暗号化と同じヘッダー・パディング・チェックサムで暗号化（エンコード前のバイト列）
+fn seal_text(data: &[u8], password: &str, aad: &[u8], config: &Config) -> Result<Vec<u8>> {
+    debug!("データ長: {} バイト", data.len());
+    debug!("追加認証データ長: {} バイト", aad.len());
+
+    let header = FileHeader {
+        padded: config.pad_block.is_some(),
+        ..FileHeader::new(config)
+    };
+    let data = with_checksum(data, header.checksum);
+    let keys = KeyCache::from_config(password, config);
+    match config.pad_block {
+        Some(block) => {
+            let padded = pad(&data, block)?;
+            debug!("パディング: {} → {} バイト", data.len(), padded.len());
+            encrypt_with_header(&padded, &keys, &header, aad)
+        }
+        None => encrypt_with_header(&data, &keys, &header, aad),
+    }
+}
+
 /// 文字列をAES-GCMで復号化
-pub fn decrypt_string(
+pub fn decrypt_string(encrypted_text: &str, password: &str, config: &Config) -> Result<String> {
+    decrypt_string_with_aad(encrypted_text, password, &[], config)
+}
+
+/// `encrypt_string_with_aad` で暗号化した文字列を、同じ追加認証データを指定して復号化
+///
+/// `aad` が暗号化時と異なる場合は、パスワードの誤りと同じく `WrongPasswordOrCorrupt` になります。
+pub fn decrypt_string_with_aad(
     encrypted_text: &str,
     password: &str,
+    aad: &[u8],
     config: &Config,
-    verbose: bool,
 ) -> Result<String> {
-    if verbose {
-        println!("=== AES-GCM 文字列復号化開始 ===");
-        println!("暗号文長: {} 文字", encrypted_text.len());
-    }
+    let plaintext = decrypt_from_text_with_aad(encrypted_text, password, aad, config)?;
 
-    // Base64デコード
-    let data = general_purpose::STANDARD
-        .decode(encrypted_text)
-        .context("Base64デコードに失敗しました")?;
-    if verbose {
-        println!("Base64デコード完了。データ長: {} バイト", data.len());
-    }
+    // UTF-8文字列に変換
+    let result = String::from_utf8(plaintext)?;
+    debug!("文字列変換完了: {} 文字", result.chars().count());
 
-    if data.len() < 12 {
-        return Err(anyhow!("データが短すぎます（最低12バイトのナンスが必要）"));
-    }
+    Ok(result)
+}
 
-    // ナンスと暗号文を分離
-    let (nonce_bytes, ciphertext) = data.split_at(12);
-    let nonce = Nonce::from_slice(nonce_bytes);
-    if verbose {
-        println!("ナンス抽出: {}", base64_encode(nonce_bytes));
-        println!("暗号文長: {} バイト", ciphertext.len());
+/// 文字列の暗号文を復号化し、UTF-8に変換せずにバイト列のまま返す
+///
+/// `encrypt_to_text` で暗号化したバイナリデータや、`decrypt_string` がUTF-8変換に失敗する
+/// データを取り出す場合に使います。
+pub fn decrypt_from_text(encrypted_text: &str, password: &str, config: &Config) -> Result<Vec<u8>> {
+    decrypt_from_text_with_aad(encrypted_text, password, &[], config)
+}
+
+/// 追加認証データを指定して `decrypt_from_text` と同じく復号化
+pub fn decrypt_from_text_with_aad(
+    encrypted_text: &str,
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Result<Vec<u8>> {
+    decrypt_text_with_keys(
+        encrypted_text,
+        &KeyCache::from_config(password, config),
+        aad,
+        config,
+    )
+}
+
+/// 空白・改行で区切られた複数の暗号文をまとめて復号化（各暗号文は `decrypt_from_text_with_aad` と同じ形式）
+///
+/// 導出したキーは全体で共有するため、同じソルトとパラメータで暗号化された暗号文
+/// （`Config::salt` を指定して暗号化したものなど）のキー導出は一度で済みます。
+/// 結果は暗号文ごとに (行番号（1始まり）, 復号結果) の組で返し、途中で失敗しても残りの復号化を続けます。
+pub fn decrypt_text_lines(
+    text: &str,
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+) -> Vec<(usize, Result<Vec<u8>>)> {
+    let keys = KeyCache::from_config(password, config);
+    text.lines()
+        .enumerate()
+        .flat_map(|(index, line)| line.split_whitespace().map(move |item| (index + 1, item)))
+        .map(|(line, item)| (line, decrypt_text_with_keys(item, &keys, aad, config)))
+        .collect()
+}
+
+fn decrypt_text_with_keys(
+    encrypted_text: &str,
+    keys: &KeyCache,
+    aad: &[u8],
+    config: &Config,
+) -> Result<Vec<u8>> {
+    info!("=== AES-GCM 文字列復号化開始 ===");
+    debug!("暗号文長: {} 文字", encrypted_text.len());
+
+    // 形式を推定してデコード（16進数に見える場合は Base64 も候補に残す）
+    let primary = detect_encoding(encrypted_text);
+    let mut candidates = vec![decode_text(encrypted_text, &primary)?];
+    if primary == OutputFormat::Hex {
+        if let Ok(data) = decode_text(encrypted_text, &OutputFormat::Base64) {
+            candidates.push(data);
+        }
     }
+    debug!(
+        "{primary:?}デコード完了。データ長: {} バイト",
+        candidates[0].len()
+    );
 
-    // キーを再生成（Argon2使用）
-    let key = generate_key_from_password(password, config, verbose)?;
-    if verbose {
-        println!("Argon2キー再生成完了");
+    // 候補を順に復号化し、最初に認証に成功したものを採用
+    let mut errors = Vec::new();
+    let mut plaintext = None;
+    for data in &candidates {
+        match decrypt_with_aad(data, keys, aad, config) {
+            Ok((decrypted, _)) => {
+                plaintext = Some(decrypted);
+                break;
+            }
+            Err(e) => errors.push(e),
+        }
     }
 
-    // AES-GCM復号化エンジンを初期化
-    let cipher = Aes256Gcm::new(&key.into());
-    if verbose {
-        println!("AES-GCM復号エンジン初期化完了");
+    // すべて失敗した場合は、認証まで進んだ候補のエラーを優先して返す
+    let plaintext = match plaintext {
+        Some(plaintext) => plaintext,
+        None => {
+            let index = errors
+                .iter()
+                .position(|e| matches!(e, CryptoError::WrongPasswordOrCorrupt))
+                .unwrap_or(0);
+            return Err(errors.swap_remove(index));
+        }
+    };
+    debug!("復号化完了。データ長: {} バイト", plaintext.len());
+    info!("=== AES-GCM 文字列復号化完了 ===");
+
+    Ok(plaintext)
+}
+
+/// 暗号化データのヘッダーに記録されたパスワードのヒント（アーマー形式にも対応）
+///
+/// ヒントは暗号化せずに記録されているため、パスワードなしで読み取れます。
+/// 標準形式以外のデータや、ヒントのないデータでは `None` を返します。
+pub fn password_hint(data: &[u8]) -> Result<Option<String>> {
+    Ok(standard_header(data)?.and_then(|header| header.hint))
+}
+
+/// 標準形式のヘッダー（アーマー形式・エンコードされたファイルにも対応し、それ以外の形式では `None`）
+pub(crate) fn standard_header(data: &[u8]) -> Result<Option<FileHeader>> {
+    if is_armored(data) {
+        return standard_header(&dearmor(data)?);
+    }
+    if !has_magic(data) {
+        return match decode_encoded_file(data) {
+            Some((_, decoded)) if has_magic(&decoded) => standard_header(&decoded),
+            _ => Ok(None),
+        };
     }
+    Ok(Some(FileHeader::parse(data)?.0))
+}
 
-    // 復号化実行
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|e| anyhow!("復号化に失敗: {e}"))?;
-    if verbose {
-        println!("復号化完了。データ長: {} バイト", plaintext.len());
+/// 暗号化された文字列に記録されたパスワードのヒント
+///
+/// `decrypt_string` と同じく、Base64・16進数のどちらの表現でも読み取ります。
+pub fn string_password_hint(encrypted_text: &str) -> Option<String> {
+    [OutputFormat::Hex, OutputFormat::Base64]
+        .iter()
+        .filter_map(|format| decode_text(encrypted_text, format).ok())
+        .find_map(|data| password_hint(&data).ok().flatten())
+}
+
+/// 暗号文の文字列表現を推定
+///
+/// 偶数長で16進数字のみなら Hex、それ以外は Base64 とみなします。
+pub fn detect_encoding(text: &str) -> OutputFormat {
+    if !text.is_empty()
+        && text.len().is_multiple_of(2)
+        && text.bytes().all(|b| b.is_ascii_hexdigit())
+    {
+        OutputFormat::Hex
+    } else {
+        OutputFormat::Base64
     }
+}
 
-    // UTF-8文字列に変換
-    let result = String::from_utf8(plaintext).context("UTF-8変換に失敗しました")?;
+/// 指定した形式で暗号文をデコード
+fn decode_text(text: &str, format: &OutputFormat) -> Result<Vec<u8>> {
+    match format {
+        OutputFormat::Base64 => base64_decode(text),
+        OutputFormat::Hex => hex_decode(text),
+    }
+}
 
-    if verbose {
-        println!("文字列変換完了: {} 文字", result.chars().count());
-        println!("=== AES-GCM 文字列復号化完了 ===");
+/// 暗号文を指定した形式の文字列にエンコード
+pub(crate) fn encode_text(data: &[u8], format: &OutputFormat) -> String {
+    match format {
+        OutputFormat::Base64 => base64_encode(data),
+        OutputFormat::Hex => hex_encode(data),
     }
+}
 
-    Ok(result)
+/// 設定の `file_format` でBase64 / 16進数の文字列として書き込まれた暗号化ファイルをデコード
+///
+/// 前後の空白（末尾の改行）は無視し、エンコードの形式とデコードした結果を返します。
+/// 文字列として読めない場合や、デコードした結果が標準形式・エンベロープ形式で始まらない
+/// 場合は `None` を返します。
+pub(crate) fn decode_encoded_file(data: &[u8]) -> Option<(OutputFormat, Vec<u8>)> {
+    let text = std::str::from_utf8(data.trim_ascii()).ok()?;
+    [OutputFormat::Hex, OutputFormat::Base64]
+        .into_iter()
+        .filter_map(|format| decode_text(text, &format).ok().map(|data| (format, data)))
+        .find(|(_, decoded)| has_magic(decoded) || is_envelope(decoded))
+}
+
+/// ファイルの先頭部分が `file_format` でエンコードされた暗号化ファイルのものか
+///
+/// 先頭部分だけでも判定できるよう、16進数は2文字、Base64は4文字単位に切り詰めてデコードします。
+#[cfg(feature = "std-fs")]
+pub(crate) fn is_encoded_file(head: &[u8]) -> bool {
+    let head = head.trim_ascii();
+    [(OutputFormat::Hex, 2), (OutputFormat::Base64, 4)]
+        .iter()
+        .any(|(format, unit)| {
+            std::str::from_utf8(&head[..head.len() / unit * unit])
+                .ok()
+                .and_then(|text| decode_text(text, format).ok())
+                .is_some_and(|decoded| has_magic(&decoded) || is_envelope(&decoded))
+        })
 }
@@ -0,0 +1,305 @@
+//! 複数のパスワードで復号化できるエンベロープ形式
+//!
+//! ランダムなコンテンツキーでデータを暗号化し、そのコンテンツキーを
+//! パスワードごとに導出したキーで包んだ「スロット」をヘッダーに並べます。
+//! キー導出のアルゴリズムとパラメータはヘッダーに1つだけ記録し、全スロットで共有します。
+//!
+//! 形式: マジックナンバー(11) + キー導出パラメータ + メタデータブロック + スロット数(1) + スロット × N + ナンス(12) + 暗号文
+//! スロット: ソルト(16) + ナンス(12) + 暗号化されたコンテンツキー(32 + タグ16)
+//!
+//! ヘッダー全体（マジックナンバーから最後のスロットまで）を暗号文の追加認証データにします。
+
+use crate::config::Config;
+use crate::error::{CryptoError, Result};
+use crate::header::FileMetadata;
+use crate::key_derivation::{derive_key_with_pepper, KdfParams, Pepper};
+use crate::timing::{time_phase, Phase};
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use log::debug;
+use rand::RngCore;
+
+/// エンベロープ形式のマジックナンバー
+pub(crate) const ENVELOPE_MAGIC: &[u8; 11] = b"GCMENVELOPE";
+
+/// スロットのソルトのサイズ（バイト）
+const SLOT_SALT_SIZE: usize = 16;
+
+/// 1スロットのサイズ: ソルト(16) + ナンス(12) + コンテンツキー(32) + タグ(16)
+const SLOT_SIZE: usize = SLOT_SALT_SIZE + 12 + 32 + 16;
+
+/// スロット数の上限
+pub const MAX_SLOTS: usize = u8::MAX as usize;
+
+/// データがエンベロープ形式かどうか
+pub fn is_envelope(data: &[u8]) -> bool {
+    data.starts_with(ENVELOPE_MAGIC)
+}
+
+/// 1行に1つずつパスワードを書いたテキストからパスワードを取り出す
+///
+/// 空行と `#` で始まる行は無視します。行末の改行（`\r\n` を含む）以外はそのままパスワードになります。
+pub fn parse_recipients(text: &str) -> Vec<String> {
+    text.lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// 複数のパスワードのいずれでも復号化できるようにデータを暗号化
+pub fn encrypt_envelope(data: &[u8], passwords: &[&str], config: &Config) -> Result<Vec<u8>> {
+    encrypt_envelope_with_metadata(data, passwords, None, config)
+}
+
+/// 元ファイルのメタデータをヘッダーに記録してエンベロープ形式で暗号化
+pub(crate) fn encrypt_envelope_with_metadata(
+    data: &[u8],
+    passwords: &[&str],
+    metadata: Option<&FileMetadata>,
+    config: &Config,
+) -> Result<Vec<u8>> {
+    if passwords.is_empty() || passwords.len() > MAX_SLOTS {
+        return Err(CryptoError::InvalidInput(format!(
+            "パスワードは1〜{MAX_SLOTS}個で指定してください: {}",
+            passwords.len()
+        )));
+    }
+    if config.hint.is_some() {
+        return Err(CryptoError::InvalidInput(
+            "複数のパスワードではパスワードのヒントを記録できません".to_string(),
+        ));
+    }
+    if config.checksum {
+        return Err(CryptoError::InvalidInput(
+            "複数のパスワードでは平文のチェックサムを記録できません".to_string(),
+        ));
+    }
+
+    let mut content_key = [0u8; 32];
+    rand::rng().fill_bytes(&mut content_key);
+
+    let params = KdfParams::from_config(config);
+    let slots = passwords
+        .iter()
+        .map(|password| wrap_content_key(&content_key, password, &params, config.pepper.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+
+    seal(&content_key, &params, metadata, &slots, data)
+}
+
+/// エンベロープ形式のデータを、いずれかのスロットに対応するパスワードで復号化
+///
+/// キー導出のパラメータはヘッダーから読み取るため、設定の値は使いません。
+pub fn decrypt_envelope(data: &[u8], password: &str, config: &Config) -> Result<Vec<u8>> {
+    open_envelope(data, password, config).map(|(plaintext, _)| plaintext)
+}
+
+/// `decrypt_envelope` と同じく復号化し、ヘッダーに記録された元ファイルのメタデータも返す
+pub(crate) fn open_envelope(
+    data: &[u8],
+    password: &str,
+    config: &Config,
+) -> Result<(Vec<u8>, Option<FileMetadata>)> {
+    let envelope = Envelope::parse(data)?;
+    let (_, content_key) = envelope.unlock(password, config.pepper.as_ref())?;
+    let plaintext = envelope.open(&content_key)?;
+    Ok((plaintext, envelope.metadata))
+}
+
+/// `old_password` のスロットだけを `new_password` で包み直す（他のスロットはそのまま）
+pub fn rewrap_envelope(
+    data: &[u8],
+    old_password: &str,
+    new_password: &str,
+    config: &Config,
+) -> Result<Vec<u8>> {
+    let envelope = Envelope::parse(data)?;
+    let (index, content_key) = envelope.unlock(old_password, config.pepper.as_ref())?;
+    let plaintext = envelope.open(&content_key)?;
+
+    // 他のスロットと同じく、エンベロープに記録されたパラメータで包む
+    let mut slots: Vec<[u8; SLOT_SIZE]> = envelope.slots.to_vec();
+    slots[index] = wrap_content_key(
+        &content_key,
+        new_password,
+        &envelope.params,
+        config.pepper.as_ref(),
+    )?;
+
+    // ヘッダーが変わるため、同じコンテンツキー・新しいナンスで暗号化し直す
+    seal(
+        &content_key,
+        &envelope.params,
+        envelope.metadata.as_ref(),
+        &slots,
+        &plaintext,
+    )
+}
+
+/// エンベロープ形式のヘッダー（キー導出パラメータ、メタデータ、スロット数、ヘッダーのバイト数）
+///
+/// パスワードは不要で、キー導出も行いません。
+#[cfg(feature = "std-fs")]
+pub(crate) fn envelope_header(
+    data: &[u8],
+) -> Result<(KdfParams, Option<FileMetadata>, usize, usize)> {
+    let envelope = Envelope::parse(data)?;
+    Ok((
+        envelope.params,
+        envelope.metadata,
+        envelope.slots.len(),
+        envelope.header.len(),
+    ))
+}
+
+/// 解析済みのエンベロープ
+struct Envelope<'a> {
+    header: &'a [u8],
+    params: KdfParams,
+    metadata: Option<FileMetadata>,
+    slots: Vec<[u8; SLOT_SIZE]>,
+    nonce: &'a [u8],
+    ciphertext: &'a [u8],
+}
+
+impl<'a> Envelope<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self> {
+        if !is_envelope(data) {
+            return Err(CryptoError::InvalidFormat(
+                "エンベロープ形式ではありません".to_string(),
+            ));
+        }
+
+        let mut input = &data[ENVELOPE_MAGIC.len()..];
+        let params = KdfParams::read_from(&mut input)?;
+        let metadata = FileMetadata::read_from(&mut input)?;
+        let count_offset = data.len() - input.len();
+        let slot_count = *data.get(count_offset).ok_or_else(truncated)? as usize;
+        if slot_count == 0 {
+            return Err(CryptoError::InvalidFormat(
+                "エンベロープのスロット数が不正です".to_string(),
+            ));
+        }
+
+        let header_len = count_offset + 1 + slot_count * SLOT_SIZE;
+        if data.len() < header_len + 12 {
+            return Err(truncated());
+        }
+
+        let (header, body) = data.split_at(header_len);
+        let slots = header[count_offset + 1..]
+            .chunks_exact(SLOT_SIZE)
+            .map(|slot| slot.try_into().expect("チャンクはスロットサイズ"))
+            .collect();
+        let (nonce, ciphertext) = body.split_at(12);
+
+        Ok(Self {
+            header,
+            params,
+            metadata,
+            slots,
+            nonce,
+            ciphertext,
+        })
+    }
+
+    /// パスワードで開けるスロットを探し、その番号とコンテンツキーを返す
+    fn unlock(&self, password: &str, pepper: Option<&Pepper>) -> Result<(usize, [u8; 32])> {
+        for (index, slot) in self.slots.iter().enumerate() {
+            let (salt, rest) = slot.split_at(SLOT_SALT_SIZE);
+            let (nonce, wrapped) = rest.split_at(12);
+
+            let kek = derive_key_with_pepper(password, salt, &self.params, pepper)?;
+            let cipher = Aes256Gcm::new(&kek.into());
+            if let Ok(key) = cipher.decrypt(Nonce::from_slice(nonce), wrapped) {
+                debug!("スロット {index} でコンテンツキーを取得");
+                let key = key.try_into().map_err(|_| {
+                    CryptoError::InvalidFormat("コンテンツキーの長さが不正です".to_string())
+                })?;
+                return Ok((index, key));
+            }
+        }
+
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    }
+
+    fn open(&self, content_key: &[u8; 32]) -> Result<Vec<u8>> {
+        let cipher = Aes256Gcm::new(content_key.into());
+        time_phase(Phase::Cipher, || {
+            cipher.decrypt(
+                Nonce::from_slice(self.nonce),
+                Payload {
+                    msg: self.ciphertext,
+                    aad: self.header,
+                },
+            )
+        })
+        .map_err(|_| CryptoError::WrongPasswordOrCorrupt)
+    }
+}
+
+fn truncated() -> CryptoError {
+    CryptoError::Truncated("エンベロープのヘッダーが途中で切れています".to_string())
+}
+
+/// コンテンツキーをパスワードから導出したキーで包んだスロットを作成
+fn wrap_content_key(
+    content_key: &[u8; 32],
+    password: &str,
+    params: &KdfParams,
+    pepper: Option<&Pepper>,
+) -> Result<[u8; SLOT_SIZE]> {
+    let mut salt = [0u8; SLOT_SALT_SIZE];
+    rand::rng().fill_bytes(&mut salt);
+    let mut nonce = [0u8; 12];
+    rand::rng().fill_bytes(&mut nonce);
+
+    let kek = derive_key_with_pepper(password, &salt, params, pepper)?;
+    let cipher = Aes256Gcm::new(&kek.into());
+    let wrapped = cipher
+        .encrypt(Nonce::from_slice(&nonce), content_key.as_slice())
+        .map_err(|_| CryptoError::Encryption)?;
+
+    let mut slot = [0u8; SLOT_SIZE];
+    slot[..SLOT_SALT_SIZE].copy_from_slice(&salt);
+    slot[SLOT_SALT_SIZE..SLOT_SALT_SIZE + 12].copy_from_slice(&nonce);
+    slot[SLOT_SALT_SIZE + 12..].copy_from_slice(&wrapped);
+    Ok(slot)
+}
+
+/// ヘッダーを組み立て、コンテンツキーでデータを暗号化
+fn seal(
+    content_key: &[u8; 32],
+    params: &KdfParams,
+    metadata: Option<&FileMetadata>,
+    slots: &[[u8; SLOT_SIZE]],
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let mut output = ENVELOPE_MAGIC.to_vec();
+    params.write_to(&mut output);
+    FileMetadata::write_to(metadata, &mut output);
+    output.push(slots.len() as u8);
+    for slot in slots {
+        output.extend_from_slice(slot);
+    }
+
+    let mut nonce = [0u8; 12];
+    rand::rng().fill_bytes(&mut nonce);
+    let cipher = Aes256Gcm::new(content_key.into());
+    let ciphertext = time_phase(Phase::Cipher, || {
+        cipher.encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: data,
+                aad: &output,
+            },
+        )
+    })
+    .map_err(|_| CryptoError::Encryption)?;
+
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
@@ -0,0 +1,98 @@
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// ライブラリの処理結果
+pub type Result<T, E = CryptoError> = std::result::Result<T, E>;
+
+/// 暗号化・復号化処理のエラー
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    /// パスワードが間違っているか、データが改ざん・破損している
+    #[error("復号化に失敗しました（パスワードが間違っているか、データが破損しています）")]
+    WrongPasswordOrCorrupt,
+
+    /// このツールで暗号化されたデータではない（マジックナンバーがない）
+    #[error("このファイルは mycrypt で暗号化されていません")]
+    NotEncrypted,
+
+    /// 暗号化処理そのものに失敗した
+    #[error("暗号化に失敗しました")]
+    Encryption,
+
+    /// 入力データの形式が不正
+    #[error("{0}")]
+    InvalidFormat(String),
+
+    /// データが途中で切れている
+    #[error("{0}")]
+    Truncated(String),
+
+    /// 引数やパラメータが不正
+    #[error("{0}")]
+    InvalidInput(String),
+
+    /// ペッパーを使って暗号化されたデータだが、ペッパーが指定されていない
+    #[error("このデータはペッパーを使って暗号化されています（復号化にはペッパーが必要です）")]
+    PepperRequired,
+
+    /// キー導出に失敗した
+    #[error("{0}")]
+    KeyDerivation(String),
+
+    /// ファイル入出力エラー
+    #[error("{context}: {source}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+
+    /// 復号化した平文が、暗号化時に記録したSHA-256と一致しない
+    #[error("平文のチェックサムが一致しません（復号化結果が元のデータと異なります）")]
+    ChecksumMismatch,
+
+    /// 暗号化したファイルを復号化した結果が、元のファイルの内容と一致しない
+    #[error(
+        "暗号化したファイルを検証できませんでした（復号化した結果が元のファイルと一致しません）"
+    )]
+    VerifyMismatch,
+
+    /// 出力先のファイルを他のプロセスが処理中（`with_output_lock` のロックを取得できない）
+    #[error("他のプロセスがこのファイルを処理中です: {}", .0.display())]
+    Locked(PathBuf),
+
+    /// 復号化結果がUTF-8文字列ではない
+    #[error("UTF-8変換に失敗しました")]
+    Utf8(#[from] std::string::FromUtf8Error),
+}
+
+/// `io::Result` にエラーの文脈を付与する
+#[cfg(feature = "std-fs")]
+pub(crate) trait IoResultExt<T> {
+    fn io_context(self, context: &str) -> Result<T>;
+
+    fn with_io_context<F>(self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> String;
+}
+
+#[cfg(feature = "std-fs")]
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn io_context(self, context: &str) -> Result<T> {
+        self.map_err(|source| CryptoError::Io {
+            context: context.to_string(),
+            source,
+        })
+    }
+
+    fn with_io_context<F>(self, f: F) -> Result<T>
+    where
+        F: FnOnce() -> String,
+    {
+        self.map_err(|source| CryptoError::Io {
+            context: f(),
+            source,
+        })
+    }
+}
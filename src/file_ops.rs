@@ -1,52 +1,334 @@
-use crate::base64_encode;
-use crate::config::Config;
-use crate::key_derivation::generate_key_from_password;
+use crate::armor::{ARMOR_BEGIN, armor, is_armored};
+use crate::compress::{CompressMode, compress, compress_with_mode};
+use crate::config::{Config, DEFAULT_PROGRESS_TEMPLATE, validate_chunk_size};
+use crate::crypto::{
+    decode_encoded_file, decrypt_with_metadata, encode_text, encrypt_with_header, is_encoded_file,
+    password_hint, seal_with_header, standard_header, with_checksum,
+};
+use crate::envelope::{
+    ENVELOPE_MAGIC, encrypt_envelope_with_metadata, is_envelope, rewrap_envelope,
+};
+use crate::error::{CryptoError, IoResultExt, Result};
+use crate::header::{
+    FileHeader, FileMetadata, KdfBlock, KeyCache, MAX_PATH_LEN, METADATA_SIZE, has_magic,
+};
+use crate::key_derivation::{KdfParams, Pepper, SALT_SIZE, derive_subkey};
+use crate::timing::{Phase, Timed, time_phase};
+use crate::volume::{
+    VOLUME_MAGIC, VolumeReader, VolumeWriter, is_volume_file, volume_base_path, volume_path,
+};
 use aes_gcm::{
     Aes256Gcm, Nonce,
     aead::{Aead, KeyInit},
 };
-use anyhow::{Context, Result, anyhow};
+use hmac::{Hmac, Mac};
 use indicatif::{ProgressBar, ProgressStyle};
+use log::{debug, info};
 use rand::RngCore;
+use sha2::Sha256;
 use std::{
-    fs::{self, File},
-    io::{BufReader, BufWriter, Read, Write},
-    path::{Path, PathBuf},
+    collections::HashSet,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::{self, BufReader, BufWriter, Read, Write},
+    ops::Deref,
+    path::{Component, Path, PathBuf},
 };
 
+/// AES-GCM認証タグのサイズ（バイト）
+pub(crate) const GCM_TAG_SIZE: usize = 16;
+
+/// ストリーミング形式のマジックナンバー
+pub(crate) const STREAM_MAGIC: &[u8; 9] = b"GCMSTREAM";
+
+/// ストリーミング形式のフッターの目印（チャンクのナンスと同じ12バイト）
+pub(crate) const STREAM_FOOTER_MARKER: &[u8; 12] = b"GCMSTREAMEND";
+
+/// フッターのHMAC-SHA256のサイズ（バイト）
+pub(crate) const STREAM_MAC_SIZE: usize = 32;
+
+/// フッターのHMAC用サブキーのラベル
+const STREAM_MAC_LABEL: &[u8] = b"mycrypt stream footer mac";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// ファイル全体の整合性検証用のHMACを初期化
+fn stream_mac(key: &[u8; 32]) -> HmacSha256 {
+    <HmacSha256 as Mac>::new_from_slice(&derive_subkey(key, STREAM_MAC_LABEL))
+        .expect("HMACは任意長のキーを受け付ける")
+}
+
+/// 読み込み途中でファイルが終わった場合は `Truncated` として扱う
+fn read_exact_or_truncated<R: Read>(reader: &mut R, buf: &mut [u8], context: &str) -> Result<()> {
+    reader.read_exact(buf).map_err(|source| {
+        if source.kind() == io::ErrorKind::UnexpectedEof {
+            CryptoError::Truncated(format!("{context}: ファイルが途中で切れています"))
+        } else {
+            CryptoError::Io {
+                context: context.to_string(),
+                source,
+            }
+        }
+    })
+}
+
+/// 入力と出力が同じファイル（シンボリックリンクや相対パスの違いを含む）なら拒否
+///
+/// 出力ファイルを作成すると入力が切り詰められて失われるため、処理を始める前に確認します。
+/// 出力ファイルがまだ存在しない場合は同一ではありません。
+fn reject_same_file(input_path: &Path, output_path: &Path) -> Result<()> {
+    let Ok(output) = fs::canonicalize(output_path) else {
+        return Ok(());
+    };
+    if fs::canonicalize(input_path).is_ok_and(|input| input == output) {
+        return Err(CryptoError::InvalidInput(
+            "入力と出力が同一ファイルです".to_string(),
+        ));
+    }
+    Ok(())
+}
+
 /// 出力ファイルのパスを決定
+///
+/// `output` が指定されていない場合は、暗号化なら `config.encrypted_extension`（既定は `enc`）を付け、
+/// 復号化ならその拡張子を除いた名前にします。名前が設定の拡張子で終わっていない場合は最後の拡張子を除き、
+/// 拡張子がなく除去すると入力と同じ名前になる場合は、代わりに `.dec` を付けます。
+/// `config.default_output_dir` を設定すると、自動で決めたファイル名を入力と同じディレクトリではなく
+/// そのディレクトリに置きます（ディレクトリは作成しないため、書き込む前に呼び出し側で作成してください）。
+/// その名前のファイルが既に存在する場合は、`force` でなければ `foo (1).enc`・`foo (2).enc` のように
+/// 番号を付けて、存在しない名前を返します。
 pub fn determine_output_path(
     input: &Path,
     output: &Option<PathBuf>,
+    config: &Config,
     is_encrypt: bool,
+    force: bool,
 ) -> Result<PathBuf> {
-    match output {
-        Some(path) => Ok(path.clone()),
-        None => {
-            if is_encrypt {
-                // 暗号化の場合:.enc拡張子の追加
-                let mut path = input.to_path_buf();
-                let new_name = format!(
-                    "{}.enc",
-                    input
-                        .file_name()
-                        .and_then(|s| s.to_str())
-                        .ok_or_else(|| anyhow!("無効なファイル名"))?
-                );
-                path.set_file_name(new_name);
-                Ok(path)
-            } else {
-                // 復号化の場合:.enc拡張子の除去
-                let path = input.to_path_buf();
-                if let Some(stem) = path.file_stem() {
-                    let mut new_path = path.clone();
-                    new_path.set_file_name(stem);
-                    Ok(new_path)
-                } else {
-                    Err(anyhow!("暗号化ファイルの拡張子が不正です"))
-                }
-            }
+    determine_output_path_excluding(input, output, config, is_encrypt, force, &HashSet::new())
+}
+
+/// 出力ファイルのパスを、同じ一括処理で既に割り当てた `taken` と重ならないように決定
+///
+/// 決め方は `determine_output_path` と同じですが、`taken` に含まれる名前は `force` でも使わず、
+/// まだ書き込まれていない名前にも番号を付けます（`output` を指定した場合はそのまま返します）。
+pub fn determine_output_path_excluding(
+    input: &Path,
+    output: &Option<PathBuf>,
+    config: &Config,
+    is_encrypt: bool,
+    force: bool,
+    taken: &HashSet<PathBuf>,
+) -> Result<PathBuf> {
+    if let Some(path) = output {
+        return Ok(path.clone());
+    }
+
+    let extension = &config.encrypted_extension;
+    let mut path = if is_encrypt {
+        // 暗号化の場合:拡張子の追加
+        let mut path = input.to_path_buf();
+        let new_name = format!(
+            "{}.{extension}",
+            input
+                .file_name()
+                .and_then(|s| s.to_str())
+                .ok_or_else(|| CryptoError::InvalidInput("無効なファイル名".to_string()))?
+        );
+        path.set_file_name(new_name);
+        path
+    } else {
+        // 復号化の場合:拡張子の除去（分割したボリュームは番号を除いてから）
+        let base = volume_base_path(input).ok().flatten();
+        let input = base.as_deref().unwrap_or(input);
+        let Some(stem) = input.file_stem() else {
+            return Err(CryptoError::InvalidInput(
+                "暗号化ファイルの拡張子が不正です".to_string(),
+            ));
+        };
+        let stripped = input
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(&format!(".{extension}")))
+            .filter(|name| !name.is_empty());
+        let new_path = match stripped {
+            Some(name) => input.with_file_name(name),
+            None => input.with_file_name(stem),
+        };
+        if new_path == input {
+            // 拡張子がない（`secret`、`.env` など）と入力と同じパスになり、
+            // 暗号化ファイルを上書きしてしまうため `.dec` を付ける
+            let mut name = stem.to_os_string();
+            name.push(".dec");
+            input.with_file_name(name)
+        } else {
+            new_path
+        }
+    };
+    if let Some(dir) = &config.default_output_dir {
+        path = dir.join(path.file_name().unwrap_or_default());
+    }
+
+    if !taken.contains(&path) && (force || !path.exists()) {
+        return Ok(path);
+    }
+    Ok(numbered_path(&path, taken))
+}
+
+/// `foo.enc` に対して、存在せず `taken` にもない `foo (1).enc`・`foo (2).enc`… のうち最初のものを返す
+fn numbered_path(path: &Path, taken: &HashSet<PathBuf>) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+
+    (1u64..)
+        .map(|n| path.with_file_name(format!("{stem} ({n}){extension}")))
+        .find(|candidate| !taken.contains(candidate) && !candidate.exists())
+        .expect("番号付きのファイル名は必ず見つかる")
+}
+
+/// 同じパスワードで複数のファイルを続けて暗号化・復号化するための一括処理
+///
+/// 重いキー導出は一括処理全体で1回だけ行います。暗号化では全ファイルが同じソルトと
+/// パラメータを共有し、復号化では同じキー導出ブロックを持つファイルのキーを使い回します。
+pub struct FileBatch<'a> {
+    keys: KeyCache,
+    config: &'a Config,
+    progress: BatchProgress<'a>,
+}
+
+/// 一括処理のストリーミング処理で進捗を通知する先
+enum BatchProgress<'a> {
+    /// 端末にプログレスバーを表示
+    Terminal,
+    /// 通知しない
+    Hidden,
+    /// 呼び出し側のコールバックで通知
+    Callback(Box<dyn Fn(u64, u64) + Send + Sync + 'a>),
+}
+
+impl<'a> FileBatch<'a> {
+    pub fn new(password: &str, config: &'a Config) -> Self {
+        Self {
+            keys: KeyCache::from_config(password, config),
+            config,
+            progress: BatchProgress::Terminal,
+        }
+    }
+
+    /// ストリーミング処理でプログレスバーを表示しない（複数ファイルを並列に処理する場合など）
+    pub fn without_progress(mut self) -> Self {
+        self.progress = BatchProgress::Hidden;
+        self
+    }
+
+    /// 並列に処理する場合に、同時に行うキー導出のメモリ使用量の合計を `bytes` までに制限する
+    ///
+    /// 復号化ではファイルごとにソルトが異なるため、並列数だけキー導出が同時に行われます。
+    pub fn with_memory_budget(mut self, bytes: u64) -> Self {
+        self.keys = self.keys.with_memory_budget(bytes);
+        self
+    }
+
+    /// ストリーミング処理の進捗を、プログレスバーの代わりに `callback(処理済みバイト数, 総バイト数)` で通知する
+    ///
+    /// 複数のスレッドから同じ一括処理を使う場合、コールバックも並行して呼ばれます。
+    pub fn with_progress(mut self, callback: impl Fn(u64, u64) + Send + Sync + 'a) -> Self {
+        self.progress = BatchProgress::Callback(Box::new(callback));
+        self
+    }
+
+    /// 設定された通知先に進捗を通知しながら、ストリーミング処理の `run` を実行
+    fn run_streaming<F>(&self, message: &'static str, run: F) -> Result<()>
+    where
+        F: FnOnce(&mut dyn FnMut(u64, u64)) -> Result<()>,
+    {
+        match &self.progress {
+            BatchProgress::Terminal => with_terminal_progress(message, self.config, run),
+            BatchProgress::Hidden => run(&mut |_, _| {}),
+            BatchProgress::Callback(callback) => run(&mut |done, total| callback(done, total)),
+        }
+    }
+
+    /// ファイルを暗号化（ストリーミングの場合は端末にプログレスバーを表示）
+    pub fn encrypt_file(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        streaming: bool,
+    ) -> Result<()> {
+        if !streaming {
+            return encrypt_standard(input_path, output_path, &self.keys, self.config, None);
+        }
+        self.run_streaming("AES-GCM暗号化完了", |progress| {
+            encrypt_streaming(input_path, output_path, &self.keys, self.config, progress)
+        })
+    }
+
+    /// ファイルをストリーミング形式で暗号化し、`volume_size` バイトごとのボリュームに分割する
+    ///
+    /// `output_path` に `.001`・`.002`… を付けたボリュームを作成し、そのパスを順に返します。
+    pub fn encrypt_file_split(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        volume_size: u64,
+    ) -> Result<Vec<PathBuf>> {
+        let mut volumes = Vec::new();
+        self.run_streaming("AES-GCM暗号化完了", |progress| {
+            volumes = encrypt_split(
+                input_path,
+                output_path,
+                volume_size,
+                &self.keys,
+                self.config,
+                progress,
+            )?;
+            Ok(())
+        })?;
+        Ok(volumes)
+    }
+
+    /// ファイルを標準形式で暗号化し、元のディレクトリからの相対パスをヘッダーに記録する（再帰的な暗号化用）
+    ///
+    /// 記録したパスは、復号化時に `restore_path_into` で元のディレクトリ構成を復元するために使います。
+    /// ストリーミング形式のヘッダーには相対パスを記録する場所がないため、標準形式のみに対応します。
+    pub fn encrypt_file_with_path(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        relative_path: &Path,
+    ) -> Result<()> {
+        let relative_path = encode_relative_path(relative_path)?;
+        encrypt_standard(
+            input_path,
+            output_path,
+            &self.keys,
+            self.config,
+            Some(relative_path),
+        )
+    }
+
+    /// ファイルを復号化（ストリーミングの場合は端末にプログレスバーを表示）
+    pub fn decrypt_file(
+        &self,
+        input_path: &Path,
+        output_path: &Path,
+        streaming: bool,
+    ) -> Result<()> {
+        if !streaming {
+            return decrypt_standard(input_path, output_path, &self.keys, self.config);
         }
+        self.run_streaming("AES-GCM復号化完了", |progress| {
+            decrypt_streaming(input_path, output_path, &self.keys, self.config, progress)
+        })
+    }
+
+    /// 暗号化したファイルを復号化し、元のファイルと一致するか確認する（平文はディスクに書き出さない）
+    ///
+    /// 暗号化と同じ一括処理で使う場合、キー導出は行いません。
+    pub fn verify_file(&self, encrypted_path: &Path, original_path: &Path) -> Result<()> {
+        verify_encrypted(encrypted_path, original_path, &self.keys, self.config)
     }
 }
 
@@ -56,67 +338,397 @@ pub fn encrypt_file_standard(
     output_path: &Path,
     password: &str,
     config: &Config,
-    verbose: bool,
 ) -> Result<()> {
-    if verbose {
-        println!("=== AES-GCM 標準ファイル暗号化開始 ===");
-        println!("入力ファイル: {}", input_path.display());
-        println!("出力ファイル: {}", output_path.display());
-    }
+    encrypt_standard(
+        input_path,
+        output_path,
+        &KeyCache::from_config(password, config),
+        config,
+        None,
+    )
+}
+
+fn encrypt_standard(
+    input_path: &Path,
+    output_path: &Path,
+    keys: &KeyCache,
+    config: &Config,
+    relative_path: Option<String>,
+) -> Result<()> {
+    reject_same_file(input_path, output_path)?;
+
+    info!("=== AES-GCM 標準ファイル暗号化開始 ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}", output_path.display());
 
     // ファイルサイズ取得
     let metadata = fs::metadata(input_path)
-        .with_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
+        .with_io_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
     let file_size = metadata.len();
 
-    if verbose {
-        println!("ファイルサイズ: {file_size} バイト");
+    debug!("ファイルサイズ: {file_size} バイト");
+
+    // ファイルを読み込み
+    let input_data = time_phase(Phase::Read, || read_input(input_path))
+        .with_io_context(|| format!("ファイル読み込みに失敗: {}", input_path.display()))?;
+
+    debug!("ファイル読み込み完了: {} バイト", input_data.len());
+
+    // チェックサムは元の平文に付け、圧縮はその後に行う
+    let plaintext = with_checksum(&input_data, config.checksum);
+    let compressed = compress_with_mode(&plaintext, config.compress, input_path)?;
+    if let Some(compressed) = &compressed {
+        debug!("圧縮: {} → {} バイト", plaintext.len(), compressed.len());
+    }
+
+    // 暗号化実施（ヘッダー + ナンス + 暗号文）
+    let header = FileHeader {
+        kdf: keys.kdf_block(config).clone(),
+        metadata: preserved_metadata(&metadata, config),
+        padded: false,
+        hint: config.hint.clone(),
+        checksum: config.checksum,
+        path: relative_path,
+        compressed: compressed.is_some().then_some(plaintext.len() as u64),
+    };
+    let sealed = seal_with_header(
+        compressed.as_deref().unwrap_or(&plaintext),
+        keys,
+        &header,
+        &[],
+    )?;
+    drop(compressed);
+    drop(plaintext);
+    drop(input_data);
+
+    debug!("暗号化完了: {} バイト", sealed.ciphertext.len());
+
+    // 結合用のバッファを作らず、各部分をそのままファイルに書き込み（文字列にする場合を除く）
+    let write_sealed = || -> io::Result<()> {
+        let mut output_file = File::create(output_path)?;
+        if config.file_format.is_some() {
+            let data = [
+                &sealed.header[..],
+                &sealed.nonce[..],
+                &sealed.ciphertext[..],
+            ]
+            .concat();
+            return output_file.write_all(&encode_output(data, config));
+        }
+        output_file.write_all(&sealed.header)?;
+        output_file.write_all(&sealed.nonce)?;
+        output_file.write_all(&sealed.ciphertext)
+    };
+    time_phase(Phase::Write, write_sealed)
+        .with_io_context(|| format!("出力ファイルの書き込みに失敗: {}", output_path.display()))?;
+
+    debug!(
+        "ファイル書き込み完了: {} バイト",
+        sealed.header.len() + sealed.nonce.len() + sealed.ciphertext.len()
+    );
+    info!("=== AES-GCM 標準ファイル暗号化完了 ===");
+
+    Ok(())
+}
+
+/// 複数のパスワードのいずれでも復号化できるようにファイルを暗号化（エンベロープ形式）
+///
+/// 復号化は `decrypt_file_standard` でいずれか1つのパスワードを指定して行います。
+pub fn encrypt_file_for_passwords(
+    input_path: &Path,
+    output_path: &Path,
+    passwords: &[&str],
+    config: &Config,
+) -> Result<()> {
+    reject_same_file(input_path, output_path)?;
+
+    info!("=== エンベロープ形式ファイル暗号化開始 ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}", output_path.display());
+    debug!("パスワード数: {}", passwords.len());
+
+    let metadata = fs::metadata(input_path)
+        .with_io_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
+    let input_data = time_phase(Phase::Read, || fs::read(input_path))
+        .with_io_context(|| format!("ファイル読み込みに失敗: {}", input_path.display()))?;
+
+    let output_data = encrypt_envelope_with_metadata(
+        &input_data,
+        passwords,
+        preserved_metadata(&metadata, config).as_ref(),
+        config,
+    )?;
+    let output_data = encode_output(output_data, config);
+
+    time_phase(Phase::Write, || fs::write(output_path, &output_data))
+        .with_io_context(|| format!("出力ファイルの書き込みに失敗: {}", output_path.display()))?;
+
+    debug!("ファイル書き込み完了: {} バイト", output_data.len());
+    info!("=== エンベロープ形式ファイル暗号化完了 ===");
+
+    Ok(())
+}
+
+/// ファイルの形式を先頭のマジックナンバーから判別して復号化
+///
+/// ストリーミング形式なら `decrypt_file_streaming`（端末にプログレスバーを表示）、
+/// それ以外（標準形式・エンベロープ形式・アーマー形式）なら `decrypt_file_standard` で復号化します。
+pub fn decrypt_file_auto(
+    input_path: &Path,
+    output_path: &Path,
+    password: &str,
+    config: &Config,
+) -> Result<()> {
+    if detect_format(input_path)? == Some(EncFormat::Streaming) {
+        decrypt_file_streaming(input_path, output_path, password, config)
+    } else {
+        decrypt_file_standard(input_path, output_path, password, config)
     }
+}
+
+/// 暗号化したファイルを復号化し、元のファイルと一致するか確認する（平文はディスクに書き出さない）
+///
+/// 元のファイルを削除・置換する前に、書き込んだ暗号文が正しく復号化できることを確かめるために使います。
+/// 一致しない場合は `VerifyMismatch`、復号化できない場合は復号化と同じエラーを返します。
+pub fn verify_encrypted_file(
+    encrypted_path: &Path,
+    original_path: &Path,
+    password: &str,
+    config: &Config,
+) -> Result<()> {
+    verify_encrypted(
+        encrypted_path,
+        original_path,
+        &KeyCache::from_config(password, config),
+        config,
+    )
+}
+
+fn verify_encrypted(
+    encrypted_path: &Path,
+    original_path: &Path,
+    keys: &KeyCache,
+    config: &Config,
+) -> Result<()> {
+    debug!("暗号化結果を検証: {}", encrypted_path.display());
+    let original = File::open(original_path)
+        .with_io_context(|| format!("元ファイルのオープンに失敗: {}", original_path.display()))?;
+    let mut compare = CompareWriter::new(BufReader::new(original));
+    let context = || {
+        format!(
+            "暗号化ファイルの読み込みに失敗: {}",
+            encrypted_path.display()
+        )
+    };
+
+    let result = if detect_format(encrypted_path)? == Some(EncFormat::Streaming) {
+        let (mut reader, _) = open_stream_input(encrypted_path)?;
+        decrypt_stream(&mut reader, &mut compare, keys, 0, &mut |_, _| {}).map(|_| ())
+    } else {
+        let encrypted_data = read_input(encrypted_path).with_io_context(context)?;
+        decrypt_with_metadata(&encrypted_data, keys, config).and_then(|(plaintext, _)| {
+            compare
+                .write_all(&plaintext)
+                .io_context("元ファイルとの比較に失敗")
+        })
+    };
+    compare.finish(result)
+}
 
-    // キーとナンスを生成
-    let key = generate_key_from_password(password, config, verbose)?;
-    let mut nonce_bytes = [0u8; 12];
-    rand::rng().fill_bytes(&mut nonce_bytes);
-    let nonce = Nonce::from_slice(&nonce_bytes);
+/// キー導出のソルト（16バイト）をファイルから読み込む（`--salt-file`）
+///
+/// 外部で管理しているソルトで暗号化する場合に、読み込んだ値を `Config::salt` に設定します。
+/// ソルトは通常どおりヘッダーに記録されるため、復号化にはソルトのファイルは不要です。
+/// ファイルがちょうど16バイトでなければ `InvalidInput` を返します。
+pub fn read_salt_file(path: &Path) -> Result<[u8; SALT_SIZE]> {
+    let data = fs::read(path)
+        .with_io_context(|| format!("ソルトファイルの読み込みに失敗: {}", path.display()))?;
+    data.as_slice().try_into().map_err(|_| {
+        CryptoError::InvalidInput(format!(
+            "ソルトファイルは {SALT_SIZE} バイトである必要があります（{} バイト）: {}",
+            data.len(),
+            path.display()
+        ))
+    })
+}
 
-    if verbose {
-        println!("キー生成完了");
-        println!("ナンス: {}", base64_encode(&nonce_bytes));
+/// ペッパーのファイル（任意のバイト列、末尾の改行も含めてそのまま使う）を読み込む
+///
+/// 空のファイルは `InvalidInput` を返します。
+pub fn read_pepper_file(path: &Path) -> Result<Pepper> {
+    let data = fs::read(path)
+        .with_io_context(|| format!("ペッパーファイルの読み込みに失敗: {}", path.display()))?;
+    Pepper::new(data).map_err(|e| CryptoError::InvalidInput(format!("{e}: {}", path.display())))
+}
+
+/// 暗号化ファイルのヘッダーに記録されたパスワードのヒント（パスワードは不要）
+///
+/// 標準形式はヘッダー部分だけを読み込みます。アーマー形式はファイル全体を読み込みます。
+pub fn file_password_hint(path: &Path) -> Result<Option<String>> {
+    password_hint(&read_header_bytes(path)?)
+}
+
+/// 暗号化ファイルのヘッダーを含む先頭部分を読み込む（アーマー形式・文字列で書き込んだファイルは全体）
+fn read_header_bytes(path: &Path) -> Result<Vec<u8>> {
+    let context = || format!("暗号化ファイルの読み込みに失敗: {}", path.display());
+    let mut file = File::open(path).with_io_context(context)?;
+
+    // ヘッダー（ヒント・相対パスを含む）は8KBに収まる
+    let mut head = Vec::new();
+    (&mut file)
+        .take(8 * 1024)
+        .read_to_end(&mut head)
+        .with_io_context(context)?;
+    if is_armored(&head) || is_encoded_file(&head) {
+        file.read_to_end(&mut head).with_io_context(context)?;
     }
+    Ok(head)
+}
 
-    // AES-GCM暗号化エンジンを初期化
-    let cipher = Aes256Gcm::new(&key.into());
+/// 再帰的な暗号化でヘッダーに記録された、元のディレクトリからの相対パス（パスワードは不要）
+///
+/// `..` や絶対パスなど、復元先のディレクトリの外を指すパスは `InvalidFormat` で拒否します。
+/// ヘッダーは復号化に成功するまで認証されていないため、書き換えられている可能性があります。
+pub fn file_relative_path(path: &Path) -> Result<Option<PathBuf>> {
+    standard_header(&read_header_bytes(path)?)?
+        .and_then(|header| header.path)
+        .map(|stored| decode_relative_path(&stored))
+        .transpose()
+}
 
-    // ファイルを読み込み
-    let input_data = fs::read(input_path)
-        .with_context(|| format!("ファイル読み込みに失敗: {}", input_path.display()))?;
+/// 暗号化ファイルを `dir` の中に復元する場合の出力パス（`decrypt-file --into`）
+///
+/// ヘッダーに相対パスが記録されていれば `dir` からその位置に、なければ拡張子を除いた
+/// ファイル名で `dir` の直下に置きます（途中のディレクトリは作成しないため、呼び出し側で作成してください）。
+/// 既に存在する場合の扱いは `determine_output_path` と同じです。
+pub fn restore_path_into(
+    input: &Path,
+    dir: &Path,
+    config: &Config,
+    force: bool,
+) -> Result<PathBuf> {
+    restore_path_into_excluding(input, dir, config, force, &HashSet::new())
+}
+
+/// `restore_path_into` と同じ出力パスを、同じ一括処理で既に割り当てた `taken` と重ならないように決定
+pub fn restore_path_into_excluding(
+    input: &Path,
+    dir: &Path,
+    config: &Config,
+    force: bool,
+    taken: &HashSet<PathBuf>,
+) -> Result<PathBuf> {
+    let path = match file_relative_path(input)? {
+        Some(relative) => dir.join(relative),
+        None => {
+            let decrypted = determine_output_path(input, &None, config, false, true)?;
+            dir.join(decrypted.file_name().unwrap_or_default())
+        }
+    };
 
-    if verbose {
-        println!("ファイル読み込み完了: {} バイト", input_data.len());
+    if !taken.contains(&path) && (force || !path.exists()) {
+        return Ok(path);
     }
+    Ok(numbered_path(&path, taken))
+}
 
-    // 暗号化実施
-    let ciphertext = cipher
-        .encrypt(nonce, input_data.as_slice())
-        .map_err(|e| anyhow!("ファイル暗号化に失敗: {e}"))?;
+/// 相対パスを `/` 区切りの文字列にしてヘッダーに記録できる形にする
+fn encode_relative_path(path: &Path) -> Result<String> {
+    let invalid =
+        || CryptoError::InvalidInput(format!("相対パスとして記録できません: {}", path.display()));
+    let components = path
+        .components()
+        .map(|component| match component {
+            Component::Normal(name) => name.to_str().ok_or_else(invalid),
+            _ => Err(invalid()),
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let encoded = components.join("/");
+    if encoded.is_empty() || encoded.len() > MAX_PATH_LEN {
+        return Err(invalid());
+    }
+    Ok(encoded)
+}
 
-    if verbose {
-        println!("暗号化完了: {} バイト", ciphertext.len());
+/// ヘッダーに記録された相対パスを検証し、このOSのパスに戻す
+///
+/// 各要素がそれぞれ通常のファイル名1つとして解釈されることを確認するため、
+/// `..`・`.`・空の要素・ルートやドライブの指定（Windowsでは `\` や `C:` を含む要素）は拒否します。
+fn decode_relative_path(stored: &str) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for name in stored.split('/') {
+        let mut components = Path::new(name).components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(normal)), None) if normal == name => path.push(normal),
+            _ => {
+                return Err(CryptoError::InvalidFormat(format!(
+                    "ヘッダーの相対パスが復元先のディレクトリの外を指しています: {stored}"
+                )));
+            }
+        }
     }
+    Ok(path)
+}
 
-    // 出力データを構成(ナンス + 暗号文)
-    let mut output_data = nonce_bytes.to_vec();
-    output_data.extend_from_slice(&ciphertext);
+/// 暗号化ファイルの形式（`detect_format` の判定結果）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncFormat {
+    /// 標準形式（エンベロープ形式・アーマー形式を含む、`decrypt_file_standard` で復号化）
+    Standard,
+    /// ストリーミング形式（`decrypt_file_streaming` で復号化）
+    Streaming,
+}
 
-    // ファイルに書き込み
-    fs::write(output_path, &output_data)
-        .with_context(|| format!("出力ファイルの書き込みに失敗: {}", output_path.display()))?;
+/// ファイルの先頭だけを読み、このツールで暗号化されたファイルかどうかと、その形式を判定する
+///
+/// 復号化もキー導出も行いません。暗号化ファイルでなければ `None` を返し、マジックナンバーより
+/// 短いファイルもエラーにはせず `None` とします。マジックナンバーのない旧バージョンの形式は判別できません。
+/// 分割したボリュームはストリーミング形式と判定します。
+pub fn detect_format(path: &Path) -> Result<Option<EncFormat>> {
+    let context = || format!("暗号化ファイルの読み込みに失敗: {}", path.display());
+    let file = File::open(path).with_io_context(context)?;
+
+    // アーマー形式の先頭の空白を考慮しても1KBあれば判定できる
+    let mut head = Vec::new();
+    file.take(1024)
+        .read_to_end(&mut head)
+        .with_io_context(context)?;
+
+    let format = if head.starts_with(STREAM_MAGIC) || head.starts_with(VOLUME_MAGIC) {
+        Some(EncFormat::Streaming)
+    } else if has_magic(&head) || is_envelope(&head) || is_armored(&head) || is_encoded_file(&head)
+    {
+        Some(EncFormat::Standard)
+    } else {
+        None
+    };
+    Ok(format)
+}
 
-    if verbose {
-        println!("ファイル書き込み完了: {} バイト", output_data.len());
-        println!("=== AES-GCM 標準ファイル暗号化完了 ===");
+/// ストリーミング形式で暗号化されたファイルか（先頭のマジックナンバーで判定）
+pub fn is_streaming_file(path: &Path) -> Result<bool> {
+    Ok(detect_format(path)? == Some(EncFormat::Streaming))
+}
+
+/// 暗号化ファイルをASCIIアーマー形式に書き換える（標準形式・エンベロープ形式のみ）
+///
+/// アーマー形式のファイルは `decrypt_file_standard` がそのまま復号化します。
+pub fn armor_file(path: &Path) -> Result<()> {
+    let data = fs::read(path)
+        .with_io_context(|| format!("暗号化ファイルの読み込みに失敗: {}", path.display()))?;
+    if data.starts_with(STREAM_MAGIC) || data.starts_with(VOLUME_MAGIC) {
+        return Err(CryptoError::InvalidInput(
+            "ストリーミング形式のファイルはアーマー形式にできません".to_string(),
+        ));
     }
+    // 文字列（`file_format`）で書き込んだファイルは、バイナリに戻してからアーマー形式にする
+    let data = decode_encoded_file(&data).map_or(data, |(_, decoded)| decoded);
 
+    let text = armor(&data)?;
+    fs::write(path, text)
+        .with_io_context(|| format!("出力ファイルの書き込みに失敗: {}", path.display()))?;
+    debug!("ASCIIアーマー形式に変換: {}", path.display());
     Ok(())
 }
 
@@ -126,341 +738,1435 @@ pub fn decrypt_file_standard(
     output_path: &Path,
     password: &str,
     config: &Config,
-    verbose: bool,
 ) -> Result<()> {
-    if verbose {
-        println!("=== AES-GCM 標準ファイル復号化開始 ===");
-        println!("入力ファイル: {}", input_path.display());
-        println!("出力ファイル: {}", output_path.display());
-    }
+    decrypt_standard(
+        input_path,
+        output_path,
+        &KeyCache::from_config(password, config),
+        config,
+    )
+}
+
+fn decrypt_standard(
+    input_path: &Path,
+    output_path: &Path,
+    keys: &KeyCache,
+    config: &Config,
+) -> Result<()> {
+    reject_same_file(input_path, output_path)?;
+
+    info!("=== AES-GCM 標準ファイル復号化開始 ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}", output_path.display());
 
     // 暗号化ファイルを読み込み
-    let encrypted_data = fs::read(input_path)
-        .with_context(|| format!("暗号化ファイルの読み込みに失敗: {}", input_path.display()))?;
+    let encrypted_data = time_phase(Phase::Read, || read_input(input_path))
+        .with_io_context(|| format!("暗号化ファイルの読み込みに失敗: {}", input_path.display()))?;
 
-    if verbose {
-        println!(
-            "暗号化ファイル読み込み完了: {} バイト",
-            encrypted_data.len()
-        );
-    }
+    debug!(
+        "暗号化ファイル読み込み完了: {} バイト",
+        encrypted_data.len()
+    );
 
-    if encrypted_data.len() < 12 {
-        return Err(anyhow!("暗号化ファイルが不正です（サイズが小さすぎます）"));
+    if encrypted_data.starts_with(STREAM_MAGIC) {
+        return Err(CryptoError::InvalidFormat(
+            "ストリーミング形式で暗号化されたファイルです（ストリーミングで復号化してください）"
+                .to_string(),
+        ));
     }
 
-    // ナンスと暗号文を分離
-    let (nonce_bytes, ciphertext) = encrypted_data.split_at(12);
-    let nonce = Nonce::from_slice(nonce_bytes);
+    // 復号化実行
+    let (plaintext, metadata) = decrypt_with_metadata(&encrypted_data, keys, config)?;
+    drop(encrypted_data);
+
+    debug!("復号化完了: {} バイト", plaintext.len());
 
-    if verbose {
-        println!("ナンス抽出: {}", base64_encode(nonce_bytes));
-        println!("暗号文サイズ: {} バイト", ciphertext.len());
-    }
+    // ファイルに書き込み
+    time_phase(Phase::Write, || fs::write(output_path, &plaintext))
+        .with_io_context(|| format!("出力ファイルの書き込みに失敗: {}", output_path.display()))?;
 
-    // キーを再生成
-    let key = generate_key_from_password(password, config, verbose)?;
-    let cipher = Aes256Gcm::new(&key.into());
+    debug!("ファイル書き込み完了");
+    restore_metadata(output_path, metadata, config)?;
+    info!("=== AES-GCM 標準ファイル復号化完了 ===");
 
-    if verbose {
-        println!("復号化エンジン初期化完了");
+    Ok(())
+}
+
+/// 標準処理で読み込んだ入力ファイルの内容
+enum InputData {
+    Heap(Vec<u8>),
+    /// メモリマップした内容（ヒープにコピーしない）
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+impl Deref for InputData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            InputData::Heap(data) => data,
+            #[cfg(feature = "mmap")]
+            InputData::Mapped(map) => map,
+        }
     }
+}
 
-    // 復号化実行
-    let plaintext = cipher
-        .decrypt(nonce, ciphertext)
-        .map_err(|e| anyhow!("ファイル復号化に失敗: {e}"))?;
+/// 標準処理の入力ファイルを読み込む（`mmap` フィーチャーが有効な場合はメモリマップ）
+fn read_input(path: &Path) -> io::Result<InputData> {
+    #[cfg(feature = "mmap")]
+    {
+        let file = File::open(path)?;
+        // 空のファイルはマップできないプラットフォームがあるため通常の読み込みにする
+        if file.metadata()?.len() > 0 {
+            // SAFETY: マップ中に他のプロセスがファイルを書き換えると読み取る内容が変わり、
+            // 切り詰められるとSIGBUSで終了する可能性がある。この制約はREADMEに記載している
+            let map = unsafe { memmap2::Mmap::map(&file)? };
+            return Ok(InputData::Mapped(map));
+        }
+    }
+    fs::read(path).map(InputData::Heap)
+}
 
-    if verbose {
-        println!("復号化完了: {} バイト", plaintext.len());
+/// 設定の `file_format` が指定されていれば、暗号化したデータをその形式の文字列（末尾に改行）にする
+fn encode_output(data: Vec<u8>, config: &Config) -> Vec<u8> {
+    match &config.file_format {
+        Some(format) => {
+            let mut text = encode_text(&data, format);
+            text.push('\n');
+            text.into_bytes()
+        }
+        None => data,
     }
+}
 
-    // ファイルに書き込み
-    fs::write(output_path, &plaintext)
-        .with_context(|| format!("出力ファイルの書き込みに失敗: {}", output_path.display()))?;
+/// 設定で有効な場合に、ヘッダーに記録する元ファイルのメタデータを返す
+fn preserved_metadata(metadata: &fs::Metadata, config: &Config) -> Option<FileMetadata> {
+    config
+        .preserve_metadata
+        .then(|| FileMetadata::from_fs(metadata))
+}
 
-    if verbose {
-        println!("ファイル書き込み完了");
-        println!("=== AES-GCM 標準ファイル復号化完了 ===");
+/// 設定で有効な場合に、ヘッダーに記録されていたメタデータを出力ファイルに適用
+fn restore_metadata(path: &Path, metadata: Option<FileMetadata>, config: &Config) -> Result<()> {
+    match metadata {
+        Some(metadata) if config.preserve_metadata => {
+            debug!("元ファイルのパーミッションと更新日時を復元");
+            metadata.apply(path)
+        }
+        _ => Ok(()),
     }
+}
 
-    Ok(())
+/// 端末表示用のプログレスバーを作成（設定の `show_progress` が無効なら何も表示しない）
+///
+/// テンプレートは設定の読み込み時に検証済みのため、ここで解釈できない場合は既定のテンプレートを使います。
+fn terminal_progress_bar(config: &Config) -> ProgressBar {
+    if !config.show_progress {
+        return ProgressBar::hidden();
+    }
+    let template = config
+        .progress_template
+        .as_deref()
+        .unwrap_or(DEFAULT_PROGRESS_TEMPLATE);
+    let style = ProgressStyle::default_bar()
+        .template(template)
+        .or_else(|_| ProgressStyle::default_bar().template(DEFAULT_PROGRESS_TEMPLATE))
+        .expect("既定のテンプレートは常に解釈できる");
+    let progress = ProgressBar::new(0);
+    progress.set_style(style.progress_chars("#>-"));
+    progress
 }
 
-/// AES-GCMストリーミング暗号化（大容量ファイル対応）
+/// AES-GCMストリーミング暗号化（大容量ファイル対応、端末にプログレスバーを表示）
 pub fn encrypt_file_streaming(
     input_path: &Path,
     output_path: &Path,
     password: &str,
     config: &Config,
-    verbose: bool,
 ) -> Result<()> {
-    const CHUNK_SIZE: usize = 64 * 1024; // 64KB のチャンク
+    with_terminal_progress("AES-GCM暗号化完了", config, |progress| {
+        encrypt_streaming(
+            input_path,
+            output_path,
+            &KeyCache::from_config(password, config),
+            config,
+            progress,
+        )
+    })
+}
+
+/// 端末にプログレスバーを表示しながら `run` を実行
+fn with_terminal_progress<F>(message: &'static str, config: &Config, run: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn FnMut(u64, u64)) -> Result<()>,
+{
+    let progress = terminal_progress_bar(config);
+    run(&mut |done, total| {
+        progress.set_length(total);
+        progress.set_position(done);
+    })?;
+    progress.finish_with_message(message);
+    Ok(())
+}
+
+/// AES-GCMストリーミング暗号化（進捗を `progress(処理済みバイト数, 総バイト数)` で通知）
+pub fn encrypt_file_streaming_with_progress(
+    input_path: &Path,
+    output_path: &Path,
+    password: &str,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    encrypt_streaming(
+        input_path,
+        output_path,
+        &KeyCache::from_config(password, config),
+        config,
+        progress,
+    )
+}
+
+/// AES-GCMストリーミング暗号化の結果を `volume_size` バイトごとのボリュームに分割する（端末にプログレスバーを表示）
+///
+/// `output_path` に `.001`・`.002`… を付けたボリュームを作成し、そのパスを順に返します。
+/// 復号化はいずれかのボリューム（通常は `.001`）を `decrypt_file_streaming` などに渡すと、
+/// 全ボリュームをつなげて行います。
+pub fn encrypt_file_split(
+    input_path: &Path,
+    output_path: &Path,
+    volume_size: u64,
+    password: &str,
+    config: &Config,
+) -> Result<Vec<PathBuf>> {
+    let mut volumes = Vec::new();
+    with_terminal_progress("AES-GCM暗号化完了", config, |progress| {
+        volumes = encrypt_split(
+            input_path,
+            output_path,
+            volume_size,
+            &KeyCache::from_config(password, config),
+            config,
+            progress,
+        )?;
+        Ok(())
+    })?;
+    Ok(volumes)
+}
 
-    if verbose {
-        println!("=== AES-GCM ストリーミング暗号化開始 ===");
-        println!("入力ファイル: {}", input_path.display());
-        println!("出力ファイル: {}", output_path.display());
-        println!("チャンクサイズ: {} KB", CHUNK_SIZE / 1024);
+fn encrypt_streaming(
+    input_path: &Path,
+    output_path: &Path,
+    keys: &KeyCache,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    let chunk_size = config.stream_chunk_size;
+    validate_chunk_size(chunk_size)?;
+    reject_stream_options(config)?;
+    reject_same_file(input_path, output_path)?;
+
+    info!("=== AES-GCM ストリーミング暗号化開始 ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}", output_path.display());
+    debug!("チャンクサイズ: {} KB", chunk_size / 1024);
+
+    let create_output = || {
+        File::create(output_path)
+            .map(BufWriter::new)
+            .with_io_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))
+    };
+    encrypt_file_to(input_path, create_output, keys, config, progress)?;
+
+    info!("=== AES-GCM ストリーミング暗号化完了 ===");
+
+    Ok(())
+}
+
+/// ストリーミング形式で暗号化し、`volume_size` バイトごとのボリュームに分割して書き込む
+///
+/// 失敗した場合は作成途中のボリュームを削除します。
+fn encrypt_split(
+    input_path: &Path,
+    output_path: &Path,
+    volume_size: u64,
+    keys: &KeyCache,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<Vec<PathBuf>> {
+    validate_chunk_size(config.stream_chunk_size)?;
+    reject_stream_options(config)?;
+    reject_same_file(input_path, &volume_path(output_path, 1))?;
+
+    info!("=== AES-GCM ストリーミング暗号化開始（分割） ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}.*", output_path.display());
+    debug!("分割サイズ: {volume_size} バイト");
+
+    // ボリュームは最初の書き込みで作成されるため、入力を開く前に用意しておける
+    let mut writer = VolumeWriter::new(output_path, volume_size)?;
+    if let Err(e) = encrypt_file_to(input_path, || Ok(&mut writer), keys, config, progress) {
+        writer.discard();
+        return Err(e);
     }
+    let volumes = writer.finish()?;
+
+    info!(
+        "=== AES-GCM ストリーミング暗号化完了（{} 個に分割） ===",
+        volumes.len()
+    );
 
+    Ok(volumes)
+}
+
+/// 入力ファイルをストリーミング形式で暗号化し、入力を開けてから `create_output` で作成した出力に書き込む
+fn encrypt_file_to<W: Write>(
+    input_path: &Path,
+    create_output: impl FnOnce() -> Result<W>,
+    keys: &KeyCache,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
     // ファイルサイズの取得
     let metadata = fs::metadata(input_path)
-        .with_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
+        .with_io_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
     let file_size = metadata.len();
 
-    if verbose {
-        println!(
-            "ファイルサイズ: {file_size} バイト ({:.2} MB)",
-            file_size as f64 / 1_048_576.0
-        );
-    }
-
-    // プログレスバーを設定
-    let progress = ProgressBar::new(file_size);
-    progress.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .progress_chars("#>-")
+    debug!(
+        "ファイルサイズ: {file_size} バイト ({:.2} MB)",
+        file_size as f64 / 1_048_576.0
     );
 
-    // キーを生成
-    let key = generate_key_from_password(password, config, verbose)?;
+    // ファイルを開く
+    let mut input_file =
+        BufReader::new(File::open(input_path).with_io_context(|| {
+            format!("入力ファイルのオープンに失敗: {}", input_path.display())
+        })?);
+
+    encrypt_stream(
+        &mut input_file,
+        &mut create_output()?,
+        keys,
+        &FileHeader {
+            kdf: keys.kdf_block(config).clone(),
+            metadata: preserved_metadata(&metadata, config),
+            padded: false,
+            hint: None,
+            checksum: false,
+            path: None,
+            compressed: None,
+        },
+        config.stream_chunk_size,
+        file_size,
+        progress,
+    )
+}
 
-    if verbose {
-        println!("キー生成完了");
+/// ストリーミング形式のヘッダーにはパスワードのヒントやチェックサム、圧縮の有無を記録する場所がない
+fn reject_stream_options(config: &Config) -> Result<()> {
+    if config.hint.is_some() {
+        return Err(CryptoError::InvalidInput(
+            "ストリーミング形式ではパスワードのヒントを記録できません".to_string(),
+        ));
+    }
+    if config.checksum {
+        return Err(CryptoError::InvalidInput(
+            "ストリーミング形式では平文のチェックサムを記録できません".to_string(),
+        ));
     }
+    if config.compress != CompressMode::Never {
+        return Err(CryptoError::InvalidInput(
+            "ストリーミング形式では平文を圧縮できません".to_string(),
+        ));
+    }
+    Ok(())
+}
 
-    // ファイルを開く
-    let mut input_file = BufReader::new(
-        File::open(input_path)
-            .with_context(|| format!("入力ファイルのオープンに失敗: {}", input_path.display()))?,
-    );
+/// 平文を読み取り、ストリーミング形式（ヘッダー + チャンク列 + フッター）で書き込む
+///
+/// キー導出ブロックとメタデータは標準形式と共通の `FileHeader` で受け取ります。
+fn encrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    keys: &KeyCache,
+    file_header: &FileHeader,
+    chunk_size: usize,
+    total_size: u64,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    // ヘッダーに記録するパラメータでキーを生成
+    let key = keys.derive(&file_header.kdf)?;
+
+    debug!("キー生成完了");
+
+    // 一括処理では複数のファイルが同じキーを共有するため、
+    // ナンスの前半8バイトはファイルごとのランダム値にする
+    let mut nonce_prefix = [0u8; 8];
+    rand::rng().fill_bytes(&mut nonce_prefix);
+
+    write_stream(
+        reader,
+        writer,
+        StreamSealer::new(&key, nonce_prefix),
+        file_header,
+        chunk_size,
+        total_size,
+        progress,
+    )
+}
 
-    let mut output_file = BufWriter::new(
-        File::create(output_path)
-            .with_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))?,
-    );
+/// `sealer` で組み立てたヘッダー・チャンク・フッターを順に書き込む
+fn write_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    mut sealer: StreamSealer,
+    file_header: &FileHeader,
+    chunk_size: usize,
+    total_size: u64,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    let reader = &mut Timed(reader);
+    let writer = &mut Timed(writer);
+    progress(0, total_size);
 
-    // ファイルヘッダーを書き込み (マジックナンバー + チャンクサイズ)
-    let header = b"GCMSTREAM";
-    output_file
-        .write_all(header)
-        .context("ヘッダーの書き込みに失敗")?;
-    output_file
-        .write_all(&(CHUNK_SIZE as u32).to_le_bytes())
-        .context("チャンクサイズの書き込みに失敗")?;
+    writer
+        .write_all(&sealer.header(file_header, chunk_size))
+        .io_context("ヘッダーの書き込みに失敗")?;
 
-    if verbose {
-        println!("AES-GCM暗号エンジン準備完了");
-        println!("ストリーミング処理開始...");
-    }
+    debug!("ストリーミング処理開始...");
 
     // チャンクごとに処理
-    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut buffer = vec![0u8; chunk_size];
     let mut processed_bytes = 0u64;
-    let mut chunk_counter = 0u64;
 
     loop {
-        let bytes_read = input_file
-            .read(&mut buffer)
-            .context("ファイル読み込み中にエラーが発生")?;
+        let bytes_read = read_chunk(reader, &mut buffer)?;
 
         if bytes_read == 0 {
             break; // EOF
         }
 
-        // チャンクごとにユニークなナンス生成
-        let mut nonce_bytes = [0u8; 12];
-        // チャンクカウンターを最初の8バイトに設定
-        let counter_bytes = chunk_counter.to_le_bytes();
-        nonce_bytes[0..8].copy_from_slice(&counter_bytes);
-        // 残りの4バイトにランダム要素を追加
-        let mut random_part = [0u8; 4];
-        rand::rng().fill_bytes(&mut random_part);
-        nonce_bytes[8..12].copy_from_slice(&random_part);
-
-        let nonce = Nonce::from_slice(&nonce_bytes);
-
-        // AES-GCM暗号化エンジンを初期化（チャンクごとに新しいインスタンス）
-        let cipher = Aes256Gcm::new(&key.into());
-
-        // データを暗号化
-        let chunk_data = &buffer[..bytes_read];
-        let encrypted_chunk = cipher
-            .encrypt(nonce, chunk_data)
-            .map_err(|e| anyhow!("チャンク暗号化に失敗: {e}"))?;
-
-        // チャンクデータを書き込み: ナンス(12) + 暗号化データ長(4) + 暗号化データ
-        output_file
-            .write_all(&nonce_bytes)
-            .context("ナンスの書き込みに失敗")?;
-        output_file
-            .write_all(&(encrypted_chunk.len() as u32).to_le_bytes())
-            .context("チャンク長の書き込みに失敗")?;
-        output_file
-            .write_all(&encrypted_chunk)
-            .context("暗号化チャンクの書き込みに失敗")?;
+        writer
+            .write_all(&sealer.seal_chunk(&buffer[..bytes_read])?)
+            .io_context("暗号化チャンクの書き込みに失敗")?;
 
         processed_bytes += bytes_read as u64;
-        chunk_counter += 1;
-        progress.set_position(processed_bytes);
+        progress(processed_bytes, total_size);
     }
 
+    let chunk_count = sealer.chunk_counter;
+    writer
+        .write_all(&sealer.finish())
+        .io_context("フッターの書き込みに失敗")?;
+
     // バッファをフラッシュ
-    output_file
+    writer
         .flush()
-        .context("出力ファイルのフラッシュに失敗")?;
+        .io_context("出力ファイルのフラッシュに失敗")?;
+
+    debug!("処理済みバイト数: {processed_bytes} バイト");
+    debug!("処理済みチャンク数: {chunk_count}");
+
+    Ok(())
+}
+
+/// ストリーミング形式のバイト列を組み立てる（入出力は行わない）
+///
+/// ヘッダー・チャンク・フッターを書き込む順に作ると、フッターのHMACもあわせて計算されます。
+struct StreamSealer {
+    cipher: Aes256Gcm,
+    mac: HmacSha256,
+    nonce_prefix: [u8; 8],
+    chunk_counter: u64,
+}
 
-    progress.finish_with_message("AES-GCM暗号化完了");
+impl StreamSealer {
+    fn new(key: &[u8; 32], nonce_prefix: [u8; 8]) -> Self {
+        Self {
+            cipher: Aes256Gcm::new(key.into()),
+            // ファイル全体（ヘッダー + 全チャンク）を対象にHMACを計算
+            mac: stream_mac(key),
+            nonce_prefix,
+            chunk_counter: 0,
+        }
+    }
 
-    if verbose {
-        println!("処理済みバイト数: {processed_bytes} バイト");
-        println!("処理済みチャンク数: {chunk_counter}");
-        println!("=== AES-GCM ストリーミング暗号化完了 ===");
+    /// ヘッダー: マジックナンバー + チャンクサイズ(4) + キー導出ブロック + メタデータブロック
+    fn header(&mut self, file_header: &FileHeader, chunk_size: usize) -> Vec<u8> {
+        let mut header = STREAM_MAGIC.to_vec();
+        header.extend_from_slice(&(chunk_size as u32).to_le_bytes());
+        file_header.kdf.write_to(&mut header);
+        FileMetadata::write_to(file_header.metadata.as_ref(), &mut header);
+        self.mac.update(&header);
+        header
     }
 
-    Ok(())
+    /// チャンク: ナンス(12) + 暗号化データ長(4) + 暗号化データ
+    fn seal_chunk(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        // チャンクごとにユニークなナンス生成（ファイル固有のプレフィックス + チャンクカウンター）
+        let counter = u32::try_from(self.chunk_counter)
+            .map_err(|_| CryptoError::InvalidInput("チャンク数が上限を超えました".to_string()))?;
+        let mut nonce_bytes = [0u8; 12];
+        nonce_bytes[0..8].copy_from_slice(&self.nonce_prefix);
+        nonce_bytes[8..12].copy_from_slice(&counter.to_le_bytes());
+
+        let encrypted_chunk = time_phase(Phase::Cipher, || {
+            self.cipher.encrypt(Nonce::from_slice(&nonce_bytes), data)
+        })
+        .map_err(|_| CryptoError::Encryption)?;
+
+        let mut chunk = Vec::with_capacity(nonce_bytes.len() + 4 + encrypted_chunk.len());
+        chunk.extend_from_slice(&nonce_bytes);
+        chunk.extend_from_slice(&(encrypted_chunk.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(&encrypted_chunk);
+        self.mac.update(&chunk);
+        self.chunk_counter += 1;
+        Ok(chunk)
+    }
+
+    /// フッター: 目印(12) + HMAC(32)
+    fn finish(self) -> Vec<u8> {
+        let mut footer = STREAM_FOOTER_MARKER.to_vec();
+        footer.extend_from_slice(&self.mac.finalize().into_bytes());
+        footer
+    }
+}
+
+/// バッファが埋まるか入力が終わるまで読み込む（パイプからの短い読み込み対策）
+fn read_chunk<R: Read>(reader: &mut R, buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(source) => {
+                return Err(CryptoError::Io {
+                    context: "ファイル読み込み中にエラーが発生".to_string(),
+                    source,
+                });
+            }
+        }
+    }
+    Ok(filled)
+}
+
+/// 任意の入力をストリーミング形式で暗号化して書き込む（標準入力から標準出力へのパイプなど）
+///
+/// チャンク単位で処理するため、入力のサイズによらずメモリ使用量は一定です。
+/// 元ファイルのメタデータは記録しません。
+pub fn encrypt_from_reader<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    password: &str,
+    config: &Config,
+) -> Result<()> {
+    let chunk_size = config.stream_chunk_size;
+    validate_chunk_size(chunk_size)?;
+    reject_stream_options(config)?;
+
+    let keys = KeyCache::from_config(password, config);
+    let header = FileHeader {
+        kdf: keys.kdf_block(config).clone(),
+        metadata: None,
+        padded: false,
+        hint: None,
+        checksum: false,
+        path: None,
+        compressed: None,
+    };
+    encrypt_stream(
+        reader,
+        writer,
+        &keys,
+        &header,
+        chunk_size,
+        0,
+        &mut |_, _| {},
+    )
+}
+
+/// ナンスのプレフィックスを指定して `encrypt_from_reader` と同じ形式で暗号化（テストベクター・形式の検証用）
+///
+/// ソルトは設定の `salt` で指定します。チャンクのナンスはプレフィックスとチャンク番号だけで決まるため、
+/// 出力のバイト列をそのまま比較できます。
+///
+/// **本番環境では絶対に使用しないでください。** 同じキーとプレフィックスで2つのデータを暗号化すると
+/// ナンスが再利用され、平文の排他的論理和が漏れるうえ暗号文を改ざんできるようになります。
+#[doc(hidden)]
+pub fn encrypt_from_reader_with_nonce_prefix<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    password: &str,
+    config: &Config,
+    nonce_prefix: &[u8; 8],
+) -> Result<()> {
+    let chunk_size = config.stream_chunk_size;
+    validate_chunk_size(chunk_size)?;
+    reject_stream_options(config)?;
+
+    let header = FileHeader {
+        kdf: KdfBlock::new(config),
+        metadata: None,
+        padded: false,
+        hint: None,
+        checksum: false,
+        path: None,
+        compressed: None,
+    };
+    let key = header
+        .kdf
+        .derive_key_with_pepper(password, config.pepper.as_ref())?;
+    write_stream(
+        reader,
+        writer,
+        StreamSealer::new(&key, *nonce_prefix),
+        &header,
+        chunk_size,
+        0,
+        &mut |_, _| {},
+    )
+}
+
+/// ストリーミング形式の暗号データを読み取り、復号化したデータを書き込む
+///
+/// 復号化したデータはチャンクごとに書き込むため、末尾の整合性検証で失敗した場合でも
+/// それまでのデータは書き込み済みです（エラーが返った場合は出力を破棄してください）。
+pub fn decrypt_to_writer<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    password: &str,
+) -> Result<()> {
+    decrypt_to_writer_with_pepper(reader, writer, password, None)
 }
 
-/// AES-GCMストリーミング復号化（大容量ファイル対応）
+/// `decrypt_to_writer` と同じだが、ペッパーを使って暗号化されたデータには `pepper` を使う
+pub fn decrypt_to_writer_with_pepper<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    password: &str,
+    pepper: Option<&Pepper>,
+) -> Result<()> {
+    let keys = KeyCache::new(password).with_pepper(pepper.cloned());
+    decrypt_stream(reader, writer, &keys, 0, &mut |_, _| {}).map(|_| ())
+}
+
+/// AES-GCMストリーミング復号化（大容量ファイル対応、端末にプログレスバーを表示）
 pub fn decrypt_file_streaming(
     input_path: &Path,
     output_path: &Path,
     password: &str,
     config: &Config,
-    verbose: bool,
 ) -> Result<()> {
-    if verbose {
-        println!("=== AES-GCM ストリーミング復号化開始 ===");
-        println!("入力ファイル: {}", input_path.display());
-        println!("出力ファイル: {}", output_path.display());
-    }
+    with_terminal_progress("AES-GCM復号化完了", config, |progress| {
+        decrypt_streaming(
+            input_path,
+            output_path,
+            &KeyCache::from_config(password, config),
+            config,
+            progress,
+        )
+    })
+}
 
-    // ファイルサイズを取得
-    let metadata = fs::metadata(input_path)
-        .with_context(|| format!("ファイル情報の取得に失敗: {}", input_path.display()))?;
-    let file_size = metadata.len();
+/// AES-GCMストリーミング復号化（進捗を `progress(処理済みバイト数, 総バイト数)` で通知）
+///
+/// キー導出のパラメータはファイルのヘッダーから読み取るため、設定の値は使いません。
+/// 設定で有効な場合は、ヘッダーに記録された元ファイルのメタデータを出力に適用します。
+pub fn decrypt_file_streaming_with_progress(
+    input_path: &Path,
+    output_path: &Path,
+    password: &str,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    decrypt_streaming(
+        input_path,
+        output_path,
+        &KeyCache::from_config(password, config),
+        config,
+        progress,
+    )
+}
 
-    if file_size < 17 {
-        // ヘッダー(9) + チャンクサイズ(4) + 最小チャンク(4) = 17
-        return Err(anyhow!("暗号化ファイルが不正です（サイズが小さすぎます）"));
-    }
+fn decrypt_streaming(
+    input_path: &Path,
+    output_path: &Path,
+    keys: &KeyCache,
+    config: &Config,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<()> {
+    reject_same_file(input_path, output_path)?;
 
-    if verbose {
-        println!(
-            "ファイルサイズ: {} バイト ({:.2} MB)",
-            file_size,
-            file_size as f64 / 1_048_576.0
-        );
-    }
+    info!("=== AES-GCM ストリーミング復号化開始 ===");
+    debug!("入力ファイル: {}", input_path.display());
+    debug!("出力ファイル: {}", output_path.display());
 
-    // キーの生成
-    let key = generate_key_from_password(password, config, verbose)?;
+    let (mut input_file, file_size) = open_stream_input(input_path)?;
 
-    // ファイルを開く
-    let mut input_file = BufReader::new(
-        File::open(input_path)
-            .with_context(|| format!("入力ファイルのオープンに失敗: {}", input_path.display()))?,
+    if file_size < 79 {
+        // ヘッダー(9) + チャンクサイズ(4) + キー導出ブロック(最小21) + メタデータブロック(最小1)
+        // + フッター(12 + 32) = 79。空のファイルを暗号化するとチャンクがなく、この最小サイズになる
+        return Err(CryptoError::Truncated(
+            "暗号化ファイルが不正です（サイズが小さすぎます）".to_string(),
+        ));
+    }
+
+    debug!(
+        "ファイルサイズ: {} バイト ({:.2} MB)",
+        file_size,
+        file_size as f64 / 1_048_576.0
     );
 
     let mut output_file = BufWriter::new(
         File::create(output_path)
-            .with_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))?,
+            .with_io_context(|| format!("出力ファイルの作成に失敗: {}", output_path.display()))?,
     );
 
-    // ヘッダーを読み込み
-    let mut header = [0u8; 9];
-    input_file
-        .read_exact(&mut header)
-        .context("ヘッダーの読み込みに失敗")?;
+    let metadata = decrypt_stream(&mut input_file, &mut output_file, keys, file_size, progress)?;
+    drop(output_file);
+    restore_metadata(output_path, metadata, config)?;
+
+    info!("=== AES-GCM ストリーミング復号化完了 ===");
+
+    Ok(())
+}
 
-    if &header != b"GCMSTREAM" {
-        return Err(anyhow!("無効なファイル形式です"));
+/// ストリーミング形式の暗号化ファイルを開き、リーダーとデータのバイト数を返す
+///
+/// 分割したボリュームは全ボリュームを確認し、番号順につなげて読み込みます。
+fn open_stream_input(path: &Path) -> Result<(Box<dyn Read>, u64)> {
+    if is_volume_file(path)? {
+        let reader = VolumeReader::open(path)?;
+        let len = reader.len();
+        return Ok((Box::new(reader), len));
+    }
+    let file = File::open(path)
+        .with_io_context(|| format!("入力ファイルのオープンに失敗: {}", path.display()))?;
+    let len = file
+        .metadata()
+        .with_io_context(|| format!("ファイル情報の取得に失敗: {}", path.display()))?
+        .len();
+    Ok((Box::new(BufReader::new(file)), len))
+}
+
+/// ストリーミング形式のヘッダー
+pub(crate) struct StreamHeader {
+    /// ヘッダー全体のバイト列（フッターのHMACの対象）
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) chunk_size: usize,
+    pub(crate) kdf: KdfBlock,
+    pub(crate) metadata: Option<FileMetadata>,
+}
+
+/// ストリーミング形式のヘッダー（マジックナンバー + チャンクサイズ + キー導出ブロック + メタデータブロック）を読み込む
+pub(crate) fn read_stream_header<R: Read>(reader: &mut R) -> Result<StreamHeader> {
+    let mut magic = [0u8; 9];
+    read_exact_or_truncated(reader, &mut magic, "ヘッダーの読み込みに失敗")?;
+
+    // キー導出の前に形式を確認し、対象外のファイルはすぐに拒否する
+    if &magic != STREAM_MAGIC {
+        if has_magic(&magic)
+            || ENVELOPE_MAGIC.starts_with(&magic)
+            || ARMOR_BEGIN.as_bytes().starts_with(&magic)
+        {
+            return Err(CryptoError::InvalidFormat(
+                "標準形式で暗号化されたファイルです（ストリーミングなしで復号化してください）"
+                    .to_string(),
+            ));
+        }
+        return Err(CryptoError::NotEncrypted);
     }
 
     // チャンクサイズを読み込み
     let mut chunk_size_bytes = [0u8; 4];
-    input_file
-        .read_exact(&mut chunk_size_bytes)
-        .context("チャンクサイズの読み込みに失敗")?;
-    let _chunk_size = u32::from_le_bytes(chunk_size_bytes) as usize;
-
-    if verbose {
-        println!("ファイル形式確認完了");
-        println!("AES-GCM復号エンジン準備完了");
-        println!("ストリーミング処理開始...");
-    }
-
-    // データサイズから進捗バーを設定（ヘッダー分を除く）
-    let data_size = file_size - 13; // ヘッダー(9) + チャンクサイズ(4)
-    let progress = ProgressBar::new(data_size);
-    progress.set_style(
-        ProgressStyle::default_bar()
-            .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-            .unwrap()
-            .progress_chars("#>-")
-    );
+    read_exact_or_truncated(
+        reader,
+        &mut chunk_size_bytes,
+        "チャンクサイズの読み込みに失敗",
+    )?;
+    let chunk_size = u32::from_le_bytes(chunk_size_bytes) as usize;
+    validate_chunk_size(chunk_size).map_err(|e| {
+        CryptoError::InvalidFormat(format!("ヘッダーのチャンクサイズが不正です: {e}"))
+    })?;
+
+    // キー導出ブロックを読み込み
+    let kdf_bytes = read_kdf_block(reader)?;
+    let kdf = KdfBlock::read_from(&mut kdf_bytes.as_slice())?;
+
+    // メタデータブロックを読み込み
+    let metadata_bytes = read_metadata_block(reader)?;
+    let metadata = FileMetadata::read_from(&mut metadata_bytes.as_slice())?;
+
+    let mut bytes = magic.to_vec();
+    bytes.extend_from_slice(&chunk_size_bytes);
+    bytes.extend_from_slice(&kdf_bytes);
+    bytes.extend_from_slice(&metadata_bytes);
+
+    Ok(StreamHeader {
+        bytes,
+        chunk_size,
+        kdf,
+        metadata,
+    })
+}
 
-    let mut processed_bytes = 0u64;
-    let mut chunk_counter = 0u64;
+/// ストリーミング形式の暗号文を読み取り、復号化した平文を書き込む
+///
+/// `total_size` は暗号化ファイル全体のサイズで、進捗の計算にのみ使います。進捗は `reader` から
+/// 実際に読み込んだバイト数（ヘッダーとフッターを含む）で通知するため、終端で `total_size` に一致します。
+/// 戻り値はヘッダーに記録された元ファイルのメタデータです。
+fn decrypt_stream<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    keys: &KeyCache,
+    total_size: u64,
+    progress: &mut dyn FnMut(u64, u64),
+) -> Result<Option<FileMetadata>> {
+    let mut decryptor = DecryptReader::with_keys(CountingReader::new(Timed(reader)), keys)?;
+    let writer = &mut Timed(writer);
+
+    debug!("ストリーミング処理開始...");
+    progress(decryptor.inner.count, total_size);
+
+    // チャンクごとに復号化し、復号化されたデータを書き込み
+    while decryptor.next_chunk()? {
+        writer
+            .write_all(&decryptor.plaintext)
+            .io_context("復号化データの書き込み中にエラーが発生")?;
+        progress(decryptor.inner.count, total_size);
+    }
+    progress(decryptor.inner.count, total_size);
 
-    // チャンクごとに復号化
-    loop {
-        // ナンスを読み込み
+    // バッファをフラッシュ
+    writer
+        .flush()
+        .io_context("出力ファイルのフラッシュに失敗")?;
+
+    debug!("処理済みチャンク数: {}", decryptor.chunk_counter);
+
+    Ok(decryptor.metadata)
+}
+
+/// ストリーミング形式の暗号データを読み込みながら復号化するリーダー
+///
+/// `read` の呼び出しに応じてチャンクを1つずつ読み込み、認証してから復号化するため、
+/// 平文全体をメモリやディスクに展開せずに先頭から順に処理できます（動画の再生など）。
+/// チャンクの認証や形式の検証に失敗した場合は、`CryptoError` を包んだ `io::Error` を返し、
+/// 以降の読み込みもすべて失敗します。
+///
+/// 切り詰めや末尾のチャンクの削除は、最後のフッターを検証するまで検出できません。
+/// `read` が 0 を返した時点でファイル全体の整合性が検証済みです。
+pub struct DecryptReader<R> {
+    inner: R,
+    cipher: Aes256Gcm,
+    /// ファイル全体の整合性検証用のHMAC（フッターの検証で消費）
+    mac: Option<HmacSha256>,
+    metadata: Option<FileMetadata>,
+    /// 認証前に巨大な確保をしないための、ヘッダーのチャンクサイズ + GCMタグのサイズ
+    max_encrypted_len: usize,
+    encrypted_chunk: Vec<u8>,
+    /// 復号化済みのチャンクと、そのうち返し終えた位置
+    plaintext: Vec<u8>,
+    position: usize,
+    chunk_counter: u64,
+    finished: bool,
+    failed: bool,
+}
+
+impl<R: Read> DecryptReader<R> {
+    /// ヘッダーを読み込み、記録されたパラメータでパスワードからキーを導出する
+    ///
+    /// 形式が異なる場合やヘッダーが壊れている場合は、チャンクを読む前にエラーを返します。
+    /// パスワードの誤りは、最初のチャンク（チャンクがない場合はフッター）の読み込みで検出されます。
+    pub fn new(reader: R, password: &str) -> Result<Self> {
+        Self::with_keys(reader, &KeyCache::new(password))
+    }
+
+    fn with_keys(mut reader: R, keys: &KeyCache) -> Result<Self> {
+        let header = read_stream_header(&mut reader)?;
+
+        // ヘッダーのパラメータでキーを生成
+        debug!("キー導出アルゴリズム: {}", header.kdf.params.name());
+        let key = keys.derive(&header.kdf)?;
+
+        let mut mac = stream_mac(&key);
+        mac.update(&header.bytes);
+
+        debug!("ファイル形式確認完了");
+        debug!("チャンクサイズ: {} KB", header.chunk_size / 1024);
+        debug!("AES-GCM復号エンジン準備完了");
+
+        // ヘッダーのチャンクサイズ + GCMタグ分を確保して使い回す
+        let max_encrypted_len = header.chunk_size + GCM_TAG_SIZE;
+        Ok(Self {
+            inner: reader,
+            cipher: Aes256Gcm::new(&key.into()),
+            mac: Some(mac),
+            metadata: header.metadata,
+            max_encrypted_len,
+            encrypted_chunk: Vec::with_capacity(max_encrypted_len),
+            plaintext: Vec::with_capacity(header.chunk_size),
+            position: 0,
+            chunk_counter: 0,
+            finished: false,
+            failed: false,
+        })
+    }
+
+    /// ヘッダーに記録された元ファイルのメタデータ
+    pub fn metadata(&self) -> Option<&FileMetadata> {
+        self.metadata.as_ref()
+    }
+
+    /// 元のリーダーを返す
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// 次のチャンクを読み込んで `plaintext` に復号化する
+    ///
+    /// フッターに到達して整合性を検証できた場合は `false` を返します。
+    fn next_chunk(&mut self) -> Result<bool> {
+        if self.finished {
+            return Ok(false);
+        }
+
+        // ナンス（またはフッターの目印）を読み込み。フッターより前の終端は切り詰め
         let mut nonce_bytes = [0u8; 12];
-        match input_file.read_exact(&mut nonce_bytes) {
-            Ok(()) => {}
-            Err(ref e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
-                break; // ファイル終端
-            }
-            Err(e) => return Err(anyhow!("ナンス読み込みエラー: {}", e)),
+        read_exact_or_truncated(&mut self.inner, &mut nonce_bytes, "ナンスの読み込みに失敗")?;
+
+        if &nonce_bytes == STREAM_FOOTER_MARKER {
+            let mac = self.mac.take().expect("フッターは一度だけ検証する");
+            verify_stream_footer(&mut self.inner, mac, self.chunk_counter)?;
+            self.finished = true;
+            return Ok(false);
         }
         let nonce = Nonce::from_slice(&nonce_bytes);
 
         // 暗号化データ長を読み込み
         let mut encrypted_len_bytes = [0u8; 4];
-        input_file
-            .read_exact(&mut encrypted_len_bytes)
-            .context("暗号化データ長の読み込みに失敗")?;
+        read_exact_or_truncated(
+            &mut self.inner,
+            &mut encrypted_len_bytes,
+            "暗号化データ長の読み込みに失敗",
+        )?;
         let encrypted_len = u32::from_le_bytes(encrypted_len_bytes) as usize;
 
-        // 暗号化データを読み込み
-        let mut encrypted_chunk = vec![0u8; encrypted_len];
-        input_file
-            .read_exact(&mut encrypted_chunk)
-            .context("暗号化チャンクの読み込みに失敗")?;
+        // 認証前に巨大な確保をしないよう、ヘッダーのチャンクサイズで長さを制限
+        if !(GCM_TAG_SIZE..=self.max_encrypted_len).contains(&encrypted_len) {
+            return Err(CryptoError::InvalidFormat(
+                "チャンク長が不正です".to_string(),
+            ));
+        }
 
-        // AES-GCM復号化エンジンを初期化（チャンクごとに新しいインスタンス）
-        let cipher = Aes256Gcm::new(&key.into());
+        // 暗号化データを読み込み
+        self.encrypted_chunk.resize(encrypted_len, 0);
+        read_exact_or_truncated(
+            &mut self.inner,
+            &mut self.encrypted_chunk,
+            "暗号化チャンクの読み込みに失敗",
+        )?;
 
         // データを復号化
-        let decrypted_chunk = cipher
-            .decrypt(nonce, encrypted_chunk.as_slice())
-            .map_err(|e| anyhow!("チャンク復号化に失敗: {e}"))?;
+        self.plaintext = time_phase(Phase::Cipher, || {
+            self.cipher.decrypt(nonce, self.encrypted_chunk.as_slice())
+        })
+        .map_err(|_| CryptoError::WrongPasswordOrCorrupt)?;
+        self.position = 0;
+
+        let mac = self.mac.as_mut().expect("フッターの検証前");
+        mac.update(&nonce_bytes);
+        mac.update(&encrypted_len_bytes);
+        mac.update(&self.encrypted_chunk);
+
+        self.chunk_counter += 1;
+        Ok(true)
+    }
+}
 
-        // 復号化されたデータを書き込み
-        output_file
-            .write_all(&decrypted_chunk)
-            .context("復号化データの書き込み中にエラーが発生")?;
+impl<R: Read> Read for DecryptReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.failed {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "以前のエラーのため、これ以上読み込めません",
+            ));
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
 
-        processed_bytes += (12 + 4 + encrypted_len) as u64; // ナンス + 長さ + データ
-        chunk_counter += 1;
-        progress.set_position(processed_bytes);
+        while self.position == self.plaintext.len() {
+            match self.next_chunk() {
+                Ok(true) => {}
+                Ok(false) => return Ok(0),
+                Err(e) => {
+                    self.failed = true;
+                    return Err(crypto_to_io_error(e));
+                }
+            }
+        }
+
+        let remaining = &self.plaintext[self.position..];
+        let len = remaining.len().min(buf.len());
+        buf[..len].copy_from_slice(&remaining[..len]);
+        self.position += len;
+        Ok(len)
     }
+}
 
-    // バッファをフラッシュ
-    output_file
-        .flush()
-        .context("出力ファイルのフラッシュに失敗")?;
+/// `CryptoError` を `io::Error` に包む（元のエラーは `get_ref` / `into_inner` で取り出せる）
+fn crypto_to_io_error(error: CryptoError) -> io::Error {
+    let kind = match &error {
+        CryptoError::Io { source, .. } => source.kind(),
+        CryptoError::Truncated(_) => io::ErrorKind::UnexpectedEof,
+        _ => io::ErrorKind::InvalidData,
+    };
+    io::Error::new(kind, error)
+}
+
+/// 読み込んだバイト数を数えるリーダー（進捗の計算用）
+struct CountingReader<R> {
+    inner: R,
+    count: u64,
+}
+
+impl<R: Read> CountingReader<R> {
+    fn new(inner: R) -> Self {
+        Self { inner, count: 0 }
+    }
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+/// 書き込まれたデータを元ファイルの内容と先頭から順に比較するライター（暗号化結果の検証用）
+pub(crate) struct CompareWriter<R> {
+    original: R,
+    buffer: Vec<u8>,
+    mismatch: bool,
+}
+
+impl<R: Read> CompareWriter<R> {
+    pub(crate) fn new(original: R) -> Self {
+        Self {
+            original,
+            buffer: Vec::new(),
+            mismatch: false,
+        }
+    }
+
+    /// 書き込みの結果と合わせて、内容が最後まで一致したかを返す
+    ///
+    /// 不一致で書き込みが失敗した場合は、書き込み側のエラーではなく `VerifyMismatch` を返します。
+    pub(crate) fn finish(mut self, result: Result<()>) -> Result<()> {
+        if self.mismatch {
+            return Err(CryptoError::VerifyMismatch);
+        }
+        result?;
+        // 元ファイルに残りがあれば、復号化した結果の方が短い
+        let mut rest = [0u8; 1];
+        match self.original.read(&mut rest) {
+            Ok(0) => Ok(()),
+            Ok(_) => Err(CryptoError::VerifyMismatch),
+            Err(e) => Err(CryptoError::Io {
+                context: "元ファイルの読み込みに失敗".to_string(),
+                source: e,
+            }),
+        }
+    }
+}
+
+impl<R: Read> Write for CompareWriter<R> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buffer.resize(data.len(), 0);
+        let matches = match self.original.read_exact(&mut self.buffer) {
+            Ok(()) => self.buffer == data,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => false,
+            Err(e) => return Err(e),
+        };
+        if !matches {
+            self.mismatch = true;
+            return Err(io::Error::other("元ファイルの内容と一致しません"));
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// ストリームからキー導出ブロック（識別子 + パラメータ + ソルト）のバイト列を読み込む
+fn read_kdf_block<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut id = [0u8; 1];
+    read_exact_or_truncated(reader, &mut id, "キー導出ブロックの読み込みに失敗")?;
+    let params_len = KdfParams::encoded_params_len(id[0]).ok_or_else(|| {
+        CryptoError::InvalidFormat(format!("不明なキー導出アルゴリズムです: {}", id[0]))
+    })?;
+
+    let mut block = vec![0u8; 1 + params_len + SALT_SIZE];
+    block[0] = id[0];
+    read_exact_or_truncated(reader, &mut block[1..], "キー導出ブロックの読み込みに失敗")?;
+    Ok(block)
+}
+
+/// ストリームからメタデータブロック（有無 + メタデータ）のバイト列を読み込む
+fn read_metadata_block<R: Read>(reader: &mut R) -> Result<Vec<u8>> {
+    let mut present = [0u8; 1];
+    read_exact_or_truncated(reader, &mut present, "メタデータの読み込みに失敗")?;
+
+    let mut block = vec![present[0]];
+    if present[0] == 1 {
+        block.resize(1 + METADATA_SIZE, 0);
+        read_exact_or_truncated(reader, &mut block[1..], "メタデータの読み込みに失敗")?;
+    }
+    Ok(block)
+}
+
+/// フッターのHMACを検証し、その後にデータが続いていないことを確認
+fn verify_stream_footer<R: Read>(
+    reader: &mut R,
+    mac: HmacSha256,
+    chunk_counter: u64,
+) -> Result<()> {
+    let mut tag = [0u8; STREAM_MAC_SIZE];
+    read_exact_or_truncated(reader, &mut tag, "フッターの読み込みに失敗")?;
+
+    if mac.verify_slice(&tag).is_err() {
+        // チャンクが1つもなければ、パスワード違いとの区別がつかない
+        if chunk_counter == 0 {
+            return Err(CryptoError::WrongPasswordOrCorrupt);
+        }
+        return Err(CryptoError::Truncated(
+            "ファイルが途中で切れています（ファイル全体の整合性検証に失敗しました）".to_string(),
+        ));
+    }
+
+    let mut trailing = [0u8; 1];
+    match reader.read(&mut trailing) {
+        Ok(0) => Ok(()),
+        Ok(_) => Err(CryptoError::InvalidFormat(
+            "フッターの後に余分なデータがあります".to_string(),
+        )),
+        Err(source) => Err(CryptoError::Io {
+            context: "フッターの読み込みに失敗".to_string(),
+            source,
+        }),
+    }
+}
+
+/// 出力先と同じディレクトリに置く一時ファイルのパス
+fn temp_path_for(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.tmp", std::process::id()))
+}
+
+/// 一時ファイルに書き込んでから置き換えることで、途中で失敗しても元のファイルを壊さない
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
+    replace_atomic(path, |writer| {
+        writer
+            .write_all(data)
+            .io_context("一時ファイルの書き込みに失敗")
+    })
+}
+
+/// `write` で一時ファイルを書き込み、成功した場合のみ `path` を置き換える
+fn replace_atomic<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<()>,
+{
+    let temp_path = temp_path_for(path);
+
+    let result = (|| {
+        let mut writer =
+            BufWriter::new(File::create(&temp_path).with_io_context(|| {
+                format!("一時ファイルの作成に失敗: {}", temp_path.display())
+            })?);
+        write(&mut writer)?;
+        let file = writer
+            .into_inner()
+            .map_err(|e| e.into_error())
+            .io_context("一時ファイルのフラッシュに失敗")?;
+        file.sync_all().io_context("一時ファイルの同期に失敗")?;
+        fs::rename(&temp_path, path)
+            .with_io_context(|| format!("ファイルの置き換えに失敗: {}", path.display()))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// ファイルを処理結果で置き換える（`--in-place` 用）
+///
+/// `write` には同じディレクトリの一時ファイルのパスを渡し、その出力が成功した場合のみ
+/// `fs::rename` で元のファイルを置き換えます。途中で失敗しても元のファイルは残ります。
+pub fn write_in_place<F>(path: &Path, write: F) -> Result<()>
+where
+    F: FnOnce(&Path) -> Result<()>,
+{
+    let temp_path = temp_path_for(path);
+
+    let result = (|| {
+        write(&temp_path)?;
+        File::open(&temp_path)
+            .and_then(|file| file.sync_all())
+            .io_context("一時ファイルの同期に失敗")?;
+        fs::rename(&temp_path, path)
+            .with_io_context(|| format!("ファイルの置き換えに失敗: {}", path.display()))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// 出力先 `path` のロックファイル（`<出力先>.lock`）に排他的なアドバイザリロックをかけて `write` を実行する
+///
+/// 同じ出力先を処理中の他のプロセスがあれば、`write` を実行せずに `CryptoError::Locked` を返します
+/// （cron と手動の実行が重なった場合などに、書き込みが混ざったファイルを作らないため）。
+/// 出力先は一時ファイルからの置き換えで別のファイルになるため、出力先そのものではなく
+/// 置き換えの影響を受けないロックファイルをロックします。
+/// ロックファイルは終了時（`write` が失敗した場合も含む）に削除し、ロックを解放します。
+/// ロックは同じ仕組みでロックする処理どうしでのみ有効で、書き込み自体を禁止するものではありません。
+pub fn with_output_lock<T, E>(path: &Path, write: impl FnOnce() -> Result<T, E>) -> Result<T, E>
+where
+    E: From<CryptoError>,
+{
+    let lock_path = lock_path_for(path);
+    let Some(lock) = lock_output(path, &lock_path)? else {
+        return write();
+    };
+
+    let result = write();
+    // ロックを保持したまま削除し、次の処理には新しいロックファイルを作らせる
+    let _ = fs::remove_file(&lock_path);
+    drop(lock);
+    result
+}
+
+/// 出力先 `path` のロックファイルのパス
+fn lock_path_for(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
 
-    progress.finish_with_message("AES-GCM復号化完了");
+/// ロックファイルを開いてロックする（ファイルシステムがロックに対応していなければ `None`）
+fn lock_output(path: &Path, lock_path: &Path) -> Result<Option<File>> {
+    let context = || format!("出力ファイルのロックに失敗: {}", lock_path.display());
+    loop {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(lock_path)
+            .with_io_context(context)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Err(CryptoError::Locked(path.to_path_buf())),
+            // ロックに対応していないファイルシステムでは、ロックせずに続行する
+            Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => {
+                debug!(
+                    "出力ファイルをロックできません（非対応）: {}",
+                    path.display()
+                );
+                drop(file);
+                let _ = fs::remove_file(lock_path);
+                return Ok(None);
+            }
+            Err(TryLockError::Error(source)) => {
+                return Err(CryptoError::Io {
+                    context: context(),
+                    source,
+                });
+            }
+        }
+        // 開いてからロックするまでの間に、先に処理していたプロセスが終了してロックファイルを
+        // 削除していれば、削除されたファイルのロックでは排他にならないため開き直す
+        if is_current_lock_file(&file, lock_path) {
+            debug!("出力ファイルをロック: {}", path.display());
+            return Ok(Some(file));
+        }
+    }
+}
 
-    if verbose {
-        println!("処理済みチャンク数: {chunk_counter}");
-        println!("=== AES-GCM ストリーミング復号化完了 ===");
+/// ロックした `file` が、まだ `lock_path` にあるロックファイルか
+#[cfg(unix)]
+fn is_current_lock_file(file: &File, lock_path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(lock_path)) {
+        (Ok(locked), Ok(current)) => locked.dev() == current.dev() && locked.ino() == current.ino(),
+        _ => false,
     }
+}
 
+/// ロックした `file` が、まだ `lock_path` にあるロックファイルか
+///
+/// Windowsでは開かれているファイルを削除しても、閉じられるまで同じ名前のファイルを作成できないため、
+/// ロックファイルが残っていればロックしたファイルと同じです。
+#[cfg(not(unix))]
+fn is_current_lock_file(_file: &File, lock_path: &Path) -> bool {
+    lock_path.exists()
+}
+
+/// 暗号化ファイルを新しいパスワードで再暗号化（平文はディスクに書き出さない）
+///
+/// 標準形式はメモリ上で復号化・再暗号化し、ストリーミング形式は
+/// 復号化スレッドと暗号化をパイプで繋いでチャンク単位で処理します。
+/// 結果は一時ファイル経由で同じパスに置き換えます。
+pub fn rotate_file(
+    path: &Path,
+    old_password: &str,
+    new_password: &str,
+    config: &Config,
+) -> Result<()> {
+    info!("=== パスワード変更開始 ===");
+    debug!("対象ファイル: {}", path.display());
+
+    let mut magic = [0u8; 9];
+    let is_streaming = {
+        let mut file = File::open(path)
+            .with_io_context(|| format!("ファイルのオープンに失敗: {}", path.display()))?;
+        matches!(file.read_exact(&mut magic), Ok(())) && &magic == STREAM_MAGIC
+    };
+    if &magic == VOLUME_MAGIC {
+        return Err(CryptoError::InvalidInput(
+            "分割したボリュームのパスワードは変更できません（復号化してから暗号化し直してください）"
+                .to_string(),
+        ));
+    }
+
+    if is_streaming {
+        rotate_streaming(path, old_password, new_password, config)?;
+    } else {
+        let encrypted_data = fs::read(path)
+            .with_io_context(|| format!("暗号化ファイルの読み込みに失敗: {}", path.display()))?;
+        // 文字列（`file_format`）で書き込んだファイルはデコードしてから扱う
+        let encrypted_data =
+            decode_encoded_file(&encrypted_data).map_or(encrypted_data, |(_, decoded)| decoded);
+        let rotated = if is_envelope(&encrypted_data) {
+            // 複数パスワードの場合は該当するスロットだけを差し替える
+            rewrap_envelope(&encrypted_data, old_password, new_password, config)?
+        } else {
+            // 記録されている元ファイルのメタデータ・相対パス・圧縮の有無はそのまま引き継ぐ
+            let (plaintext, metadata) = decrypt_with_metadata(
+                &encrypted_data,
+                &KeyCache::from_config(old_password, config),
+                config,
+            )?;
+            let old_header = standard_header(&encrypted_data)?;
+            let plaintext = with_checksum(&plaintext, config.checksum);
+            let compressed = if old_header
+                .as_ref()
+                .is_some_and(|header| header.compressed.is_some())
+            {
+                Some(compress(&plaintext)?)
+            } else {
+                None
+            };
+            let header = FileHeader {
+                kdf: KdfBlock::new(config),
+                metadata,
+                padded: false,
+                hint: config.hint.clone(),
+                checksum: config.checksum,
+                path: old_header.and_then(|header| header.path),
+                compressed: compressed.is_some().then_some(plaintext.len() as u64),
+            };
+            encrypt_with_header(
+                compressed.as_deref().unwrap_or(&plaintext),
+                &KeyCache::from_config(new_password, config),
+                &header,
+                &[],
+            )?
+        };
+        write_atomic(path, &encode_output(rotated, config))?;
+    }
+
+    info!("=== パスワード変更完了 ===");
     Ok(())
 }
+
+/// ストリーミング形式のファイルをパイプ経由で再暗号化
+fn rotate_streaming(
+    path: &Path,
+    old_password: &str,
+    new_password: &str,
+    config: &Config,
+) -> Result<()> {
+    let chunk_size = config.stream_chunk_size;
+    validate_chunk_size(chunk_size)?;
+
+    let file_size = fs::metadata(path)
+        .with_io_context(|| format!("ファイル情報の取得に失敗: {}", path.display()))?
+        .len();
+
+    // 暗号化側は復号化より先にヘッダーを書くため、引き継ぐメタデータを先に読んでおく
+    let metadata = {
+        let mut file = BufReader::new(
+            File::open(path)
+                .with_io_context(|| format!("ファイルのオープンに失敗: {}", path.display()))?,
+        );
+        read_stream_header(&mut file)?.metadata
+    };
+    let new_header = FileHeader {
+        kdf: KdfBlock::new(config),
+        metadata,
+        padded: false,
+        hint: None,
+        checksum: false,
+        path: None,
+        compressed: None,
+    };
+
+    let mut input_file = BufReader::new(
+        File::open(path)
+            .with_io_context(|| format!("ファイルのオープンに失敗: {}", path.display()))?,
+    );
+    let (mut pipe_reader, pipe_writer) = io::pipe().io_context("パイプの作成に失敗")?;
+
+    replace_atomic(path, |output| {
+        std::thread::scope(|scope| {
+            let decryptor = scope.spawn(move || {
+                let mut pipe_writer = BufWriter::new(pipe_writer);
+                decrypt_stream(
+                    &mut input_file,
+                    &mut pipe_writer,
+                    &KeyCache::from_config(old_password, config),
+                    file_size,
+                    &mut |_, _| {},
+                )
+            });
+
+            let encrypted = encrypt_stream(
+                &mut pipe_reader,
+                output,
+                &KeyCache::from_config(new_password, config),
+                &new_header,
+                chunk_size,
+                0,
+                &mut |_, _| {},
+            );
+            // 暗号化側が先に失敗した場合に復号化スレッドが書き込みで止まらないよう閉じる
+            drop(pipe_reader);
+
+            let decrypted = decryptor.join().expect("復号化スレッドがパニックしました");
+            // 暗号化側の失敗は復号化側ではパイプ切断として現れるため、暗号化側を優先して報告
+            encrypted?;
+            decrypted.map(|_| ())
+        })
+    })
+}
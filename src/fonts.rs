@@ -0,0 +1,122 @@
+//! GUI用の日本語フォント読み込み
+//!
+//! OSごとによく使われる日本語フォントのパスを順に探し、最初に見つかったものを egui に登録します。
+//! どれも見つからない場合は、埋め込みのフォントを登録します。
+
+use log::{debug, warn};
+use std::path::PathBuf;
+
+/// egui に登録するフォント名
+pub const JAPANESE_FONT: &str = "japanese_font";
+
+/// 候補のフォントが見つからない場合に使う日本語フォント
+///
+/// Noto Sans JP から、JIS 第1水準までの漢字・かな・記号とこのクレートのメッセージで使う文字だけを取り出した
+/// サブセットです（SIL Open Font License、`assets/fonts/OFL.txt`）。
+pub const EMBEDDED_JAPANESE_FONT: &[u8] = include_bytes!("../assets/fonts/NotoSansJP-Subset.otf");
+
+/// 日本語フォントの候補パス（優先順）
+fn candidate_paths() -> Vec<PathBuf> {
+    #[cfg(target_os = "windows")]
+    {
+        let windir = std::env::var_os("WINDIR").unwrap_or_else(|| "C:\\Windows".into());
+        let fonts_dir = PathBuf::from(windir).join("Fonts");
+        [
+            "YuGothM.ttc",  // Yu Gothic Medium
+            "yugothic.ttf", // Yu Gothic
+            "meiryo.ttc",   // Meiryo
+            "msgothic.ttc", // MS Gothic
+            "BIZ-UDGothicR.ttc",
+            "NotoSansJP-VF.ttf",
+        ]
+        .iter()
+        .map(|name| fonts_dir.join(name))
+        .collect()
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        [
+            "/System/Library/Fonts/ヒラギノ角ゴシック W3.ttc",
+            "/System/Library/Fonts/Hiragino Sans GB.ttc",
+            "/System/Library/Fonts/Supplemental/Osaka.ttf",
+            "/Library/Fonts/Arial Unicode.ttf",
+            "/Library/Fonts/Arial Unicode MS.ttf",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        let mut paths: Vec<PathBuf> = [
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/truetype/noto/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/TTF/NotoSansCJK-Regular.ttc",
+            "/usr/share/fonts/opentype/ipaexfont-gothic/ipaexg.ttf",
+            "/usr/share/fonts/truetype/takao-gothic/TakaoGothic.ttf",
+            "/usr/share/fonts/vl-gothic-fonts/VL-Gothic-Regular.ttf",
+            "/usr/share/fonts/truetype/vlgothic/VL-Gothic-Regular.ttf",
+            "/usr/share/fonts/truetype/fonts-japanese-gothic.ttf",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect();
+        // ユーザーがインストールしたフォント
+        if let Some(data_dir) = dirs::data_dir() {
+            paths.push(data_dir.join("fonts/NotoSansCJK-Regular.ttc"));
+            paths.push(data_dir.join("fonts/NotoSansJP-Regular.ttf"));
+        }
+        paths
+    }
+}
+
+/// 日本語フォントを探して egui に登録する
+///
+/// OSのフォントが見つかれば `true`、見つからず埋め込みのフォントを登録した場合は `false` を返します。
+pub fn install_japanese_font(ctx: &egui::Context) -> bool {
+    let (fonts, found) = japanese_font_definitions(candidate_paths());
+    ctx.set_fonts(fonts);
+    found
+}
+
+/// `candidates` のうち最初に読み込めたフォント（なければ埋め込みのフォント）を登録したフォント定義
+///
+/// フォントはプロポーショナルフォントの先頭と、等幅フォントのフォールバックに追加します。
+/// 候補のフォントを読み込めたかどうかも返します。
+pub fn japanese_font_definitions(
+    candidates: impl IntoIterator<Item = PathBuf>,
+) -> (egui::FontDefinitions, bool) {
+    let (font_data, found) = match candidates
+        .into_iter()
+        .find_map(|path| std::fs::read(&path).ok().map(|data| (path, data)))
+    {
+        Some((path, data)) => {
+            debug!("日本語フォントを読み込みました: {}", path.display());
+            (egui::FontData::from_owned(data), true)
+        }
+        None => {
+            warn!("日本語フォントが見つからないため、埋め込みのフォントを使います（一部の漢字は表示されません）");
+            (egui::FontData::from_static(EMBEDDED_JAPANESE_FONT), false)
+        }
+    };
+
+    let mut fonts = egui::FontDefinitions::default();
+    fonts
+        .font_data
+        .insert(JAPANESE_FONT.to_owned(), font_data.into());
+    fonts
+        .families
+        .entry(egui::FontFamily::Proportional)
+        .or_default()
+        .insert(0, JAPANESE_FONT.to_owned());
+    fonts
+        .families
+        .entry(egui::FontFamily::Monospace)
+        .or_default()
+        .push(JAPANESE_FONT.to_owned());
+    (fonts, found)
+}
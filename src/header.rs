@@ -0,0 +1,652 @@
+//! 暗号化データのヘッダー
+//!
+//! 標準形式: マジックナンバー(7) + バージョン(1) + フラグ(1) + キー導出ブロック + メタデータブロック + ナンス(12) + 暗号文
+//! キー導出ブロック: アルゴリズム識別子(1) + パラメータ + ソルト(16)
+//! メタデータブロック: 有無(1) + [パーミッション(4) + 更新日時の秒(8) + ナノ秒(4)]
+//! ヒントブロック（ヒントのフラグがある場合のみ）: 長さ(1) + パスワードのヒント（UTF-8）
+//! 相対パスブロック（相対パスのフラグがある場合のみ）: 長さ(2) + 元のディレクトリからの相対パス（UTF-8、`/` 区切り）
+//! 圧縮ブロック（圧縮のフラグがある場合のみ）: 展開後のバイト数(8)
+//!
+//! チェックサムのフラグがある場合、平文の末尾に元の平文のSHA-256(32)を付けてから暗号化します
+//! （圧縮とパディングはその後に行います）。ヘッダーにそのまま記録すると推測した平文が正しいかを
+//! 確かめられてしまうため、暗号文の中に置きます。
+//!
+//! 圧縮のフラグがある場合は、平文を Deflate で圧縮してから暗号化します。復号化では圧縮ブロックの
+//! バイト数を超えて展開しません。
+//!
+//! バージョン1のヘッダーにはフラグとメタデータブロックが、バージョン2にはフラグがありません。
+//!
+//! ナンスより前のヘッダー全体を暗号文の追加認証データにするため、
+//! パラメータを書き換えると復号化に失敗します。パスワードのヒントは暗号化せずに記録するため
+//! 誰でも読めますが、同じく認証されるため書き換えは検出されます。
+
+use crate::config::Config;
+use crate::error::{CryptoError, Result};
+use crate::key_derivation::{derive_key_with_pepper, generate_salt, KdfParams, Pepper, SALT_SIZE};
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+// ファイルのメタデータの取得・適用用
+#[cfg(feature = "std-fs")]
+use crate::error::IoResultExt;
+#[cfg(feature = "std-fs")]
+use filetime::FileTime;
+#[cfg(feature = "std-fs")]
+use std::{fs, path::Path};
+
+/// 標準形式のマジックナンバー
+pub const FILE_MAGIC: &[u8; 7] = b"MYCRYPT";
+
+/// 標準形式のバージョン
+pub const FORMAT_VERSION: u8 = 3;
+
+/// AES-GCMのナンスのサイズ（バイト）
+pub const NONCE_SIZE: usize = 12;
+
+/// フラグ: 平文に長さを隠すためのパディングが付いている
+const FLAG_PADDED: u8 = 0x01;
+
+/// フラグ: メタデータブロックの後にパスワードのヒントが続く
+const FLAG_HINT: u8 = 0x02;
+
+/// フラグ: 平文の末尾に元の平文のSHA-256が付いている（復号化後に検証して取り除く）
+const FLAG_CHECKSUM: u8 = 0x04;
+
+/// フラグ: ヒントブロックの後に元のディレクトリからの相対パスが続く
+const FLAG_PATH: u8 = 0x08;
+
+/// フラグ: 平文が Deflate で圧縮されている（復号化後に展開する）
+const FLAG_COMPRESSED: u8 = 0x10;
+
+/// 記録できる相対パスの最大バイト数（UTF-8）
+pub const MAX_PATH_LEN: usize = 4096;
+
+/// パスワードのヒントの最大バイト数（UTF-8）
+pub const MAX_HINT_LEN: usize = u8::MAX as usize;
+
+/// パスワードのヒントとして記録できるか検証
+pub fn validate_hint(hint: &str) -> Result<()> {
+    if hint.is_empty() || hint.len() > MAX_HINT_LEN {
+        return Err(CryptoError::InvalidInput(format!(
+            "ヒントは1〜{MAX_HINT_LEN}バイトで指定してください: {} バイト",
+            hint.len()
+        )));
+    }
+    if hint.chars().any(char::is_control) {
+        return Err(CryptoError::InvalidInput(
+            "ヒントに制御文字は使用できません".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// データが標準形式のヘッダーで始まっているか
+pub fn has_magic(data: &[u8]) -> bool {
+    data.starts_with(FILE_MAGIC)
+}
+
+/// キー導出のアルゴリズム・パラメータとソルト
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KdfBlock {
+    pub params: KdfParams,
+    pub salt: [u8; SALT_SIZE],
+}
+
+impl KdfBlock {
+    /// 設定のアルゴリズムとソルトで作成（設定にソルトがなければランダムに生成）
+    pub fn new(config: &Config) -> Self {
+        Self {
+            params: KdfParams::from_config(config),
+            salt: config.salt.unwrap_or_else(generate_salt),
+        }
+    }
+
+    /// パスワードからキーを導出
+    pub fn derive_key(&self, password: &str) -> Result<[u8; 32]> {
+        self.derive_key_with_pepper(password, None)
+    }
+
+    /// パスワードからキーを導出（ペッパーを使うパラメータでは `pepper` を混ぜる）
+    pub fn derive_key_with_pepper(
+        &self,
+        password: &str,
+        pepper: Option<&Pepper>,
+    ) -> Result<[u8; 32]> {
+        derive_key_with_pepper(password, &self.salt, &self.params, pepper)
+    }
+
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        self.params.write_to(out);
+        out.extend_from_slice(&self.salt);
+    }
+
+    pub(crate) fn read_from(input: &mut &[u8]) -> Result<Self> {
+        let params = KdfParams::read_from(input)?;
+        let Some((salt, rest)) = input.split_first_chunk::<SALT_SIZE>() else {
+            return Err(CryptoError::Truncated(
+                "ヘッダーのソルトが途中で切れています".to_string(),
+            ));
+        };
+        *input = rest;
+        Ok(Self {
+            params,
+            salt: *salt,
+        })
+    }
+}
+
+/// 導出済みのキー（導出中はロックされている）
+type KeySlot = Arc<Mutex<Option<[u8; 32]>>>;
+
+/// 1つのパスワードで導出したキーを、キー導出ブロックごとに再利用するキャッシュ
+///
+/// 複数のファイルを同じパスワードで処理する場合に、重いキー導出を一度で済ませるために使います。
+/// 暗号化では全ファイルで同じキー導出ブロック（ソルト）を共有し、復号化では同じブロックを
+/// 持つファイルのキーを使い回します。
+pub struct KeyCache {
+    password: String,
+    shared: OnceLock<KdfBlock>,
+    /// キー導出ブロックごとのキー（ブロックごとにロックし、異なるブロックは並列に導出できる）
+    keys: Mutex<Vec<(KdfBlock, KeySlot)>>,
+    memory_budget: Option<MemoryBudget>,
+    pepper: Option<Pepper>,
+}
+
+impl KeyCache {
+    pub fn new(password: &str) -> Self {
+        Self {
+            password: password.to_string(),
+            shared: OnceLock::new(),
+            keys: Mutex::new(Vec::new()),
+            memory_budget: None,
+            pepper: None,
+        }
+    }
+
+    /// 設定のペッパー（`Config::pepper`）をキー導出に使うキャッシュを作成
+    pub fn from_config(password: &str, config: &Config) -> Self {
+        Self::new(password).with_pepper(config.pepper.clone())
+    }
+
+    /// ペッパーを使うパラメータのキー導出に `pepper` を混ぜる
+    pub fn with_pepper(mut self, pepper: Option<Pepper>) -> Self {
+        self.pepper = pepper;
+        self
+    }
+
+    /// 同時に行うキー導出のメモリ使用量の合計を `bytes` までに制限する
+    ///
+    /// 上限を超える場合、新しいキー導出は他の導出が終わるまで待ちます。
+    /// 1回で上限を超えるキー導出は、他の導出が行われていないときに単独で行います。
+    pub fn with_memory_budget(mut self, bytes: u64) -> Self {
+        self.memory_budget = Some(MemoryBudget {
+            limit: bytes,
+            in_use: Mutex::new(0),
+            released: Condvar::new(),
+        });
+        self
+    }
+
+    pub fn password(&self) -> &str {
+        &self.password
+    }
+
+    /// 暗号化で共有するキー導出ブロック（最初の呼び出しで設定から作成）
+    pub fn kdf_block(&self, config: &Config) -> &KdfBlock {
+        self.shared.get_or_init(|| KdfBlock::new(config))
+    }
+
+    /// キー導出ブロックに対応するキー（導出済みならキャッシュから返す）
+    ///
+    /// 同じブロックを複数のスレッドが同時に要求した場合、導出は1回だけ行い、他のスレッドはその結果を待ちます。
+    pub fn derive(&self, kdf: &KdfBlock) -> Result<[u8; 32]> {
+        let slot = {
+            let mut keys = self.keys.lock().expect("キーキャッシュのロックに失敗");
+            match keys.iter().find(|(block, _)| block == kdf) {
+                Some((_, slot)) => Arc::clone(slot),
+                None => {
+                    let slot = Arc::new(Mutex::new(None));
+                    keys.push((kdf.clone(), Arc::clone(&slot)));
+                    slot
+                }
+            }
+        };
+
+        let mut key = slot.lock().expect("キーキャッシュのロックに失敗");
+        if let Some(key) = *key {
+            return Ok(key);
+        }
+        // 信頼できないヘッダーのパラメータで、メモリ使用量を見積もる前に確認する
+        kdf.params.validate()?;
+        let _permit = self
+            .memory_budget
+            .as_ref()
+            .map(|budget| budget.acquire(kdf.params.memory_bytes()));
+        let derived = kdf.derive_key_with_pepper(&self.password, self.pepper.as_ref())?;
+        *key = Some(derived);
+        Ok(derived)
+    }
+}
+
+/// 並列に行うキー導出のメモリ使用量の上限（`KeyCache::with_memory_budget`）
+struct MemoryBudget {
+    limit: u64,
+    /// 導出中のキー導出が使用しているメモリの合計（バイト）
+    in_use: Mutex<u64>,
+    released: Condvar,
+}
+
+impl MemoryBudget {
+    /// `bytes` を使うキー導出を、上限に収まるまで待ってから開始する
+    fn acquire(&self, bytes: u64) -> MemoryPermit<'_> {
+        let mut in_use = self.in_use.lock().expect("メモリ使用量のロックに失敗");
+        while *in_use > 0 && in_use.saturating_add(bytes) > self.limit {
+            in_use = self
+                .released
+                .wait(in_use)
+                .expect("メモリ使用量のロックに失敗");
+        }
+        *in_use = in_use.saturating_add(bytes);
+        MemoryPermit {
+            budget: self,
+            bytes,
+        }
+    }
+}
+
+/// キー導出が終わったら（失敗した場合も）使用量を戻す
+struct MemoryPermit<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl Drop for MemoryPermit<'_> {
+    fn drop(&mut self) {
+        let mut in_use = self.budget.in_use.lock().unwrap_or_else(|e| e.into_inner());
+        *in_use = in_use.saturating_sub(self.bytes);
+        self.budget.released.notify_all();
+    }
+}
+
+/// メタデータブロックのバイト数（有無の1バイトを除く）
+pub(crate) const METADATA_SIZE: usize = 16;
+
+/// 元ファイルのパーミッションと更新日時
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// パーミッション（Unix以外では読み取り専用なら 0o444、それ以外は 0o644）
+    pub mode: u32,
+    /// 更新日時（UNIXエポックからの秒）
+    pub modified_secs: i64,
+    /// 更新日時のナノ秒部分
+    pub modified_nanos: u32,
+}
+
+impl FileMetadata {
+    /// ファイルのメタデータから作成
+    #[cfg(feature = "std-fs")]
+    pub fn from_fs(metadata: &fs::Metadata) -> Self {
+        let modified = FileTime::from_last_modification_time(metadata);
+        Self {
+            mode: permissions_mode(&metadata.permissions()),
+            modified_secs: modified.unix_seconds(),
+            modified_nanos: modified.nanoseconds(),
+        }
+    }
+
+    /// パーミッションと更新日時をファイルに適用
+    ///
+    /// 読み取り専用にすると更新日時を変更できない環境があるため、更新日時を先に設定します。
+    #[cfg(feature = "std-fs")]
+    pub fn apply(&self, path: &Path) -> Result<()> {
+        let modified = FileTime::from_unix_time(self.modified_secs, self.modified_nanos);
+        filetime::set_file_mtime(path, modified)
+            .with_io_context(|| format!("更新日時の設定に失敗: {}", path.display()))?;
+
+        let mut permissions = fs::metadata(path)
+            .with_io_context(|| format!("ファイル情報の取得に失敗: {}", path.display()))?
+            .permissions();
+        set_permissions_mode(&mut permissions, self.mode);
+        fs::set_permissions(path, permissions)
+            .with_io_context(|| format!("パーミッションの設定に失敗: {}", path.display()))
+    }
+
+    /// 有無(1) + パーミッション(4) + 秒(8) + ナノ秒(4) の形式で書き込む
+    pub(crate) fn write_to(metadata: Option<&Self>, out: &mut Vec<u8>) {
+        match metadata {
+            Some(metadata) => {
+                out.push(1);
+                out.extend_from_slice(&metadata.mode.to_le_bytes());
+                out.extend_from_slice(&metadata.modified_secs.to_le_bytes());
+                out.extend_from_slice(&metadata.modified_nanos.to_le_bytes());
+            }
+            None => out.push(0),
+        }
+    }
+
+    /// `write_to` の形式を読み込み、読み込んだ分だけ `input` を進める
+    pub(crate) fn read_from(input: &mut &[u8]) -> Result<Option<Self>> {
+        let (&present, rest) = input.split_first().ok_or_else(metadata_truncated)?;
+        *input = rest;
+        match present {
+            0 => Ok(None),
+            1 => {
+                let Some((bytes, rest)) = input.split_first_chunk::<METADATA_SIZE>() else {
+                    return Err(metadata_truncated());
+                };
+                *input = rest;
+                Ok(Some(Self::from_bytes(bytes)))
+            }
+            _ => Err(CryptoError::InvalidFormat(
+                "ヘッダーのメタデータが不正です".to_string(),
+            )),
+        }
+    }
+
+    /// 有無のバイトを除いたメタデータブロックを復元
+    pub(crate) fn from_bytes(bytes: &[u8; METADATA_SIZE]) -> Self {
+        let (mode, rest) = bytes.split_first_chunk::<4>().expect("4バイト以上");
+        let (secs, nanos) = rest.split_first_chunk::<8>().expect("8バイト以上");
+        Self {
+            mode: u32::from_le_bytes(*mode),
+            modified_secs: i64::from_le_bytes(*secs),
+            modified_nanos: u32::from_le_bytes(nanos.try_into().expect("4バイト")),
+        }
+    }
+}
+
+fn metadata_truncated() -> CryptoError {
+    CryptoError::Truncated("ヘッダーのメタデータが途中で切れています".to_string())
+}
+
+#[cfg(all(feature = "std-fs", unix))]
+fn permissions_mode(permissions: &fs::Permissions) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.mode() & 0o7777
+}
+
+#[cfg(all(feature = "std-fs", not(unix)))]
+fn permissions_mode(permissions: &fs::Permissions) -> u32 {
+    if permissions.readonly() {
+        0o444
+    } else {
+        0o644
+    }
+}
+
+/// setuid などの特殊ビットは復元しない
+#[cfg(all(feature = "std-fs", unix))]
+fn set_permissions_mode(permissions: &mut fs::Permissions, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    permissions.set_mode(mode & 0o777);
+}
+
+/// Unix以外では読み取り専用かどうかだけを復元
+#[cfg(all(feature = "std-fs", not(unix)))]
+fn set_permissions_mode(permissions: &mut fs::Permissions, mode: u32) {
+    permissions.set_readonly(mode & 0o222 == 0);
+}
+
+/// 標準形式のヘッダー
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHeader {
+    pub kdf: KdfBlock,
+    /// 元ファイルのメタデータ（文字列の暗号化や保持しない設定の場合は `None`）
+    pub metadata: Option<FileMetadata>,
+    /// 平文にパディングが付いているか（復号化後に取り除く）
+    pub padded: bool,
+    /// パスワードのヒント（暗号化せずに記録する）
+    pub hint: Option<String>,
+    /// 平文の末尾に元の平文のSHA-256が付いているか（復号化後に検証する）
+    pub checksum: bool,
+    /// 再帰的に暗号化した場合の、元のディレクトリからの相対パス（`/` 区切り）
+    pub path: Option<String>,
+    /// 平文を圧縮した場合の展開後のバイト数（復号化後に展開する）
+    pub compressed: Option<u64>,
+}
+
+impl FileHeader {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            kdf: KdfBlock::new(config),
+            metadata: None,
+            padded: false,
+            hint: config.hint.clone(),
+            checksum: config.checksum,
+            path: None,
+            compressed: None,
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes_with_version(FORMAT_VERSION)
+    }
+
+    /// 指定したバージョンの形式で書き出す
+    ///
+    /// バージョン1・2にはフラグがないため、パディング・ヒント・チェックサム・相対パス・圧縮は書き出されません
+    /// （バージョン1はメタデータも書き出されません）。
+    fn to_bytes_with_version(&self, version: u8) -> Vec<u8> {
+        let mut out = FILE_MAGIC.to_vec();
+        out.push(version);
+        if version < 3 {
+            self.kdf.write_to(&mut out);
+            if version == 2 {
+                FileMetadata::write_to(self.metadata.as_ref(), &mut out);
+            }
+            return out;
+        }
+
+        let mut flags = 0;
+        if self.padded {
+            flags |= FLAG_PADDED;
+        }
+        if self.hint.is_some() {
+            flags |= FLAG_HINT;
+        }
+        if self.checksum {
+            flags |= FLAG_CHECKSUM;
+        }
+        if self.path.is_some() {
+            flags |= FLAG_PATH;
+        }
+        if self.compressed.is_some() {
+            flags |= FLAG_COMPRESSED;
+        }
+
+        out.push(flags);
+        self.kdf.write_to(&mut out);
+        FileMetadata::write_to(self.metadata.as_ref(), &mut out);
+        if let Some(hint) = &self.hint {
+            // 長さは validate_hint で検証済み（上限を超える場合は文字の境界で切り詰める）
+            let len = (0..=hint.len().min(MAX_HINT_LEN))
+                .rev()
+                .find(|&len| hint.is_char_boundary(len))
+                .unwrap_or(0);
+            out.push(len as u8);
+            out.extend_from_slice(&hint.as_bytes()[..len]);
+        }
+        if let Some(path) = &self.path {
+            // 長さは記録する前に検証済み
+            let len = path.len().min(MAX_PATH_LEN);
+            out.extend_from_slice(&(len as u16).to_le_bytes());
+            out.extend_from_slice(&path.as_bytes()[..len]);
+        }
+        if let Some(len) = self.compressed {
+            out.extend_from_slice(&len.to_le_bytes());
+        }
+        out
+    }
+
+    /// データ先頭のヘッダーを解析し、ヘッダーとそのバイト数を返す
+    pub fn parse(data: &[u8]) -> Result<(Self, usize)> {
+        if !has_magic(data) {
+            return Err(CryptoError::InvalidFormat(
+                "ヘッダーのマジックナンバーが不正です".to_string(),
+            ));
+        }
+
+        let mut input = &data[FILE_MAGIC.len()..];
+        let (&version, rest) = input
+            .split_first()
+            .ok_or_else(|| CryptoError::Truncated("ヘッダーが途中で切れています".to_string()))?;
+        if !(1..=FORMAT_VERSION).contains(&version) {
+            return Err(CryptoError::InvalidFormat(format!(
+                "対応していない形式のバージョンです: {version}"
+            )));
+        }
+        input = rest;
+
+        let flags = if version >= 3 {
+            let (&flags, rest) = input.split_first().ok_or_else(|| {
+                CryptoError::Truncated("ヘッダーが途中で切れています".to_string())
+            })?;
+            input = rest;
+            flags
+        } else {
+            0
+        };
+        if flags & !(FLAG_PADDED | FLAG_HINT | FLAG_CHECKSUM | FLAG_PATH | FLAG_COMPRESSED) != 0 {
+            return Err(CryptoError::InvalidFormat(format!(
+                "ヘッダーに不明なフラグがあります: {flags:#04x}"
+            )));
+        }
+
+        let kdf = KdfBlock::read_from(&mut input)?;
+        let metadata = if version >= 2 {
+            FileMetadata::read_from(&mut input)?
+        } else {
+            None
+        };
+        let hint = if flags & FLAG_HINT != 0 {
+            Some(read_hint(&mut input)?)
+        } else {
+            None
+        };
+        let path = if flags & FLAG_PATH != 0 {
+            Some(read_path(&mut input)?)
+        } else {
+            None
+        };
+        let compressed = if flags & FLAG_COMPRESSED != 0 {
+            let Some((len, rest)) = input.split_first_chunk::<8>() else {
+                return Err(CryptoError::Truncated(
+                    "ヘッダーの圧縮ブロックが途中で切れています".to_string(),
+                ));
+            };
+            input = rest;
+            Some(u64::from_le_bytes(*len))
+        } else {
+            None
+        };
+        let len = data.len() - input.len();
+        Ok((
+            Self {
+                kdf,
+                metadata,
+                padded: flags & FLAG_PADDED != 0,
+                hint,
+                checksum: flags & FLAG_CHECKSUM != 0,
+                path,
+                compressed,
+            },
+            len,
+        ))
+    }
+}
+
+/// 標準形式の暗号化データ全体（ヘッダー + ナンス + 暗号文）
+///
+/// `encrypt_bytes` の出力を構造として扱うための型です。`parse` と `to_bytes` は互いに逆の変換で、
+/// ヘッダーのバイト列は暗号文の追加認証データになるため、解析したデータを書き戻すと元と同じになります。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncryptedFile {
+    /// 形式のバージョン（1〜`FORMAT_VERSION`、新しく暗号化したデータは `FORMAT_VERSION`）
+    pub version: u8,
+    pub header: FileHeader,
+    pub nonce: [u8; NONCE_SIZE],
+    /// AES-256-GCMの暗号文（末尾に16バイトの認証タグを含む）
+    pub ciphertext: Vec<u8>,
+}
+
+impl EncryptedFile {
+    /// 標準形式のデータを解析（復号化はしないため、パスワードは不要）
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let view = EncryptedView::parse(data)?;
+        Ok(Self {
+            version: view.version,
+            header: view.header,
+            nonce: view.nonce,
+            ciphertext: view.ciphertext.to_vec(),
+        })
+    }
+
+    /// 追加認証データにするヘッダー部分のバイト列
+    pub fn header_bytes(&self) -> Vec<u8> {
+        self.header.to_bytes_with_version(self.version)
+    }
+
+    /// ヘッダー + ナンス + 暗号文を結合
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = self.header_bytes();
+        out.extend_from_slice(&self.nonce);
+        out.extend_from_slice(&self.ciphertext);
+        out
+    }
+}
+
+/// 標準形式のデータを暗号文をコピーせずに解析した結果
+pub(crate) struct EncryptedView<'a> {
+    pub(crate) version: u8,
+    pub(crate) header: FileHeader,
+    /// 元のデータのヘッダー部分
+    pub(crate) header_bytes: &'a [u8],
+    pub(crate) nonce: [u8; NONCE_SIZE],
+    pub(crate) ciphertext: &'a [u8],
+}
+
+impl<'a> EncryptedView<'a> {
+    pub(crate) fn parse(data: &'a [u8]) -> Result<Self> {
+        let (header, header_len) = FileHeader::parse(data)?;
+        let (header_bytes, body) = data.split_at(header_len);
+        let Some((nonce, ciphertext)) = body.split_first_chunk::<NONCE_SIZE>() else {
+            return Err(CryptoError::Truncated(format!(
+                "データが短すぎます（最低{NONCE_SIZE}バイトのナンスが必要）"
+            )));
+        };
+        Ok(Self {
+            // バージョンは FileHeader::parse で検証済み
+            version: header_bytes[FILE_MAGIC.len()],
+            header,
+            header_bytes,
+            nonce: *nonce,
+            ciphertext,
+        })
+    }
+}
+
+/// ヒントブロック（長さ + UTF-8）を読み込み、読み込んだ分だけ `input` を進める
+fn read_hint(input: &mut &[u8]) -> Result<String> {
+    let truncated = || CryptoError::Truncated("ヘッダーのヒントが途中で切れています".to_string());
+    let (&len, rest) = input.split_first().ok_or_else(truncated)?;
+    let (bytes, rest) = rest.split_at_checked(len as usize).ok_or_else(truncated)?;
+    *input = rest;
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| CryptoError::InvalidFormat("ヘッダーのヒントが不正です".to_string()))
+}
+
+/// 相対パスブロック（長さ + UTF-8）を読み込み、読み込んだ分だけ `input` を進める
+///
+/// 内容が安全なパスかどうかはここでは検証しません（復元する側で検証します）。
+fn read_path(input: &mut &[u8]) -> Result<String> {
+    let truncated = || CryptoError::Truncated("ヘッダーの相対パスが途中で切れています".to_string());
+    let (len, rest) = input.split_first_chunk::<2>().ok_or_else(truncated)?;
+    let len = u16::from_le_bytes(*len) as usize;
+    if len > MAX_PATH_LEN {
+        return Err(CryptoError::InvalidFormat(
+            "ヘッダーの相対パスが長すぎます".to_string(),
+        ));
+    }
+    let (bytes, rest) = rest.split_at_checked(len).ok_or_else(truncated)?;
+    *input = rest;
+    String::from_utf8(bytes.to_vec())
+        .map_err(|_| CryptoError::InvalidFormat("ヘッダーの相対パスが不正です".to_string()))
+}
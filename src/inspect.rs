@@ -0,0 +1,247 @@
+//! 暗号化データのヘッダー情報の解析（デバッグ用）
+//!
+//! パスワードは不要で、キー導出や復号化は一切行いません。ヘッダーを読み取るだけなので、
+//! 表示される内容は改ざんされていないことが保証されたものではありません。
+
+use crate::armor::{dearmor, is_armored};
+use crate::config::OutputFormat;
+use crate::crypto::{decode_encoded_file, is_encoded_file};
+use crate::envelope::{envelope_header, is_envelope};
+use crate::error::{CryptoError, IoResultExt, Result};
+use crate::file_ops::{
+    read_stream_header, GCM_TAG_SIZE, STREAM_FOOTER_MARKER, STREAM_MAC_SIZE, STREAM_MAGIC,
+};
+use crate::header::{has_magic, FileHeader, FileMetadata, FILE_MAGIC};
+use crate::key_derivation::{KdfParams, SALT_SIZE};
+use crate::volume::{VolumeReader, VOLUME_MAGIC};
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// ヘッダーを判別するために先頭から読み込むバイト数（エンベロープの最大スロット数でも収まる）
+const HEAD_LEN: u64 = 64 * 1024;
+
+/// 暗号化データのヘッダー情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileInfo {
+    /// ASCIIアーマー形式で保存されているか
+    pub armored: bool,
+    /// 設定の `file_format` でBase64 / 16進数の文字列として保存されている場合は、その形式
+    pub encoding: Option<OutputFormat>,
+    /// 暗号化データのバイト数（アーマー形式・文字列の場合は解除後）
+    pub size: u64,
+    /// ヘッダーのバイト数
+    pub header_len: usize,
+    /// キー導出のアルゴリズムとパラメータ
+    pub kdf: KdfParams,
+    /// キー導出のソルト（エンベロープ形式はスロットごとに異なるため `None`）
+    pub salt: Option<[u8; SALT_SIZE]>,
+    /// 記録されている元ファイルのメタデータ
+    pub metadata: Option<FileMetadata>,
+    /// 形式ごとの情報
+    pub format: FormatInfo,
+}
+
+/// 形式ごとのヘッダー情報
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatInfo {
+    Standard {
+        version: u8,
+        /// 平文にパディングが付いているか
+        padded: bool,
+        /// パスワードのヒント
+        hint: Option<String>,
+        /// 平文のSHA-256が記録されているか
+        checksum: bool,
+        /// 再帰的に暗号化した場合の、元のディレクトリからの相対パス
+        path: Option<String>,
+        /// 平文を圧縮した場合の展開後のバイト数
+        compressed: Option<u64>,
+        /// 暗号文（認証タグを含む）のバイト数
+        ciphertext_len: u64,
+    },
+    Streaming {
+        chunk_size: usize,
+        /// 読み取れたチャンクの数
+        chunk_count: u64,
+        /// フッターまで揃っていて、その後に余分なデータがないか（`false` なら途中で切れている）
+        complete: bool,
+        /// 分割したボリュームの場合は、その数
+        volumes: Option<u32>,
+    },
+    Envelope {
+        /// パスワードのスロット数
+        slots: usize,
+    },
+}
+
+/// 暗号化ファイルのヘッダー情報を読み取る
+///
+/// ストリーミング形式はチャンクの長さをたどってチャンク数を数えますが、中身は読み込みません。
+/// 分割したボリュームは全ボリュームをつなげて読み取ります（`size` は全ボリュームのデータの合計です）。
+pub fn inspect_file(path: &Path) -> Result<FileInfo> {
+    let context = || format!("暗号化ファイルの読み込みに失敗: {}", path.display());
+    let mut file = File::open(path).with_io_context(context)?;
+    let size = file.metadata().with_io_context(context)?.len();
+
+    let mut head = Vec::new();
+    (&mut file)
+        .take(HEAD_LEN)
+        .read_to_end(&mut head)
+        .with_io_context(context)?;
+
+    if head.starts_with(STREAM_MAGIC) {
+        file.seek(SeekFrom::Start(0)).with_io_context(context)?;
+        return inspect_stream(&mut BufReader::new(file), size);
+    }
+    if head.starts_with(VOLUME_MAGIC) {
+        let mut reader = VolumeReader::open(path)?;
+        let (count, size) = (reader.count(), reader.len());
+        let mut info = inspect_stream(&mut reader, size)?;
+        if let FormatInfo::Streaming { volumes, .. } = &mut info.format {
+            *volumes = Some(count);
+        }
+        return Ok(info);
+    }
+    if is_armored(&head) || is_encoded_file(&head) {
+        file.read_to_end(&mut head).with_io_context(context)?;
+        return inspect_bytes(&head);
+    }
+    inspect_header(&head, size)
+}
+
+/// メモリ上の暗号化データのヘッダー情報を読み取る（アーマー形式・文字列で保存したデータにも対応）
+pub fn inspect_bytes(data: &[u8]) -> Result<FileInfo> {
+    if is_armored(data) {
+        let info = inspect_bytes(&dearmor(data)?)?;
+        return Ok(FileInfo {
+            armored: true,
+            ..info
+        });
+    }
+    if data.starts_with(STREAM_MAGIC) {
+        return inspect_stream(&mut Cursor::new(data), data.len() as u64);
+    }
+    if !has_magic(data) && !is_envelope(data) {
+        if let Some((format, decoded)) = decode_encoded_file(data) {
+            let info = inspect_header(&decoded, decoded.len() as u64)?;
+            return Ok(FileInfo {
+                encoding: Some(format),
+                ..info
+            });
+        }
+    }
+    inspect_header(data, data.len() as u64)
+}
+
+/// 標準形式・エンベロープ形式のヘッダーを解析（`head` はデータの先頭、`size` は全体のサイズ）
+fn inspect_header(head: &[u8], size: u64) -> Result<FileInfo> {
+    if is_envelope(head) {
+        let (kdf, metadata, slots, header_len) = envelope_header(head)?;
+        return Ok(FileInfo {
+            armored: false,
+            encoding: None,
+            size,
+            header_len,
+            kdf,
+            salt: None,
+            metadata,
+            format: FormatInfo::Envelope { slots },
+        });
+    }
+    if !has_magic(head) {
+        return Err(CryptoError::NotEncrypted);
+    }
+
+    let (header, header_len) = FileHeader::parse(head)?;
+    let version = head[FILE_MAGIC.len()];
+    let ciphertext_len = size
+        .checked_sub(header_len as u64 + 12)
+        .ok_or_else(|| CryptoError::Truncated("ナンスが途中で切れています".to_string()))?;
+    Ok(FileInfo {
+        armored: false,
+        encoding: None,
+        size,
+        header_len,
+        kdf: header.kdf.params,
+        salt: Some(header.kdf.salt),
+        metadata: header.metadata,
+        format: FormatInfo::Standard {
+            version,
+            padded: header.padded,
+            hint: header.hint,
+            checksum: header.checksum,
+            path: header.path,
+            compressed: header.compressed,
+            ciphertext_len,
+        },
+    })
+}
+
+/// ストリーミング形式のヘッダーを解析し、チャンクの長さをたどってチャンク数を数える
+fn inspect_stream<R: Read + Seek>(reader: &mut R, size: u64) -> Result<FileInfo> {
+    let header = read_stream_header(reader)?;
+    let max_chunk_len = (header.chunk_size + GCM_TAG_SIZE) as u64;
+
+    let mut position = header.bytes.len() as u64;
+    let mut chunk_count = 0u64;
+    let complete = loop {
+        // ナンス(12) + 長さ(4)、またはフッターの目印(12) + HMAC(32)
+        let mut record = [0u8; 16];
+        let read = read_up_to(reader, &mut record).io_context("チャンクの読み込みに失敗")?;
+        if read >= STREAM_FOOTER_MARKER.len()
+            && &record[..STREAM_FOOTER_MARKER.len()] == STREAM_FOOTER_MARKER
+        {
+            break position + (STREAM_FOOTER_MARKER.len() + STREAM_MAC_SIZE) as u64 == size;
+        }
+        if read < record.len() {
+            break false;
+        }
+
+        let len = u32::from_le_bytes(record[12..].try_into().expect("4バイト")) as u64;
+        if !(GCM_TAG_SIZE as u64..=max_chunk_len).contains(&len) {
+            return Err(CryptoError::InvalidFormat(format!(
+                "{} 番目のチャンク長が不正です: {len} バイト",
+                chunk_count + 1
+            )));
+        }
+        position += record.len() as u64 + len;
+        if position > size {
+            break false;
+        }
+        reader
+            .seek(SeekFrom::Start(position))
+            .io_context("チャンクの読み飛ばしに失敗")?;
+        chunk_count += 1;
+    };
+
+    Ok(FileInfo {
+        armored: false,
+        encoding: None,
+        size,
+        header_len: header.bytes.len(),
+        kdf: header.kdf.params,
+        salt: Some(header.kdf.salt),
+        metadata: header.metadata,
+        format: FormatInfo::Streaming {
+            chunk_size: header.chunk_size,
+            chunk_count,
+            complete,
+            volumes: None,
+        },
+    })
+}
+
+/// バッファが埋まるか入力が終わるまで読み込み、読み込んだバイト数を返す
+fn read_up_to<R: Read>(reader: &mut R, buffer: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
@@ -1,24 +1,623 @@
 use crate::base64_encode;
 use crate::config::{Argon2Config, Config};
-use anyhow::{Result, anyhow};
+use crate::error::{CryptoError, Result};
+use crate::timing::{record_phase, Phase};
 use argon2::Argon2;
+#[cfg(feature = "std-fs")]
+use hmac::{Hmac, Mac};
+use log::{debug, info, warn};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::cell::RefCell;
+use std::fmt;
 use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use zeroize::Zeroizing;
 
-/// Argon2を使用してパスワードから安全なキーを導出
+/// ソルトのサイズ（バイト）
+pub const SALT_SIZE: usize = 16;
+
+/// ペッパーを使って導出したことを示す、ヘッダーの識別子の最上位ビット（Argon2のみ）
+const PEPPER_FLAG: u8 = 0x80;
+
+/// Argon2に混ぜる秘密の値（ペッパー）
+///
+/// 暗号化データには記録しないため、暗号化ファイルとパスワードだけでは復号化できなくなります。
+/// 破棄時にメモリ上から消去し、`Debug` でも内容は表示しません。
+#[derive(Clone)]
+pub struct Pepper(Zeroizing<Vec<u8>>);
+
+impl Pepper {
+    /// 任意のバイト列からペッパーを作成（空のバイト列は不可）
+    pub fn new(bytes: Vec<u8>) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(CryptoError::InvalidInput("ペッパーが空です".to_string()));
+        }
+        Ok(Self(Zeroizing::new(bytes)))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Pepper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Pepper(..)")
+    }
+}
+
+/// キー導出アルゴリズム
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KdfAlgorithm {
+    /// Argon2id（デフォルト、推奨）
+    #[default]
+    Argon2id,
+    /// PBKDF2-HMAC-SHA256（他ツールとの互換用）
+    Pbkdf2,
+    /// scrypt（他ツールとの互換用）
+    Scrypt,
+}
+
+impl fmt::Display for KdfAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KdfAlgorithm::Argon2id => "argon2id",
+            KdfAlgorithm::Pbkdf2 => "pbkdf2",
+            KdfAlgorithm::Scrypt => "scrypt",
+        })
+    }
+}
+
+impl FromStr for KdfAlgorithm {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "argon2id" | "argon2" => Ok(KdfAlgorithm::Argon2id),
+            "pbkdf2" => Ok(KdfAlgorithm::Pbkdf2),
+            "scrypt" => Ok(KdfAlgorithm::Scrypt),
+            _ => Err(CryptoError::InvalidInput(format!(
+                "不明なキー導出アルゴリズムです: {s}（argon2id / pbkdf2 / scrypt）"
+            ))),
+        }
+    }
+}
+
+/// Argon2の種類
+///
+/// 通常は Argon2id を使い、Argon2i / Argon2d はそれらで作られた既存のシステムとの互換用です。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Argon2Variant {
+    /// Argon2id（デフォルト、推奨）
+    #[default]
+    Argon2id,
+    /// Argon2i（サイドチャネル攻撃への耐性を重視）
+    Argon2i,
+    /// Argon2d（GPUによる総当たりへの耐性を重視）
+    Argon2d,
+}
+
+impl From<Argon2Variant> for argon2::Algorithm {
+    fn from(variant: Argon2Variant) -> Self {
+        match variant {
+            Argon2Variant::Argon2id => argon2::Algorithm::Argon2id,
+            Argon2Variant::Argon2i => argon2::Algorithm::Argon2i,
+            Argon2Variant::Argon2d => argon2::Algorithm::Argon2d,
+        }
+    }
+}
+
+impl fmt::Display for Argon2Variant {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Argon2Variant::Argon2id => "argon2id",
+            Argon2Variant::Argon2i => "argon2i",
+            Argon2Variant::Argon2d => "argon2d",
+        })
+    }
+}
+
+impl FromStr for Argon2Variant {
+    type Err = CryptoError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "argon2id" => Ok(Argon2Variant::Argon2id),
+            "argon2i" => Ok(Argon2Variant::Argon2i),
+            "argon2d" => Ok(Argon2Variant::Argon2d),
+            _ => Err(CryptoError::InvalidInput(format!(
+                "不明なArgon2の種類です: {s}（argon2id / argon2i / argon2d）"
+            ))),
+        }
+    }
+}
+
+/// キー導出アルゴリズムとそのパラメータ（ファイルヘッダーに記録する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfParams {
+    Argon2 {
+        variant: Argon2Variant,
+        memory_cost: u32,
+        time_cost: u32,
+        parallelism: u32,
+        /// ペッパーを混ぜて導出するか（ペッパー自体は記録しない）
+        peppered: bool,
+    },
+    Pbkdf2 {
+        iterations: u32,
+    },
+    Scrypt {
+        log_n: u8,
+        r: u32,
+        p: u32,
+    },
+}
+
+impl fmt::Display for KdfParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KdfParams::Argon2 {
+                variant,
+                memory_cost,
+                time_cost,
+                parallelism,
+                peppered,
+            } => write!(
+                f,
+                "{variant} (m={memory_cost} KB, t={time_cost}, p={parallelism}{})",
+                if *peppered { ", pepper" } else { "" }
+            ),
+            KdfParams::Pbkdf2 { iterations } => write!(f, "pbkdf2 (iterations={iterations})"),
+            KdfParams::Scrypt { log_n, r, p } => write!(f, "scrypt (log_n={log_n}, r={r}, p={p})"),
+        }
+    }
+}
+
+impl KdfParams {
+    /// 設定で選択されているアルゴリズムのパラメータ
+    pub fn from_config(config: &Config) -> Self {
+        match config.kdf {
+            KdfAlgorithm::Argon2id => KdfParams::Argon2 {
+                variant: config.argon2.variant,
+                memory_cost: config.argon2.memory_cost,
+                time_cost: config.argon2.time_cost,
+                parallelism: config.argon2.parallelism,
+                peppered: config.pepper.is_some(),
+            },
+            KdfAlgorithm::Pbkdf2 => KdfParams::Pbkdf2 {
+                iterations: config.pbkdf2.iterations,
+            },
+            KdfAlgorithm::Scrypt => KdfParams::Scrypt {
+                log_n: config.scrypt.log_n,
+                r: config.scrypt.r,
+                p: config.scrypt.p,
+            },
+        }
+    }
+
+    /// キー導出1回あたりのおおよそのメモリ使用量（バイト）
+    ///
+    /// ヘッダーから読み込んだ検証前のパラメータでも呼べるよう、`u64` に収まらない場合は `u64::MAX` を返します。
+    pub fn memory_bytes(&self) -> u64 {
+        match *self {
+            KdfParams::Argon2 { memory_cost, .. } => u64::from(memory_cost) * 1024,
+            KdfParams::Pbkdf2 { .. } => 0,
+            KdfParams::Scrypt { log_n, r, .. } => 1u64
+                .checked_shl(u32::from(log_n))
+                .and_then(|n| n.checked_mul(128 * u64::from(r)))
+                .unwrap_or(u64::MAX),
+        }
+    }
+
+    /// キー導出の実装が受け付けるパラメータか確認する（ヘッダーから読み込んだ値の検証用）
+    pub(crate) fn validate(&self) -> Result<()> {
+        let valid = match *self {
+            KdfParams::Argon2 {
+                memory_cost,
+                time_cost,
+                parallelism,
+                ..
+            } => argon2::Params::new(memory_cost, time_cost, parallelism, Some(32)).is_ok(),
+            KdfParams::Pbkdf2 { .. } => true,
+            KdfParams::Scrypt { log_n, r, p } => scrypt::Params::new(log_n, r, p, 32).is_ok(),
+        };
+        if valid {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidFormat(format!(
+                "キー導出のパラメータが不正です: {self}"
+            )))
+        }
+    }
+
+    /// 設定の `kdf` に対応するアルゴリズム
+    ///
+    /// Argon2の種類（Argon2i / Argon2d）は区別しないため、表示には `name` を使います。
+    pub fn algorithm(&self) -> KdfAlgorithm {
+        match self {
+            KdfParams::Argon2 { .. } => KdfAlgorithm::Argon2id,
+            KdfParams::Pbkdf2 { .. } => KdfAlgorithm::Pbkdf2,
+            KdfParams::Scrypt { .. } => KdfAlgorithm::Scrypt,
+        }
+    }
+
+    /// アルゴリズムの名前（Argon2は実際の種類: argon2id / argon2i / argon2d）
+    pub fn name(&self) -> &'static str {
+        match self {
+            KdfParams::Argon2 { variant, .. } => match variant {
+                Argon2Variant::Argon2id => "argon2id",
+                Argon2Variant::Argon2i => "argon2i",
+                Argon2Variant::Argon2d => "argon2d",
+            },
+            KdfParams::Pbkdf2 { .. } => "pbkdf2",
+            KdfParams::Scrypt { .. } => "scrypt",
+        }
+    }
+
+    /// ヘッダーに記録する識別子
+    ///
+    /// Argon2id は従来どおり 1 とし、Argon2i / Argon2d には別の識別子を割り当てます。
+    /// ペッパーを使う場合は最上位ビット（`PEPPER_FLAG`）を立てます。
+    fn id(&self) -> u8 {
+        match self {
+            KdfParams::Argon2 {
+                variant, peppered, ..
+            } => {
+                let id = match variant {
+                    Argon2Variant::Argon2id => 1,
+                    Argon2Variant::Argon2i => 4,
+                    Argon2Variant::Argon2d => 5,
+                };
+                if *peppered {
+                    id | PEPPER_FLAG
+                } else {
+                    id
+                }
+            }
+            KdfParams::Pbkdf2 { .. } => 2,
+            KdfParams::Scrypt { .. } => 3,
+        }
+    }
+
+    /// 識別子(1) + パラメータ（u32 はリトルエンディアン）の形式で書き込む
+    pub(crate) fn write_to(&self, out: &mut Vec<u8>) {
+        out.push(self.id());
+        match self {
+            KdfParams::Argon2 {
+                memory_cost,
+                time_cost,
+                parallelism,
+                ..
+            } => {
+                out.extend_from_slice(&memory_cost.to_le_bytes());
+                out.extend_from_slice(&time_cost.to_le_bytes());
+                out.extend_from_slice(&parallelism.to_le_bytes());
+            }
+            KdfParams::Pbkdf2 { iterations } => {
+                out.extend_from_slice(&iterations.to_le_bytes());
+            }
+            KdfParams::Scrypt { log_n, r, p } => {
+                out.push(*log_n);
+                out.extend_from_slice(&r.to_le_bytes());
+                out.extend_from_slice(&p.to_le_bytes());
+            }
+        }
+    }
+
+    /// 識別子に対応するパラメータ部分のバイト数（不明な識別子は `None`）
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn encoded_params_len(id: u8) -> Option<usize> {
+        match split_pepper_flag(id) {
+            (1 | 4 | 5, _) => Some(12),
+            (2, false) => Some(4),
+            (3, false) => Some(9),
+            _ => None,
+        }
+    }
+
+    /// `write_to` の形式を読み込み、読み込んだ分だけ `input` を進める
+    pub(crate) fn read_from(input: &mut &[u8]) -> Result<Self> {
+        let raw_id = read_u8(input)?;
+        let (id, peppered) = split_pepper_flag(raw_id);
+        let argon2 = |variant, input: &mut &[u8]| -> Result<Self> {
+            Ok(KdfParams::Argon2 {
+                variant,
+                memory_cost: read_u32(input)?,
+                time_cost: read_u32(input)?,
+                parallelism: read_u32(input)?,
+                peppered,
+            })
+        };
+        match (id, peppered) {
+            (1, _) => argon2(Argon2Variant::Argon2id, input),
+            (4, _) => argon2(Argon2Variant::Argon2i, input),
+            (5, _) => argon2(Argon2Variant::Argon2d, input),
+            (2, false) => Ok(KdfParams::Pbkdf2 {
+                iterations: read_u32(input)?,
+            }),
+            (3, false) => Ok(KdfParams::Scrypt {
+                log_n: read_u8(input)?,
+                r: read_u32(input)?,
+                p: read_u32(input)?,
+            }),
+            _ => Err(CryptoError::InvalidFormat(format!(
+                "不明なキー導出アルゴリズムです: {raw_id}"
+            ))),
+        }
+    }
+}
+
+/// 識別子をアルゴリズムの識別子とペッパーのフラグに分ける
+fn split_pepper_flag(id: u8) -> (u8, bool) {
+    (id & !PEPPER_FLAG, id & PEPPER_FLAG != 0)
+}
+
+fn read_u8(input: &mut &[u8]) -> Result<u8> {
+    let (&value, rest) = input.split_first().ok_or_else(header_truncated)?;
+    *input = rest;
+    Ok(value)
+}
+
+fn read_u32(input: &mut &[u8]) -> Result<u32> {
+    let Some((bytes, rest)) = input.split_first_chunk::<4>() else {
+        return Err(header_truncated());
+    };
+    *input = rest;
+    Ok(u32::from_le_bytes(*bytes))
+}
+
+fn header_truncated() -> CryptoError {
+    CryptoError::Truncated("ヘッダーのキー導出パラメータが途中で切れています".to_string())
+}
+
+/// ランダムなソルトを生成
+pub fn generate_salt() -> [u8; SALT_SIZE] {
+    let mut salt = [0u8; SALT_SIZE];
+    rand::rng().fill_bytes(&mut salt);
+    salt
+}
+
+/// パラメータで指定されたアルゴリズムでキーを導出
+///
+/// ペッパーを使うパラメータでは `PepperRequired` を返します（`derive_key_with_pepper` を使ってください）。
+pub fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> Result<[u8; 32]> {
+    derive_key_with_pepper(password, salt, params, None)
+}
+
+/// パラメータで指定されたアルゴリズムでキーを導出（ペッパーを使うパラメータでは `pepper` を混ぜる）
+///
+/// ペッパーを使わないパラメータでは `pepper` は無視します。
+pub fn derive_key_with_pepper(
+    password: &str,
+    salt: &[u8],
+    params: &KdfParams,
+    pepper: Option<&Pepper>,
+) -> Result<[u8; 32]> {
+    match *params {
+        KdfParams::Argon2 {
+            variant,
+            memory_cost,
+            time_cost,
+            parallelism,
+            peppered,
+        } => {
+            let secret = if peppered {
+                Some(pepper.ok_or(CryptoError::PepperRequired)?.as_bytes())
+            } else {
+                None
+            };
+            argon2_key(
+                password,
+                salt,
+                &Argon2Config {
+                    variant,
+                    memory_cost,
+                    time_cost,
+                    parallelism,
+                },
+                secret,
+            )
+        }
+        KdfParams::Pbkdf2 { iterations } => {
+            if iterations == 0 {
+                return Err(CryptoError::KeyDerivation(
+                    "PBKDF2の反復回数は1以上が必要です".to_string(),
+                ));
+            }
+            Ok(derive_key_with_pbkdf2(password, salt, iterations))
+        }
+        KdfParams::Scrypt { log_n, r, p } => derive_key_with_scrypt(password, salt, log_n, r, p),
+    }
+}
+
+/// 利用可能な物理メモリ（バイト、取得できない環境では None）
+#[cfg(feature = "std-fs")]
+pub fn available_memory() -> Option<u64> {
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    Some(system.available_memory()).filter(|&bytes| bytes > 0)
+}
+
+/// `std-fs` フィーチャーがない場合（WASMなど）はメモリ量を確認しない
+#[cfg(not(feature = "std-fs"))]
+pub fn available_memory() -> Option<u64> {
+    None
+}
+
+/// キー導出の開始・完了の通知
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfEvent {
+    /// キー導出を開始した（アルゴリズムとパラメータ）
+    Started(KdfParams),
+    /// キー導出が完了した（失敗した場合は通知しない）
+    Finished(KdfParams),
+}
+
+type KdfObserver = Box<dyn FnMut(KdfEvent)>;
+
+thread_local! {
+    static KDF_OBSERVER: RefCell<Option<KdfObserver>> = const { RefCell::new(None) };
+    static LAST_KDF_DURATION: RefCell<Option<Duration>> = const { RefCell::new(None) };
+}
+
+/// `run` の実行中に、このスレッドで行われるキー導出の開始・完了を `observer` に通知
+///
+/// キー導出は暗号化・復号化の内部で行われるため、GUIなどで「鍵を導出中」の段階を
+/// 一括の暗号化処理と区別して表示するために使います。通知は `run` を実行したスレッドでのみ行います。
+pub fn with_kdf_observer<T>(
+    observer: impl FnMut(KdfEvent) + 'static,
+    run: impl FnOnce() -> T,
+) -> T {
+    let previous = KDF_OBSERVER.with(|slot| slot.replace(Some(Box::new(observer))));
+    let result = run();
+    KDF_OBSERVER.with(|slot| slot.replace(previous));
+    result
+}
+
+fn notify_kdf(event: KdfEvent) {
+    KDF_OBSERVER.with(|slot| {
+        if let Some(observer) = slot.borrow_mut().as_mut() {
+            observer(event);
+        }
+    });
+}
+
+/// このスレッドで最後に完了したキー導出の処理時間
+///
+/// `derive_key_with_argon2` などを呼んだ直後に読むと、その処理時間がわかります（GUIの「テスト実行」用）。
+/// キー導出が完了していない場合と、wasm32-unknown-unknown（計測しない）では `None` を返します。
+pub fn last_kdf_duration() -> Option<Duration> {
+    LAST_KDF_DURATION.with(|slot| *slot.borrow())
+}
+
+/// キー導出の開始を通知し、処理時間の計測を開始
+///
+/// wasm32-unknown-unknown では `Instant` が使えないため計測しません。
+fn begin_kdf(params: KdfParams) -> Option<Instant> {
+    notify_kdf(KdfEvent::Started(params));
+    (!cfg!(all(target_arch = "wasm32", target_os = "unknown"))).then(Instant::now)
+}
+
+/// キー導出の処理時間をデバッグログに出力し、完了を通知
+fn finish_kdf(params: KdfParams, start_time: Option<Instant>) {
+    let elapsed = start_time.map(|start_time| start_time.elapsed());
+    if let Some(elapsed) = elapsed {
+        debug!("キー導出完了 - 処理時間: {:.2}秒", elapsed.as_secs_f64());
+        record_phase(Phase::KeyDerivation, elapsed);
+    }
+    LAST_KDF_DURATION.with(|slot| slot.replace(elapsed));
+    notify_kdf(KdfEvent::Finished(params));
+}
+
+/// キー導出に必要なメモリが、利用可能なメモリに収まるかを確認する
+///
+/// 復号化ではパラメータが信頼できないヘッダーから来るため、スワップでの停滞や
+/// OOMで強制終了される前に、利用可能なメモリを超える場合はエラーにします。
+/// 半分を超える場合は警告を表示して続行します。
+fn check_memory(params: &KdfParams) -> Result<()> {
+    let Some(available) = available_memory() else {
+        return Ok(());
+    };
+
+    let algorithm = params.name();
+    let required = params.memory_bytes();
+    let required_mb = required / 1_048_576;
+    let available_mb = available / 1_048_576;
+    if required > available {
+        return Err(CryptoError::KeyDerivation(format!(
+            "{algorithm}のキー導出に約 {required_mb} MB のメモリが必要ですが、利用可能なメモリは約 {available_mb} MB です（設定または暗号化ファイルのヘッダーのパラメータを確認してください）"
+        )));
+    }
+    if required > available / 2 {
+        warn!(
+            "{algorithm}のキー導出に約 {required_mb} MB のメモリを使用します（利用可能なメモリは約 {available_mb} MB）。処理が遅くなる可能性があります"
+        );
+    }
+    Ok(())
+}
+
+/// scryptでパスワードからキーを導出（コストは N = 2^log_n）
+pub fn derive_key_with_scrypt(
+    password: &str,
+    salt: &[u8],
+    log_n: u8,
+    r: u32,
+    p: u32,
+) -> Result<[u8; 32]> {
+    info!("=== scryptキー導出開始 ===");
+    debug!("パラメータ:");
+    debug!("  log_n: {log_n}");
+    debug!("  r: {r}");
+    debug!("  p: {p}");
+    debug!("  ソルト: {}", base64_encode(salt));
+
+    let params = scrypt::Params::new(log_n, r, p, 32)
+        .map_err(|e| CryptoError::KeyDerivation(format!("scryptパラメータの設定に失敗: {e}")))?;
+    let kdf = KdfParams::Scrypt { log_n, r, p };
+    check_memory(&kdf)?;
+
+    let start_time = begin_kdf(kdf);
+
+    let mut key = [0u8; 32];
+    scrypt::scrypt(password.as_bytes(), salt, &params, &mut key)
+        .map_err(|e| CryptoError::KeyDerivation(format!("scryptキー導出に失敗: {e}")))?;
+
+    finish_kdf(kdf, start_time);
+    info!("=== scryptキー導出完了 ===");
+
+    Ok(key)
+}
+
+/// PBKDF2-HMAC-SHA256でパスワードからキーを導出
+pub fn derive_key_with_pbkdf2(password: &str, salt: &[u8], iterations: u32) -> [u8; 32] {
+    info!("=== PBKDF2キー導出開始 ===");
+    debug!("  反復回数: {iterations}");
+    debug!("  ソルト: {}", base64_encode(salt));
+
+    let kdf = KdfParams::Pbkdf2 { iterations };
+    let start_time = begin_kdf(kdf);
+
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, iterations, &mut key);
+
+    finish_kdf(kdf, start_time);
+    info!("=== PBKDF2キー導出完了 ===");
+
+    key
+}
+
+/// Argon2を使用してパスワードから安全なキーを導出（種類は `config.variant`）
 pub fn derive_key_with_argon2(
     password: &str,
     salt: &[u8],
     config: &Argon2Config,
-    verbose: bool,
 ) -> Result<[u8; 32]> {
-    if verbose {
-        println!("=== Argon2キー導出開始 ===");
-        println!("パラメータ:");
-        println!("  メモリ使用量: {} KB", config.memory_cost);
-        println!("  時間コスト: {}", config.time_cost);
-        println!("  並列度: {}", config.parallelism);
-        println!("  ソルト: {}", base64_encode(salt));
-    }
+    argon2_key(password, salt, config, None)
+}
+
+/// Argon2でキーを導出（`secret` はペッパーで、Argon2の秘密鍵の入力として混ぜる）
+fn argon2_key(
+    password: &str,
+    salt: &[u8],
+    config: &Argon2Config,
+    secret: Option<&[u8]>,
+) -> Result<[u8; 32]> {
+    info!("=== Argon2キー導出開始 ===");
+    debug!("パラメータ:");
+    debug!("  種類: {}", config.variant);
+    debug!("  メモリ使用量: {} KB", config.memory_cost);
+    debug!("  時間コスト: {}", config.time_cost);
+    debug!("  並列度: {}", config.parallelism);
+    debug!("  ソルト: {}", base64_encode(salt));
+    debug!(
+        "  ペッパー: {}",
+        if secret.is_some() { "あり" } else { "なし" }
+    );
 
     // Argon2パラメータを設定
     let params = argon2::Params::new(
@@ -27,50 +626,51 @@ pub fn derive_key_with_argon2(
         config.parallelism,
         Some(32), // 出力長：32バイト
     )
-    .map_err(|e| anyhow!("Argon2パラメータの設定に失敗: {}", e))?;
+    .map_err(|e| CryptoError::KeyDerivation(format!("Argon2パラメータの設定に失敗: {e}")))?;
+    let kdf = KdfParams::Argon2 {
+        variant: config.variant,
+        memory_cost: config.memory_cost,
+        time_cost: config.time_cost,
+        parallelism: config.parallelism,
+        peppered: secret.is_some(),
+    };
+    check_memory(&kdf)?;
 
-    let argon2 = Argon2::new(
-        argon2::Algorithm::Argon2id, // 最も安全な variant
-        argon2::Version::V0x13,      // 最新バージョン
-        params,
-    );
+    let argon2 = match secret {
+        Some(secret) => Argon2::new_with_secret(
+            secret,
+            config.variant.into(),
+            argon2::Version::V0x13, // 最新バージョン
+            params,
+        )
+        .map_err(|e| CryptoError::KeyDerivation(format!("ペッパーの設定に失敗: {e}")))?,
+        None => Argon2::new(
+            config.variant.into(),
+            argon2::Version::V0x13, // 最新バージョン
+            params,
+        ),
+    };
 
     // キー導出を実行
-    let start_time = std::time::Instant::now();
+    let start_time = begin_kdf(kdf);
 
     let mut key = [0u8; 32];
     argon2
         .hash_password_into(password.as_bytes(), salt, &mut key)
-        .map_err(|e| anyhow!("Argon2キー導出に失敗: {}", e))?;
+        .map_err(|e| CryptoError::KeyDerivation(format!("Argon2キー導出に失敗: {e}")))?;
 
-    let duration = start_time.elapsed();
-
-    if verbose {
-        println!("キー導出完了 - 処理時間: {:.2}秒", duration.as_secs_f64());
-        println!("=== Argon2キー導出完了 ===");
-    }
+    finish_kdf(kdf, start_time);
+    info!("=== Argon2キー導出完了 ===");
 
     Ok(key)
 }
 
-/// 旧式のキー導出（後方互換性のため）
-pub fn generate_key_from_password_legacy(password: &str) -> [u8; 32] {
-    let mut key = [0u8; 32];
-    let password_bytes = password.as_bytes();
-
-    for (i, &byte) in password_bytes.iter().cycle().take(32).enumerate() {
-        key[i] = byte;
-    }
-
-    key
-}
-
 /// パスワードから32バイトキーを生成（Argon2使用）
-pub fn generate_key_from_password(
-    password: &str,
-    config: &Config,
-    verbose: bool,
-) -> Result<[u8; 32]> {
+///
+/// ソルトをパスワードから決定的に作る旧形式用です。ヘッダーのないファイルの復号化
+/// （`legacy-compat` フィーチャーの `legacy::decrypt_headerless`、設定の `legacy_deterministic_salt`
+/// が有効な場合）にのみ使います。新しいデータの暗号化はヘッダーに記録したランダムなソルトを使います。
+pub fn generate_key_from_password(password: &str, config: &Config) -> Result<[u8; 32]> {
     // ソルトを生成（実際のアプリケーションでは保存が必要）
     // ここでは簡易的にパスワードからソルトを導出
     let mut salt = [0u8; 16];
@@ -84,5 +684,15 @@ pub fn generate_key_from_password(
     salt[..8].copy_from_slice(&hash_bytes);
     salt[8..16].copy_from_slice(&hash_bytes);
 
-    derive_key_with_argon2(password, &salt, &config.argon2, verbose)
+    derive_key_with_argon2(password, &salt, &config.argon2)
+}
+
+/// 導出済みのキーから用途別のサブキーを導出（HMAC-SHA256）
+///
+/// 同じキーを暗号化とMACなど別の用途に使い回さないために使います。
+#[cfg(feature = "std-fs")]
+pub(crate) fn derive_subkey(key: &[u8; 32], label: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMACは任意長のキーを受け付ける");
+    mac.update(label);
+    mac.finalize().into_bytes().into()
 }
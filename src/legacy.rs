@@ -0,0 +1,68 @@
+//! 旧バージョンで暗号化されたデータとの互換用（`legacy-compat` フィーチャー）
+//!
+//! `decrypt_legacy` はキー導出関数を使わないため安全ではありません。
+//! 古いデータの復号化以外には使用しないでください。
+
+use crate::config::Config;
+use crate::error::{CryptoError, Result};
+use crate::key_derivation::generate_key_from_password;
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+
+/// 旧式のキー導出（パスワードのバイト列を繰り返して32バイトにするだけ）
+///
+/// # 警告
+///
+/// KDFを一切使っておらず、キーからパスワードがそのまま復元できます。
+/// 新しいデータの暗号化には絶対に使用しないでください。
+#[deprecated(note = "KDFを使わない安全でないキー導出です。旧データの復号化にのみ使用してください")]
+pub fn unsafe_legacy_key(password: &str) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    let password_bytes = password.as_bytes();
+
+    for (i, &byte) in password_bytes.iter().cycle().take(32).enumerate() {
+        key[i] = byte;
+    }
+
+    key
+}
+
+/// 旧式キーで暗号化されたデータ（ナンス(12) + 暗号文）を復号化
+pub fn decrypt_legacy(data: &[u8], password: &str) -> Result<Vec<u8>> {
+    if data.len() < 12 {
+        return Err(CryptoError::Truncated(
+            "データが短すぎます（最低12バイトのナンスが必要）".to_string(),
+        ));
+    }
+
+    let (nonce_bytes, ciphertext) = data.split_at(12);
+    #[allow(deprecated)]
+    let key = unsafe_legacy_key(password);
+    let cipher = Aes256Gcm::new(&key.into());
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| CryptoError::WrongPasswordOrCorrupt)
+}
+
+/// ヘッダーのない旧形式（ナンス(12) + 暗号文）を設定のArgon2パラメータで復号化
+///
+/// ソルトがパスワードから決定的に作られていた頃の形式です。
+/// マジックナンバーがないため、このツールで暗号化されていないデータとは区別できません。
+pub fn decrypt_headerless(data: &[u8], password: &str, config: &Config) -> Result<Vec<u8>> {
+    if data.len() < 12 {
+        return Err(CryptoError::Truncated(
+            "データが短すぎます（最低12バイトのナンスが必要）".to_string(),
+        ));
+    }
+
+    let (nonce_bytes, ciphertext) = data.split_at(12);
+    let key = generate_key_from_password(password, config)?;
+    let cipher = Aes256Gcm::new(&key.into());
+
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| CryptoError::WrongPasswordOrCorrupt)
+}
@@ -1,19 +1,113 @@
+#[cfg(feature = "age")]
+pub mod age_compat;
+pub mod armor;
+#[cfg(feature = "tokio")]
+pub mod async_stream;
+pub mod compress;
 pub mod config;
+pub mod context;
 pub mod crypto;
+pub mod envelope;
+pub mod error;
+#[cfg(feature = "std-fs")]
 pub mod file_ops;
+#[cfg(feature = "gui")]
+pub mod fonts;
+pub mod header;
+#[cfg(feature = "std-fs")]
+pub mod inspect;
 pub mod key_derivation;
+#[cfg(feature = "legacy-compat")]
+pub mod legacy;
+pub mod password;
+pub mod timing;
+#[cfg(feature = "std-fs")]
+pub mod volume;
 
 // 公開API
-pub use config::{Argon2Config, Config, OutputFormat};
-pub use crypto::{decrypt_string, encrypt_string};
+#[cfg(feature = "age")]
+pub use age_compat::{
+    decrypt_age, decrypt_file_age, encrypt_age, encrypt_file_age, is_age, verify_file_age,
+};
+pub use armor::{armor, dearmor, is_armored};
+#[cfg(feature = "tokio")]
+pub use async_stream::{decrypt_stream_async, encrypt_stream_async};
+pub use compress::CompressMode;
+pub use config::{Argon2Config, Config, ConfigBuilder, OutputFormat, Pbkdf2Config, ScryptConfig};
+pub use context::CryptContext;
+pub use crypto::{
+    decrypt_bytes, decrypt_bytes_with_aad, decrypt_from_text, decrypt_from_text_with_aad,
+    decrypt_string, decrypt_string_with_aad, decrypt_text_lines, detect_encoding, encrypt_bytes,
+    encrypt_string, encrypt_string_with_aad, encrypt_to_raw, encrypt_to_raw_with_aad,
+    encrypt_to_text, encrypt_to_text_with_aad, encrypt_with_salt_and_nonce, password_hint,
+    string_password_hint,
+};
+pub use error::CryptoError;
+#[cfg(feature = "std-fs")]
 pub use file_ops::{
-    decrypt_file_standard, decrypt_file_streaming, encrypt_file_standard, encrypt_file_streaming,
+    armor_file, decrypt_file_auto, decrypt_file_standard, decrypt_file_streaming,
+    decrypt_file_streaming_with_progress, decrypt_to_writer, decrypt_to_writer_with_pepper,
+    detect_format, encrypt_file_for_passwords, encrypt_file_split, encrypt_file_standard,
+    encrypt_file_streaming, encrypt_file_streaming_with_progress, encrypt_from_reader,
+    encrypt_from_reader_with_nonce_prefix, file_password_hint, file_relative_path,
+    is_streaming_file, read_pepper_file, read_salt_file, restore_path_into,
+    restore_path_into_excluding, rotate_file, verify_encrypted_file, with_output_lock,
+    write_in_place, DecryptReader, EncFormat, FileBatch,
+};
+pub use header::EncryptedFile;
+#[cfg(feature = "std-fs")]
+pub use inspect::{inspect_bytes, inspect_file, FileInfo, FormatInfo};
+pub use key_derivation::{
+    derive_key, derive_key_with_argon2, derive_key_with_pbkdf2, derive_key_with_pepper,
+    derive_key_with_scrypt, generate_key_from_password, last_kdf_duration, with_kdf_observer,
+    Argon2Variant, KdfAlgorithm, KdfEvent, KdfParams, Pepper,
 };
-pub use key_derivation::{derive_key_with_argon2, generate_key_from_password};
+pub use password::{
+    check_password, generate_password, password_strength, PasswordCharset, PasswordIssue,
+    PasswordStrength,
+};
+pub use timing::{enable_phase_timing, phase_times, PhaseTimes};
 
 // 共通ユーティリティ
 use base64::{engine::general_purpose, Engine as _};
 
+/// Base64（標準アルファベット、パディングあり）にエンコード
 pub fn base64_encode(data: &[u8]) -> String {
     general_purpose::STANDARD.encode(data)
 }
+
+/// `base64_encode` と同じ形式のBase64文字列をデコード
+pub fn base64_decode(s: &str) -> error::Result<Vec<u8>> {
+    general_purpose::STANDARD
+        .decode(s)
+        .map_err(|e| CryptoError::InvalidFormat(format!("Base64デコードに失敗しました: {e}")))
+}
+
+/// 小文字の16進数文字列にエンコード
+pub fn hex_encode(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 16進数文字列をデコード（大文字・小文字どちらも可）
+pub fn hex_decode(s: &str) -> error::Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return Err(CryptoError::InvalidFormat(
+            "16進数デコードに失敗しました: 長さが奇数です".to_string(),
+        ));
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| {
+            // from_str_radix は先頭の符号（"+f" など）を受け付けるため、16進数の数字だけか先に確認
+            s.get(i..i + 2)
+                .filter(|pair| pair.bytes().all(|b| b.is_ascii_hexdigit()))
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| {
+                    CryptoError::InvalidFormat(
+                        "16進数デコードに失敗しました: 不正な文字が含まれています".to_string(),
+                    )
+                })
+        })
+        .collect()
+}
@@ -1,19 +1,52 @@
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use anyhow::{bail, Context, Result};
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use encript_tool::{
+    compress::CompressMode,
     config::{
-        create_config_file, delete_config_file, get_default_config_path, load_config, Config,
+        create_config_file, delete_config_file, get_default_config_path, load_config,
+        load_config_file, resolve_config_path, save_config, Argon2Config, Config, ConfigSource,
+        Pbkdf2Config, ScryptConfig, DEFAULT_PROGRESS_TEMPLATE, MIN_STREAM_CHUNK_SIZE,
     },
-    crypto::{decrypt_string, encrypt_string},
+    crypto::{
+        decrypt_bytes_with_aad, decrypt_from_text_with_aad, decrypt_text_lines,
+        encrypt_string_with_aad, encrypt_to_raw_with_aad, encrypt_to_text_with_aad, password_hint,
+        string_password_hint,
+    },
+    envelope::parse_recipients,
+    error::CryptoError,
     file_ops::{
-        decrypt_file_standard, decrypt_file_streaming, determine_output_path,
-        encrypt_file_standard, encrypt_file_streaming,
+        armor_file, decrypt_to_writer_with_pepper, detect_format, determine_output_path_excluding,
+        encrypt_file_for_passwords, encrypt_from_reader, file_password_hint, read_pepper_file,
+        read_salt_file, restore_path_into_excluding, rotate_file, verify_encrypted_file,
+        with_output_lock, write_in_place, EncFormat, FileBatch,
+    },
+    hex_decode, hex_encode,
+    inspect::{inspect_file, FileInfo, FormatInfo},
+    key_derivation::{
+        available_memory, derive_key_with_pepper, KdfAlgorithm, KdfParams, Pepper, SALT_SIZE,
     },
+    password::{check_password, generate_password, PasswordCharset, PasswordIssue},
+    timing::{enable_phase_timing, phase_times, PhaseTimes},
+    volume::{is_volume_file, validate_volume_size, volume_path, volume_paths},
 };
+use env_logger::WriteStyle;
+use log::{debug, info, warn, LevelFilter};
+use rand::RngCore;
+use serde_json::json;
 use std::{
+    collections::{HashMap, HashSet},
     fs,
-    io::{self, Read, Write},
+    io::{self, IsTerminal, Read, Write},
+    num::NonZeroUsize,
     path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
 };
 // GUIモジュール用の再エクスポート
 #[cfg(feature = "gui")]
@@ -23,6 +56,7 @@ use encript_tool::{decrypt_string as lib_decrypt, encrypt_string as lib_encrypt}
 
 #[cfg(feature = "gui")]
 mod gui;
+mod style;
 
 /// AES-GCM暗号化ツール
 #[derive(Parser)]
@@ -39,15 +73,87 @@ struct Cli {
     /// 設定ファイルのパスを指定
     #[arg(long, global = true)]
     config: Option<PathBuf>,
+
+    /// 結果をJSONで標準出力に出力（スクリプト向け）
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// 完了メッセージやプログレスバーを表示しない（エラーのみ表示、--verbose とは併用不可、--json の出力は抑えない）
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// ストリーミング処理の進捗をプログレスバーの代わりに1行ずつのJSON（{"done":…,"total":…}）で標準エラー出力に出力
+    #[arg(long, global = true)]
+    progress_json: bool,
+
+    /// ストリーミング処理のプログレスバーを表示しない（この実行に限り設定の show_progress を上書き）
+    #[arg(long, global = true)]
+    no_progress: bool,
+
+    /// 一括処理で同時に行うキー導出のメモリ使用量の合計の上限（例: 1GB、超える場合はキー導出を順番待ちにする）
+    #[arg(long, global = true, value_name = "SIZE", value_parser = parse_size)]
+    max_memory: Option<u64>,
+
+    /// 暗号化時のキー導出アルゴリズム（argon2id / pbkdf2 / scrypt、復号化時はヘッダーから自動判別）
+    #[arg(long, global = true, value_name = "KDF")]
+    algorithm: Option<KdfAlgorithm>,
+
+    /// 暗号化時のArgon2のメモリ使用量（KB、この実行に限り設定ファイルの値を上書き）
+    #[arg(long, global = true, value_name = "KB")]
+    argon2_memory: Option<u32>,
+
+    /// 暗号化時のArgon2の時間コスト（繰り返し回数、この実行に限り設定ファイルの値を上書き）
+    #[arg(long, global = true, value_name = "N")]
+    argon2_time: Option<u32>,
+
+    /// 暗号化時のArgon2の並列度（この実行に限り設定ファイルの値を上書き）
+    #[arg(long, global = true, value_name = "N")]
+    argon2_parallelism: Option<u32>,
+
+    /// Argon2に混ぜる秘密の値（ペッパー）を読み込むファイル（未指定なら環境変数 MYCRYPT_PEPPER）
+    #[arg(long, global = true, value_name = "PATH")]
+    pepper_file: Option<PathBuf>,
+
+    /// 暗号化時に要求するパスワードの推定エントロピーの下限（ビット、設定の min_password_entropy を上書き）
+    #[arg(long, global = true, value_name = "BITS")]
+    min_entropy: Option<f64>,
+
+    /// 推定エントロピーが下限に満たないパスワードでも、警告を表示して暗号化する（空のパスワードは不可）
+    #[arg(long, global = true)]
+    allow_weak_password: bool,
+
+    /// 実際には処理せず、読み込み・書き込み・削除するファイルを表示（encrypt-file / decrypt-file）
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// 一括処理（encrypt-file / decrypt-file）で失敗したファイルがあっても残りのファイルを処理し、最後に失敗したファイルをまとめて表示
+    #[arg(long, global = true)]
+    keep_going: bool,
+
+    /// 出力に色を付けない（NO_COLOR 環境変数や、出力先が端末でない場合も色なし）
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// 終了時に、キー導出・暗号化/復号化・読み込み・書き込みそれぞれの処理時間を標準エラー出力に表示（--quiet では表示しない）
+    #[arg(long, global = true)]
+    time: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// 文字列を暗号化する
     Encrypt {
-        /// 暗号化するテキスト（指定しない場合は標準入力から読み取り）
+        /// 暗号化するテキスト（指定しない場合は --in または標準入力から読み取り）
         text: Option<String>,
 
+        /// 暗号化するテキストをファイルから読み取る
+        #[arg(long = "in", value_name = "PATH", conflicts_with = "text")]
+        input: Option<PathBuf>,
+
+        /// 暗号文を標準出力ではなくファイルに書き込む
+        #[arg(long = "out", value_name = "PATH")]
+        output: Option<PathBuf>,
+
         /// 暗号化用のパスワード
         #[arg(short, long)]
         password: Option<String>,
@@ -56,6 +162,10 @@ enum Commands {
         #[arg(long)]
         password_env: Option<String>,
 
+        /// 標準入力から1行読み取ってパスワードにする
+        #[arg(long, conflicts_with_all = ["password", "password_env"])]
+        stdin_password: bool,
+
         /// 詳細な処理過程を表示
         #[arg(short, long)]
         verbose: bool,
@@ -63,12 +173,60 @@ enum Commands {
         /// 改行を出力しない
         #[arg(short, long)]
         no_newline: bool,
+
+        /// 平文をNバイトの倍数までパディングし、暗号文から長さがわからないようにする
+        #[arg(long, value_name = "N")]
+        pad_to: Option<usize>,
+
+        /// 入力をチャンク単位でストリーミング形式（バイナリ）に暗号化する（大きな入力やバイナリ向け）
+        #[arg(long, conflicts_with_all = ["text", "pad_to", "no_newline"])]
+        stream: bool,
+
+        /// パスワードのヒントを暗号文に記録する（暗号化されず、誰でも読める）
+        #[arg(long, value_name = "TEXT", conflicts_with = "stream")]
+        hint: Option<String>,
+
+        /// 暗号化するテキストをクリップボードから読み取る
+        #[arg(long, conflicts_with_all = ["text", "input", "stream"])]
+        from_clipboard: bool,
+
+        /// 暗号文を標準出力ではなくクリップボードに書き込む
+        #[arg(long, conflicts_with_all = ["output", "no_newline", "stream"])]
+        to_clipboard: bool,
+
+        /// --in または標準入力をテキストではなくバイト列のまま暗号化する（前後の空白も除去しない）
+        #[arg(long, conflicts_with_all = ["text", "stream", "from_clipboard"])]
+        binary_input: bool,
+
+        /// 元の平文のSHA-256を暗号文に記録し、復号化時に検証する
+        #[arg(long, conflicts_with = "stream")]
+        checksum: bool,
+
+        /// 暗号文と一緒に認証する追加認証データ（暗号文には含まれず、復号化時に同じ値が必要）
+        #[arg(long, value_name = "STRING", conflicts_with = "stream")]
+        aad: Option<String>,
+
+        /// 暗号文をBase64 / 16進数にせずバイナリのまま出力する（標準形式のファイルと同じ形式）
+        #[arg(long, conflicts_with_all = ["stream", "to_clipboard", "no_newline"])]
+        raw: bool,
+
+        /// キー導出にランダムなソルトの代わりに、このファイルの16バイトを使う（外部で管理するソルト）
+        #[arg(long, value_name = "PATH")]
+        salt_file: Option<PathBuf>,
     },
     /// 暗号化された文字列を復号化する
     Decrypt {
-        /// 復号化する暗号文（指定しない場合は標準入力から読み取り）
+        /// 復号化する暗号文（指定しない場合は --in または標準入力から読み取り）
         text: Option<String>,
 
+        /// 復号化する暗号文をファイルから読み取る
+        #[arg(long = "in", value_name = "PATH", conflicts_with = "text")]
+        input: Option<PathBuf>,
+
+        /// 復号結果を標準出力ではなくファイルに書き込む
+        #[arg(long = "out", value_name = "PATH")]
+        output: Option<PathBuf>,
+
         /// 復号化用のパスワード
         #[arg(short, long)]
         password: Option<String>,
@@ -77,6 +235,10 @@ enum Commands {
         #[arg(long)]
         password_env: Option<String>,
 
+        /// 標準入力から1行読み取ってパスワードにする
+        #[arg(long, conflicts_with_all = ["password", "password_env"])]
+        stdin_password: bool,
+
         /// 詳細な処理過程を表示
         #[arg(short, long)]
         verbose: bool,
@@ -84,24 +246,69 @@ enum Commands {
         /// 改行を出力しない
         #[arg(short, long)]
         no_newline: bool,
+
+        /// ストリーミング形式（`encrypt --stream` の出力）をチャンク単位で復号化する
+        #[arg(long, conflicts_with_all = ["text", "no_newline"])]
+        stream: bool,
+
+        /// 復号化せずに、記録されているパスワードのヒントを表示する
+        #[arg(long, conflicts_with = "stream")]
+        show_hint: bool,
+
+        /// 復号化する暗号文をクリップボードから読み取る
+        #[arg(long, conflicts_with_all = ["text", "input", "stream"])]
+        from_clipboard: bool,
+
+        /// 復号結果を標準出力ではなくクリップボードに書き込む
+        #[arg(long, conflicts_with_all = ["output", "no_newline", "stream", "show_hint"])]
+        to_clipboard: bool,
+
+        /// 復号結果をUTF-8文字列に変換せず、バイト列のまま出力する（`encrypt --binary-input` 向け）
+        #[arg(long, conflicts_with_all = ["no_newline", "stream", "show_hint", "to_clipboard"])]
+        binary: bool,
+
+        /// 暗号化時に指定した追加認証データ（`encrypt --aad` と同じ値）
+        #[arg(long, value_name = "STRING", conflicts_with_all = ["stream", "show_hint"])]
+        aad: Option<String>,
+
+        /// 暗号文をBase64 / 16進数ではなくバイナリとして読み取る（encrypt --raw や encrypt-file の出力）
+        #[arg(long, conflicts_with_all = ["text", "stream", "from_clipboard"])]
+        raw: bool,
+
+        /// 空白・改行で区切られた複数の暗号文をそれぞれ復号化し、1行ずつ出力する
+        #[arg(long, conflicts_with_all = ["stream", "show_hint", "to_clipboard", "binary", "raw", "no_newline"])]
+        lines: bool,
     },
     /// ファイルを暗号化する
     EncryptFile {
-        /// 暗号化するファイルパス
-        input: PathBuf,
+        /// 暗号化するファイルパス（複数指定可、`*.txt` などのワイルドカードも使用可）
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-        /// 出力ファイルパス(指定しない場合は 元ファイル名.enc)
+        /// 出力ファイルパス(指定しない場合は 元ファイル名.enc、入力が1つの場合のみ指定可)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
-        /// 暗号化用のパスワード
+        /// 暗号化用のパスワード（複数指定すると、いずれのパスワードでも復号化できる）
         #[arg(short, long)]
-        password: Option<String>,
+        password: Vec<String>,
+
+        /// 1行に1つずつパスワードを書いたファイル（空行と `#` で始まる行は無視、いずれのパスワードでも復号化できる）
+        #[arg(
+            long,
+            value_name = "PATH",
+            conflicts_with_all = ["password", "password_env", "stdin_password"]
+        )]
+        recipients_file: Option<PathBuf>,
 
         /// 環境変数からパスワードを読み取る
         #[arg(long)]
         password_env: Option<String>,
 
+        /// 標準入力から1行読み取ってパスワードにする
+        #[arg(long, conflicts_with_all = ["password", "password_env"])]
+        stdin_password: bool,
+
         /// 詳細処理過程表示
         #[arg(short, long)]
         verbose: bool,
@@ -110,16 +317,90 @@ enum Commands {
         #[arg(long)]
         delete_original: bool,
 
-        /// ストリーミング処理を使用（大容量ファイル用）
+        /// ストリーミング処理を使用（大容量ファイル用、指定しない場合はファイルサイズで自動選択）
         #[arg(long)]
         streaming: bool,
+
+        /// ファイルサイズにかかわらず標準（非ストリーミング）処理を使用
+        #[arg(long, conflicts_with = "streaming")]
+        no_streaming: bool,
+
+        /// ストリーミング処理のチャンクサイズ（バイト、4KB〜16MB）
+        #[arg(long)]
+        chunk_size: Option<usize>,
+
+        /// 元ファイルのパーミッションと更新日時を記録しない
+        #[arg(long)]
+        no_preserve_metadata: bool,
+
+        /// 複数ファイルを同時に処理するスレッド数（既定はCPU数、メモリに応じて制限）
+        #[arg(long, value_name = "N")]
+        threads: Option<usize>,
+
+        /// メールなどに貼り付けられるASCIIアーマー形式（テキスト）で出力
+        #[arg(long, conflicts_with = "streaming")]
+        armor: bool,
+
+        /// パスワードのヒントをファイルに記録する（暗号化されず、誰でも読める）
+        #[arg(long, value_name = "TEXT", conflicts_with = "streaming")]
+        hint: Option<String>,
+
+        /// 元の平文のSHA-256をファイルに記録し、復号化時に検証する
+        #[arg(long, conflicts_with = "streaming")]
+        checksum: bool,
+
+        /// 平文を圧縮してから暗号化する（auto / always / never、auto は拡張子と先頭の内容で判断。復号化時は自動で展開）
+        #[arg(long, value_name = "MODE", default_value_t = CompressMode::Never, conflicts_with = "streaming")]
+        compress: CompressMode,
+
+        /// キー導出にランダムなソルトの代わりに、このファイルの16バイトを使う（外部で管理するソルト）
+        #[arg(long, value_name = "PATH", conflicts_with = "recipients_file")]
+        salt_file: Option<PathBuf>,
+
+        /// ディレクトリ内のファイルを再帰的に暗号化し、ディレクトリからの相対パスを記録する
+        #[arg(short, long, conflicts_with_all = ["streaming", "output"])]
+        recursive: bool,
+
+        /// 出力先を指定しない場合に、暗号化したファイルを置くディレクトリ（存在しなければ作成）
+        #[arg(long, value_name = "DIR", conflicts_with = "output")]
+        output_dir: Option<PathBuf>,
+
+        /// 出力先を指定しない場合に、暗号化したファイルに付ける拡張子（既定は enc）
+        #[arg(long, value_name = "EXT", conflicts_with = "output")]
+        ext: Option<String>,
+
+        /// 自動で決めた出力先が既に存在する場合も、番号を付けずに上書きする
+        #[arg(long)]
+        force: bool,
+
+        /// 元ファイルを暗号化した内容で置き換える（一時ファイルに書き込んでから置き換え）
+        #[arg(long, conflicts_with_all = ["output", "output_dir", "ext", "force", "delete_original"])]
+        in_place: bool,
+
+        /// --delete-original / --in-place で元ファイルを削除・置換する前に、暗号化したファイルを復号化して元ファイルと比較する検証を省略する
+        #[arg(long)]
+        no_verify: bool,
+
+        /// 出力するファイルの形式（age はパスフレーズで暗号化した age ファイル、既定の拡張子は age）
+        #[arg(long, value_enum, default_value_t = FileFormat::Native)]
+        format: FileFormat,
+
+        /// ストリーミング形式で暗号化し、このサイズごとの 出力.001・出力.002… に分割する（例: 2GB、ストリーミング処理を使用）
+        #[arg(
+            long,
+            value_name = "SIZE",
+            value_parser = parse_volume_size,
+            conflicts_with_all = ["no_streaming", "armor", "hint", "checksum", "compress", "recursive", "in_place"]
+        )]
+        split: Option<u64>,
     },
     /// 暗号化されたファイルを復号化する
     DecryptFile {
-        /// 復号化するファイルのパス
-        input: PathBuf,
+        /// 復号化するファイルのパス（複数指定可、`*.enc` などのワイルドカードも使用可）
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
 
-        /// 出力ファイルのパス(指定しない場合は自動決定)
+        /// 出力ファイルのパス(指定しない場合は自動決定、入力が1つの場合のみ指定可)
         #[arg(short, long)]
         output: Option<PathBuf>,
 
@@ -131,6 +412,10 @@ enum Commands {
         #[arg(long)]
         password_env: Option<String>,
 
+        /// 標準入力から1行読み取ってパスワードにする
+        #[arg(long, conflicts_with_all = ["password", "password_env"])]
+        stdin_password: bool,
+
         /// 詳細な処理過程を表示
         #[arg(short, long)]
         verbose: bool,
@@ -139,20 +424,168 @@ enum Commands {
         #[arg(long)]
         delete_encrypted: bool,
 
-        /// ストリーミング処理を使用（大容量ファイル用）
+        /// ストリーミング形式として復号化（指定しない場合はファイルの先頭から自動判別）
+        #[arg(long)]
+        streaming: bool,
+
+        /// 記録されたパーミッションと更新日時を復元しない
+        #[arg(long)]
+        no_preserve_metadata: bool,
+
+        /// 複数ファイルを同時に処理するスレッド数（既定はCPU数、メモリに応じて制限）
+        #[arg(long, value_name = "N")]
+        threads: Option<usize>,
+
+        /// 出力先を指定しない場合に、復号化したファイルを置くディレクトリ（存在しなければ作成）
+        #[arg(long, value_name = "DIR", conflicts_with = "output")]
+        output_dir: Option<PathBuf>,
+
+        /// 出力先を指定しない場合に、復号化したファイル名から除く拡張子（既定は enc）
+        #[arg(long, value_name = "EXT", conflicts_with = "output")]
+        ext: Option<String>,
+
+        /// 自動で決めた出力先が既に存在する場合も、番号を付けずに上書きする
+        #[arg(long)]
+        force: bool,
+
+        /// 暗号化ファイルを復号化した内容で置き換える（一時ファイルに書き込んでから置き換え）
+        #[arg(long, conflicts_with_all = ["output", "output_dir", "ext", "force", "delete_encrypted"])]
+        in_place: bool,
+
+        /// 復号化せずに、記録されているパスワードのヒントを表示する
+        #[arg(long)]
+        show_hint: bool,
+
+        /// 復号化したファイルをこのディレクトリに置く（`--recursive` で暗号化したファイルは元の相対パスに復元）
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["output", "output_dir", "in_place"])]
+        into: Option<PathBuf>,
+
+        /// 復号化するファイルの形式（age はパスフレーズで暗号化した age ファイル、既定の拡張子は age）
+        #[arg(long, value_enum, default_value_t = FileFormat::Native)]
+        format: FileFormat,
+
+        /// プロンプトで入力したパスワードが違う場合に、入力し直せる回数（最初の入力を含む）
+        #[arg(long, value_name = "N", default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+        max_attempts: u32,
+    },
+    /// 暗号化ファイルを新しいパスワードで再暗号化する（平文はディスクに書き出さない）
+    Rotate {
+        /// 再暗号化するファイルのパス
+        file: PathBuf,
+
+        /// 現在のパスワード
+        #[arg(long)]
+        old_password: Option<String>,
+
+        /// 現在のパスワードを環境変数から読み取る
+        #[arg(long)]
+        old_password_env: Option<String>,
+
+        /// 新しいパスワード
+        #[arg(long)]
+        new_password: Option<String>,
+
+        /// 新しいパスワードを環境変数から読み取る
+        #[arg(long)]
+        new_password_env: Option<String>,
+
+        /// 詳細な処理過程を表示
+        #[arg(short, long)]
+        verbose: bool,
+    },
+    /// 暗号化ファイルのヘッダー情報を表示する（パスワード不要）
+    Inspect {
+        /// 調べる暗号化ファイルのパス
+        file: PathBuf,
+    },
+    /// ランダムなパスワードを生成する
+    GenPassword {
+        /// パスワードの長さ（8〜1024）
+        #[arg(short, long, default_value_t = 24)]
+        length: usize,
+
+        /// 記号を含めない（英数字のみ）
+        #[arg(long)]
+        no_symbols: bool,
+    },
+    /// ランダムなデータでファイルの暗号化・復号化の速度を計測する
+    Bench {
+        /// 計測に使うデータのサイズ（例: 100MB、512KB、1GB）
+        #[arg(long, default_value = "100MB", value_parser = parse_size)]
+        size: u64,
+
+        /// ストリーミング処理で計測する
         #[arg(long)]
         streaming: bool,
     },
+    /// 暗号化・復号化の往復を試し、暗号方式・キー導出・AESのハードウェア支援の有無を表示する
+    Selftest,
+    /// パスワードとソルトから導出したキーを16進数で表示する（他の実装との相互検証用、キーが漏れるため通常は使わない）
+    #[command(hide = true)]
+    DeriveKey {
+        /// キー導出に使うソルト（16バイトを32文字の16進数で指定）
+        #[arg(long, value_name = "HEX")]
+        salt: String,
+
+        /// キー導出に使うパスワード
+        #[arg(short, long)]
+        password: Option<String>,
+
+        /// 環境変数からパスワードを読み取る
+        #[arg(long)]
+        password_env: Option<String>,
+
+        /// 導出したキーを表示することの確認（指定しない場合は実行しない）
+        #[arg(long)]
+        danger_print_key: bool,
+    },
     /// 設定ファイルを管理する
     Config {
         #[command(subcommand)]
         action: ConfigAction,
     },
+    /// シェルの補完スクリプトを標準出力に出力する（例: source <(encript_tool completions bash)）
+    Completions {
+        /// 補完スクリプトを生成するシェル
+        shell: Shell,
+    },
 
     #[cfg(feature = "gui")]
     Gui,
 }
 
+impl Commands {
+    /// サブコマンドで --verbose が指定されているか
+    fn verbose(&self) -> bool {
+        match self {
+            Commands::Encrypt { verbose, .. }
+            | Commands::Decrypt { verbose, .. }
+            | Commands::EncryptFile { verbose, .. }
+            | Commands::DecryptFile { verbose, .. }
+            | Commands::Rotate { verbose, .. } => *verbose,
+            _ => false,
+        }
+    }
+
+    /// `--time` の表示で、AES-GCMの処理時間に付ける名前
+    fn cipher_label(&self) -> &'static str {
+        match self {
+            Commands::Encrypt { .. } | Commands::EncryptFile { .. } => "暗号化",
+            Commands::Decrypt { .. } | Commands::DecryptFile { .. } => "復号化",
+            _ => "暗号化/復号化",
+        }
+    }
+}
+
+/// `encrypt-file` / `decrypt-file` で読み書きするファイルの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum FileFormat {
+    /// このツールの形式
+    Native,
+    /// age 形式（age フィーチャーを有効にしてビルドした場合のみ）
+    Age,
+}
+
 #[derive(Subcommand)]
 enum ConfigAction {
     /// デフォルト設定ファイルを作成
@@ -161,118 +594,975 @@ enum ConfigAction {
     Show,
     /// 設定ファイルのパスを表示
     Path,
+    /// 設定値を取得（例: argon2.memory_cost）
+    Get {
+        /// 設定キー
+        key: String,
+    },
+    /// 設定値を変更して保存（例: argon2.memory_cost 131072）
+    Set {
+        /// 設定キー
+        key: String,
+        /// 新しい値
+        value: String,
+    },
     /// 設定ファイルを削除
     Reset,
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// 引数の指定ミス（clap の解析エラー）
+const EXIT_USAGE: u8 = 64;
+/// Ctrl-C で中断された（128 + SIGINT）
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// 書き込み途中の出力ファイル（Ctrl-C で中断された場合に削除する）
+///
+/// 一括処理では複数のスレッドが同時に書き込むため、書き込み中のパスをすべて保持します。
+#[derive(Clone, Default)]
+struct PartialOutputs(Arc<Mutex<Vec<PathBuf>>>);
+
+impl PartialOutputs {
+    /// `path` を書き込み中として登録して `write` を実行し、終わったら（成否にかかわらず）登録を外す
+    fn track<T>(&self, path: &Path, write: impl FnOnce() -> T) -> T {
+        self.0.lock().unwrap().push(path.to_path_buf());
+        let result = write();
+        let mut paths = self.0.lock().unwrap();
+        if let Some(index) = paths.iter().position(|p| p == path) {
+            paths.swap_remove(index);
+        }
+        result
+    }
+
+    /// Ctrl-C で書き込み途中のファイルを削除して終了するハンドラーを登録
+    fn install_interrupt_handler(&self) {
+        let partial = self.clone();
+        let installed = ctrlc::set_handler(move || {
+            // ロックを保持したまま終了し、削除後に新しいファイルが登録・作成されないようにする
+            let paths = partial.0.lock().unwrap_or_else(|e| e.into_inner());
+            for path in paths.iter() {
+                match fs::remove_file(path) {
+                    Ok(()) => eprintln!("書き込み途中のファイルを削除しました: {}", path.display()),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                    Err(e) => eprintln!(
+                        "{} 書き込み途中のファイルを削除できませんでした: {}: {e}",
+                        style::warning("警告:"),
+                        path.display()
+                    ),
+                }
+            }
+            eprintln!("{}", style::error("中断しました"));
+            std::process::exit(EXIT_INTERRUPTED);
+        });
+        if let Err(e) = installed {
+            warn!("Ctrl-C のハンドラーを登録できませんでした: {e}");
+        }
+    }
+}
+
+/// エラーの原因に応じた終了コード
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FailureKind {
+    /// その他のエラー
+    Other = 1,
+    /// パスワード違い・認証失敗
+    WrongPassword = 2,
+    /// ファイルが見つからない
+    NotFound = 3,
+    /// 入力データの形式が不正
+    InvalidFormat = 4,
+    /// 設定ファイルのエラー
+    Config = 5,
+}
+
+impl FailureKind {
+    /// エラーチェーンをたどって終了コードの種別を決定
+    fn classify(error: &anyhow::Error) -> Self {
+        if error.downcast_ref::<ConfigError>().is_some() {
+            return FailureKind::Config;
+        }
+
+        for cause in error.chain() {
+            if let Some(e) = cause.downcast_ref::<CryptoError>() {
+                match e {
+                    CryptoError::WrongPasswordOrCorrupt | CryptoError::PepperRequired => {
+                        return FailureKind::WrongPassword
+                    }
+                    CryptoError::NotEncrypted
+                    | CryptoError::InvalidFormat(_)
+                    | CryptoError::Truncated(_)
+                    | CryptoError::Utf8(_) => return FailureKind::InvalidFormat,
+                    _ => {}
+                }
+            }
+            if let Some(e) = cause.downcast_ref::<io::Error>() {
+                if e.kind() == io::ErrorKind::NotFound {
+                    return FailureKind::NotFound;
+                }
+            }
+        }
+        FailureKind::Other
+    }
+}
+
+/// 設定ファイルの読み込み・検証に失敗したことを示すエラー文脈
+#[derive(Debug)]
+struct ConfigError;
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("設定エラー")
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) if e.use_stderr() => {
+            let _ = e.print();
+            return ExitCode::from(EXIT_USAGE);
+        }
+        // --help / --version
+        Err(e) => e.exit(),
+    };
+    // --verbose はサブコマンドの引数のため、clap の conflicts_with では検出できない
+    if cli.quiet && cli.command.verbose() {
+        let _ = Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--quiet と --verbose は同時に指定できません",
+            )
+            .print();
+        return ExitCode::from(EXIT_USAGE);
+    }
+
+    style::init(cli.no_color);
+
+    let partial = PartialOutputs::default();
+    partial.install_interrupt_handler();
+
+    if cli.time {
+        enable_phase_timing();
+    }
+    let result = run(&cli, &partial);
+    if cli.time && !cli.quiet {
+        eprintln!(
+            "{}",
+            format_phase_times(&phase_times(), cli.command.cipher_label())
+        );
+    }
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if cli.json {
+                print_json(json!({ "ok": false, "error": format!("{e:#}") }));
+            } else {
+                eprintln!("{} {e:?}", style::error("Error:"));
+            }
+            ExitCode::from(FailureKind::classify(&e) as u8)
+        }
+    }
+}
 
+fn run(cli: &Cli, partial: &PartialOutputs) -> Result<()> {
     // 設定ファイルを読み込み
-    let config = load_config(cli.config.as_deref())?;
+    let mut config = load_config(cli.config.as_deref()).context(ConfigError)?;
+    if let Some(algorithm) = cli.algorithm {
+        config.kdf = algorithm;
+    }
+    let argon2_overridden = apply_argon2_overrides(cli, &mut config)?;
+    if cli.no_progress {
+        config.show_progress = false;
+    }
+    if let Some(bits) = cli.min_entropy {
+        config.min_password_entropy = bits;
+        config.validate()?;
+    }
+    config.pepper = load_pepper(cli.pepper_file.as_deref())?;
+    if config.pepper.is_some() {
+        config.validate()?;
+    }
+
+    // --verbose または設定のデフォルトでデバッグログを有効化（--quiet ではエラーのみ）
+    init_logger(cli.command.verbose() || config.default_verbose, cli.quiet);
+
+    if !config.ignored_keys.is_empty() {
+        warn!(
+            "設定ファイルの不明な項目を無視しました: {}",
+            config.ignored_keys.join(", ")
+        );
+    }
+
+    if argon2_overridden {
+        info!(
+            "Argon2パラメータを上書き: メモリ {} KB, 時間コスト {}, 並列度 {}",
+            config.argon2.memory_cost, config.argon2.time_cost, config.argon2.parallelism
+        );
+    }
+
+    let batch_command = matches!(
+        cli.command,
+        Commands::EncryptFile { .. } | Commands::DecryptFile { .. }
+    );
+    if cli.dry_run && !batch_command {
+        bail!("--dry-run は encrypt-file / decrypt-file でのみ使用できます");
+    }
+    if cli.keep_going && !batch_command {
+        bail!("--keep-going は encrypt-file / decrypt-file でのみ使用できます");
+    }
 
     match &cli.command {
         Commands::Encrypt {
             text,
+            input,
+            output,
             password,
             password_env,
-            verbose,
+            stdin_password,
             no_newline,
+            pad_to,
+            stream,
+            hint,
+            from_clipboard,
+            to_clipboard,
+            binary_input,
+            checksum,
+            aad,
+            raw,
+            salt_file,
+            ..
         } => {
-            let input_text = get_input_text(text)?;
-            let password = get_password_with_config(password, password_env, &config)?;
-            let verbose = *verbose || config.default_verbose;
+            config.checksum = *checksum;
+            if let Some(path) = salt_file {
+                config.salt = Some(read_salt_file(path)?);
+            }
+            if let Some(block) = pad_to {
+                config.pad_block = Some(*block);
+                config.validate()?;
+            }
+            if hint.is_some() {
+                config.hint = hint.clone();
+                config.validate()?;
+            }
 
-            let encrypted = encrypt_string(&input_text, &password, &config, verbose)?;
+            if !*from_clipboard {
+                let prompt =
+                    reads_password_prompt(password, password_env, *stdin_password, &config);
+                check_stdin_usage(*stdin_password, prompt, text, input)?;
+            }
+            let password =
+                get_password_with_config(password, password_env, *stdin_password, &config)?;
+            check_encrypt_password(&password, &config, cli)?;
+
+            if *stream {
+                check_stream_json(cli.json)?;
+                return run_stream(input, output, partial, |reader, writer| {
+                    encrypt_from_reader(reader, writer, &password, &config)
+                });
+            }
+
+            let aad = aad.as_deref().unwrap_or_default().as_bytes();
+            if *raw {
+                if cli.json && output.is_none() {
+                    bail!("--raw で標準出力に書き出す場合は --json と併用できません");
+                }
+                let encrypted = if *binary_input {
+                    encrypt_to_raw_with_aad(&get_input_bytes(input)?, &password, aad, &config)?
+                } else {
+                    let input_text = read_input_text(text, input, *from_clipboard)?;
+                    encrypt_to_raw_with_aad(input_text.as_bytes(), &password, aad, &config)?
+                };
+                write_output_bytes(&encrypted, output)?;
+                if let (true, Some(path)) = (cli.json, output) {
+                    print_json(json!({
+                        "operation": "encrypt",
+                        "output": path.display().to_string(),
+                        "ok": true,
+                    }));
+                }
+                return Ok(());
+            }
+
+            let encrypted = if *binary_input {
+                encrypt_to_text_with_aad(&get_input_bytes(input)?, &password, aad, &config)?
+            } else {
+                let input_text = read_input_text(text, input, *from_clipboard)?;
+                encrypt_string_with_aad(&input_text, &password, aad, &config)?
+            };
 
-            if *no_newline {
-                print!("{encrypted}");
+            if *to_clipboard {
+                write_clipboard(&encrypted)?;
+                report_clipboard("encrypt", "暗号文", cli.json, cli.quiet);
+            } else if cli.json {
+                report_text_result("encrypt", &encrypted, output)?;
             } else {
-                println!("{encrypted}");
+                write_output_text(&encrypted, output, *no_newline)?;
             }
         }
 
         Commands::Decrypt {
             text,
+            input,
+            output,
             password,
             password_env,
-            verbose,
+            stdin_password,
             no_newline,
+            stream,
+            show_hint,
+            from_clipboard,
+            to_clipboard,
+            binary,
+            aad,
+            raw,
+            lines,
+            ..
         } => {
-            let input_text = get_input_text(text)?;
-            let password = get_password_with_config(password, password_env, &config)?;
-            let verbose = *verbose || config.default_verbose;
+            if *show_hint {
+                let hint = if *raw {
+                    password_hint(&get_input_bytes(input)?)?
+                } else {
+                    string_password_hint(&read_input_text(text, input, *from_clipboard)?)
+                };
+                report_hint(None, hint, cli.json);
+                return Ok(());
+            }
+
+            if !*from_clipboard {
+                let prompt =
+                    reads_password_prompt(password, password_env, *stdin_password, &config);
+                check_stdin_usage(*stdin_password, prompt, text, input)?;
+            }
+            let password =
+                get_password_with_config(password, password_env, *stdin_password, &config)?;
+
+            if *stream {
+                check_stream_json(cli.json)?;
+                return run_stream(input, output, partial, |reader, writer| {
+                    decrypt_to_writer_with_pepper(reader, writer, &password, config.pepper.as_ref())
+                });
+            }
+
+            if *binary && cli.json {
+                bail!("--binary は --json と併用できません");
+            }
+
+            let aad = aad.as_deref().unwrap_or_default().as_bytes();
+            if *lines {
+                if cli.json {
+                    bail!("--lines は --json と併用できません");
+                }
+                let input_text = read_input_text(text, input, *from_clipboard)?;
+                return decrypt_lines(&input_text, &password, aad, &config, output);
+            }
+
+            let decrypted = if *raw {
+                let data = get_input_bytes(input)?;
+                decrypt_bytes_with_aad(&data, &password, aad, &config).inspect_err(|e| {
+                    print_hint_on_failure(e, || password_hint(&data).ok().flatten())
+                })?
+            } else {
+                let input_text = read_input_text(text, input, *from_clipboard)?;
+                decrypt_from_text_with_aad(&input_text, &password, aad, &config).inspect_err(
+                    |e| print_hint_on_failure(e, || string_password_hint(&input_text)),
+                )?
+            };
 
-            let decrypted = decrypt_string(&input_text, &password, &config, verbose)?;
+            if *binary {
+                write_output_bytes(&decrypted, output)?;
+                return Ok(());
+            }
+            let decrypted = String::from_utf8(decrypted)
+                .map_err(CryptoError::from)
+                .context(
+                    "復号結果がテキストではありません（バイナリデータは --binary で出力できます）",
+                )?;
 
-            if *no_newline {
-                print!("{decrypted}");
+            if *to_clipboard {
+                write_clipboard(&decrypted)?;
+                report_clipboard("decrypt", "復号結果", cli.json, cli.quiet);
+            } else if cli.json {
+                report_text_result("decrypt", &decrypted, output)?;
             } else {
-                println!("{decrypted}");
+                write_output_text(&decrypted, output, *no_newline)?;
             }
         }
         Commands::EncryptFile {
-            input,
+            inputs,
             output,
             password,
             password_env,
-            verbose,
+            stdin_password,
             delete_original,
             streaming,
+            no_streaming,
+            chunk_size,
+            no_preserve_metadata,
+            threads,
+            armor,
+            output_dir,
+            ext,
+            force,
+            in_place,
+            hint,
+            checksum,
+            compress,
+            recursive,
+            format,
+            recipients_file,
+            no_verify,
+            salt_file,
+            split,
+            ..
         } => {
-            let password = get_password_with_config(password, password_env, &config)?;
-            let verbose = *verbose || config.default_verbose;
-            let output_path = determine_output_path(input, output, true)?;
+            let password = match recipients_file {
+                Some(path) => read_recipients_file(path)?,
+                None => password.clone(),
+            };
+            let age = *format == FileFormat::Age;
+            if age {
+                check_age_available()?;
+                if *streaming
+                    || *armor
+                    || hint.is_some()
+                    || *checksum
+                    || *compress != CompressMode::Never
+                    || *recursive
+                    || salt_file.is_some()
+                    || split.is_some()
+                {
+                    bail!(
+                        "--format age は --streaming / --armor / --hint / --checksum / --compress / --recursive / --salt-file / --split と併用できません"
+                    );
+                }
+                if password.len() > 1 {
+                    bail!("--format age では複数のパスワードを指定できません");
+                }
+                // age は独自にキーを導出するため、ペッパーを混ぜられない
+                if config.pepper.is_some() {
+                    bail!("--format age ではペッパーを使用できません");
+                }
+                config.encrypted_extension = AGE_EXTENSION.to_string();
+            }
+            config.checksum = *checksum;
+            config.compress = *compress;
+            if output_dir.is_some() {
+                config.default_output_dir = output_dir.clone();
+            }
+            if let Some(ext) = ext {
+                config.encrypted_extension = ext.trim_start_matches('.').to_string();
+                config.validate()?;
+            }
+            if let Some(size) = chunk_size {
+                config.stream_chunk_size = *size;
+                config.validate()?;
+            }
+            if hint.is_some() {
+                config.hint = hint.clone();
+                config.validate()?;
+            }
+            if *no_preserve_metadata {
+                config.preserve_metadata = false;
+            }
+            if let Some(path) = salt_file {
+                config.salt = Some(read_salt_file(path)?);
+            }
 
-            if *streaming {
-                encrypt_file_streaming(input, &output_path, &password, &config, verbose)?;
-            } else {
-                encrypt_file_standard(input, &output_path, &password, &config, verbose)?;
+            // --split はストリーミング形式で書き込む
+            let streaming = *streaming || split.is_some();
+
+            if password.len() > 1 && streaming {
+                bail!("複数のパスワードはストリーミング処理と併用できません");
             }
+            if password.len() > 1 && hint.is_some() {
+                bail!("複数のパスワードは --hint と併用できません");
+            }
+            if password.len() > 1 && *checksum {
+                bail!("複数のパスワードは --checksum と併用できません");
+            }
+            if password.len() > 1 && *compress != CompressMode::Never {
+                bail!("複数のパスワードは --compress と併用できません");
+            }
+            if password.len() > 1 && *recursive {
+                bail!("複数のパスワードは --recursive と併用できません");
+            }
+            if password.len() > 1 && salt_file.is_some() {
+                bail!("複数のパスワードは --salt-file と併用できません");
+            }
+
+            let (inputs, relative_paths) = if *recursive {
+                expand_directories(inputs)?
+            } else {
+                (inputs.clone(), HashMap::new())
+            };
+            let targets = file_targets(&inputs, output, &config, true, *force, *in_place, None)?;
+            let threads = batch_threads(*threads, &config, targets.len())?;
 
-            if *delete_original {
-                fs::remove_file(input)
-                    .with_context(|| format!("元ファイルの削除に失敗: {}", input.display()))?;
-                if verbose {
-                    println!("元ファイルを削除しました: {}", input.display());
+            // --streaming / --no-streaming がなければ、入力ファイルのサイズで処理方式を選ぶ
+            // （ストリーミング形式で使えないオプションがある場合は標準処理のまま）
+            let auto_streaming = !streaming
+                && !*no_streaming
+                && !age
+                && password.len() <= 1
+                && !*armor
+                && hint.is_none()
+                && !*checksum
+                && *compress == CompressMode::Never
+                && !*recursive;
+            let use_streaming = |input: &Path, bytes_in: u64| {
+                if streaming {
+                    return true;
                 }
+                let streaming = auto_streaming && bytes_in > config.streaming_threshold;
+                if auto_streaming {
+                    info!(
+                        "処理方式: {}（{} バイト、閾値 {} バイト）: {}",
+                        if streaming {
+                            "ストリーミング"
+                        } else {
+                            "標準"
+                        },
+                        bytes_in,
+                        config.streaming_threshold,
+                        input.display()
+                    );
+                }
+                streaming
+            };
+
+            if cli.dry_run {
+                for (input, output_path, bytes_in) in &targets {
+                    let mode = if age {
+                        DryRunMode::Age
+                    } else if password.len() > 1 {
+                        DryRunMode::Envelope(password.len())
+                    } else if use_streaming(input, *bytes_in) {
+                        DryRunMode::Streaming
+                    } else {
+                        DryRunMode::Standard
+                    };
+                    let plan = DryRunPlan {
+                        operation: "encrypt_file",
+                        input,
+                        output: output_path,
+                        mode,
+                        delete: *delete_original,
+                    };
+                    plan.report(cli.json)?;
+                }
+                return Ok(());
             }
 
-            println!("ファイル暗号化完了: {}", output_path.display());
+            create_output_dir(config.default_output_dir.as_deref(), output, *in_place)?;
+
+            // 単一のパスワードでは、全ファイルで1回のキー導出を共有する
+            let passwords: Vec<&str> = password.iter().map(String::as_str).collect();
+            let single_password = if passwords.len() > 1 {
+                for password in &passwords {
+                    check_encrypt_password(password, &config, cli)?;
+                }
+                None
+            } else {
+                let password = get_password_with_config(
+                    &password.first().cloned(),
+                    password_env,
+                    *stdin_password,
+                    &config,
+                )?;
+                check_encrypt_password(&password, &config, cli)?;
+                Some(password)
+            };
+            let batch = single_password
+                .as_deref()
+                .map(|password| file_batch(password, &config, threads, cli));
+            // 元ファイルを削除・置換する場合は、その前に暗号化したファイルを復号化して確かめる
+            let verify = (*delete_original || *in_place) && !*no_verify;
+
+            run_batch(&targets, threads, cli, |(input, output_path, bytes_in)| {
+                let encrypt = |output_path: &Path| -> Result<(), CryptoError> {
+                    if age {
+                        // --format age では複数のパスワードを受け付けないため、パスワードは1つ
+                        let password = single_password.as_deref().unwrap_or_default();
+                        encrypt_file_as_age(input, output_path, password)?;
+                        if verify {
+                            verify_file_as_age(output_path, input, password)?;
+                        }
+                        return Ok(());
+                    }
+                    if let (Some(batch), Some(size)) = (&batch, split) {
+                        let volumes = batch.encrypt_file_split(input, output_path, *size)?;
+                        if verify {
+                            batch.verify_file(&volumes[0], input)?;
+                            debug!("暗号化結果を検証しました: {}", volumes[0].display());
+                        }
+                        return Ok(());
+                    }
+                    match &batch {
+                        Some(batch) => match relative_paths.get(input) {
+                            Some(relative) => {
+                                batch.encrypt_file_with_path(input, output_path, relative)?
+                            }
+                            None => batch.encrypt_file(
+                                input,
+                                output_path,
+                                use_streaming(input, *bytes_in),
+                            )?,
+                        },
+                        None => {
+                            encrypt_file_for_passwords(input, output_path, &passwords, &config)?
+                        }
+                    }
+                    if *armor {
+                        armor_file(output_path)?;
+                    }
+                    if verify {
+                        match &batch {
+                            Some(batch) => batch.verify_file(output_path, input)?,
+                            None => {
+                                verify_encrypted_file(output_path, input, passwords[0], &config)?
+                            }
+                        }
+                        debug!("暗号化結果を検証しました: {}", output_path.display());
+                    }
+                    Ok(())
+                };
+                // 同じ出力先を処理中の他のプロセスと書き込みが混ざらないよう、出力先をロックする
+                let lock_path = match split {
+                    Some(_) => volume_path(output_path, 1),
+                    None => output_path.clone(),
+                };
+                with_output_lock(&lock_path, || {
+                    if *in_place {
+                        write_in_place(input, |temp| partial.track(temp, || encrypt(temp)))
+                    } else {
+                        partial.track(output_path, || encrypt(output_path))
+                    }
+                })?;
+
+                if *delete_original {
+                    fs::remove_file(input)
+                        .with_context(|| format!("元ファイルの削除に失敗: {}", input.display()))?;
+                    info!("元ファイルを削除しました: {}", input.display());
+                }
+
+                if split.is_some() {
+                    report_split_result(input, output_path, *bytes_in, cli)?;
+                } else if cli.json {
+                    report_file_result("encrypt_file", input, output_path, *bytes_in)?;
+                } else if !cli.quiet {
+                    println!(
+                        "{}",
+                        style::success(format_args!(
+                            "ファイル暗号化完了: {}",
+                            output_path.display()
+                        ))
+                    );
+                }
+                Ok(())
+            })?;
         }
 
         Commands::DecryptFile {
-            input,
+            inputs,
             output,
             password,
             password_env,
-            verbose,
+            stdin_password,
             delete_encrypted,
             streaming,
+            no_preserve_metadata,
+            threads,
+            output_dir,
+            ext,
+            force,
+            in_place,
+            show_hint,
+            into,
+            format,
+            max_attempts,
+            ..
         } => {
-            let password = get_password_with_config(password, password_env, &config)?;
-            let verbose = *verbose || config.default_verbose;
-            let output_path = determine_output_path(input, output, false)?;
-
-            if *streaming {
-                decrypt_file_streaming(input, &output_path, &password, &config, verbose)?;
-            } else {
-                decrypt_file_standard(input, &output_path, &password, &config, verbose)?;
+            let age = *format == FileFormat::Age;
+            if age {
+                check_age_available()?;
+                if *streaming || *show_hint || into.is_some() {
+                    bail!("--format age は --streaming / --show-hint / --into と併用できません");
+                }
+                config.encrypted_extension = AGE_EXTENSION.to_string();
+            }
+            if output_dir.is_some() {
+                config.default_output_dir = output_dir.clone();
+            }
+            if let Some(ext) = ext {
+                config.encrypted_extension = ext.trim_start_matches('.').to_string();
+                config.validate()?;
+            }
+            if *no_preserve_metadata {
+                config.preserve_metadata = false;
+            }
+
+            if *show_hint {
+                let inputs = expand_inputs(inputs)?;
+                for input in &inputs {
+                    let label = (inputs.len() > 1).then_some(input.as_path());
+                    report_hint(label, file_password_hint(input)?, cli.json);
+                }
+                return Ok(());
             }
 
-            if *delete_encrypted {
-                fs::remove_file(input)
-                    .with_context(|| format!("暗号化ファイルの削除に失敗: {}", input.display()))?;
-                if verbose {
-                    println!("暗号化ファイルを削除しました: {}", input.display());
+            let targets = file_targets(
+                inputs,
+                output,
+                &config,
+                false,
+                *force,
+                *in_place,
+                into.as_deref(),
+            )?;
+            let threads = batch_threads(*threads, &config, targets.len())?;
+
+            if cli.dry_run {
+                for (input, output_path, _) in &targets {
+                    let plan = DryRunPlan {
+                        operation: "decrypt_file",
+                        input,
+                        output: output_path,
+                        mode: if age {
+                            DryRunMode::Age
+                        } else if *streaming || detect_format(input)? == Some(EncFormat::Streaming)
+                        {
+                            DryRunMode::Streaming
+                        } else {
+                            DryRunMode::Standard
+                        },
+                        delete: *delete_encrypted,
+                    };
+                    plan.report(cli.json)?;
+                }
+                return Ok(());
+            }
+
+            create_output_dir(config.default_output_dir.as_deref(), output, *in_place)?;
+
+            // 対話的なプロンプト以外（引数・環境変数・標準入力の1行）のパスワードは入力し直せない
+            let max_attempts =
+                if prompts_for_password(password, password_env, *stdin_password, &config) {
+                    *max_attempts
+                } else {
+                    1
+                };
+            let password =
+                get_password_with_config(password, password_env, *stdin_password, &config)?;
+            let retry = PasswordRetry::new(password, max_attempts, |password| {
+                file_batch(password, &config, threads, cli)
+            });
+
+            run_batch(&targets, threads, cli, |(input, output_path, bytes_in)| {
+                // --into では、記録された相対パスのディレクトリを作成してから書き込む
+                if into.is_some() {
+                    if let Some(parent) = output_path.parent() {
+                        fs::create_dir_all(parent).with_context(|| {
+                            format!("出力ディレクトリの作成に失敗: {}", parent.display())
+                        })?;
+                    }
                 }
+                // 分割したボリュームは全ボリュームをつなげて復号化し、削除する場合も全ボリュームを削除する
+                let volumes = if !age && is_volume_file(input)? {
+                    if *in_place {
+                        bail!(
+                            "分割したボリュームは --in-place で復号化できません: {}",
+                            input.display()
+                        );
+                    }
+                    volume_paths(input)?
+                } else {
+                    vec![input.clone()]
+                };
+                // --streaming がなければ、ファイルの先頭から形式を判別する
+                let streaming =
+                    !age && (*streaming || detect_format(input)? == Some(EncFormat::Streaming));
+                // パスワードを入力し直す間も、出力先のロックは保持する
+                let decrypted = with_output_lock(output_path, || {
+                    retry.run(|password, batch| {
+                        let decrypt = |output_path: &Path| {
+                            if age {
+                                decrypt_file_as_age(input, output_path, password)
+                            } else {
+                                batch.decrypt_file(input, output_path, streaming)
+                            }
+                        };
+                        if *in_place {
+                            write_in_place(input, |temp| partial.track(temp, || decrypt(temp)))
+                        } else {
+                            partial.track(output_path, || decrypt(output_path))
+                        }
+                    })
+                });
+                decrypted.inspect_err(|e| {
+                    print_hint_on_failure(e, || file_password_hint(input).ok().flatten())
+                })?;
+
+                if *delete_encrypted {
+                    for input in &volumes {
+                        fs::remove_file(input).with_context(|| {
+                            format!("暗号化ファイルの削除に失敗: {}", input.display())
+                        })?;
+                        info!("暗号化ファイルを削除しました: {}", input.display());
+                    }
+                }
+
+                if cli.json {
+                    report_file_result("decrypt_file", input, output_path, *bytes_in)?;
+                } else if !cli.quiet {
+                    println!(
+                        "{}",
+                        style::success(format_args!(
+                            "ファイル復号化完了: {}",
+                            output_path.display()
+                        ))
+                    );
+                }
+                Ok(())
+            })?;
+        }
+
+        Commands::Rotate {
+            file,
+            old_password,
+            old_password_env,
+            new_password,
+            new_password_env,
+            ..
+        } => {
+            let old_password =
+                get_password_with_config(old_password, old_password_env, false, &config)?;
+            let new_password = get_password_with_prompt(
+                new_password,
+                new_password_env,
+                None,
+                "新しいパスワードを入力してください: ",
+            )?;
+            check_encrypt_password(&new_password, &config, cli)?;
+
+            with_output_lock(file, || {
+                rotate_file(file, &old_password, &new_password, &config)
+            })?;
+
+            if cli.json {
+                print_json(json!({
+                    "operation": "rotate",
+                    "file": file.display().to_string(),
+                    "ok": true,
+                }));
+            } else if !cli.quiet {
+                println!(
+                    "{}",
+                    style::success(format_args!("パスワード変更完了: {}", file.display()))
+                );
             }
+        }
 
-            println!("ファイル復号化完了: {}", output_path.display());
+        Commands::Inspect { file } => {
+            let info = inspect_file(file)
+                .with_context(|| format!("ヘッダーの読み取りに失敗: {}", file.display()))?;
+            report_inspect(file, &info, cli.json);
+        }
+
+        Commands::GenPassword { length, no_symbols } => {
+            if !(8..=1024).contains(length) {
+                bail!("パスワードの長さは8〜1024で指定してください: {length}");
+            }
+
+            let charset = if *no_symbols {
+                PasswordCharset::Alphanumeric
+            } else {
+                PasswordCharset::AlphanumericSymbols
+            };
+            let password = generate_password(*length, charset);
+            if cli.json {
+                print_json(json!({
+                    "operation": "gen_password",
+                    "password": password,
+                    "ok": true,
+                }));
+            } else {
+                println!("{password}");
+            }
+        }
+
+        Commands::Bench { size, streaming } => {
+            // 計測用のファイルは一時ディレクトリに作成し、失敗した場合も削除する
+            let dir =
+                std::env::temp_dir().join(format!("encript_tool-bench-{}", std::process::id()));
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("一時ディレクトリの作成に失敗: {}", dir.display()))?;
+            let result = run_bench(&dir, *size, *streaming, &config);
+            let _ = fs::remove_dir_all(&dir);
+            result?.report(cli.json);
+        }
+
+        Commands::Selftest => {
+            let dir =
+                std::env::temp_dir().join(format!("encript_tool-selftest-{}", std::process::id()));
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("一時ディレクトリの作成に失敗: {}", dir.display()))?;
+            let report = run_selftest(&dir, &config);
+            let _ = fs::remove_dir_all(&dir);
+            report.report(cli.json);
+            if !report.passed() {
+                // 失敗した項目は表示済みのため、エラーメッセージを重ねずに終了する
+                std::process::exit(FailureKind::Other as i32);
+            }
+        }
+
+        Commands::DeriveKey {
+            salt,
+            password,
+            password_env,
+            danger_print_key,
+        } => {
+            if !*danger_print_key {
+                bail!("キーを表示するには --danger-print-key を指定してください");
+            }
+            let salt: [u8; SALT_SIZE] = hex_decode(salt.trim())?.try_into().map_err(|_| {
+                anyhow::anyhow!("ソルトは {SALT_SIZE} バイト（16進数で32文字）で指定してください")
+            })?;
+            let password = get_password_with_config(password, password_env, false, &config)?;
+            let params = KdfParams::from_config(&config);
+
+            // --quiet でも警告は必ず表示する
+            eprintln!(
+                "{} 導出したキーを表示します。このキーがあればパスワードなしで同じパラメータの暗号文を復号化できるため、相互検証以外では使わず、出力を保存・共有しないでください",
+                style::warning("警告:")
+            );
+            let key = derive_key_with_pepper(&password, &salt, &params, config.pepper.as_ref())?;
+            if cli.json {
+                print_json(json!({
+                    "operation": "derive_key",
+                    "algorithm": params.name(),
+                    "key": hex_encode(&key),
+                    "ok": true,
+                }));
+            } else {
+                println!("{}", hex_encode(&key));
+            }
         }
 
         Commands::Config { action } => {
-            handle_config_command(action, cli.config.as_deref())?;
+            handle_config_command(action, cli.config.as_deref(), cli.quiet)?;
+        }
+
+        Commands::Completions { shell } => {
+            // 標準出力へ直接生成すると、書き込みに失敗した（パイプが閉じられた）場合にパニックするため、
+            // いったんメモリに生成してから通常のエラー処理で書き込む
+            let mut script = Vec::new();
+            clap_complete::generate(
+                *shell,
+                &mut completion_command(),
+                env!("CARGO_BIN_NAME"),
+                &mut script,
+            );
+            write_output_bytes(&script, &None)?;
         }
 
         #[cfg(feature = "gui")]
@@ -290,7 +1580,7 @@ fn main() -> Result<()> {
                 native_options,
                 Box::new(|cc| Ok(Box::new(gui::CryptApp::new(cc)))),
             ) {
-                eprintln!("GUI起動エラー: {}", e);
+                eprintln!("{} {e}", style::error("GUI起動エラー:"));
                 std::process::exit(1);
             }
         }
@@ -299,25 +1589,1521 @@ fn main() -> Result<()> {
     Ok(())
 }
 
-/// 入力テキストを取得（引数または標準入力）
-fn get_input_text(text: &Option<String>) -> Result<String> {
-    match text {
-        Some(t) => Ok(t.clone()),
+/// --argon2-memory / --argon2-time / --argon2-parallelism を設定に適用して検証
+///
+/// いずれかが指定された場合は `true` を返します。パラメータはヘッダーに記録されるため、
+/// 復号化時に同じ指定をする必要はありません。
+fn apply_argon2_overrides(cli: &Cli, config: &mut Config) -> Result<bool> {
+    let overrides = [
+        (cli.argon2_memory, &mut config.argon2.memory_cost),
+        (cli.argon2_time, &mut config.argon2.time_cost),
+        (cli.argon2_parallelism, &mut config.argon2.parallelism),
+    ];
+    let mut overridden = false;
+    for (value, field) in overrides {
+        if let Some(value) = value {
+            *field = value;
+            overridden = true;
+        }
+    }
+    if overridden {
+        config.validate()?;
+    }
+    Ok(overridden)
+}
+
+/// ペッパーを指定する環境変数（値のバイト列をそのまま使う）
+const PEPPER_ENV: &str = "MYCRYPT_PEPPER";
+
+/// --pepper-file、なければ環境変数 MYCRYPT_PEPPER からペッパーを読み込む（どちらもなければ None）
+///
+/// ペッパーは設定ファイルには保存できません。
+fn load_pepper(pepper_file: Option<&Path>) -> Result<Option<Pepper>> {
+    if let Some(path) = pepper_file {
+        return Ok(Some(read_pepper_file(path)?));
+    }
+    match std::env::var_os(PEPPER_ENV).filter(|value| !value.is_empty()) {
+        Some(value) => Ok(Some(Pepper::new(value.into_encoded_bytes())?)),
+        None => Ok(None),
+    }
+}
+
+/// `--time` で表示する処理段階ごとの時間（例: 「鍵導出: 0.48s, 暗号化: 0.12s, 読み込み: 0.05s, 書き込み: 0.30s」）
+fn format_phase_times(times: &PhaseTimes, cipher_label: &str) -> String {
+    format!(
+        "鍵導出: {:.2}s, {cipher_label}: {:.2}s, 読み込み: {:.2}s, 書き込み: {:.2}s",
+        times.key_derivation.as_secs_f64(),
+        times.cipher.as_secs_f64(),
+        times.read.as_secs_f64(),
+        times.write.as_secs_f64(),
+    )
+}
+
+/// ロガーを初期化（出力先は標準エラー、RUST_LOG で上書き可能）
+fn init_logger(verbose: bool, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else if verbose {
+        LevelFilter::Debug
+    } else {
+        LevelFilter::Warn
+    };
+
+    let write_style = if style::stderr_color() {
+        WriteStyle::Auto
+    } else {
+        WriteStyle::Never
+    };
+
+    env_logger::Builder::new()
+        .filter_level(level)
+        .write_style(write_style)
+        .parse_default_env()
+        .format_timestamp(None)
+        .init();
+}
+
+/// 入力テキストを取得（引数 → --in のファイル → 標準入力の順）
+fn get_input_text(text: &Option<String>, input: &Option<PathBuf>) -> Result<String> {
+    if let Some(t) = text {
+        return Ok(t.clone());
+    }
+
+    let buffer = match input {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("入力ファイルの読み取りに失敗: {}", path.display()))?,
         None => {
             let mut buffer = String::new();
             io::stdin()
                 .read_to_string(&mut buffer)
                 .context("標準入力の読み取りに失敗しました")?;
-            Ok(buffer.trim().to_string())
+            buffer
+        }
+    };
+    Ok(buffer.trim().to_string())
+}
+
+/// 入力をバイト列のまま取得（--in のファイル → 標準入力の順）
+fn get_input_bytes(input: &Option<PathBuf>) -> Result<Vec<u8>> {
+    match input {
+        Some(path) => fs::read(path)
+            .with_context(|| format!("入力ファイルの読み取りに失敗: {}", path.display())),
+        None => {
+            let mut buffer = Vec::new();
+            io::stdin()
+                .read_to_end(&mut buffer)
+                .context("標準入力の読み取りに失敗しました")?;
+            Ok(buffer)
+        }
+    }
+}
+
+/// 入力テキストを取得（--from-clipboard の場合はクリップボードから）
+fn read_input_text(
+    text: &Option<String>,
+    input: &Option<PathBuf>,
+    from_clipboard: bool,
+) -> Result<String> {
+    if from_clipboard {
+        read_clipboard()
+    } else {
+        get_input_text(text, input)
+    }
+}
+
+/// クリップボードを開く（デスクトップ環境がない場合はエラー）
+#[cfg(feature = "clipboard")]
+fn open_clipboard() -> Result<arboard::Clipboard> {
+    arboard::Clipboard::new()
+        .context("クリップボードにアクセスできません（デスクトップ環境の外では使用できません）")
+}
+
+/// クリップボードのテキストを読み取る
+#[cfg(feature = "clipboard")]
+fn read_clipboard() -> Result<String> {
+    let text = open_clipboard()?
+        .get_text()
+        .context("クリップボードからテキストを読み取れませんでした")?;
+    Ok(text.trim().to_string())
+}
+
+/// クリップボードにテキストを書き込む
+///
+/// Linux（X11 / Wayland）では、終了後も内容を残すにはクリップボードマネージャーが必要です。
+#[cfg(feature = "clipboard")]
+fn write_clipboard(text: &str) -> Result<()> {
+    open_clipboard()?
+        .set_text(text)
+        .context("クリップボードへの書き込みに失敗しました")
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn read_clipboard() -> Result<String> {
+    bail!("{CLIPBOARD_DISABLED}")
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn write_clipboard(_text: &str) -> Result<()> {
+    bail!("{CLIPBOARD_DISABLED}")
+}
+
+#[cfg(not(feature = "clipboard"))]
+const CLIPBOARD_DISABLED: &str =
+    "クリップボードを使うには clipboard フィーチャーを有効にしてビルドしてください（cargo build --features clipboard）";
+
+/// `--format age` で暗号化したファイルに付ける拡張子
+const AGE_EXTENSION: &str = "age";
+
+/// `--format age` が使えるビルドか確認する
+fn check_age_available() -> Result<()> {
+    if cfg!(feature = "age") {
+        Ok(())
+    } else {
+        bail!("{AGE_DISABLED}")
+    }
+}
+
+#[cfg(feature = "age")]
+fn encrypt_file_as_age(input: &Path, output: &Path, password: &str) -> Result<(), CryptoError> {
+    encript_tool::age_compat::encrypt_file_age(input, output, password)
+}
+
+#[cfg(feature = "age")]
+fn decrypt_file_as_age(input: &Path, output: &Path, password: &str) -> Result<(), CryptoError> {
+    encript_tool::age_compat::decrypt_file_age(input, output, password)
+}
+
+#[cfg(feature = "age")]
+fn verify_file_as_age(
+    encrypted: &Path,
+    original: &Path,
+    password: &str,
+) -> Result<(), CryptoError> {
+    encript_tool::age_compat::verify_file_age(encrypted, original, password)
+}
+
+#[cfg(not(feature = "age"))]
+fn encrypt_file_as_age(_input: &Path, _output: &Path, _password: &str) -> Result<(), CryptoError> {
+    Err(CryptoError::InvalidInput(AGE_DISABLED.to_string()))
+}
+
+#[cfg(not(feature = "age"))]
+fn decrypt_file_as_age(_input: &Path, _output: &Path, _password: &str) -> Result<(), CryptoError> {
+    Err(CryptoError::InvalidInput(AGE_DISABLED.to_string()))
+}
+
+#[cfg(not(feature = "age"))]
+fn verify_file_as_age(
+    _encrypted: &Path,
+    _original: &Path,
+    _password: &str,
+) -> Result<(), CryptoError> {
+    Err(CryptoError::InvalidInput(AGE_DISABLED.to_string()))
+}
+
+const AGE_DISABLED: &str =
+    "age 形式を使うには age フィーチャーを有効にしてビルドしてください（cargo build --features age）";
+
+/// クリップボードに書き込んだことを表示（結果そのものは出力しない）
+fn report_clipboard(operation: &str, label: &str, json: bool, quiet: bool) {
+    if json {
+        print_json(json!({
+            "operation": operation,
+            "output": "clipboard",
+            "ok": true,
+        }));
+    } else if !quiet {
+        println!(
+            "{}",
+            style::success(format_args!("{label}をクリップボードにコピーしました"))
+        );
+    }
+}
+
+/// `--show-hint` の結果を表示（複数ファイルの場合は `label` にファイルのパスを渡す）
+fn report_hint(label: Option<&Path>, hint: Option<String>, json: bool) {
+    if json {
+        print_json(json!({
+            "operation": "show_hint",
+            "input": label.map(|path| path.display().to_string()),
+            "hint": hint,
+            "ok": true,
+        }));
+        return;
+    }
+
+    let prefix = label
+        .map(|path| format!("{}: ", path.display()))
+        .unwrap_or_default();
+    match hint {
+        Some(hint) => println!("{prefix}ヒント: {hint}"),
+        None => println!("{prefix}ヒントは記録されていません"),
+    }
+}
+
+/// `inspect` の結果を表示（ヘッダーは認証されていないことを明記する）
+fn report_inspect(path: &Path, info: &FileInfo, json: bool) {
+    let salt = info.salt.as_ref().map(|salt| hex_encode(salt));
+    let metadata = info.metadata.as_ref();
+
+    if json {
+        let format = match &info.format {
+            FormatInfo::Standard {
+                version,
+                padded,
+                hint,
+                checksum,
+                path,
+                compressed,
+                ciphertext_len,
+            } => json!({
+                "type": "standard",
+                "version": version,
+                "padded": padded,
+                "hint": hint,
+                "checksum": checksum,
+                "path": path,
+                "compressed": compressed,
+                "ciphertext_len": ciphertext_len,
+            }),
+            FormatInfo::Streaming {
+                chunk_size,
+                chunk_count,
+                complete,
+                volumes,
+            } => json!({
+                "type": "streaming",
+                "chunk_size": chunk_size,
+                "chunk_count": chunk_count,
+                "complete": complete,
+                "volumes": volumes,
+            }),
+            FormatInfo::Envelope { slots } => json!({
+                "type": "envelope",
+                "slots": slots,
+            }),
+        };
+        print_json(json!({
+            "operation": "inspect",
+            "input": path.display().to_string(),
+            "armored": info.armored,
+            "encoding": info.encoding.as_ref().map(|format| format!("{format:?}").to_lowercase()),
+            "size": info.size,
+            "header_len": info.header_len,
+            "cipher": "AES-256-GCM",
+            "kdf": info.kdf.to_string(),
+            "salt": salt,
+            "mode": metadata.map(|m| format!("{:o}", m.mode)),
+            "modified_secs": metadata.map(|m| m.modified_secs),
+            "format": format,
+            "ok": true,
+        }));
+        return;
+    }
+
+    let format = match &info.format {
+        FormatInfo::Standard { version, .. } => format!("標準（バージョン {version}）"),
+        FormatInfo::Streaming { .. } => "ストリーミング".to_string(),
+        FormatInfo::Envelope { .. } => "複数パスワード".to_string(),
+    };
+    let armored = match (&info.encoding, info.armored) {
+        (Some(format), _) => format!("、{format:?}の文字列"),
+        (None, true) => "、ASCIIアーマー".to_string(),
+        (None, false) => String::new(),
+    };
+    println!("{}", style::note(format_args!("{}", path.display())));
+    println!("  形式: {format}{armored}");
+    println!("  サイズ: {} バイト", info.size);
+    println!("  ヘッダー長: {} バイト", info.header_len);
+    println!("  暗号方式: AES-256-GCM");
+    println!("  キー導出: {}", info.kdf);
+    match &salt {
+        Some(salt) => println!("  ソルト: {salt}"),
+        None => println!("  ソルト: パスワードのスロットごとに異なります"),
+    }
+    match metadata {
+        Some(m) => println!(
+            "  メタデータ: パーミッション {:o}、更新日時 {}（UNIX秒）",
+            m.mode, m.modified_secs
+        ),
+        None => println!("  メタデータ: なし"),
+    }
+    match &info.format {
+        FormatInfo::Standard {
+            padded,
+            hint,
+            checksum,
+            path,
+            compressed,
+            ciphertext_len,
+            ..
+        } => {
+            println!("  パディング: {}", if *padded { "あり" } else { "なし" });
+            println!("  ヒント: {}", hint.as_deref().unwrap_or("なし"));
+            println!(
+                "  チェックサム: {}",
+                if *checksum {
+                    "あり（SHA-256）"
+                } else {
+                    "なし"
+                }
+            );
+            if let Some(path) = path {
+                println!("  相対パス: {path}");
+            }
+            match compressed {
+                Some(len) => println!("  圧縮: あり（Deflate、展開後 {len} バイト）"),
+                None => println!("  圧縮: なし"),
+            }
+            println!("  暗号文: {ciphertext_len} バイト（認証タグを含む）");
+        }
+        FormatInfo::Streaming {
+            chunk_size,
+            chunk_count,
+            complete,
+            volumes,
+        } => {
+            if let Some(volumes) = volumes {
+                println!("  ボリューム数: {volumes}");
+            }
+            println!("  チャンクサイズ: {chunk_size} バイト");
+            println!("  チャンク数: {chunk_count}");
+            if *complete {
+                println!("  フッター: あり");
+            } else {
+                println!("  フッター: {}", style::warning("途中で切れています"));
+            }
+        }
+        FormatInfo::Envelope { slots } => println!("  スロット数: {slots}"),
+    }
+    println!(
+        "{}",
+        style::note("※ パスワードを使わずに読み取ったため、内容は改ざんされていないことが保証されていません")
+    );
+}
+
+/// パスワード違いで復号化に失敗した場合、記録されているヒントを標準エラーに表示
+fn print_hint_on_failure(error: &CryptoError, hint: impl FnOnce() -> Option<String>) {
+    if matches!(error, CryptoError::WrongPasswordOrCorrupt) {
+        if let Some(hint) = hint() {
+            eprintln!("ヒント: {hint}");
+        }
+    }
+}
+
+/// `--stream` は結果をそのまま出力するため、`--json` とは併用できない
+fn check_stream_json(json: bool) -> Result<()> {
+    if json {
+        bail!("--stream は --json と併用できません");
+    }
+    Ok(())
+}
+
+/// `--stream` の入出力を開いて処理する
+///
+/// 入力は --in 指定時はファイル、それ以外は標準入力、出力は --out 指定時はファイル、それ以外は
+/// 標準出力です。いずれもチャンク単位で読み書きするため、全体をメモリに読み込みません。
+/// 失敗した場合や Ctrl-C で中断された場合、--out のファイルは途中までの内容を残さないよう削除します。
+fn run_stream<F>(
+    input: &Option<PathBuf>,
+    output: &Option<PathBuf>,
+    partial: &PartialOutputs,
+    run: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut Box<dyn Read>, &mut Box<dyn Write>) -> Result<(), CryptoError>,
+{
+    let mut reader: Box<dyn Read> = match input {
+        Some(path) => Box::new(io::BufReader::new(fs::File::open(path).with_context(
+            || format!("入力ファイルのオープンに失敗: {}", path.display()),
+        )?)),
+        None => Box::new(io::stdin().lock()),
+    };
+
+    let stream = || -> Result<()> {
+        let mut writer: Box<dyn Write> = match output {
+            Some(path) => Box::new(io::BufWriter::new(
+                fs::File::create(path)
+                    .with_context(|| format!("出力ファイルの作成に失敗: {}", path.display()))?,
+            )),
+            None => Box::new(io::BufWriter::new(io::stdout().lock())),
+        };
+
+        let result = run(&mut reader, &mut writer);
+        drop(writer);
+        if let Some(path) = output {
+            if result.is_err() {
+                let _ = fs::remove_file(path);
+            } else {
+                info!("結果を書き込みました: {}", path.display());
+            }
+        }
+        Ok(result?)
+    };
+    match output {
+        Some(path) => partial.track(path, stream),
+        None => stream(),
+    }
+}
+
+/// 結果を出力（--out 指定時はファイル、それ以外は標準出力）
+fn write_output_text(text: &str, output: &Option<PathBuf>, no_newline: bool) -> Result<()> {
+    let newline = if no_newline { "" } else { "\n" };
+
+    match output {
+        Some(path) => {
+            fs::write(path, format!("{text}{newline}"))
+                .with_context(|| format!("出力ファイルの書き込みに失敗: {}", path.display()))?;
+            info!("結果を書き込みました: {}", path.display());
+        }
+        None => print!("{text}{newline}"),
+    }
+    Ok(())
+}
+
+/// `decrypt --lines`: 暗号文ごとに復号化して1行ずつ出力する
+///
+/// 失敗した暗号文は行番号とともに標準エラー出力に報告して残りの処理を続け、
+/// 最後に失敗した件数と最初のエラーを返します。
+fn decrypt_lines(
+    text: &str,
+    password: &str,
+    aad: &[u8],
+    config: &Config,
+    output: &Option<PathBuf>,
+) -> Result<()> {
+    let mut decrypted = Vec::new();
+    let mut failures = Vec::new();
+    for (line, result) in decrypt_text_lines(text, password, aad, config) {
+        match result.and_then(|plaintext| Ok(String::from_utf8(plaintext)?)) {
+            Ok(plaintext) => decrypted.push(plaintext),
+            Err(e) => {
+                eprintln!("{} {line}行目: {e}", style::error("Error:"));
+                failures.push(e);
+            }
+        }
+    }
+
+    if !decrypted.is_empty() {
+        write_output_text(&decrypted.join("\n"), output, false)?;
+    }
+    match failures.len() {
+        0 => Ok(()),
+        count => Err(anyhow::Error::new(failures.swap_remove(0))
+            .context(format!("{count} 件の暗号文を復号化できませんでした"))),
+    }
+}
+
+/// バイト列をそのまま出力（--out 指定時はファイル、それ以外は標準出力）
+fn write_output_bytes(data: &[u8], output: &Option<PathBuf>) -> Result<()> {
+    match output {
+        Some(path) => {
+            fs::write(path, data)
+                .with_context(|| format!("出力ファイルの書き込みに失敗: {}", path.display()))?;
+            info!("結果を書き込みました: {}", path.display());
+        }
+        None => {
+            let mut stdout = io::stdout().lock();
+            stdout
+                .write_all(data)
+                .and_then(|()| stdout.flush())
+                .context("標準出力への書き込みに失敗しました")?;
         }
     }
+    Ok(())
+}
+
+/// 補完スクリプトの生成に使うコマンド定義
+///
+/// ヘルプに表示しないサブコマンド（`derive-key`）は補完の候補にも含めません。
+fn completion_command() -> clap::Command {
+    let cli = Cli::command();
+    clap::Command::new(env!("CARGO_BIN_NAME"))
+        .version(env!("CARGO_PKG_VERSION"))
+        .args(cli.get_arguments().cloned())
+        .subcommands(
+            cli.get_subcommands()
+                .filter(|sub| !sub.is_hide_set())
+                .cloned(),
+        )
+}
+
+/// JSONを1行で標準出力に出力
+fn print_json(value: serde_json::Value) {
+    println!("{value}");
+}
+
+/// ファイルサイズを取得
+fn file_len(path: &Path) -> Result<u64> {
+    Ok(fs::metadata(path)
+        .with_context(|| format!("ファイル情報の取得に失敗: {}", path.display()))?
+        .len())
+}
+
+/// 入力パスのワイルドカードを展開する
+///
+/// シェルが展開しない環境（Windowsなど）でも `*.txt` や `docs/**/*.md` を同じように扱えるよう、
+/// `*` `?` `[` を含むパスは glob パターンとして展開します（同名のファイルが存在する場合を除く）。
+/// 一致したファイルは重複を除いて指定順に並べます。
+fn expand_inputs(inputs: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = Vec::new();
+    let mut push = |path: PathBuf| {
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    };
+
+    for input in inputs {
+        let pattern = input.to_string_lossy();
+        if !pattern.contains(['*', '?', '[']) || input.exists() {
+            push(input.clone());
+            continue;
+        }
+
+        let mut matched = false;
+        for entry in
+            glob::glob(&pattern).with_context(|| format!("不正なパターンです: {pattern}"))?
+        {
+            let path = entry.context("パターンに一致するパスの読み込みに失敗")?;
+            if path.is_file() {
+                matched = true;
+                push(path);
+            }
+        }
+
+        // 存在しないファイル名として開こうとせず、パターンが空振りしたことを報告する
+        if !matched {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("パターンに一致するファイルがありません: {pattern}"),
+            )
+            .into());
+        }
+    }
+    Ok(files)
+}
+
+/// `--recursive` の入力を展開する
+///
+/// ディレクトリは中のファイルを再帰的に列挙し、各ファイルのディレクトリからの相対パスも返します。
+/// ファイルの指定はそのまま残します（相対パスは記録しません）。
+fn expand_directories(inputs: &[PathBuf]) -> Result<(Vec<PathBuf>, HashMap<PathBuf, PathBuf>)> {
+    let mut files = Vec::new();
+    let mut relative_paths = HashMap::new();
+    for input in inputs {
+        if !input.is_dir() {
+            files.push(input.clone());
+            continue;
+        }
+
+        let mut found = Vec::new();
+        collect_files(input, &mut found)?;
+        for path in found {
+            let relative = path
+                .strip_prefix(input)
+                .expect("ディレクトリ内のパス")
+                .to_path_buf();
+            relative_paths.insert(path.clone(), relative);
+            files.push(path);
+        }
+    }
+    Ok((files, relative_paths))
+}
+
+/// ディレクトリ内のファイルを名前順に再帰的に列挙する
+///
+/// シンボリックリンクはたどらず、既に暗号化されているファイルは対象外にします。
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let context = || format!("ディレクトリの読み込みに失敗: {}", dir.display());
+    let mut entries = fs::read_dir(dir)
+        .with_context(context)?
+        .collect::<io::Result<Vec<_>>>()
+        .with_context(context)?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let path = entry.path();
+        let file_type = entry.file_type().with_context(context)?;
+        if file_type.is_dir() {
+            collect_files(&path, files)?;
+        } else if file_type.is_file() {
+            if detect_format(&path)?.is_some() {
+                info!("暗号化済みのファイルをスキップしました: {}", path.display());
+                continue;
+            }
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 処理対象のファイル（入力パス、出力パス、入力サイズ）を決定
+///
+/// `in_place` の場合、出力パスは入力パスと同じになります。`into` を指定した場合（復号化のみ）は、
+/// ヘッダーに記録された相対パスをもとにそのディレクトリの中に置きます。
+/// `--output` を指定しない場合の出力先は、設定の出力ディレクトリと拡張子から決めます。
+/// 書き込む前に全ファイルの出力先を決めるため、同じ名前になるファイル（別のディレクトリの `x.txt` を
+/// `--output-dir` に出力する場合など）は、割り当て済みの名前を避けて番号を付けます。
+fn file_targets(
+    inputs: &[PathBuf],
+    output: &Option<PathBuf>,
+    config: &Config,
+    is_encrypt: bool,
+    force: bool,
+    in_place: bool,
+    into: Option<&Path>,
+) -> Result<Vec<(PathBuf, PathBuf, u64)>> {
+    let inputs = expand_inputs(inputs)?;
+    if output.is_some() && inputs.len() > 1 {
+        bail!(
+            "--output は入力ファイルが1つの場合のみ指定できます（{}個のファイルが指定されました）",
+            inputs.len()
+        );
+    }
+
+    let mut taken = HashSet::new();
+    inputs
+        .into_iter()
+        .map(|input| {
+            let output_path = match into {
+                _ if in_place => input.clone(),
+                Some(dir) => restore_path_into_excluding(&input, dir, config, force, &taken)?,
+                None => determine_output_path_excluding(
+                    &input, output, config, is_encrypt, force, &taken,
+                )?,
+            };
+            taken.insert(output_path.clone());
+            let bytes_in = file_len(&input)?;
+            Ok((input, output_path, bytes_in))
+        })
+        .collect()
+}
+
+/// 自動で決めた出力先のディレクトリが存在しなければ作成（`--output` / `--in-place` では何もしない）
+fn create_output_dir(
+    output_dir: Option<&Path>,
+    output: &Option<PathBuf>,
+    in_place: bool,
+) -> Result<()> {
+    if let (Some(dir), None, false) = (output_dir, output, in_place) {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("出力ディレクトリの作成に失敗: {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// 一括処理の並列数を決定する
+///
+/// 既定はCPU数です。キー導出はスレッドごとにメモリを消費するため（Argon2idなら `memory_cost` KB、
+/// 復号化ではファイルのヘッダーの値ですが、ここでは設定の値で見積もる）、
+/// 並列数 × 1回あたりの使用量が利用可能なメモリの半分に収まるように制限します。
+fn batch_threads(requested: Option<usize>, config: &Config, files: usize) -> Result<usize> {
+    if requested == Some(0) {
+        bail!("--threads には1以上を指定してください");
+    }
+    let mut threads = requested
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+        .min(files.max(1));
+
+    let per_thread = KdfParams::from_config(config).memory_bytes();
+    if let (Some(available), true) = (available_memory(), per_thread > 0) {
+        let limit = usize::try_from((available / 2 / per_thread).max(1)).unwrap_or(usize::MAX);
+        if threads > limit {
+            let message = format!(
+                "キー導出のメモリ使用量（1スレッドあたり約 {} MB）を抑えるため、並列数を {threads} から {limit} に制限します",
+                per_thread / 1_048_576
+            );
+            if requested.is_some() {
+                warn!("{message}");
+            } else {
+                debug!("{message}");
+            }
+            threads = limit;
+        }
+    }
+
+    debug!("並列数: {threads}");
+    Ok(threads)
+}
+
+/// 一括処理を作成（並列に処理する場合はプログレスバーが重ならないよう表示せず、--quiet でも表示しない）
+///
+/// --max-memory を指定した場合は、同時に行うキー導出のメモリ使用量をその範囲に抑えます。
+///
+/// --progress-json では、プログレスバーの代わりに進捗をJSONで標準エラー出力に書き込みます。
+fn file_batch<'a>(password: &str, config: &'a Config, threads: usize, cli: &Cli) -> FileBatch<'a> {
+    let mut batch = FileBatch::new(password, config);
+    if let Some(bytes) = cli.max_memory {
+        batch = batch.with_memory_budget(bytes);
+    }
+    if threads > 1 || cli.quiet {
+        batch.without_progress()
+    } else if cli.progress_json {
+        batch.with_progress(json_progress())
+    } else {
+        batch
+    }
+}
+
+/// --progress-json で進捗を書き込む最短の間隔（完了時は間隔にかかわらず書き込む）
+const PROGRESS_JSON_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 進捗を `{"done":処理済みバイト数,"total":総バイト数}` の1行のJSONとして標準エラー出力に書き込むコールバック
+fn json_progress() -> impl Fn(u64, u64) + Send + Sync {
+    // 最後に書き込んだ時刻と処理済みバイト数
+    let last = Mutex::new(None::<(Instant, u64)>);
+    move |done, total| {
+        let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+        let due = match *last {
+            None => true,
+            Some((_, last_done)) if done == last_done => false,
+            Some((at, _)) => done >= total || at.elapsed() >= PROGRESS_JSON_INTERVAL,
+        };
+        if due {
+            *last = Some((Instant::now(), done));
+            eprintln!("{}", json!({ "done": done, "total": total }));
+        }
+    }
+}
+
+/// プロンプトで入力したパスワードが違う場合に、入力し直してもらいながら処理する
+///
+/// 複数のスレッドが同じパスワードで同時に認証に失敗しても、入力し直すのは1回だけです。
+/// キー導出のソルトとパラメータは各ファイルのヘッダーにあるため、入力し直すたびに
+/// 作り直すのはパスワードに結び付いた `T`（キーのキャッシュなど）だけです。
+struct PasswordRetry<T, F> {
+    max_attempts: u32,
+    make: F,
+    state: Mutex<RetryState<T>>,
+}
+
+struct RetryState<T> {
+    /// 現在のパスワードが何回目の入力か
+    attempt: u32,
+    password: Arc<String>,
+    value: Arc<T>,
+}
+
+impl<T, F: Fn(&str) -> T> PasswordRetry<T, F> {
+    fn new(password: String, max_attempts: u32, make: F) -> Self {
+        let value = Arc::new(make(&password));
+        Self {
+            max_attempts,
+            make,
+            state: Mutex::new(RetryState {
+                attempt: 1,
+                password: Arc::new(password),
+                value,
+            }),
+        }
+    }
+
+    /// `run` を実行し、認証に失敗した場合は試行回数の上限までパスワードを入力し直して再実行する
+    fn run<R>(&self, run: impl Fn(&str, &T) -> Result<R, CryptoError>) -> Result<R, CryptoError> {
+        loop {
+            let (attempt, password, value) = {
+                let state = self.state.lock().unwrap();
+                (state.attempt, state.password.clone(), state.value.clone())
+            };
+            match run(&password, &value) {
+                Err(CryptoError::WrongPasswordOrCorrupt) => {
+                    let mut state = self.state.lock().unwrap();
+                    // 他のスレッドが入力し直していれば、新しいパスワードでやり直す
+                    if state.attempt != attempt {
+                        continue;
+                    }
+                    if attempt >= self.max_attempts {
+                        return Err(CryptoError::WrongPasswordOrCorrupt);
+                    }
+                    eprintln!(
+                        "{} パスワードが違うか、データが破損しています（あと{}回入力できます）",
+                        style::error("認証に失敗しました:"),
+                        self.max_attempts - attempt
+                    );
+                    let password =
+                        prompt_password("パスワードを入力してください: ").map_err(|source| {
+                            CryptoError::Io {
+                                context: "パスワードの読み取りに失敗しました".to_string(),
+                                source,
+                            }
+                        })?;
+                    state.value = Arc::new((self.make)(&password));
+                    state.password = Arc::new(password);
+                    state.attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// 一括処理の対象ファイルを `run_parallel` で処理する
+///
+/// --keep-going では失敗したファイルがあっても残りのファイルを処理し、最後に失敗したファイルごとのエラーを
+/// 表示して（--json では1ファイル1行のJSON）、成功・失敗の件数と最初のエラーを返します。
+fn run_batch(
+    targets: &[(PathBuf, PathBuf, u64)],
+    threads: usize,
+    cli: &Cli,
+    run: impl Fn(&(PathBuf, PathBuf, u64)) -> Result<()> + Sync,
+) -> Result<()> {
+    if !cli.keep_going {
+        return run_parallel(targets, threads, run);
+    }
+
+    let failures = Mutex::new(Vec::new());
+    run_parallel(targets, threads, |target| {
+        if let Err(e) = run(target) {
+            failures.lock().unwrap().push((target.0.clone(), e));
+        }
+        Ok(())
+    })?;
+
+    let mut failures = failures.into_inner().unwrap();
+    if failures.is_empty() {
+        return Ok(());
+    }
+    // 並列に処理した場合も、入力ファイルの順に表示する
+    failures.sort_by(|(a, _), (b, _)| a.cmp(b));
+    let operation = match cli.command {
+        Commands::EncryptFile { .. } => "encrypt_file",
+        _ => "decrypt_file",
+    };
+    for (input, e) in &failures {
+        if cli.json {
+            print_json(json!({
+                "operation": operation,
+                "input": input.display().to_string(),
+                "ok": false,
+                "error": format!("{e:#}"),
+            }));
+        } else {
+            eprintln!("{} {}: {e:#}", style::error("失敗:"), input.display());
+        }
+    }
+
+    let failed = failures.len();
+    let (_, first) = failures.swap_remove(0);
+    Err(first.context(format!(
+        "{} 件成功, {failed} 件失敗",
+        targets.len() - failed
+    )))
+}
+
+/// `items` の各要素を最大 `threads` 個のスレッドで処理する
+///
+/// いずれかの要素で失敗すると新しい要素の処理を開始せず、最初のエラーを返します。
+fn run_parallel<T: Sync>(
+    items: &[T],
+    threads: usize,
+    run: impl Fn(&T) -> Result<()> + Sync,
+) -> Result<()> {
+    if threads <= 1 {
+        return items.iter().try_for_each(run);
+    }
+
+    let next = AtomicUsize::new(0);
+    let failure: Mutex<Option<anyhow::Error>> = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..threads.min(items.len()) {
+            scope.spawn(|| loop {
+                if failure.lock().unwrap().is_some() {
+                    break;
+                }
+                let Some(item) = items.get(next.fetch_add(1, Ordering::Relaxed)) else {
+                    break;
+                };
+                if let Err(e) = run(item) {
+                    failure.lock().unwrap().get_or_insert(e);
+                    break;
+                }
+            });
+        }
+    });
+
+    match failure.into_inner().unwrap() {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// `100MB` のような単位付きのサイズを解析する（KB・MB・GBは1024倍単位、大文字・小文字は区別しない）
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("サイズの数値が不正です: {value}"))?;
+
+    let multiplier: u64 = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => {
+            return Err(format!(
+                "サイズの単位が不正です（B / KB / MB / GB）: {value}"
+            ))
+        }
+    };
+    match number.checked_mul(multiplier) {
+        Some(0) => Err("サイズには1バイト以上を指定してください".to_string()),
+        Some(size) => Ok(size),
+        None => Err(format!("サイズが大きすぎます: {value}")),
+    }
+}
+
+/// `--split` の分割サイズを解析する（`parse_size` と同じ形式で、ボリュームの最小サイズ以上）
+fn parse_volume_size(value: &str) -> Result<u64, String> {
+    let size = parse_size(value)?;
+    validate_volume_size(size).map_err(|e| e.to_string())?;
+    Ok(size)
+}
+
+/// `bench` の計測結果
+struct BenchResult {
+    size: u64,
+    streaming: bool,
+    kdf: String,
+    kdf_time: Duration,
+    encrypt_time: Duration,
+    decrypt_time: Duration,
+}
+
+impl BenchResult {
+    fn report(&self, json: bool) {
+        let mb = self.size as f64 / 1_048_576.0;
+        let throughput = |time: Duration| mb / time.as_secs_f64().max(f64::EPSILON);
+        let total_time = self.encrypt_time + self.decrypt_time;
+
+        if json {
+            print_json(json!({
+                "operation": "bench",
+                "size": self.size,
+                "mode": if self.streaming { "streaming" } else { "standard" },
+                "kdf": self.kdf,
+                "kdf_seconds": self.kdf_time.as_secs_f64(),
+                "encrypt_seconds": self.encrypt_time.as_secs_f64(),
+                "encrypt_mb_per_sec": throughput(self.encrypt_time),
+                "decrypt_seconds": self.decrypt_time.as_secs_f64(),
+                "decrypt_mb_per_sec": throughput(self.decrypt_time),
+                "total_seconds": total_time.as_secs_f64(),
+                "total_mb_per_sec": throughput(total_time),
+                "ok": true,
+            }));
+            return;
+        }
+
+        let mode = if self.streaming {
+            "ストリーミング"
+        } else {
+            "標準"
+        };
+        println!(
+            "{}",
+            style::note(format_args!("ベンチマーク: {mb:.2} MB（{mode}）"))
+        );
+        println!(
+            "  キー導出: {:.3} 秒（{}、速度の計測には含めません）",
+            self.kdf_time.as_secs_f64(),
+            self.kdf
+        );
+        for (label, time) in [
+            ("暗号化", self.encrypt_time),
+            ("復号化", self.decrypt_time),
+            ("合計", total_time),
+        ] {
+            println!(
+                "  {label}: {:.3} 秒（{:.2} MB/s）",
+                time.as_secs_f64(),
+                throughput(time)
+            );
+        }
+    }
+}
+
+/// `dir` にランダムなデータのファイルを作成し、暗号化と復号化にかかる時間を計測する
+///
+/// キー導出は最初に空のファイルを暗号化して済ませ、以降は同じキーを使い回すため、
+/// 暗号化・復号化の時間にはキー導出を含みません。
+fn run_bench(dir: &Path, size: u64, streaming: bool, config: &Config) -> Result<BenchResult> {
+    let input = dir.join("bench.bin");
+    let encrypted = dir.join("bench.bin.enc");
+    let decrypted = dir.join("bench.out");
+
+    // ランダムなデータを1MBずつ書き込む
+    let mut writer = io::BufWriter::new(
+        fs::File::create(&input)
+            .with_context(|| format!("計測用ファイルの作成に失敗: {}", input.display()))?,
+    );
+    let mut buffer = vec![0u8; 1_048_576];
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = remaining.min(buffer.len() as u64) as usize;
+        rand::rng().fill_bytes(&mut buffer[..len]);
+        writer.write_all(&buffer[..len])?;
+        remaining -= len as u64;
+    }
+    writer.flush().context("計測用ファイルの書き込みに失敗")?;
+    drop(writer);
+
+    let password = generate_password(24, PasswordCharset::Alphanumeric);
+    let batch = FileBatch::new(&password, config).without_progress();
+
+    let warmup = dir.join("warmup");
+    fs::write(&warmup, b"").context("計測用ファイルの作成に失敗")?;
+    let started = Instant::now();
+    batch.encrypt_file(&warmup, &dir.join("warmup.enc"), streaming)?;
+    let kdf_time = started.elapsed();
+
+    let started = Instant::now();
+    batch.encrypt_file(&input, &encrypted, streaming)?;
+    let encrypt_time = started.elapsed();
+
+    let started = Instant::now();
+    batch.decrypt_file(&encrypted, &decrypted, streaming)?;
+    let decrypt_time = started.elapsed();
+
+    if file_len(&decrypted)? != size {
+        bail!("復号化したデータのサイズが一致しません");
+    }
+
+    Ok(BenchResult {
+        size,
+        streaming,
+        kdf: KdfParams::from_config(config).to_string(),
+        kdf_time,
+        encrypt_time,
+        decrypt_time,
+    })
+}
+
+/// `selftest` の結果
+struct SelfTestReport {
+    kdf: String,
+    aes_hardware: Option<bool>,
+    checks: Vec<(&'static str, Result<()>)>,
+}
+
+impl SelfTestReport {
+    fn passed(&self) -> bool {
+        self.checks.iter().all(|(_, result)| result.is_ok())
+    }
+
+    fn report(&self, json: bool) {
+        if json {
+            let checks: Vec<_> = self
+                .checks
+                .iter()
+                .map(|(name, result)| match result {
+                    Ok(()) => json!({ "name": name, "ok": true }),
+                    Err(e) => json!({ "name": name, "ok": false, "error": format!("{e:#}") }),
+                })
+                .collect();
+            print_json(json!({
+                "operation": "selftest",
+                "version": env!("CARGO_PKG_VERSION"),
+                "cipher": "AES-256-GCM",
+                "kdf": self.kdf,
+                "aes_hardware": self.aes_hardware,
+                "checks": checks,
+                "ok": self.passed(),
+            }));
+            return;
+        }
+
+        println!(
+            "{}",
+            style::note(format_args!(
+                "セルフテスト: encript_tool {}",
+                env!("CARGO_PKG_VERSION")
+            ))
+        );
+        println!("  暗号方式: AES-256-GCM");
+        println!("  キー導出: {}（テストは軽量なパラメータで実行）", self.kdf);
+        let aes_hardware = match self.aes_hardware {
+            Some(true) => "あり",
+            Some(false) => "なし",
+            None => "不明",
+        };
+        println!("  AESのハードウェア支援: {aes_hardware}");
+        for (name, result) in &self.checks {
+            match result {
+                Ok(()) => println!("  {} {name}", style::success("OK")),
+                Err(e) => println!("  {} {name}: {e:#}", style::warning("NG")),
+            }
+        }
+        if self.passed() {
+            println!("{}", style::success("すべてのテストに成功しました"));
+        } else {
+            eprintln!("{} セルフテストに失敗しました", style::error("Error:"));
+        }
+    }
+}
+
+/// 固定のデータで文字列・ファイル（標準・ストリーミング）の暗号化と復号化を往復させ、結果を確かめる
+///
+/// キー導出のアルゴリズムなどは設定のまま、パラメータだけを最小限にして短時間で終わらせます。
+/// 一部のテストが失敗しても、残りのテストは実行します。
+fn run_selftest(dir: &Path, config: &Config) -> SelfTestReport {
+    let test_config = Config {
+        argon2: Argon2Config {
+            memory_cost: 1024,
+            time_cost: 1,
+            parallelism: 1,
+            ..config.argon2.clone()
+        },
+        pbkdf2: Pbkdf2Config { iterations: 1000 },
+        scrypt: ScryptConfig {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        },
+        stream_chunk_size: MIN_STREAM_CHUNK_SIZE,
+        ..config.clone()
+    };
+    let password = "encript_tool selftest password";
+    // 複数のチャンクと端数を含むサイズにする
+    let data: Vec<u8> = (0..MIN_STREAM_CHUNK_SIZE * 3 + 123)
+        .map(|i| (i % 251) as u8)
+        .collect();
+
+    let checks = vec![
+        (
+            "文字列の暗号化・復号化",
+            selftest_string(password, &test_config),
+        ),
+        (
+            "誤ったパスワードの拒否",
+            selftest_wrong_password(password, &test_config),
+        ),
+        (
+            "ファイルの暗号化・復号化（標準）",
+            selftest_file(dir, &data, false, password, &test_config),
+        ),
+        (
+            "ファイルの暗号化・復号化（ストリーミング）",
+            selftest_file(dir, &data, true, password, &test_config),
+        ),
+    ];
+
+    SelfTestReport {
+        kdf: KdfParams::from_config(config).to_string(),
+        aes_hardware: aes_hardware(),
+        checks,
+    }
+}
+
+fn selftest_string(password: &str, config: &Config) -> Result<()> {
+    let text = "encript_tool セルフテスト 🔐";
+    let encrypted = encrypt_string_with_aad(text, password, b"", config)?;
+    let decrypted = decrypt_from_text_with_aad(&encrypted, password, b"", config)?;
+    if decrypted != text.as_bytes() {
+        bail!("復号化した文字列が元の文字列と一致しません");
+    }
+    Ok(())
+}
+
+fn selftest_wrong_password(password: &str, config: &Config) -> Result<()> {
+    let encrypted = encrypt_string_with_aad("selftest", password, b"", config)?;
+    match decrypt_from_text_with_aad(&encrypted, "wrong password", b"", config) {
+        Err(CryptoError::WrongPasswordOrCorrupt) => Ok(()),
+        Err(e) => bail!("想定外のエラーになりました: {e}"),
+        Ok(_) => bail!("誤ったパスワードで復号化できてしまいました"),
+    }
+}
+
+/// ファイルを暗号化し、キー導出をやり直して（ヘッダーから）復号化した結果を元のデータと比べる
+fn selftest_file(
+    dir: &Path,
+    data: &[u8],
+    streaming: bool,
+    password: &str,
+    config: &Config,
+) -> Result<()> {
+    let (name, format) = if streaming {
+        ("streaming", EncFormat::Streaming)
+    } else {
+        ("standard", EncFormat::Standard)
+    };
+    let input = dir.join(format!("{name}.bin"));
+    let encrypted = dir.join(format!("{name}.bin.enc"));
+    let decrypted = dir.join(format!("{name}.out"));
+
+    fs::write(&input, data).context("テスト用ファイルの作成に失敗")?;
+    FileBatch::new(password, config)
+        .without_progress()
+        .encrypt_file(&input, &encrypted, streaming)?;
+    if detect_format(&encrypted)? != Some(format) {
+        bail!("暗号化ファイルの形式が想定と異なります");
+    }
+    FileBatch::new(password, config)
+        .without_progress()
+        .decrypt_file(&encrypted, &decrypted, streaming)?;
+    let output = fs::read(&decrypted).context("復号化したファイルの読み込みに失敗")?;
+    if output != data {
+        bail!("復号化したデータが元のデータと一致しません");
+    }
+    Ok(())
+}
+
+/// CPUがAESのハードウェア命令に対応しているか（判定できないアーキテクチャでは `None`）
+fn aes_hardware() -> Option<bool> {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        Some(std::arch::is_x86_feature_detected!("aes"))
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        Some(std::arch::is_aarch64_feature_detected!("aes"))
+    }
+    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        None
+    }
+}
+
+/// 文字列の暗号化・復号化結果をJSONで出力（--out 指定時はファイルに書き込み、パスを出力）
+fn report_text_result(operation: &str, text: &str, output: &Option<PathBuf>) -> Result<()> {
+    match output {
+        Some(path) => {
+            write_output_text(text, output, false)?;
+            print_json(json!({
+                "operation": operation,
+                "output": path.display().to_string(),
+                "ok": true,
+            }));
+        }
+        None => print_json(json!({
+            "operation": operation,
+            "result": text,
+            "ok": true,
+        })),
+    }
+    Ok(())
+}
+
+/// ファイルの暗号化・復号化結果をJSONで出力
+fn report_file_result(operation: &str, input: &Path, output: &Path, bytes_in: u64) -> Result<()> {
+    print_json(json!({
+        "operation": operation,
+        "input": input.display().to_string(),
+        "output": output.display().to_string(),
+        "bytes_in": bytes_in,
+        "bytes_out": file_len(output)?,
+        "ok": true,
+    }));
+    Ok(())
+}
+
+/// `--split` で分割して暗号化した結果を表示する（JSONではボリュームのパスを列挙する）
+fn report_split_result(input: &Path, output: &Path, bytes_in: u64, cli: &Cli) -> Result<()> {
+    let volumes = volume_paths(&volume_path(output, 1))?;
+    if cli.json {
+        let mut bytes_out = 0;
+        for volume in &volumes {
+            bytes_out += file_len(volume)?;
+        }
+        print_json(json!({
+            "operation": "encrypt_file",
+            "input": input.display().to_string(),
+            "output": output.display().to_string(),
+            "volumes": volumes
+                .iter()
+                .map(|volume| volume.display().to_string())
+                .collect::<Vec<_>>(),
+            "bytes_in": bytes_in,
+            "bytes_out": bytes_out,
+            "ok": true,
+        }));
+    } else if !cli.quiet {
+        println!(
+            "{}",
+            style::success(format_args!(
+                "ファイル暗号化完了: {}.001〜{:03}（{} 個に分割）",
+                output.display(),
+                volumes.len(),
+                volumes.len()
+            ))
+        );
+    }
+    Ok(())
+}
+
+/// `--dry-run` で表示する処理方式
+#[derive(Clone, Copy)]
+enum DryRunMode {
+    Standard,
+    Streaming,
+    /// 複数パスワード（パスワードの数）
+    Envelope(usize),
+    Age,
+}
+
+/// `--dry-run` で表示するファイル操作の予定
+struct DryRunPlan<'a> {
+    operation: &'a str,
+    input: &'a Path,
+    output: &'a Path,
+    mode: DryRunMode,
+    /// 処理後に入力ファイルを削除するか
+    delete: bool,
+}
+
+impl DryRunPlan<'_> {
+    /// 予定を表示する（ディスクへの書き込みやキー導出は行わない）
+    fn report(&self, json: bool) -> Result<()> {
+        let in_place = self.input == self.output;
+        let overwrite = !in_place && self.output.exists();
+
+        if json {
+            let mode = match self.mode {
+                DryRunMode::Standard => "standard",
+                DryRunMode::Streaming => "streaming",
+                DryRunMode::Envelope(_) => "envelope",
+                DryRunMode::Age => "age",
+            };
+            print_json(json!({
+                "operation": self.operation,
+                "dry_run": true,
+                "input": self.input.display().to_string(),
+                "output": self.output.display().to_string(),
+                "mode": mode,
+                "in_place": in_place,
+                "overwrite": overwrite,
+                "delete": self.delete.then(|| self.input.display().to_string()),
+                "ok": true,
+            }));
+            return Ok(());
+        }
+
+        let mode = match self.mode {
+            DryRunMode::Standard => "標準".to_string(),
+            DryRunMode::Streaming => "ストリーミング".to_string(),
+            DryRunMode::Envelope(count) => format!("複数パスワード（{count}個）"),
+            DryRunMode::Age => "age 形式".to_string(),
+        };
+        println!("{} 実際の処理は行いません", style::note("[dry-run]"));
+        println!("  読み込み: {}", self.input.display());
+        if in_place {
+            println!("  置き換え: {} （{mode}）", self.output.display());
+        } else {
+            println!("  書き込み: {} （{mode}）", self.output.display());
+        }
+        if overwrite {
+            println!(
+                "  {}",
+                style::warning(format_args!(
+                    "警告: 出力先が既に存在するため上書きされます: {}",
+                    self.output.display()
+                ))
+            );
+        }
+        if self.delete {
+            println!("  削除: {}", self.input.display());
+        }
+        Ok(())
+    }
 }
 
 /// パスワードを取得（設定ファイル対応版）
 fn get_password_with_config(
     password: &Option<String>,
     password_env: &Option<String>,
+    stdin_password: bool,
     config: &Config,
+) -> Result<String> {
+    if stdin_password {
+        return read_password_line();
+    }
+
+    get_password_with_prompt(
+        password,
+        password_env,
+        config.default_password_env.as_deref(),
+        "パスワードを入力してください: ",
+    )
+}
+
+/// 暗号化に使うパスワードの強度を確認
+///
+/// 空のパスワードは常にエラーです。推定エントロピーが `min_password_entropy` に満たない場合は、
+/// `--allow-weak-password` があれば警告を表示して続行し、なければエラーにします。
+fn check_encrypt_password(password: &str, config: &Config, cli: &Cli) -> Result<()> {
+    match check_password(password, config.min_password_entropy) {
+        Ok(()) => Ok(()),
+        Err(issue @ PasswordIssue::Weak { .. }) if cli.allow_weak_password => {
+            if !cli.quiet {
+                eprintln!("{} {issue}", style::warning("警告:"));
+            }
+            Ok(())
+        }
+        Err(PasswordIssue::Empty) => bail!("{}", PasswordIssue::Empty),
+        Err(issue) => bail!("{issue}（--allow-weak-password で続行できます）"),
+    }
+}
+
+/// `--recipients-file` のファイルから暗号化用のパスワードを読み取る
+fn read_recipients_file(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("受信者ファイルの読み取りに失敗: {}", path.display()))?;
+    let passwords = parse_recipients(&text);
+    if passwords.is_empty() {
+        bail!("受信者ファイルにパスワードがありません: {}", path.display());
+    }
+    Ok(passwords)
+}
+
+/// 標準入力から1行だけ読み取ってパスワードにする（行末の改行のみ取り除く）
+fn read_password_line() -> Result<String> {
+    let mut line = String::new();
+    let read = io::stdin()
+        .read_line(&mut line)
+        .context("標準入力からパスワードの読み取りに失敗しました")?;
+    if read == 0 {
+        bail!("標準入力からパスワードを読み取れませんでした（入力が空です）");
+    }
+
+    let password = line.strip_suffix('\n').unwrap_or(&line);
+    let password = password.strip_suffix('\r').unwrap_or(password);
+    Ok(password.to_string())
+}
+
+/// パスワードとテキストの両方を標準入力から読もうとしていないか確認
+///
+/// `--stdin-password` の場合は常にエラーです。プロンプトで入力する場合も、標準入力が端末でなければ
+/// パスワードの行とテキストを区別できない（プロンプトが先頭の1行を読んでしまう）ためエラーにします。
+fn check_stdin_usage(
+    stdin_password: bool,
+    prompt: bool,
+    text: &Option<String>,
+    input: &Option<PathBuf>,
+) -> Result<()> {
+    if text.is_some() || input.is_some() {
+        return Ok(());
+    }
+    if stdin_password {
+        bail!(
+            "--stdin-password を指定した場合、標準入力はパスワードに使われます。テキストは引数か --in で指定してください"
+        );
+    }
+    if prompt && !io::stdin().is_terminal() {
+        bail!(
+            "標準入力からテキストを読む場合は、パスワードを -p か --password-env（または設定の default_password_env）で指定してください"
+        );
+    }
+    Ok(())
+}
+
+/// パスワードを取得（引数 → 指定の環境変数 → デフォルトの環境変数 → プロンプトの順）
+fn get_password_with_prompt(
+    password: &Option<String>,
+    password_env: &Option<String>,
+    default_env: Option<&str>,
+    prompt: &str,
 ) -> Result<String> {
     if let Some(pwd) = password {
         return Ok(pwd.clone());
@@ -330,26 +3116,61 @@ fn get_password_with_config(
     }
 
     // 設定ファイルのデフォルト環境変数を使用
-    if let Some(env_var) = &config.default_password_env {
+    if let Some(env_var) = default_env {
         if let Ok(pwd) = std::env::var(env_var) {
             return Ok(pwd);
         }
     }
 
-    // パスワードプロンプトを表示
-    eprint!("パスワードを入力してください: ");
+    prompt_password(prompt).context("パスワードの読み取りに失敗しました")
+}
+
+/// パスワードのプロンプトを表示して、標準入力から1行読み取る
+fn prompt_password(prompt: &str) -> io::Result<String> {
+    eprint!("{prompt}");
     io::stderr().flush()?;
 
     let mut password = String::new();
-    io::stdin()
-        .read_line(&mut password)
-        .context("パスワードの読み取りに失敗しました")?;
+    io::stdin().read_line(&mut password)?;
 
     Ok(password.trim().to_string())
 }
 
+/// `get_password_with_config` がパスワードを端末のプロンプトから読み取ることになるか
+///
+/// 引数・環境変数・`--stdin-password` で指定された場合や、標準入力が端末でない場合は `false` です。
+fn prompts_for_password(
+    password: &Option<String>,
+    password_env: &Option<String>,
+    stdin_password: bool,
+    config: &Config,
+) -> bool {
+    reads_password_prompt(password, password_env, stdin_password, config)
+        && io::stdin().is_terminal()
+}
+
+/// `get_password_with_config` がパスワードをプロンプト（標準入力の1行）から読み取ることになるか
+fn reads_password_prompt(
+    password: &Option<String>,
+    password_env: &Option<String>,
+    stdin_password: bool,
+    config: &Config,
+) -> bool {
+    password.is_none()
+        && password_env.is_none()
+        && !stdin_password
+        && config
+            .default_password_env
+            .as_deref()
+            .is_none_or(|env_var| std::env::var(env_var).is_err())
+}
+
 /// 設定コマンドを処理
-fn handle_config_command(action: &ConfigAction, config_path: Option<&Path>) -> Result<()> {
+fn handle_config_command(
+    action: &ConfigAction,
+    config_path: Option<&Path>,
+    quiet: bool,
+) -> Result<()> {
     match action {
         ConfigAction::Init => {
             let path = match config_path {
@@ -358,7 +3179,15 @@ fn handle_config_command(action: &ConfigAction, config_path: Option<&Path>) -> R
             };
 
             create_config_file(&path)?;
-            println!("設定ファイルを作成しました: {}", path.display());
+            if !quiet {
+                println!(
+                    "{}",
+                    style::success(format_args!(
+                        "設定ファイルを作成しました: {}",
+                        path.display()
+                    ))
+                );
+            }
         }
 
         ConfigAction::Show => {
@@ -368,33 +3197,117 @@ fn handle_config_command(action: &ConfigAction, config_path: Option<&Path>) -> R
             println!("  デフォルト詳細表示: {}", config.default_verbose);
             println!("  デフォルト環境変数: {:?}", config.default_password_env);
             println!("  設定バージョン: {}", config.version);
+            println!(
+                "  ストリーミングチャンクサイズ: {} バイト",
+                config.stream_chunk_size
+            );
+            println!(
+                "  自動でストリーミング処理を使うサイズ: {} バイト超",
+                config.streaming_threshold
+            );
+            println!("  メタデータの保持: {}", config.preserve_metadata);
+            match config.pad_block {
+                Some(block) => println!("  文字列のパディング: {block} バイト単位"),
+                None => println!("  文字列のパディング: なし"),
+            }
+            println!("  暗号化ファイルの拡張子: .{}", config.encrypted_extension);
+            match &config.file_format {
+                Some(format) => println!("  暗号化ファイルの書き込み形式: {format:?}"),
+                None => println!("  暗号化ファイルの書き込み形式: バイナリ"),
+            }
+            match &config.default_output_dir {
+                Some(dir) => println!("  出力ディレクトリ: {}", dir.display()),
+                None => println!("  出力ディレクトリ: 入力ファイルと同じ場所"),
+            }
+            println!(
+                "  パスワードのエントロピーの下限: {} ビット",
+                config.min_password_entropy
+            );
+            println!("  プログレスバーの表示: {}", config.show_progress);
+            println!(
+                "  プログレスバーのテンプレート: {}",
+                config
+                    .progress_template
+                    .as_deref()
+                    .unwrap_or(DEFAULT_PROGRESS_TEMPLATE)
+            );
+            println!(
+                "  ヘッダーのない旧形式の復号化: {}",
+                config.legacy_deterministic_salt
+            );
+            println!("  キー導出アルゴリズム: {}", config.kdf);
             println!("  Argon2設定:");
+            println!("    種類: {}", config.argon2.variant);
             println!("    メモリ使用量: {} KB", config.argon2.memory_cost);
             println!("    時間コスト: {}", config.argon2.time_cost);
             println!("    並列度: {}", config.argon2.parallelism);
+            println!("  PBKDF2設定:");
+            println!("    反復回数: {}", config.pbkdf2.iterations);
+            if config.kdf == KdfAlgorithm::Scrypt {
+                println!("  scrypt設定:");
+                println!("    log_n: {}", config.scrypt.log_n);
+                println!("    r: {}", config.scrypt.r);
+                println!("    p: {}", config.scrypt.p);
+            }
         }
 
         ConfigAction::Path => {
-            let path = match config_path {
-                Some(p) => p.to_path_buf(),
-                None => get_default_config_path()?,
-            };
+            let (path, source) = resolve_config_path(config_path)?;
             println!("設定ファイルパス: {}", path.display());
+            println!(
+                "{}",
+                match source {
+                    ConfigSource::Explicit => "（--config で指定されたファイルを使用）",
+                    ConfigSource::Project => "（プロジェクトの .mycrypt.toml を使用）",
+                    ConfigSource::Default => "（デフォルトの設定ファイルを使用）",
+                }
+            );
             if path.exists() {
                 println!("（ファイルは存在します）");
             } else {
-                println!("（ファイルは存在しません - 'config init' で作成できます）");
+                println!(
+                    "{}",
+                    style::warning("（ファイルは存在しません - 'config init' で作成できます）")
+                );
+            }
+        }
+
+        ConfigAction::Get { key } => {
+            let config = load_config(config_path)?;
+            println!("{}", config.get_value(key)?);
+        }
+
+        ConfigAction::Set { key, value } => {
+            let (path, _) = resolve_config_path(config_path)?;
+
+            // 環境変数の上書きをファイルに書き戻さないよう、ファイルの内容だけを読み込む
+            let mut config = load_config_file(Some(&path))?;
+            config.set_value(key, value)?;
+            save_config(&config, &path)?;
+            if !quiet {
+                println!(
+                    "{}",
+                    style::success(format_args!(
+                        "設定を更新しました: {key} = {}",
+                        config.get_value(key)?
+                    ))
+                );
             }
         }
 
         ConfigAction::Reset => {
-            let path = match config_path {
-                Some(p) => p.to_path_buf(),
-                None => get_default_config_path()?,
-            };
+            let (path, _) = resolve_config_path(config_path)?;
 
             delete_config_file(&path)?;
-            println!("設定ファイルを削除しました: {}", path.display());
+            if !quiet {
+                println!(
+                    "{}",
+                    style::success(format_args!(
+                        "設定ファイルを削除しました: {}",
+                        path.display()
+                    ))
+                );
+            }
         }
     }
 
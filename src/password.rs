@@ -0,0 +1,154 @@
+use rand::Rng;
+use std::fmt;
+
+/// パスワードの強度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordStrength {
+    Weak,
+    Fair,
+    Strong,
+}
+
+impl PasswordStrength {
+    /// 表示用のラベル
+    pub fn label(&self) -> &'static str {
+        match self {
+            PasswordStrength::Weak => "弱い",
+            PasswordStrength::Fair => "普通",
+            PasswordStrength::Strong => "強い",
+        }
+    }
+}
+
+/// 「普通」とみなすエントロピーの下限（ビット）
+pub const FAIR_ENTROPY_BITS: f64 = 40.0;
+/// 「強い」とみなすエントロピーの下限（ビット）
+pub const STRONG_ENTROPY_BITS: f64 = 70.0;
+
+/// 文字種と長さからパスワードのエントロピー（ビット）を概算
+///
+/// 直前と同じ文字の繰り返しは長さに数えない簡易的な推定です。
+pub fn estimate_entropy(password: &str) -> f64 {
+    let mut pool = 0u32;
+    if password.chars().any(|c| c.is_ascii_lowercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_uppercase()) {
+        pool += 26;
+    }
+    if password.chars().any(|c| c.is_ascii_digit()) {
+        pool += 10;
+    }
+    if password
+        .chars()
+        .any(|c| c.is_ascii_punctuation() || c == ' ')
+    {
+        pool += 33;
+    }
+    if !password.is_ascii() {
+        pool += 100;
+    }
+
+    if pool == 0 {
+        return 0.0;
+    }
+
+    let mut length = 0u32;
+    let mut previous = None;
+    for c in password.chars() {
+        if previous != Some(c) {
+            length += 1;
+        }
+        previous = Some(c);
+    }
+
+    f64::from(length) * f64::from(pool).log2()
+}
+
+/// パスワードの強度を判定
+pub fn password_strength(password: &str) -> PasswordStrength {
+    let entropy = estimate_entropy(password);
+    if entropy >= STRONG_ENTROPY_BITS {
+        PasswordStrength::Strong
+    } else if entropy >= FAIR_ENTROPY_BITS {
+        PasswordStrength::Fair
+    } else {
+        PasswordStrength::Weak
+    }
+}
+
+/// 暗号化に使うパスワードの強度チェックで見つかった問題
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PasswordIssue {
+    /// パスワードが空（常に拒否する）
+    Empty,
+    /// エントロピーの推定値が下限に満たない
+    Weak { entropy: f64, min_entropy: f64 },
+}
+
+impl fmt::Display for PasswordIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PasswordIssue::Empty => f.write_str("空のパスワードでは暗号化できません"),
+            PasswordIssue::Weak {
+                entropy,
+                min_entropy,
+            } => write!(
+                f,
+                "パスワードが弱すぎます（推定エントロピー {entropy:.1} ビット、下限 {min_entropy:.1} ビット）"
+            ),
+        }
+    }
+}
+
+/// 暗号化に使うパスワードが空でなく、推定エントロピーが `min_entropy` 以上かを確認
+///
+/// キー導出が重くても、弱いパスワードは総当たりで簡単に破られます。
+/// `min_entropy` が0の場合は空のパスワードだけを拒否します。
+pub fn check_password(password: &str, min_entropy: f64) -> Result<(), PasswordIssue> {
+    if password.is_empty() {
+        return Err(PasswordIssue::Empty);
+    }
+    let entropy = estimate_entropy(password);
+    if entropy < min_entropy {
+        return Err(PasswordIssue::Weak {
+            entropy,
+            min_entropy,
+        });
+    }
+    Ok(())
+}
+
+/// パスワード生成に使う文字集合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordCharset {
+    /// 英大文字・英小文字・数字
+    Alphanumeric,
+    /// 英数字と記号
+    AlphanumericSymbols,
+}
+
+impl PasswordCharset {
+    fn chars(&self) -> &'static [u8] {
+        const ALPHANUMERIC: &[u8] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+        const ALPHANUMERIC_SYMBOLS: &[u8] =
+            b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+        match self {
+            PasswordCharset::Alphanumeric => ALPHANUMERIC,
+            PasswordCharset::AlphanumericSymbols => ALPHANUMERIC_SYMBOLS,
+        }
+    }
+}
+
+/// 暗号論的に安全な乱数でランダムなパスワードを生成
+///
+/// `rand::rng()` はOSのエントロピーでシードされたCSPRNGです。
+pub fn generate_password(length: usize, charset: PasswordCharset) -> String {
+    let chars = charset.chars();
+    let mut rng = rand::rng();
+    (0..length)
+        .map(|_| chars[rng.random_range(0..chars.len())] as char)
+        .collect()
+}
@@ -0,0 +1,65 @@
+//! CLIの色付き出力
+//!
+//! `--no-color` が指定された場合、`NO_COLOR` 環境変数が空でない値で設定されている場合、
+//! 出力先が端末でない場合は色を付けません。
+
+use owo_colors::OwoColorize;
+use std::env;
+use std::fmt::Display;
+use std::io::{self, IsTerminal};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static STDOUT_COLOR: AtomicBool = AtomicBool::new(false);
+static STDERR_COLOR: AtomicBool = AtomicBool::new(false);
+
+/// 標準出力・標準エラーそれぞれに色を付けるかを決定する
+pub fn init(no_color: bool) {
+    let enabled = !no_color && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty());
+    STDOUT_COLOR.store(enabled && io::stdout().is_terminal(), Ordering::Relaxed);
+    STDERR_COLOR.store(enabled && io::stderr().is_terminal(), Ordering::Relaxed);
+}
+
+/// 標準エラーに色を付けるか（ログ出力の設定に使用）
+pub fn stderr_color() -> bool {
+    STDERR_COLOR.load(Ordering::Relaxed)
+}
+
+fn stdout_color() -> bool {
+    STDOUT_COLOR.load(Ordering::Relaxed)
+}
+
+/// 成功メッセージ（標準出力、緑）
+pub fn success(message: impl Display) -> String {
+    if stdout_color() {
+        message.green().to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+/// 警告メッセージ（標準出力、黄）
+pub fn warning(message: impl Display) -> String {
+    if stdout_color() {
+        message.yellow().to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+/// 補足の見出し（標準出力、シアン）
+pub fn note(message: impl Display) -> String {
+    if stdout_color() {
+        message.cyan().to_string()
+    } else {
+        message.to_string()
+    }
+}
+
+/// エラーメッセージ（標準エラー、赤の太字）
+pub fn error(message: impl Display) -> String {
+    if stderr_color() {
+        message.red().bold().to_string()
+    } else {
+        message.to_string()
+    }
+}
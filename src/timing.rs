@@ -0,0 +1,124 @@
+//! 処理段階ごとの所要時間の計測（CLIの `--time` 用）
+//!
+//! `enable_phase_timing` を呼んだ後の暗号化・復号化で、キー導出・AES-GCMの処理・ファイルの
+//! 読み込み・書き込みにかかった時間をプロセス全体で合計します。有効にしない限り計測しません。
+//! 複数のスレッドで並列に処理した場合は各スレッドの時間の合計になるため、経過時間より長くなります。
+
+#[cfg(feature = "std-fs")]
+use std::io::{self, Read, Write};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static KEY_DERIVATION: AtomicU64 = AtomicU64::new(0);
+static CIPHER: AtomicU64 = AtomicU64::new(0);
+static READ: AtomicU64 = AtomicU64::new(0);
+static WRITE: AtomicU64 = AtomicU64::new(0);
+
+/// 計測する処理段階
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Phase {
+    /// キー導出（Argon2 / PBKDF2 / scrypt）
+    KeyDerivation,
+    /// AES-GCMによる暗号化・復号化
+    Cipher,
+    /// 入力の読み込み
+    Read,
+    /// 出力の書き込み
+    Write,
+}
+
+impl Phase {
+    fn counter(self) -> &'static AtomicU64 {
+        match self {
+            Phase::KeyDerivation => &KEY_DERIVATION,
+            Phase::Cipher => &CIPHER,
+            Phase::Read => &READ,
+            Phase::Write => &WRITE,
+        }
+    }
+}
+
+/// 処理段階ごとの所要時間の合計
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimes {
+    pub key_derivation: Duration,
+    pub cipher: Duration,
+    pub read: Duration,
+    pub write: Duration,
+}
+
+/// 計測を有効にし、これまでの合計をリセット
+///
+/// wasm32-unknown-unknown では `Instant` が使えないため、有効にしても計測しません。
+pub fn enable_phase_timing() {
+    for phase in [
+        Phase::KeyDerivation,
+        Phase::Cipher,
+        Phase::Read,
+        Phase::Write,
+    ] {
+        phase.counter().store(0, Ordering::Relaxed);
+    }
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// 有効にしてからの処理段階ごとの所要時間
+pub fn phase_times() -> PhaseTimes {
+    let load = |phase: Phase| Duration::from_nanos(phase.counter().load(Ordering::Relaxed));
+    PhaseTimes {
+        key_derivation: load(Phase::KeyDerivation),
+        cipher: load(Phase::Cipher),
+        read: load(Phase::Read),
+        write: load(Phase::Write),
+    }
+}
+
+fn enabled() -> bool {
+    !cfg!(all(target_arch = "wasm32", target_os = "unknown")) && ENABLED.load(Ordering::Relaxed)
+}
+
+/// 計測した時間を `phase` の合計に加える
+pub(crate) fn record_phase(phase: Phase, elapsed: Duration) {
+    if enabled() {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        phase.counter().fetch_add(nanos, Ordering::Relaxed);
+    }
+}
+
+/// `run` の実行時間を `phase` の合計に加える（計測が無効なら `run` を呼ぶだけ）
+pub(crate) fn time_phase<T>(phase: Phase, run: impl FnOnce() -> T) -> T {
+    if !enabled() {
+        return run();
+    }
+    let start_time = Instant::now();
+    let result = run();
+    record_phase(phase, start_time.elapsed());
+    result
+}
+
+/// 読み込みを `Phase::Read`、書き込みを `Phase::Write` として計測するラッパー
+#[cfg(feature = "std-fs")]
+pub(crate) struct Timed<T>(pub(crate) T);
+
+#[cfg(feature = "std-fs")]
+impl<R: Read> Read for Timed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        time_phase(Phase::Read, || self.0.read(buf))
+    }
+}
+
+#[cfg(feature = "std-fs")]
+impl<W: Write> Write for Timed<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        time_phase(Phase::Write, || self.0.write(buf))
+    }
+
+    fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+        time_phase(Phase::Write, || self.0.write_all(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        time_phase(Phase::Write, || self.0.flush())
+    }
+}
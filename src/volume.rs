@@ -0,0 +1,431 @@
+//! ストリーミング形式の暗号化データを決まったサイズのボリュームに分割して読み書きする
+//!
+//! `foo.enc` を分割すると `foo.enc.001`・`foo.enc.002`… の順にボリュームを作ります。
+//! 各ボリュームは ヘッダー(42) + 分割したデータ で、ヘッダーは
+//! マジックナンバー(9) + バージョン(1) + 分割の識別子(16) + 番号(4、1から) + 総数(4) + データのバイト数(8)
+//! です。分割の識別子は分割ごとのランダムな値で、別の分割のボリュームが混ざっていないかの確認に使います。
+//! 総数とデータのバイト数は全ボリュームを書き終えてから記録するため、書き込みが途中で止まった
+//! ボリュームは総数が0のまま残り、読み込み時に拒否されます。
+//!
+//! ボリュームのヘッダーは認証されませんが、中身のストリーミング形式がチャンクとファイル全体を
+//! 認証するため、順序の入れ替えや欠落は復号化でも検出されます。ヘッダーの確認は、どのボリュームに
+//! 問題があるかをわかりやすく報告するためのものです。
+
+use crate::error::{CryptoError, IoResultExt, Result};
+use log::debug;
+use rand::RngCore;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// ボリュームのマジックナンバー
+pub(crate) const VOLUME_MAGIC: &[u8; 9] = b"GCMVOLUME";
+
+/// ボリュームのヘッダーのバージョン
+const VOLUME_VERSION: u8 = 1;
+
+/// 分割の識別子のサイズ（バイト）
+const SET_ID_SIZE: usize = 16;
+
+/// ボリュームのヘッダーのバイト数
+pub const VOLUME_HEADER_SIZE: u64 = (VOLUME_MAGIC.len() + 1 + SET_ID_SIZE + 4 + 4 + 8) as u64;
+
+/// 総数とデータのバイト数を書き込む位置（ヘッダーの先頭から）
+const TOTALS_OFFSET: u64 = (VOLUME_MAGIC.len() + 1 + SET_ID_SIZE + 4) as u64;
+
+/// 1つのボリュームの最小サイズ（バイト、ヘッダーを含む）
+pub const MIN_VOLUME_SIZE: u64 = 4 * 1024;
+
+/// ボリュームのサイズ（ヘッダーを含む）が指定できる範囲か検証
+pub fn validate_volume_size(size: u64) -> Result<()> {
+    if size < MIN_VOLUME_SIZE {
+        return Err(CryptoError::InvalidInput(format!(
+            "分割サイズは {MIN_VOLUME_SIZE} バイト以上で指定してください: {size}"
+        )));
+    }
+    Ok(())
+}
+
+/// `base` を分割した `index` 番目（1から）のボリュームのパス（`foo.enc` → `foo.enc.001`）
+pub fn volume_path(base: &Path, index: u32) -> PathBuf {
+    let mut name = base.as_os_str().to_os_string();
+    name.push(format!(".{index:03}"));
+    PathBuf::from(name)
+}
+
+/// ボリュームのパスから番号の拡張子を除いたパス（`foo.enc.002` → `foo.enc`）
+///
+/// ボリュームのファイルでなければ `None` を返します。
+pub fn volume_base_path(path: &Path) -> Result<Option<PathBuf>> {
+    if !is_volume_file(path)? {
+        return Ok(None);
+    }
+    Ok(path
+        .extension()
+        .filter(|ext| !ext.is_empty() && ext.to_string_lossy().bytes().all(|b| b.is_ascii_digit()))
+        .map(|_| path.with_extension("")))
+}
+
+/// 分割したボリュームのファイルか（先頭のマジックナンバーで判定）
+pub fn is_volume_file(path: &Path) -> Result<bool> {
+    let mut magic = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(VOLUME_MAGIC.len() as u64).read_to_end(&mut magic))
+        .with_io_context(|| format!("暗号化ファイルの読み込みに失敗: {}", path.display()))?;
+    Ok(magic == VOLUME_MAGIC)
+}
+
+/// `path` のボリュームを含む分割の、全ボリュームのパス（番号順）
+///
+/// 全ボリュームのヘッダーを確認し、欠けているボリュームなどがあればエラーにします。
+pub fn volume_paths(path: &Path) -> Result<Vec<PathBuf>> {
+    Ok(VolumeReader::open(path)?
+        .volumes
+        .into_iter()
+        .map(|(path, _)| path)
+        .collect())
+}
+
+/// ボリュームのヘッダー
+struct VolumeHeader {
+    set_id: [u8; SET_ID_SIZE],
+    index: u32,
+    count: u32,
+    len: u64,
+}
+
+impl VolumeHeader {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = VOLUME_MAGIC.to_vec();
+        out.push(VOLUME_VERSION);
+        out.extend_from_slice(&self.set_id);
+        out.extend_from_slice(&self.index.to_le_bytes());
+        out.extend_from_slice(&self.count.to_le_bytes());
+        out.extend_from_slice(&self.len.to_le_bytes());
+        out
+    }
+
+    /// `path` のボリュームのヘッダーを読み込み、ファイルのサイズと一致するか確認する
+    fn read(path: &Path) -> Result<Self> {
+        let context = || format!("ボリュームの読み込みに失敗: {}", path.display());
+        let mut file = File::open(path).map_err(|source| {
+            if source.kind() == io::ErrorKind::NotFound {
+                CryptoError::Truncated(format!(
+                    "分割したボリュームが見つかりません: {}",
+                    path.display()
+                ))
+            } else {
+                CryptoError::Io {
+                    context: context(),
+                    source,
+                }
+            }
+        })?;
+        let size = file.metadata().with_io_context(context)?.len();
+
+        let mut bytes = [0u8; VOLUME_HEADER_SIZE as usize];
+        file.read_exact(&mut bytes).map_err(|source| {
+            if source.kind() == io::ErrorKind::UnexpectedEof {
+                CryptoError::Truncated(format!(
+                    "ボリュームのヘッダーが途中で切れています: {}",
+                    path.display()
+                ))
+            } else {
+                CryptoError::Io {
+                    context: context(),
+                    source,
+                }
+            }
+        })?;
+        let (magic, rest) = bytes.split_at(VOLUME_MAGIC.len());
+        if magic != VOLUME_MAGIC {
+            return Err(CryptoError::InvalidFormat(format!(
+                "分割したボリュームではありません: {}",
+                path.display()
+            )));
+        }
+        let (&version, rest) = rest.split_first().expect("ヘッダーの長さは固定");
+        if version != VOLUME_VERSION {
+            return Err(CryptoError::InvalidFormat(format!(
+                "対応していないボリュームのバージョンです: {version}: {}",
+                path.display()
+            )));
+        }
+        let (set_id, rest) = rest
+            .split_first_chunk::<SET_ID_SIZE>()
+            .expect("ヘッダーの長さは固定");
+        let (index, rest) = rest.split_first_chunk::<4>().expect("ヘッダーの長さは固定");
+        let (count, rest) = rest.split_first_chunk::<4>().expect("ヘッダーの長さは固定");
+        let len = rest.try_into().expect("ヘッダーの長さは固定");
+        let header = Self {
+            set_id: *set_id,
+            index: u32::from_le_bytes(*index),
+            count: u32::from_le_bytes(*count),
+            len: u64::from_le_bytes(len),
+        };
+
+        if header.count == 0 {
+            return Err(CryptoError::Truncated(format!(
+                "書き込みが完了していないボリュームです: {}",
+                path.display()
+            )));
+        }
+        if header.index == 0 || header.index > header.count {
+            return Err(CryptoError::InvalidFormat(format!(
+                "ボリュームの番号が不正です: {}",
+                path.display()
+            )));
+        }
+        if size != VOLUME_HEADER_SIZE + header.len {
+            return Err(CryptoError::Truncated(format!(
+                "ボリュームのサイズが記録と一致しません（途中で切れているか、余分なデータがあります）: {}",
+                path.display()
+            )));
+        }
+        Ok(header)
+    }
+}
+
+/// 書き込んだデータを `volume_size` バイトごとのボリュームに分けて書き込むライター
+pub(crate) struct VolumeWriter {
+    base: PathBuf,
+    /// 1つのボリュームに入れるデータのバイト数（ヘッダーを除く）
+    capacity: u64,
+    set_id: [u8; SET_ID_SIZE],
+    current: Option<BufWriter<File>>,
+    /// 作成したボリュームのパスと、それぞれに書き込んだデータのバイト数
+    volumes: Vec<(PathBuf, u64)>,
+}
+
+impl VolumeWriter {
+    /// `base` に番号を付けたボリュームに書き込む（ファイルは最初の書き込みで作成する）
+    pub(crate) fn new(base: &Path, volume_size: u64) -> Result<Self> {
+        validate_volume_size(volume_size)?;
+        let mut set_id = [0u8; SET_ID_SIZE];
+        rand::rng().fill_bytes(&mut set_id);
+        Ok(Self {
+            base: base.to_path_buf(),
+            capacity: volume_size - VOLUME_HEADER_SIZE,
+            set_id,
+            current: None,
+            volumes: Vec::new(),
+        })
+    }
+
+    /// 次のボリュームを作成し、総数とデータのバイト数を0にしたヘッダーを書き込む
+    fn start_volume(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.flush()?;
+        }
+        let index = u32::try_from(self.volumes.len() + 1)
+            .map_err(|_| io::Error::other("ボリュームの数が多すぎます"))?;
+        let path = volume_path(&self.base, index);
+        debug!("ボリュームを作成: {}", path.display());
+
+        let mut file = BufWriter::new(File::create(&path)?);
+        self.volumes.push((path, 0));
+        let header = VolumeHeader {
+            set_id: self.set_id,
+            index,
+            count: 0,
+            len: 0,
+        };
+        file.write_all(&header.to_bytes())?;
+        self.current = Some(file);
+        Ok(())
+    }
+
+    /// 全ボリュームのヘッダーに総数とデータのバイト数を記録し、ボリュームのパスを返す
+    pub(crate) fn finish(mut self) -> Result<Vec<PathBuf>> {
+        if self.volumes.is_empty() {
+            self.start_volume().io_context("ボリュームの作成に失敗")?;
+        }
+        if let Some(mut current) = self.current.take() {
+            current.flush().io_context("ボリュームのフラッシュに失敗")?;
+        }
+
+        let count = self.volumes.len() as u32;
+        for (path, len) in &self.volumes {
+            let mut totals = count.to_le_bytes().to_vec();
+            totals.extend_from_slice(&len.to_le_bytes());
+            OpenOptions::new()
+                .write(true)
+                .open(path)
+                .and_then(|mut file| {
+                    file.seek(SeekFrom::Start(TOTALS_OFFSET))?;
+                    file.write_all(&totals)
+                })
+                .with_io_context(|| format!("ボリュームの書き込みに失敗: {}", path.display()))?;
+        }
+        Ok(std::mem::take(&mut self.volumes)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect())
+    }
+
+    /// 作成したボリュームを削除する（書き込みに失敗した場合の後始末）
+    pub(crate) fn discard(mut self) {
+        self.current = None;
+        for (path, _) in &self.volumes {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let full = self
+            .volumes
+            .last()
+            .is_none_or(|(_, len)| *len == self.capacity);
+        if full {
+            self.start_volume()?;
+        }
+        let (_, len) = self.volumes.last_mut().expect("ボリュームは作成済み");
+        let n = buf.len().min((self.capacity - *len) as usize);
+        self.current
+            .as_mut()
+            .expect("ボリュームは作成済み")
+            .write_all(&buf[..n])?;
+        *len += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 分割したボリュームを番号順につなげて読み込むリーダー
+///
+/// 開くときに全ボリュームのヘッダーを確認し、欠けているボリューム・別の分割のボリューム・
+/// サイズが記録と異なるボリュームがあればエラーにします。
+pub(crate) struct VolumeReader {
+    /// 各ボリュームのパスとデータのバイト数
+    volumes: Vec<(PathBuf, u64)>,
+    /// つなげたデータの中での読み込み位置
+    position: u64,
+    /// 読み込み中のボリューム（読み込み位置からそのボリュームの終わりまで）
+    current: Option<io::Take<BufReader<File>>>,
+}
+
+impl VolumeReader {
+    /// いずれかのボリューム（通常は `.001`）を指定して、分割全体を開く
+    pub(crate) fn open(path: &Path) -> Result<Self> {
+        let header = VolumeHeader::read(path)?;
+        let base = volume_base_path(path)?.ok_or_else(|| {
+            CryptoError::InvalidInput(format!(
+                "ボリュームのファイル名は番号で終わる必要があります（例: foo.enc.001）: {}",
+                path.display()
+            ))
+        })?;
+
+        let mut volumes = Vec::with_capacity(header.count as usize);
+        for index in 1..=header.count {
+            let path = volume_path(&base, index);
+            let volume = VolumeHeader::read(&path).map_err(|e| match e {
+                CryptoError::Truncated(message) => CryptoError::Truncated(format!(
+                    "{message}（{} 個中 {index} 個目）",
+                    header.count
+                )),
+                e => e,
+            })?;
+            if volume.set_id != header.set_id || volume.count != header.count {
+                return Err(CryptoError::InvalidFormat(format!(
+                    "別の分割のボリュームが混ざっています: {}",
+                    path.display()
+                )));
+            }
+            if volume.index != index {
+                return Err(CryptoError::InvalidFormat(format!(
+                    "ボリュームの順序が不正です: {}（{index} 個目のはずが {} 個目）",
+                    path.display(),
+                    volume.index
+                )));
+            }
+            volumes.push((path, volume.len));
+        }
+        debug!("分割したボリューム: {} 個", volumes.len());
+
+        Ok(Self {
+            volumes,
+            position: 0,
+            current: None,
+        })
+    }
+
+    /// ボリュームの数
+    pub(crate) fn count(&self) -> u32 {
+        self.volumes.len() as u32
+    }
+
+    /// つなげたデータ全体のバイト数
+    pub(crate) fn len(&self) -> u64 {
+        self.volumes.iter().map(|(_, len)| len).sum()
+    }
+
+    /// 読み込み位置を含むボリュームを開く（終端なら `None`）
+    fn open_current(&mut self) -> io::Result<Option<&mut io::Take<BufReader<File>>>> {
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|current| current.limit() == 0)
+        {
+            let mut start = 0;
+            let Some((path, len, offset)) = self.volumes.iter().find_map(|(path, len)| {
+                let found =
+                    (self.position < start + len).then(|| (path, *len, self.position - start));
+                start += len;
+                found
+            }) else {
+                return Ok(None);
+            };
+            let mut file = File::open(path)?;
+            file.seek(SeekFrom::Start(VOLUME_HEADER_SIZE + offset))?;
+            self.current = Some(BufReader::new(file).take(len - offset));
+        }
+        Ok(self.current.as_mut())
+    }
+}
+
+impl Read for VolumeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let Some(reader) = self.open_current()? else {
+            return Ok(0);
+        };
+        let n = reader.read(buf)?;
+        if n == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "ボリュームが途中で切れています",
+            ));
+        }
+        self.position += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for VolumeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+            SeekFrom::End(offset) => self.len().checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "不正な読み込み位置です"))?;
+        if position != self.position {
+            self.position = position;
+            self.current = None;
+        }
+        Ok(position)
+    }
+}
@@ -0,0 +1,81 @@
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_from_text_with_aad, decrypt_string, decrypt_string_with_aad, encrypt_string,
+    encrypt_string_with_aad, encrypt_to_text_with_aad, Config, CryptoError, OutputFormat,
+};
+
+#[test]
+fn aad_round_trip() {
+    let config = fast_config();
+    let encrypted = encrypt_string_with_aad("token", "password", b"user:42", &config).unwrap();
+
+    assert_eq!(
+        decrypt_string_with_aad(&encrypted, "password", b"user:42", &config).unwrap(),
+        "token"
+    );
+}
+
+#[test]
+fn wrong_aad_fails_authentication() {
+    let config = fast_config();
+    let encrypted = encrypt_string_with_aad("token", "password", b"user:42", &config).unwrap();
+
+    assert!(matches!(
+        decrypt_string_with_aad(&encrypted, "password", b"user:43", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+    // AADは暗号文に含まれないため、指定しなければ復号化できない
+    assert!(matches!(
+        decrypt_string(&encrypted, "password", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
+
+#[test]
+fn aad_is_not_stored_in_output() {
+    let config = fast_config();
+    let aad = [b'x'; 64];
+    let with_aad = encrypt_string_with_aad("token", "password", &aad, &config).unwrap();
+    let without = encrypt_string("token", "password", &config).unwrap();
+
+    assert_eq!(with_aad.len(), without.len());
+}
+
+#[test]
+fn empty_aad_matches_plain_encryption() {
+    let config = fast_config();
+    let encrypted = encrypt_string("token", "password", &config).unwrap();
+    assert_eq!(
+        decrypt_string_with_aad(&encrypted, "password", b"", &config).unwrap(),
+        "token"
+    );
+
+    let encrypted = encrypt_string_with_aad("token", "password", b"", &config).unwrap();
+    assert_eq!(
+        decrypt_string(&encrypted, "password", &config).unwrap(),
+        "token"
+    );
+}
+
+#[test]
+fn aad_with_padding_checksum_and_hex() {
+    let config = Config {
+        pad_block: Some(32),
+        checksum: true,
+        default_format: OutputFormat::Hex,
+        ..fast_config()
+    };
+    let data = [0u8, 0xff, 0x80, 0x00];
+    let encrypted = encrypt_to_text_with_aad(&data, "password", b"v2", &config).unwrap();
+
+    assert_eq!(
+        decrypt_from_text_with_aad(&encrypted, "password", b"v2", &config).unwrap(),
+        data
+    );
+    assert!(matches!(
+        decrypt_from_text_with_aad(&encrypted, "password", b"v1", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "age")]
+
+use age::secrecy::SecretString;
+use encript_tool::age_compat::AGE_MAGIC;
+use encript_tool::{
+    decrypt_age, decrypt_file_age, encrypt_age, encrypt_file_age, verify_file_age, CryptoError,
+};
+use std::{fs, io::Read, iter};
+
+#[test]
+fn age_file_round_trip() {
+    let dir = std::env::temp_dir().join(format!("encript_tool-age-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let input = dir.join("notes.txt");
+    let encrypted = dir.join("notes.txt.age");
+    let decrypted = dir.join("notes.out.txt");
+    fs::write(&input, "age で暗号化するデータ").unwrap();
+
+    encrypt_file_age(&input, &encrypted, "password").unwrap();
+    assert!(fs::read(&encrypted).unwrap().starts_with(AGE_MAGIC));
+    verify_file_age(&encrypted, &input, "password").unwrap();
+
+    decrypt_file_age(&encrypted, &decrypted, "password").unwrap();
+    assert_eq!(
+        fs::read_to_string(&decrypted).unwrap(),
+        "age で暗号化するデータ"
+    );
+
+    // パスワードが違う場合は通常の形式と同じエラーになる
+    assert!(matches!(
+        decrypt_file_age(&encrypted, &decrypted, "wrong"),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn interoperates_with_age_crate() {
+    // このツールで暗号化したデータを age クレートで復号化できる
+    let mut encrypted = Vec::new();
+    encrypt_age(&mut &b"from encript_tool"[..], &mut encrypted, "password").unwrap();
+
+    let decryptor = age::Decryptor::new(&encrypted[..]).unwrap();
+    let identity = age::scrypt::Identity::new(SecretString::from("password".to_string()));
+    let mut reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .unwrap();
+    let mut plaintext = Vec::new();
+    reader.read_to_end(&mut plaintext).unwrap();
+    assert_eq!(plaintext, b"from encript_tool");
+
+    // age クレートで暗号化したデータをこのツールで復号化できる
+    let recipient = age::scrypt::Recipient::new(SecretString::from("password".to_string()));
+    let encrypted = age::encrypt(&recipient, b"from age").unwrap();
+    let mut decrypted = Vec::new();
+    decrypt_age(&encrypted[..], &mut decrypted, "password").unwrap();
+    assert_eq!(decrypted, b"from age");
+}
+
+#[test]
+fn rejects_non_passphrase_age_files() {
+    let identity = age::x25519::Identity::generate();
+    let encrypted = age::encrypt(&identity.to_public(), b"secret").unwrap();
+
+    let result = decrypt_age(&encrypted[..], &mut Vec::new(), "password");
+    assert!(matches!(result, Err(CryptoError::InvalidInput(_))));
+}
+
+#[test]
+fn rejects_non_age_data() {
+    let result = decrypt_age(&b"MYCRYPT not an age file"[..], &mut Vec::new(), "password");
+    assert!(matches!(result, Err(CryptoError::InvalidFormat(_))));
+}
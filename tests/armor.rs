@@ -0,0 +1,97 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    armor, armor_file, dearmor, decrypt_bytes, decrypt_file_standard, encrypt_bytes,
+    encrypt_file_for_passwords, encrypt_file_standard, encrypt_file_streaming, is_armored,
+    CryptoError,
+};
+use std::fs;
+
+/// 全バイト値を含むバイナリデータ
+fn binary_data() -> Vec<u8> {
+    (0..10_000u32).map(|i| (i * 7 % 256) as u8).collect()
+}
+
+#[test]
+fn armored_file_round_trip_is_byte_exact() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("photo.bin");
+    let encrypted = dir.path().join("photo.bin.enc");
+    let output = dir.path().join("photo.out");
+    fs::write(&input, binary_data()).unwrap();
+
+    let config = fast_config();
+    encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+    armor_file(&encrypted).unwrap();
+
+    let text = fs::read_to_string(&encrypted).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "-----BEGIN MYCRYPT MESSAGE-----");
+    assert_eq!(
+        lines[1],
+        "Comment: AES-256-GCM / argon2id (m=1024 KB, t=1, p=1)"
+    );
+    assert_eq!(lines[2], "");
+    assert_eq!(*lines.last().unwrap(), "-----END MYCRYPT MESSAGE-----");
+    assert!(lines.iter().all(|line| line.len() <= 64));
+
+    decrypt_file_standard(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), binary_data());
+}
+
+#[test]
+fn armored_envelope_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("shared.bin");
+    let encrypted = dir.path().join("shared.bin.enc");
+    let output = dir.path().join("shared.out");
+    fs::write(&input, binary_data()).unwrap();
+
+    let config = fast_config();
+    encrypt_file_for_passwords(&input, &encrypted, &["one", "two"], &config).unwrap();
+    armor_file(&encrypted).unwrap();
+    assert!(is_armored(&fs::read(&encrypted).unwrap()));
+
+    decrypt_file_standard(&encrypted, &output, "two", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), binary_data());
+}
+
+#[test]
+fn dearmor_accepts_crlf_and_surrounding_text() {
+    let config = fast_config();
+    let encrypted = encrypt_bytes(b"mail", "password", &config).unwrap();
+    let text = armor(&encrypted).unwrap();
+
+    // メールで改行コードがCRLFに変わり、前に空白が入っても解除できる
+    let crlf = format!("\n  {}", text.replace('\n', "\r\n"));
+    assert_eq!(dearmor(crlf.as_bytes()).unwrap(), encrypted);
+    assert_eq!(
+        decrypt_bytes(crlf.as_bytes(), "password", &config).unwrap(),
+        b"mail"
+    );
+}
+
+#[test]
+fn rejects_truncated_armor_and_streaming_files() {
+    let config = fast_config();
+    let encrypted = encrypt_bytes(b"mail", "password", &config).unwrap();
+    let text = armor(&encrypted).unwrap();
+    let truncated = text.replace("-----END MYCRYPT MESSAGE-----\n", "");
+    assert!(matches!(
+        dearmor(truncated.as_bytes()),
+        Err(CryptoError::Truncated(_))
+    ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("large.bin");
+    let encrypted = dir.path().join("large.bin.enc");
+    fs::write(&input, binary_data()).unwrap();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+    assert!(matches!(
+        armor_file(&encrypted),
+        Err(CryptoError::InvalidInput(_))
+    ));
+}
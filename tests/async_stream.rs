@@ -0,0 +1,97 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_stream_async, decrypt_to_writer, encrypt_from_reader, encrypt_stream_async, Config,
+    CryptoError,
+};
+use std::io::Cursor;
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap()
+}
+
+fn sample_data() -> Vec<u8> {
+    (0..20_000u32).map(|i| (i % 251) as u8).collect()
+}
+
+fn small_chunk_config() -> Config {
+    Config {
+        stream_chunk_size: 4096,
+        ..fast_config()
+    }
+}
+
+#[test]
+fn async_round_trip() {
+    let config = small_chunk_config();
+    let data = sample_data();
+
+    runtime().block_on(async {
+        let encrypted =
+            encrypt_stream_async(Cursor::new(data.clone()), Vec::new(), "password", &config)
+                .await
+                .unwrap();
+        let decrypted = decrypt_stream_async(Cursor::new(encrypted), Vec::new(), "password")
+            .await
+            .unwrap();
+        assert_eq!(decrypted, data);
+    });
+}
+
+#[test]
+fn async_and_sync_formats_interoperate() {
+    let config = small_chunk_config();
+    let data = sample_data();
+    let rt = runtime();
+
+    // 非同期で暗号化したデータを同期APIで復号化できる
+    let encrypted = rt
+        .block_on(encrypt_stream_async(
+            Cursor::new(data.clone()),
+            Vec::new(),
+            "password",
+            &config,
+        ))
+        .unwrap();
+    let mut decrypted = Vec::new();
+    decrypt_to_writer(&mut &encrypted[..], &mut decrypted, "password").unwrap();
+    assert_eq!(decrypted, data);
+
+    // 同期APIで暗号化したデータを非同期で復号化できる
+    let mut encrypted = Vec::new();
+    encrypt_from_reader(&mut &data[..], &mut encrypted, "password", &config).unwrap();
+    let decrypted = rt
+        .block_on(decrypt_stream_async(
+            Cursor::new(encrypted),
+            Vec::new(),
+            "password",
+        ))
+        .unwrap();
+    assert_eq!(decrypted, data);
+}
+
+#[test]
+fn async_wrong_password_fails() {
+    let config = fast_config();
+    let rt = runtime();
+
+    let encrypted = rt
+        .block_on(encrypt_stream_async(
+            Cursor::new(b"secret".to_vec()),
+            Vec::new(),
+            "password",
+            &config,
+        ))
+        .unwrap();
+    let result = rt.block_on(decrypt_stream_async(
+        Cursor::new(encrypted),
+        Vec::new(),
+        "wrong",
+    ));
+    assert!(matches!(result, Err(CryptoError::WrongPasswordOrCorrupt)));
+}
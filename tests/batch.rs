@@ -0,0 +1,126 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{encrypt_file_standard, FileBatch};
+use std::fs;
+
+/// 標準形式のキー導出ブロック（マジックナンバー・バージョン・フラグの後ろ29バイト）
+fn standard_kdf_block(data: &[u8]) -> &[u8] {
+    &data[9..38]
+}
+
+/// ストリーミング形式のキー導出ブロック（マジックナンバー・チャンクサイズの後ろ29バイト）
+fn stream_kdf_block(data: &[u8]) -> &[u8] {
+    &data[13..42]
+}
+
+#[test]
+fn batch_round_trip_shares_key_derivation() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+    let batch = FileBatch::new("password", &config);
+
+    let names = ["a.txt", "b.txt", "c.bin"];
+    for (i, name) in names.iter().enumerate() {
+        let input = dir.path().join(name);
+        fs::write(&input, vec![i as u8; 1000 * (i + 1)]).unwrap();
+        let streaming = i == 2;
+        batch
+            .encrypt_file(&input, &dir.path().join(format!("{name}.enc")), streaming)
+            .unwrap();
+    }
+
+    let a = fs::read(dir.path().join("a.txt.enc")).unwrap();
+    let b = fs::read(dir.path().join("b.txt.enc")).unwrap();
+    let c = fs::read(dir.path().join("c.bin.enc")).unwrap();
+    // 一括処理の全ファイルが同じソルトとパラメータを記録している
+    assert_eq!(standard_kdf_block(&a), standard_kdf_block(&b));
+    assert_eq!(standard_kdf_block(&a), stream_kdf_block(&c));
+
+    for (i, name) in names.iter().enumerate() {
+        let output = dir.path().join(format!("{name}.out"));
+        batch
+            .decrypt_file(&dir.path().join(format!("{name}.enc")), &output, i == 2)
+            .unwrap();
+        assert_eq!(fs::read(&output).unwrap(), vec![i as u8; 1000 * (i + 1)]);
+    }
+}
+
+#[test]
+fn batch_streaming_uses_distinct_nonces_under_shared_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+    let batch = FileBatch::new("password", &config);
+
+    let input = dir.path().join("same.txt");
+    fs::write(&input, b"same contents").unwrap();
+    let first = dir.path().join("first.enc");
+    let second = dir.path().join("second.enc");
+    batch.encrypt_file(&input, &first, true).unwrap();
+    batch.encrypt_file(&input, &second, true).unwrap();
+
+    // ヘッダー(13) + キー導出ブロック(29) + メタデータブロック(17) の後ろが最初のチャンクのナンス
+    let first = fs::read(&first).unwrap();
+    let second = fs::read(&second).unwrap();
+    assert_ne!(&first[59..71], &second[59..71]);
+    assert_ne!(first, second);
+}
+
+#[test]
+fn batch_decrypts_files_with_different_salts() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+
+    // 個別に暗号化したファイル（ソルトがそれぞれ異なる）もまとめて復号化できる
+    let mut encrypted = Vec::new();
+    for i in 0..2 {
+        let input = dir.path().join(format!("{i}.txt"));
+        let output = dir.path().join(format!("{i}.enc"));
+        fs::write(&input, format!("file {i}")).unwrap();
+        encrypt_file_standard(&input, &output, "password", &config).unwrap();
+        encrypted.push(output);
+    }
+
+    let batch = FileBatch::new("password", &config);
+    for (i, path) in encrypted.iter().enumerate() {
+        let output = dir.path().join(format!("{i}.out"));
+        batch.decrypt_file(path, &output, false).unwrap();
+        assert_eq!(fs::read_to_string(&output).unwrap(), format!("file {i}"));
+    }
+
+    let wrong = FileBatch::new("wrong", &config);
+    assert!(wrong
+        .decrypt_file(&encrypted[0], &dir.path().join("x.out"), false)
+        .is_err());
+}
+
+#[test]
+fn batch_is_shared_across_threads() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+
+    // 別々のソルトで暗号化したファイルを、1つの一括処理から並列に復号化する
+    let paths: Vec<_> = (0..4)
+        .map(|i| {
+            let input = dir.path().join(format!("{i}.txt"));
+            let encrypted = dir.path().join(format!("{i}.enc"));
+            fs::write(&input, format!("file {i}")).unwrap();
+            encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+            encrypted
+        })
+        .collect();
+
+    let batch = FileBatch::new("password", &config).without_progress();
+    std::thread::scope(|scope| {
+        for (i, path) in paths.iter().enumerate() {
+            let batch = &batch;
+            let output = dir.path().join(format!("{i}.out"));
+            scope.spawn(move || {
+                batch.decrypt_file(path, &output, false).unwrap();
+                assert_eq!(fs::read_to_string(&output).unwrap(), format!("file {i}"));
+            });
+        }
+    });
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use common::fast_config;
+use encript_tool::header::FileHeader;
+use encript_tool::{
+    decrypt_bytes, decrypt_string, encrypt_bytes, encrypt_string, Config, CryptContext, CryptoError,
+};
+
+fn checksum_config() -> Config {
+    Config {
+        checksum: true,
+        ..fast_config()
+    }
+}
+
+#[test]
+fn checksum_round_trip() {
+    let encrypted = encrypt_bytes(b"archive", "password", &checksum_config()).unwrap();
+    let plain = encrypt_bytes(b"archive", "password", &fast_config()).unwrap();
+
+    // SHA-256は暗号文の中に記録され、復号化後に取り除かれる
+    assert_eq!(encrypted.len(), plain.len() + 32);
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &fast_config()).unwrap(),
+        b"archive"
+    );
+}
+
+#[test]
+fn checksum_with_padding_round_trip() {
+    let config = Config {
+        pad_block: Some(64),
+        ..checksum_config()
+    };
+    let encrypted = encrypt_string("padded", "password", &config).unwrap();
+
+    assert_eq!(
+        decrypt_string(&encrypted, "password", &fast_config()).unwrap(),
+        "padded"
+    );
+}
+
+#[test]
+fn context_records_checksum() {
+    let context = CryptContext::derive("password", &checksum_config()).unwrap();
+    let encrypted = context.encrypt(b"record").unwrap();
+
+    assert_eq!(context.decrypt(&encrypted).unwrap(), b"record");
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &fast_config()).unwrap(),
+        b"record"
+    );
+}
+
+#[test]
+fn mismatched_checksum_is_reported() {
+    // 認証は通るが、末尾のSHA-256が平文と一致しないデータを組み立てる
+    let header = FileHeader::new(&checksum_config());
+    let header_bytes = header.to_bytes();
+    let key = header.kdf.derive_key("password").unwrap();
+    let mut payload = b"data".to_vec();
+    payload.extend_from_slice(&[0u8; 32]);
+
+    let nonce = [7u8; 12];
+    let ciphertext = Aes256Gcm::new(&key.into())
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &payload,
+                aad: &header_bytes,
+            },
+        )
+        .unwrap();
+    let mut encrypted = header_bytes;
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+
+    let err = decrypt_bytes(&encrypted, "password", &fast_config()).unwrap_err();
+    assert!(matches!(err, CryptoError::ChecksumMismatch));
+}
+
+#[cfg(feature = "std-fs")]
+#[test]
+fn standard_file_checksum_and_streaming_rejection() {
+    use encript_tool::{decrypt_file_standard, encrypt_file_standard, encrypt_file_streaming};
+    use std::fs;
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    let encrypted = dir.path().join("data.bin.enc");
+    let output = dir.path().join("data.out");
+    fs::write(&input, b"long-term storage").unwrap();
+
+    encrypt_file_standard(&input, &encrypted, "password", &checksum_config()).unwrap();
+    decrypt_file_standard(&encrypted, &output, "password", &fast_config()).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"long-term storage");
+
+    // ストリーミング形式にはチェックサムを記録する場所がない
+    let err =
+        encrypt_file_streaming(&input, &encrypted, "password", &checksum_config()).unwrap_err();
+    assert!(matches!(err, CryptoError::InvalidInput(_)));
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "std-fs")]
+
+//! パスワードとテキストを標準入力から読む場合のCLIの動作
+
+mod common;
+
+use common::{fast_config, run_with_stdin};
+use encript_tool::config::save_config;
+
+#[test]
+fn piped_text_without_password_is_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+
+    // プロンプトが先頭の1行をパスワードとして読み、空のテキストを暗号化してしまわない
+    let output = run_with_stdin(
+        &config,
+        &["--allow-weak-password", "encrypt"],
+        "secret text\n",
+    );
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("-p"));
+
+    let output = run_with_stdin(&config, &["decrypt"], "AAAA\n");
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn piped_text_with_password_round_trips() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    let password = "correct horse battery staple";
+
+    let output = run_with_stdin(&config, &["encrypt", "-p", password], "secret text\n");
+    assert!(output.status.success());
+    let encrypted = String::from_utf8(output.stdout).unwrap();
+
+    let output = run_with_stdin(&config, &["decrypt", "-p", password], &encrypted);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "secret text\n");
+}
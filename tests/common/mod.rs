@@ -0,0 +1,61 @@
+use encript_tool::{Argon2Config, Config};
+#[cfg(feature = "std-fs")]
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+/// テスト用の軽量なArgon2設定
+#[allow(dead_code)]
+pub fn fast_config() -> Config {
+    Config {
+        argon2: Argon2Config {
+            memory_cost: 1024,
+            time_cost: 1,
+            parallelism: 1,
+            ..Argon2Config::default()
+        },
+        ..Config::default()
+    }
+}
+
+/// 設定ファイル `config` を使うCLIのコマンド（環境変数のパスワードとペッパーは使わない）
+#[cfg(feature = "std-fs")]
+#[allow(dead_code)]
+pub fn cli(config: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_encript_tool"));
+    command
+        .arg("--config")
+        .arg(config)
+        .env_remove("MYCRYPT_PASSWORD")
+        .env_remove("MYCRYPT_PEPPER");
+    command
+}
+
+/// 設定ファイル `config` を使ってCLIを実行
+#[cfg(feature = "std-fs")]
+#[allow(dead_code)]
+pub fn run(config: &Path, args: &[&str]) -> Output {
+    cli(config).args(args).output().unwrap()
+}
+
+/// 設定ファイル `config` を使い、`stdin` を標準入力に渡してCLIを実行
+#[cfg(feature = "std-fs")]
+#[allow(dead_code)]
+pub fn run_with_stdin(config: &Path, args: &[&str], stdin: &str) -> Output {
+    let mut child = cli(config)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "std-fs")]
+
+//! completions サブコマンド
+
+mod common;
+
+use common::{cli, run};
+use std::process::{Command, Output, Stdio};
+
+fn completions(shell: &str) -> Output {
+    let dir = tempfile::tempdir().unwrap();
+    run(&dir.path().join("config.toml"), &["completions", shell])
+}
+
+#[test]
+fn completions_cover_subcommands_and_flags_for_each_shell() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let output = completions(shell);
+        assert!(output.status.success(), "{shell}: {output:?}");
+        let script = String::from_utf8(output.stdout).unwrap();
+        assert!(script.contains("encript_tool"), "{shell}");
+        for word in [
+            "encrypt-file",
+            "completions",
+            "reset",
+            "pepper-file",
+            "version",
+        ] {
+            assert!(script.contains(word), "{shell}: {word}");
+        }
+        // ヘルプに表示しないサブコマンドは補完の候補にも含めない
+        assert!(!script.contains("derive-key"), "{shell}");
+    }
+}
+
+#[test]
+fn closed_stdout_is_an_error_not_a_panic() {
+    let dir = tempfile::tempdir().unwrap();
+    // パイプの容量より大きい fish の補完スクリプトを、読み取り側を閉じたパイプに書き込む
+    let mut child = cli(&dir.path().join("config.toml"))
+        .args(["completions", "fish"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    drop(child.stdout.take());
+    let output = child.wait_with_output().unwrap();
+
+    assert_eq!(output.status.code(), Some(1), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("標準出力への書き込みに失敗しました"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("panicked"), "{stderr}");
+}
+
+#[test]
+fn bash_completion_completes_subcommands_and_values() {
+    if Command::new("bash").arg("--version").output().is_err() {
+        return;
+    }
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("completion.bash");
+    std::fs::write(&script, completions("bash").stdout).unwrap();
+
+    let complete = |words: &str| {
+        let output = Command::new("bash")
+            .arg("-c")
+            .arg(format!(
+                "source \"$1\"; COMP_WORDS=({words}); COMP_CWORD=$((${{#COMP_WORDS[@]}} - 1)); \
+                 _encript_tool \"${{COMP_WORDS[0]}}\" \"${{COMP_WORDS[COMP_CWORD]}}\" \
+                 \"${{COMP_WORDS[COMP_CWORD-1]}}\"; echo \"${{COMPREPLY[*]}}\""
+            ))
+            .arg("bash")
+            .arg(&script)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    };
+
+    assert_eq!(complete("encript_tool config s"), "show set");
+    assert_eq!(
+        complete("encript_tool encrypt-file --format ''"),
+        "native age"
+    );
+    assert!(complete("encript_tool completions ''").ends_with("bash elvish fish powershell zsh"));
+    assert_eq!(complete("encript_tool --js"), "--json");
+}
+
+#[test]
+fn unknown_shell_is_a_usage_error() {
+    assert_eq!(completions("tcsh").status.code(), Some(64));
+}
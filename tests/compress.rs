@@ -0,0 +1,198 @@
+#![cfg(feature = "std-fs")]
+
+//! 標準形式のファイルの暗号化前の圧縮（`--compress`）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use encript_tool::{
+    decrypt_file_standard, encrypt_file_standard, encrypt_file_streaming, inspect_file,
+    rotate_file, CompressMode, Config, CryptoError, FormatInfo,
+};
+use rand::RngCore;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn compress_config(mode: CompressMode) -> Config {
+    Config {
+        compress: mode,
+        ..fast_config()
+    }
+}
+
+fn is_compressed(path: &Path) -> bool {
+    match inspect_file(path).unwrap().format {
+        FormatInfo::Standard { compressed, .. } => compressed.is_some(),
+        format => panic!("標準形式ではありません: {format:?}"),
+    }
+}
+
+/// `name` に `data` を書き込んで暗号化し、復号化して元に戻ることを確認してから暗号化したファイルを返す
+fn encrypt_round_trip(dir: &Path, name: &str, data: &[u8], config: &Config) -> PathBuf {
+    let input = dir.join(name);
+    fs::write(&input, data).unwrap();
+    let encrypted = dir.join(format!("{name}.enc"));
+    encrypt_file_standard(&input, &encrypted, "password", config).unwrap();
+
+    // 復号化では設定の圧縮モードに関係なく、ヘッダーのフラグで展開する
+    let decrypted = dir.join(format!("{name}.out"));
+    decrypt_file_standard(&encrypted, &decrypted, "password", &fast_config()).unwrap();
+    assert_eq!(fs::read(&decrypted).unwrap(), data);
+    encrypted
+}
+
+#[test]
+fn auto_stores_high_entropy_input_uncompressed() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut data = vec![0u8; 200_000];
+    rand::rng().fill_bytes(&mut data);
+
+    let encrypted = encrypt_round_trip(
+        dir.path(),
+        "random.bin",
+        &data,
+        &compress_config(CompressMode::Auto),
+    );
+    assert!(!is_compressed(&encrypted));
+    assert!(fs::metadata(&encrypted).unwrap().len() > data.len() as u64);
+}
+
+#[test]
+fn auto_compresses_low_entropy_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = b"2024-01-01 INFO request handled\n".repeat(5_000);
+
+    let encrypted = encrypt_round_trip(
+        dir.path(),
+        "server.dat",
+        &data,
+        &compress_config(CompressMode::Auto),
+    );
+    assert!(is_compressed(&encrypted));
+    assert!(fs::metadata(&encrypted).unwrap().len() < data.len() as u64 / 10);
+}
+
+#[test]
+fn auto_skips_compressed_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    let data = vec![0u8; 100_000];
+
+    // 中身は圧縮が効くが、拡張子で既に圧縮された形式と判断する
+    let encrypted = encrypt_round_trip(
+        dir.path(),
+        "photo.JPG",
+        &data,
+        &compress_config(CompressMode::Auto),
+    );
+    assert!(!is_compressed(&encrypted));
+
+    let encrypted = encrypt_round_trip(
+        dir.path(),
+        "notes.txt",
+        &data,
+        &compress_config(CompressMode::Auto),
+    );
+    assert!(is_compressed(&encrypted));
+}
+
+#[test]
+fn always_and_never_ignore_the_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut data = vec![0u8; 10_000];
+    rand::rng().fill_bytes(&mut data);
+
+    let encrypted = encrypt_round_trip(
+        dir.path(),
+        "random.zip",
+        &data,
+        &compress_config(CompressMode::Always),
+    );
+    assert!(is_compressed(&encrypted));
+
+    let encrypted = encrypt_round_trip(dir.path(), "log.txt", &vec![b'a'; 10_000], &fast_config());
+    assert!(!is_compressed(&encrypted));
+}
+
+#[test]
+fn compression_with_checksum_and_rotation() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        checksum: true,
+        ..compress_config(CompressMode::Always)
+    };
+    let data = b"checksum of the original plaintext\n".repeat(1_000);
+    let encrypted = encrypt_round_trip(dir.path(), "data.csv", &data, &config);
+
+    // パスワードを変更しても圧縮の有無は引き継ぐ
+    rotate_file(&encrypted, "password", "new password", &fast_config()).unwrap();
+    assert!(is_compressed(&encrypted));
+    let decrypted = dir.path().join("rotated");
+    decrypt_file_standard(&encrypted, &decrypted, "new password", &fast_config()).unwrap();
+    assert_eq!(fs::read(&decrypted).unwrap(), data);
+}
+
+#[test]
+fn streaming_rejects_compression() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.txt");
+    fs::write(&input, b"data").unwrap();
+
+    let result = encrypt_file_streaming(
+        &input,
+        &dir.path().join("data.txt.enc"),
+        "password",
+        &compress_config(CompressMode::Auto),
+    );
+    assert!(matches!(result, Err(CryptoError::InvalidInput(_))));
+}
+
+#[test]
+fn cli_compress_option() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    let data = b"a,b,c\n1,2,3\n".repeat(1_000);
+    fs::write(dir.path().join("table.csv"), &data).unwrap();
+
+    let run = |args: &[&str]| {
+        cli(&config)
+            .arg("--allow-weak-password")
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[
+        "encrypt-file",
+        "table.csv",
+        "--compress",
+        "auto",
+        "-p",
+        "password",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(is_compressed(&dir.path().join("table.csv.enc")));
+
+    let output = run(&[
+        "decrypt-file",
+        "table.csv.enc",
+        "-o",
+        "out.csv",
+        "-p",
+        "password",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read(dir.path().join("out.csv")).unwrap(), data);
+
+    let output = run(&[
+        "encrypt-file",
+        "table.csv",
+        "--compress",
+        "sometimes",
+        "-p",
+        "password",
+    ]);
+    assert_eq!(output.status.code(), Some(64), "{output:?}");
+}
@@ -0,0 +1,193 @@
+#![cfg(feature = "std-fs")]
+
+use encript_tool::config::{
+    find_project_config, load_config, save_config, Config, DEFAULT_STREAMING_THRESHOLD,
+};
+use encript_tool::{Argon2Variant, KdfAlgorithm, OutputFormat};
+
+#[test]
+fn save_config_round_trips_modified_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("config.toml");
+
+    let mut config = Config::default();
+    config.argon2.memory_cost = 128 * 1024; // 128MB
+    config.argon2.time_cost = 5;
+    config.argon2.variant = Argon2Variant::Argon2i;
+    config.kdf = KdfAlgorithm::Scrypt;
+    config.scrypt.log_n = 15;
+    save_config(&config, &path).unwrap();
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .contains("kdf = \"scrypt\""));
+    assert!(std::fs::read_to_string(&path)
+        .unwrap()
+        .contains("variant = \"argon2i\""));
+
+    let loaded = load_config(Some(&path)).unwrap();
+    assert_eq!(loaded.argon2.memory_cost, 128 * 1024);
+    assert_eq!(loaded.argon2.time_cost, 5);
+    assert_eq!(loaded.argon2.variant, Argon2Variant::Argon2i);
+    assert_eq!(loaded.kdf, KdfAlgorithm::Scrypt);
+    assert_eq!(loaded.scrypt.log_n, 15);
+}
+
+#[test]
+fn streaming_threshold_defaults_when_missing_from_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        "default_format = \"Base64\"\ndefault_verbose = false\nversion = \"2.0\"\n\n\
+         [argon2]\nmemory_cost = 65536\ntime_cost = 3\nparallelism = 4\n",
+    )
+    .unwrap();
+    let mut config = load_config(Some(&path)).unwrap();
+    assert_eq!(config.streaming_threshold, DEFAULT_STREAMING_THRESHOLD);
+
+    config.set_value("streaming_threshold", "1048576").unwrap();
+    assert_eq!(config.get_value("streaming_threshold").unwrap(), "1048576");
+    assert!(config.set_value("streaming_threshold", "-1").is_err());
+}
+
+#[test]
+fn project_config_is_found_in_ancestor_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let project = dir.path().join("project");
+    let nested = project.join("src").join("deep");
+    std::fs::create_dir_all(&nested).unwrap();
+    assert_eq!(find_project_config(&nested), None);
+
+    let mut config = Config::default();
+    config.argon2.time_cost = 7;
+    let path = project.join(".mycrypt.toml");
+    save_config(&config, &path).unwrap();
+
+    // 最も近い祖先の設定ファイルが見つかる
+    assert_eq!(find_project_config(&nested), Some(path.clone()));
+    assert_eq!(load_config(Some(&path)).unwrap().argon2.time_cost, 7);
+
+    // より近いディレクトリの設定ファイルが優先される
+    let closer = nested.join(".mycrypt.toml");
+    save_config(&Config::default(), &closer).unwrap();
+    assert_eq!(find_project_config(&nested), Some(closer));
+}
+
+#[test]
+fn builder_sets_values_and_validates() {
+    let config = Config::builder()
+        .argon2_memory(128 * 1024)
+        .argon2_variant(Argon2Variant::Argon2d)
+        .kdf(KdfAlgorithm::Pbkdf2)
+        .pbkdf2_iterations(1000)
+        .encrypted_extension(".secret")
+        .format(OutputFormat::Hex)
+        .build()
+        .unwrap();
+    assert_eq!(config.argon2.memory_cost, 128 * 1024);
+    assert_eq!(config.argon2.variant, Argon2Variant::Argon2d);
+    assert_eq!(config.kdf, KdfAlgorithm::Pbkdf2);
+    assert_eq!(config.pbkdf2.iterations, 1000);
+    assert_eq!(config.encrypted_extension, "secret");
+    assert_eq!(config.default_format, OutputFormat::Hex);
+    // 指定しなかった項目は既定値のまま
+    assert_eq!(config.argon2.time_cost, Config::default().argon2.time_cost);
+
+    // 最初に失敗した検証のエラーを返す
+    let err = Config::builder()
+        .pbkdf2_iterations(0)
+        .pad_block(1)
+        .build()
+        .unwrap_err();
+    assert!(err.to_string().contains("PBKDF2"));
+    assert!(Config::builder()
+        .encrypted_extension("a/b")
+        .build()
+        .is_err());
+    assert!(Config::builder().stream_chunk_size(1).build().is_err());
+    // 組み合わせの制約は build で検証する（並列度に対してメモリが少なすぎる）
+    assert!(Config::builder()
+        .argon2_memory(8)
+        .argon2_parallelism(4)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn parse_error_reports_line_and_column() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    save_config(&Config::default(), &path).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    let line = content
+        .lines()
+        .position(|line| line.starts_with("time_cost"))
+        .unwrap()
+        + 1;
+    std::fs::write(
+        &path,
+        content.replace("time_cost = 3", "time_cost = \"three\""),
+    )
+    .unwrap();
+
+    let err = load_config(Some(&path)).unwrap_err();
+    assert!(err.to_string().contains(&format!("{line}行目、13列目")));
+    // 原因のエラーには該当行と位置を示す `^` が含まれる
+    let detail = format!("{err:#}");
+    assert!(detail.contains("time_cost = \"three\""));
+    assert!(detail.contains('^'));
+}
+
+#[test]
+fn unknown_keys_are_ignored_and_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    save_config(&Config::default(), &path).unwrap();
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(
+        &path,
+        format!("future_option = true\n{content}").replace("[argon2]", "[argon2]\nnew_cost = 1"),
+    )
+    .unwrap();
+
+    let loaded = load_config(Some(&path)).unwrap();
+    assert_eq!(loaded.ignored_keys, ["argon2.new_cost", "future_option"]);
+    assert_eq!(
+        loaded.argon2.memory_cost,
+        Config::default().argon2.memory_cost
+    );
+}
+
+#[test]
+fn progress_template_is_validated_on_load() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    save_config(&Config::default(), &path).unwrap();
+    let loaded = load_config(Some(&path)).unwrap();
+    assert!(loaded.show_progress);
+    assert_eq!(loaded.progress_template, None);
+
+    let mut config = Config::default();
+    config
+        .set_value("progress_template", "{bar:20} {percent}%")
+        .unwrap();
+    config.set_value("show_progress", "false").unwrap();
+    save_config(&config, &path).unwrap();
+    let loaded = load_config(Some(&path)).unwrap();
+    assert!(!loaded.show_progress);
+    assert_eq!(
+        loaded.progress_template.as_deref(),
+        Some("{bar:20} {percent}%")
+    );
+
+    // 解釈できないテンプレートは処理の途中ではなく読み込み時にエラーにする
+    assert!(config.set_value("progress_template", "{bar:x}").is_err());
+    let content = std::fs::read_to_string(&path).unwrap();
+    std::fs::write(&path, content.replace("{bar:20} {percent}%", "{bar:x}")).unwrap();
+    let err = load_config(Some(&path)).unwrap_err();
+    assert!(format!("{err:#}").contains("テンプレート"));
+    assert!(Config::builder()
+        .progress_template("{bar:x}")
+        .build()
+        .is_err());
+}
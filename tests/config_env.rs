@@ -0,0 +1,50 @@
+#![cfg(feature = "std-fs")]
+
+//! 環境変数はプロセス全体で共有されるため、他のテストと別のバイナリにまとめる
+
+use encript_tool::config::{load_config, save_config, Config};
+use encript_tool::OutputFormat;
+use std::env;
+
+#[test]
+fn environment_variables_override_config_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+
+    let mut file_config = Config::default();
+    file_config.argon2.memory_cost = 32 * 1024;
+    file_config.argon2.time_cost = 2;
+    save_config(&file_config, &path).unwrap();
+
+    // 環境変数が設定ファイルより優先され、未設定の項目はファイルの値のまま
+    env::set_var("MYCRYPT_ARGON2_MEMORY_COST", "131072");
+    env::set_var("MYCRYPT_DEFAULT_FORMAT", "hex");
+    env::set_var("MYCRYPT_DEFAULT_VERBOSE", "1");
+    let config = load_config(Some(&path)).unwrap();
+    assert_eq!(config.argon2.memory_cost, 131072);
+    assert_eq!(config.argon2.time_cost, 2);
+    assert_eq!(config.default_format, OutputFormat::Hex);
+    assert!(config.default_verbose);
+
+    // 設定ファイルがない場合はデフォルト値に上書きが適用される
+    let config = load_config(Some(&dir.path().join("missing.toml"))).unwrap();
+    assert_eq!(config.argon2.memory_cost, 131072);
+    assert_eq!(config.argon2.time_cost, Config::default().argon2.time_cost);
+
+    // 不正な値はエラーになり、変数名がメッセージに含まれる
+    env::set_var("MYCRYPT_ARGON2_PARALLELISM", "many");
+    let err = load_config(Some(&path)).unwrap_err();
+    assert!(
+        format!("{err:#}").contains("MYCRYPT_ARGON2_PARALLELISM"),
+        "{err:#}"
+    );
+
+    for var in [
+        "MYCRYPT_ARGON2_MEMORY_COST",
+        "MYCRYPT_DEFAULT_FORMAT",
+        "MYCRYPT_DEFAULT_VERBOSE",
+        "MYCRYPT_ARGON2_PARALLELISM",
+    ] {
+        env::remove_var(var);
+    }
+}
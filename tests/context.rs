@@ -0,0 +1,65 @@
+mod common;
+
+use common::fast_config;
+use encript_tool::{decrypt_bytes, encrypt_bytes, encrypt_string, CryptContext, CryptoError};
+
+#[test]
+fn context_round_trip_with_fresh_nonces() {
+    let context = CryptContext::derive("password", &fast_config()).unwrap();
+
+    let first = context.encrypt(b"record").unwrap();
+    let second = context.encrypt(b"record").unwrap();
+    assert_ne!(first, second);
+
+    assert_eq!(context.decrypt(&first).unwrap(), b"record");
+    assert_eq!(context.decrypt(&second).unwrap(), b"record");
+    assert_eq!(
+        context.decrypt(&context.encrypt(b"").unwrap()).unwrap(),
+        b""
+    );
+}
+
+#[test]
+fn context_output_is_standard_format() {
+    let config = fast_config();
+    let context = CryptContext::derive("password", &config).unwrap();
+
+    // コンテキストの出力は通常の関数でも復号化できる
+    let encrypted = context.encrypt(b"interop").unwrap();
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &config).unwrap(),
+        b"interop"
+    );
+    assert!(matches!(
+        decrypt_bytes(&encrypted, "wrong", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
+
+#[test]
+fn context_rejects_foreign_and_tampered_data() {
+    let config = fast_config();
+    let context = CryptContext::derive("password", &config).unwrap();
+
+    // 別のソルトで暗号化されたデータはキー導出を行わずに拒否する
+    let foreign = encrypt_bytes(b"other", "password", &config).unwrap();
+    assert!(matches!(
+        context.decrypt(&foreign),
+        Err(CryptoError::InvalidInput(_))
+    ));
+
+    let mut tampered = context.encrypt(b"record").unwrap();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(matches!(
+        context.decrypt(&tampered),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+
+    assert!(matches!(
+        context.decrypt(b"plain text"),
+        Err(CryptoError::NotEncrypted)
+    ));
+    // 文字列APIの出力（Base64）はバイト列としては扱えない
+    let text = encrypt_string("text", "password", &config).unwrap();
+    assert!(context.decrypt(text.as_bytes()).is_err());
+}
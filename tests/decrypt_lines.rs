@@ -0,0 +1,89 @@
+#![cfg(feature = "std-fs")]
+
+//! 複数の暗号文をまとめて復号化する（`decrypt_text_lines` / `decrypt --lines`）
+
+mod common;
+
+use common::{fast_config, run_with_stdin};
+use encript_tool::config::save_config;
+use encript_tool::{
+    decrypt_text_lines, encrypt_string, with_kdf_observer, Config, CryptoError, KdfEvent,
+};
+use std::{cell::Cell, rc::Rc};
+
+#[test]
+fn shared_salt_derives_key_once() {
+    let config = Config {
+        salt: Some([0x42; 16]),
+        ..fast_config()
+    };
+    let text = ["一行目", "二行目", "三行目"]
+        .map(|line| encrypt_string(line, "password", &config).unwrap())
+        .join("\n");
+
+    let derived = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&derived);
+    let results = with_kdf_observer(
+        move |event| {
+            if matches!(event, KdfEvent::Finished(_)) {
+                counter.set(counter.get() + 1);
+            }
+        },
+        || decrypt_text_lines(&text, "password", b"", &fast_config()),
+    );
+
+    assert_eq!(derived.get(), 1);
+    let plaintexts: Vec<_> = results
+        .into_iter()
+        .map(|(_, result)| String::from_utf8(result.unwrap()).unwrap())
+        .collect();
+    assert_eq!(plaintexts, ["一行目", "二行目", "三行目"]);
+}
+
+#[test]
+fn failures_are_reported_per_line() {
+    let config = fast_config();
+    let first = encrypt_string("first", "password", &config).unwrap();
+    let other = encrypt_string("other", "different", &config).unwrap();
+    let text = format!("{first}\n\n  {other}  not-base64!\n");
+
+    let results = decrypt_text_lines(&text, "password", b"", &config);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].0, 1);
+    assert_eq!(results[0].1.as_deref().unwrap(), b"first");
+    // 空行は飛ばし、同じ行の暗号文は同じ行番号になる
+    assert_eq!(results[1].0, 3);
+    assert!(matches!(
+        results[1].1,
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+    assert_eq!(results[2].0, 3);
+    assert!(results[2].1.is_err());
+}
+
+#[test]
+fn cli_continues_after_failed_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    let config = fast_config();
+    save_config(&config, &config_path).unwrap();
+    let input = [
+        encrypt_string("first", "password", &config).unwrap(),
+        encrypt_string("second", "different", &config).unwrap(),
+        encrypt_string("third", "password", &config).unwrap(),
+    ]
+    .join("\n");
+
+    let output = run_with_stdin(
+        &config_path,
+        &["decrypt", "--lines", "-p", "password"],
+        &input,
+    );
+
+    // パスワード違いの終了コードで失敗するが、残りの行は出力される
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "first\nthird\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("2行目"));
+    assert!(stderr.contains("1 件"));
+}
@@ -0,0 +1,113 @@
+#![cfg(feature = "std-fs")]
+
+//! 導出したキーの表示（`derive-key --danger-print-key`）
+
+mod common;
+
+use common::run;
+use encript_tool::config::save_config;
+use encript_tool::{
+    derive_key_with_pbkdf2, hex_encode, Argon2Config, Argon2Variant, Config, KdfAlgorithm,
+    Pbkdf2Config,
+};
+
+const SALT_HEX: &str = "11111111111111111111111111111111";
+
+#[test]
+fn prints_key_only_with_danger_flag() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(
+        &Config {
+            kdf: KdfAlgorithm::Pbkdf2,
+            pbkdf2: Pbkdf2Config { iterations: 1000 },
+            ..Config::default()
+        },
+        &config,
+    )
+    .unwrap();
+
+    let output = run(
+        &config,
+        &["derive-key", "--salt", SALT_HEX, "-p", "password"],
+    );
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("--danger-print-key を指定してください")
+    );
+
+    let output = run(
+        &config,
+        &[
+            "derive-key",
+            "--salt",
+            SALT_HEX,
+            "-p",
+            "password",
+            "--danger-print-key",
+        ],
+    );
+    assert!(output.status.success());
+    let expected = hex_encode(&derive_key_with_pbkdf2("password", &[0x11; 16], 1000));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), expected + "\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("警告"));
+}
+
+#[test]
+fn rejects_wrong_salt_length_and_is_hidden_from_help() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+
+    let output = run(
+        &config,
+        &[
+            "derive-key",
+            "--salt",
+            "1111",
+            "-p",
+            "password",
+            "--danger-print-key",
+        ],
+    );
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let help = run(&config, &["--help"]);
+    assert!(!String::from_utf8_lossy(&help.stdout).contains("derive-key"));
+}
+
+#[test]
+fn json_reports_argon2_variant() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(
+        &Config {
+            argon2: Argon2Config {
+                variant: Argon2Variant::Argon2i,
+                memory_cost: 1024,
+                time_cost: 1,
+                parallelism: 1,
+            },
+            ..Config::default()
+        },
+        &config,
+    )
+    .unwrap();
+
+    let output = run(
+        &config,
+        &[
+            "--json",
+            "derive-key",
+            "--salt",
+            SALT_HEX,
+            "-p",
+            "password",
+            "--danger-print-key",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["algorithm"], "argon2i");
+}
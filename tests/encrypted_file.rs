@@ -0,0 +1,104 @@
+mod common;
+
+use common::fast_config;
+use encript_tool::header::{FileHeader, FileMetadata, FORMAT_VERSION};
+use encript_tool::{decrypt_bytes, encrypt_bytes, Config, CryptoError, EncryptedFile, KdfParams};
+
+#[test]
+fn parses_encrypt_bytes_output() {
+    let config = fast_config();
+    let encrypted = encrypt_bytes(b"structured", "password", &config).unwrap();
+
+    let file = EncryptedFile::parse(&encrypted).unwrap();
+    assert_eq!(file.version, FORMAT_VERSION);
+    assert_eq!(file.header.kdf.params, KdfParams::from_config(&config));
+    assert_eq!(file.header.hint, None);
+    // 暗号文は平文と同じ長さ + 16バイトの認証タグ
+    assert_eq!(file.ciphertext.len(), b"structured".len() + 16);
+    assert_eq!(
+        encrypted.len(),
+        file.header_bytes().len() + file.nonce.len() + file.ciphertext.len()
+    );
+
+    // 書き戻したデータは元と同じで、そのまま復号化できる
+    assert_eq!(file.to_bytes(), encrypted);
+    assert_eq!(
+        decrypt_bytes(&file.to_bytes(), "password", &config).unwrap(),
+        b"structured"
+    );
+}
+
+#[test]
+fn to_bytes_and_parse_round_trip() {
+    let config = Config {
+        hint: Some("仕事用".to_string()),
+        checksum: true,
+        ..fast_config()
+    };
+    let header = FileHeader {
+        metadata: Some(FileMetadata {
+            mode: 0o640,
+            modified_secs: 1_600_000_000,
+            modified_nanos: 123,
+        }),
+        padded: true,
+        path: Some("docs/notes.txt".to_string()),
+        compressed: Some(4096),
+        ..FileHeader::new(&config)
+    };
+    let file = EncryptedFile {
+        version: FORMAT_VERSION,
+        header: header.clone(),
+        nonce: [7; 12],
+        ciphertext: vec![0xAB; 40],
+    };
+    assert_eq!(EncryptedFile::parse(&file.to_bytes()).unwrap(), file);
+
+    // 旧バージョンの形式もバージョンを保ったまま書き戻せる
+    let v2 = EncryptedFile {
+        version: 2,
+        header: FileHeader {
+            padded: false,
+            hint: None,
+            checksum: false,
+            path: None,
+            compressed: None,
+            ..header
+        },
+        ..file
+    };
+    let bytes = v2.to_bytes();
+    assert_eq!(bytes[7], 2);
+    assert_eq!(EncryptedFile::parse(&bytes).unwrap(), v2);
+}
+
+#[test]
+fn modified_header_fails_authentication() {
+    let config = fast_config();
+    let encrypted = encrypt_bytes(b"data", "password", &config).unwrap();
+
+    let mut file = EncryptedFile::parse(&encrypted).unwrap();
+    file.header.hint = Some("書き換えたヒント".to_string());
+    assert!(matches!(
+        decrypt_bytes(&file.to_bytes(), "password", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
+
+#[test]
+fn rejects_data_without_nonce() {
+    let encrypted = encrypt_bytes(b"data", "password", &fast_config()).unwrap();
+    let header_len = EncryptedFile::parse(&encrypted)
+        .unwrap()
+        .header_bytes()
+        .len();
+
+    assert!(matches!(
+        EncryptedFile::parse(&encrypted[..header_len + 11]),
+        Err(CryptoError::Truncated(_))
+    ));
+    assert!(matches!(
+        EncryptedFile::parse(b"not encrypted"),
+        Err(CryptoError::InvalidFormat(_))
+    ));
+}
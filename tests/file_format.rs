@@ -0,0 +1,99 @@
+#![cfg(feature = "std-fs")]
+
+//! 設定の `file_format` で、標準形式の暗号化ファイルを文字列として書き込む
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_file_standard, decrypt_from_text, detect_format, encrypt_file_for_passwords,
+    encrypt_file_standard, file_password_hint, inspect_file, rotate_file, Config, EncFormat,
+    OutputFormat,
+};
+use std::fs;
+
+#[test]
+fn encoded_files_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("notes.txt");
+    fs::write(&input, "テキストで保存する暗号文").unwrap();
+
+    for format in [OutputFormat::Base64, OutputFormat::Hex] {
+        let config = Config {
+            file_format: Some(format.clone()),
+            hint: Some("いつもの".to_string()),
+            ..fast_config()
+        };
+        let encrypted = dir.path().join("notes.txt.enc");
+        let decrypted = dir.path().join("notes.out");
+        encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+
+        let text = fs::read_to_string(&encrypted).unwrap();
+        assert!(text.ends_with('\n'));
+        assert!(text.trim_end().bytes().all(|b| b.is_ascii_graphic()));
+        assert_eq!(
+            detect_format(&encrypted).unwrap(),
+            Some(EncFormat::Standard)
+        );
+        assert_eq!(
+            file_password_hint(&encrypted).unwrap().as_deref(),
+            Some("いつもの")
+        );
+        assert_eq!(inspect_file(&encrypted).unwrap().encoding, Some(format));
+
+        // 復号化は設定にかかわらず形式を判別する
+        decrypt_file_standard(&encrypted, &decrypted, "password", &fast_config()).unwrap();
+        assert_eq!(
+            fs::read_to_string(&decrypted).unwrap(),
+            "テキストで保存する暗号文"
+        );
+
+        // 文字列の暗号文と同じ形式のため、文字列として復号化できる
+        assert_eq!(
+            decrypt_from_text(text.trim_end(), "password", &config).unwrap(),
+            "テキストで保存する暗号文".as_bytes()
+        );
+    }
+}
+
+#[test]
+fn encoded_envelope_and_rotation() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    let encrypted = dir.path().join("data.bin.enc");
+    let decrypted = dir.path().join("data.out");
+    fs::write(&input, [0u8, 1, 2, 255]).unwrap();
+    let config = Config {
+        file_format: Some(OutputFormat::Base64),
+        ..fast_config()
+    };
+
+    encrypt_file_for_passwords(&input, &encrypted, &["alice", "bob"], &config).unwrap();
+    // 複数パスワードのファイルは、スロットを差し替えても他のパスワードで復号化できる
+    rotate_file(&encrypted, "alice", "carol", &config).unwrap();
+    assert!(fs::read(&encrypted).unwrap().is_ascii());
+    for password in ["bob", "carol"] {
+        decrypt_file_standard(&encrypted, &decrypted, password, &config).unwrap();
+        assert_eq!(fs::read(&decrypted).unwrap(), [0u8, 1, 2, 255]);
+    }
+
+    // バイナリの設定で再暗号化するとバイナリに戻る
+    rotate_file(&encrypted, "bob", "dave", &fast_config()).unwrap();
+    assert!(!fs::read(&encrypted).unwrap().is_ascii());
+    decrypt_file_standard(&encrypted, &decrypted, "dave", &fast_config()).unwrap();
+    assert_eq!(fs::read(&decrypted).unwrap(), [0u8, 1, 2, 255]);
+}
+
+#[test]
+fn file_format_config_key() {
+    let mut config = Config::default();
+    assert_eq!(config.get_value("file_format").unwrap(), "binary");
+
+    config.set_value("file_format", "hex").unwrap();
+    assert_eq!(config.file_format, Some(OutputFormat::Hex));
+    assert_eq!(config.get_value("file_format").unwrap(), "hex");
+
+    config.set_value("file_format", "binary").unwrap();
+    assert_eq!(config.file_format, None);
+    assert!(config.set_value("file_format", "armor").is_err());
+}
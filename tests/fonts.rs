@@ -0,0 +1,39 @@
+#![cfg(feature = "gui")]
+
+//! GUI用の日本語フォントの読み込み
+
+use encript_tool::fonts::{japanese_font_definitions, EMBEDDED_JAPANESE_FONT, JAPANESE_FONT};
+use std::fs;
+
+#[test]
+fn falls_back_to_embedded_font() {
+    let dir = tempfile::tempdir().unwrap();
+    let (fonts, found) = japanese_font_definitions([dir.path().join("missing.ttf")]);
+    assert!(!found);
+    assert_eq!(
+        &*fonts.font_data[JAPANESE_FONT].font,
+        EMBEDDED_JAPANESE_FONT
+    );
+
+    // 登録したフォントで日本語を表示できる
+    let ctx = egui::Context::default();
+    ctx.set_fonts(fonts);
+    let _ = ctx.run(Default::default(), |_| {});
+    assert!(ctx.fonts(|fonts| {
+        fonts.has_glyphs(
+            &egui::FontId::proportional(14.0),
+            "ファイルを暗号化・復号化",
+        )
+    }));
+}
+
+#[test]
+fn prefers_first_readable_candidate() {
+    let dir = tempfile::tempdir().unwrap();
+    let font = dir.path().join("font.otf");
+    fs::write(&font, EMBEDDED_JAPANESE_FONT).unwrap();
+
+    let (fonts, found) = japanese_font_definitions([dir.path().join("missing.ttf"), font]);
+    assert!(found);
+    assert!(fonts.families[&egui::FontFamily::Proportional][0] == JAPANESE_FONT);
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    armor_file, decrypt_file_auto, decrypt_file_standard, decrypt_file_streaming, detect_format,
+    encrypt_file_for_passwords, encrypt_file_standard, encrypt_file_streaming, is_streaming_file,
+    Config, CryptoError, EncFormat,
+};
+use std::fs;
+use std::path::Path;
+
+/// 暗号化されていないファイル（JPEGのような内容）を作成
+fn write_photo(path: &Path) {
+    let mut data = vec![0xff, 0xd8, 0xff, 0xe0];
+    data.extend((0..1000u32).map(|i| (i % 251) as u8));
+    fs::write(path, &data).unwrap();
+}
+
+#[test]
+#[cfg(not(feature = "legacy-compat"))]
+fn rejects_plain_file_before_key_derivation() {
+    let dir = tempfile::tempdir().unwrap();
+    let photo = dir.path().join("photo.jpg");
+    let output = dir.path().join("photo.out");
+    write_photo(&photo);
+
+    // デフォルト設定（重いArgon2）でもキー導出を行わずに拒否される
+    let err = decrypt_file_standard(&photo, &output, "password", &Config::default()).unwrap_err();
+    assert!(matches!(err, CryptoError::NotEncrypted));
+    assert_eq!(
+        err.to_string(),
+        "このファイルは mycrypt で暗号化されていません"
+    );
+}
+
+#[test]
+fn streaming_rejects_plain_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let photo = dir.path().join("photo.jpg");
+    let output = dir.path().join("photo.out");
+    write_photo(&photo);
+
+    let err = decrypt_file_streaming(&photo, &output, "password", &Config::default()).unwrap_err();
+    assert!(matches!(err, CryptoError::NotEncrypted));
+}
+
+#[test]
+fn reports_mismatched_mode_as_invalid_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let standard = dir.path().join("standard.enc");
+    let streaming = dir.path().join("streaming.enc");
+    let output = dir.path().join("plain.out");
+    fs::write(&input, vec![b'a'; 1000]).unwrap();
+
+    let config = fast_config();
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+
+    let err = decrypt_file_streaming(&standard, &output, "password", &config).unwrap_err();
+    assert!(matches!(err, CryptoError::InvalidFormat(_)));
+    let err = decrypt_file_standard(&streaming, &output, "password", &config).unwrap_err();
+    assert!(matches!(err, CryptoError::InvalidFormat(_)));
+}
+
+#[test]
+fn auto_detects_standard_and_streaming() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let standard = dir.path().join("standard.enc");
+    let streaming = dir.path().join("streaming.enc");
+    fs::write(&input, vec![b'a'; 1000]).unwrap();
+
+    let config = fast_config();
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+    assert!(!is_streaming_file(&standard).unwrap());
+    assert!(is_streaming_file(&streaming).unwrap());
+
+    for encrypted in [&standard, &streaming] {
+        let output = dir.path().join("plain.out");
+        decrypt_file_auto(encrypted, &output, "password", &config).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), vec![b'a'; 1000]);
+    }
+}
+
+#[test]
+fn auto_treats_short_file_as_standard() {
+    let dir = tempfile::tempdir().unwrap();
+    let short = dir.path().join("short.enc");
+    fs::write(&short, b"GCM").unwrap();
+
+    assert!(!is_streaming_file(&short).unwrap());
+    assert!(decrypt_file_auto(&short, &dir.path().join("x"), "password", &fast_config()).is_err());
+}
+
+#[test]
+fn detect_format_reads_only_the_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let standard = dir.path().join("standard.enc");
+    let armored = dir.path().join("armored.enc");
+    let envelope = dir.path().join("envelope.enc");
+    let streaming = dir.path().join("streaming.enc");
+    fs::write(&input, b"hello").unwrap();
+
+    let config = fast_config();
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_standard(&input, &armored, "password", &config).unwrap();
+    armor_file(&armored).unwrap();
+    encrypt_file_for_passwords(&input, &envelope, &["one", "two"], &config).unwrap();
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+
+    for path in [&standard, &armored, &envelope] {
+        assert_eq!(detect_format(path).unwrap(), Some(EncFormat::Standard));
+    }
+    assert_eq!(
+        detect_format(&streaming).unwrap(),
+        Some(EncFormat::Streaming)
+    );
+    assert_eq!(detect_format(&input).unwrap(), None);
+
+    // マジックナンバーより短いファイル・空のファイル
+    let photo = dir.path().join("photo.jpg");
+    write_photo(&photo);
+    for (name, data) in [("short", &b"MYCRY"[..]), ("empty", &b""[..])] {
+        let path = dir.path().join(name);
+        fs::write(&path, data).unwrap();
+        assert_eq!(detect_format(&path).unwrap(), None);
+    }
+    assert_eq!(detect_format(&photo).unwrap(), None);
+    assert!(detect_format(&dir.path().join("missing")).is_err());
+}
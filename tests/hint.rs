@@ -0,0 +1,92 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    armor, decrypt_bytes, decrypt_string, encrypt_bytes, encrypt_file_standard,
+    encrypt_file_streaming, encrypt_string, file_password_hint, password_hint,
+    string_password_hint, Config, CryptoError,
+};
+use std::fs;
+
+fn hinted_config(hint: &str) -> Config {
+    Config {
+        hint: Some(hint.to_string()),
+        ..fast_config()
+    }
+}
+
+#[test]
+fn hint_is_readable_without_password() {
+    let config = hinted_config("仕事用PC 2024");
+    let encrypted = encrypt_bytes(b"secret", "password", &config).unwrap();
+
+    assert_eq!(
+        password_hint(&encrypted).unwrap().as_deref(),
+        Some("仕事用PC 2024")
+    );
+    assert_eq!(
+        password_hint(armor(&encrypted).unwrap().as_bytes())
+            .unwrap()
+            .as_deref(),
+        Some("仕事用PC 2024")
+    );
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &config).unwrap(),
+        b"secret"
+    );
+
+    // ヒントのないデータ
+    let plain = encrypt_bytes(b"secret", "password", &fast_config()).unwrap();
+    assert_eq!(password_hint(&plain).unwrap(), None);
+    assert_eq!(password_hint(b"not encrypted").unwrap(), None);
+}
+
+#[test]
+fn string_and_file_hints() {
+    let config = hinted_config("work laptop");
+    let text = encrypt_string("secret", "password", &config).unwrap();
+    assert_eq!(string_password_hint(&text).as_deref(), Some("work laptop"));
+    assert!(matches!(
+        decrypt_string(&text, "wrong", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let encrypted = dir.path().join("plain.txt.enc");
+    fs::write(&input, vec![b'x'; 4096]).unwrap();
+    encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+    assert_eq!(
+        file_password_hint(&encrypted).unwrap().as_deref(),
+        Some("work laptop")
+    );
+
+    // ストリーミング形式にはヒントを記録できない
+    assert!(matches!(
+        encrypt_file_streaming(&input, &encrypted, "password", &config),
+        Err(CryptoError::InvalidInput(_))
+    ));
+}
+
+#[test]
+fn tampered_hint_fails_authentication() {
+    let config = hinted_config("laptop");
+    let mut encrypted = encrypt_bytes(b"secret", "password", &config).unwrap();
+
+    let position = encrypted
+        .windows(6)
+        .position(|window| window == b"laptop")
+        .unwrap();
+    encrypted[position] = b'L';
+
+    assert_eq!(
+        password_hint(&encrypted).unwrap().as_deref(),
+        Some("Laptop")
+    );
+    assert!(matches!(
+        decrypt_bytes(&encrypted, "password", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{write_in_place, CryptoError, FileBatch};
+use std::fs;
+
+#[test]
+fn in_place_round_trip_replaces_the_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("secret.txt");
+    fs::write(&path, b"in place").unwrap();
+
+    let config = fast_config();
+    let batch = FileBatch::new("password", &config);
+    for streaming in [false, true] {
+        write_in_place(&path, |temp| batch.encrypt_file(&path, temp, streaming)).unwrap();
+        assert_ne!(fs::read(&path).unwrap(), b"in place");
+
+        write_in_place(&path, |temp| batch.decrypt_file(&path, temp, streaming)).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"in place");
+    }
+
+    // 一時ファイルは残らない
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn failed_in_place_decrypt_keeps_the_original() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("secret.txt");
+    fs::write(&path, b"in place").unwrap();
+
+    let config = fast_config();
+    FileBatch::new("password", &config)
+        .encrypt_file(&path, &path.with_extension("enc"), false)
+        .unwrap();
+    fs::rename(path.with_extension("enc"), &path).unwrap();
+    let encrypted = fs::read(&path).unwrap();
+
+    let wrong = FileBatch::new("wrong", &config);
+    let result = write_in_place(&path, |temp| wrong.decrypt_file(&path, temp, false));
+    assert!(matches!(result, Err(CryptoError::WrongPasswordOrCorrupt)));
+    assert_eq!(fs::read(&path).unwrap(), encrypted);
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+}
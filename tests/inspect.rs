@@ -0,0 +1,109 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    armor, base64_decode, encrypt_file_for_passwords, encrypt_file_standard,
+    encrypt_file_streaming, encrypt_string, inspect_bytes, inspect_file, Config, CryptoError,
+    FormatInfo, KdfParams,
+};
+use std::fs;
+
+#[test]
+fn standard_header_is_readable_without_password() {
+    let config = Config {
+        hint: Some("laptop".to_string()),
+        pad_block: Some(256),
+        ..fast_config()
+    };
+    let encrypted = base64_decode(&encrypt_string("secret", "password", &config).unwrap()).unwrap();
+
+    let info = inspect_bytes(&encrypted).unwrap();
+    assert!(!info.armored);
+    assert_eq!(info.size, encrypted.len() as u64);
+    assert_eq!(info.kdf, KdfParams::from_config(&config));
+    assert!(info.salt.is_some());
+    assert_eq!(
+        info.format,
+        FormatInfo::Standard {
+            version: 3,
+            padded: true,
+            hint: Some("laptop".to_string()),
+            checksum: false,
+            path: None,
+            compressed: None,
+            ciphertext_len: (encrypted.len() - info.header_len - 12) as u64,
+        }
+    );
+
+    // アーマー形式でも同じ内容が読める
+    let armored = inspect_bytes(armor(&encrypted).unwrap().as_bytes()).unwrap();
+    assert!(armored.armored);
+    assert_eq!(armored.format, info.format);
+
+    assert!(matches!(
+        inspect_bytes(b"not encrypted"),
+        Err(CryptoError::NotEncrypted)
+    ));
+}
+
+#[test]
+fn streaming_chunks_are_counted() {
+    let config = Config {
+        stream_chunk_size: 64 * 1024,
+        ..fast_config()
+    };
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    let encrypted = dir.path().join("data.bin.enc");
+    fs::write(&input, vec![7u8; 64 * 1024 * 3 + 100]).unwrap();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+
+    let info = inspect_file(&encrypted).unwrap();
+    assert_eq!(
+        info.format,
+        FormatInfo::Streaming {
+            chunk_size: 64 * 1024,
+            chunk_count: 4,
+            complete: true,
+            volumes: None,
+        }
+    );
+
+    // フッターが欠けたファイル
+    let data = fs::read(&encrypted).unwrap();
+    fs::write(&encrypted, &data[..data.len() - 10]).unwrap();
+    assert!(matches!(
+        inspect_file(&encrypted).unwrap().format,
+        FormatInfo::Streaming {
+            complete: false,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn file_formats_are_detected() {
+    let config = fast_config();
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let standard = dir.path().join("standard.enc");
+    let envelope = dir.path().join("envelope.enc");
+    fs::write(&input, b"hello").unwrap();
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_for_passwords(&input, &envelope, &["one", "two", "three"], &config).unwrap();
+
+    let info = inspect_file(&standard).unwrap();
+    assert!(matches!(info.format, FormatInfo::Standard { .. }));
+    assert!(info.metadata.is_some());
+
+    let info = inspect_file(&envelope).unwrap();
+    assert_eq!(info.format, FormatInfo::Envelope { slots: 3 });
+    assert_eq!(info.salt, None);
+
+    assert!(matches!(
+        inspect_file(&input),
+        Err(CryptoError::NotEncrypted)
+    ));
+}
@@ -0,0 +1,198 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_bytes, decrypt_file_streaming, derive_key_with_argon2, encrypt_bytes,
+    encrypt_file_streaming, last_kdf_duration, with_kdf_observer, Argon2Config, Argon2Variant,
+    Config, CryptoError, KdfAlgorithm, KdfEvent, KdfParams, Pbkdf2Config, ScryptConfig,
+};
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+fn config_for(kdf: KdfAlgorithm) -> Config {
+    Config {
+        kdf,
+        pbkdf2: Pbkdf2Config { iterations: 1000 },
+        scrypt: ScryptConfig {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        },
+        ..fast_config()
+    }
+}
+
+fn assert_bytes_round_trip(kdf: KdfAlgorithm) {
+    let config = config_for(kdf);
+    let encrypted = encrypt_bytes(b"kdf round trip", "password", &config).unwrap();
+
+    // キー導出はヘッダーの記録に従うため、復号化側の設定が異なっても復号化できる
+    let other = config_for(match kdf {
+        KdfAlgorithm::Argon2id => KdfAlgorithm::Pbkdf2,
+        KdfAlgorithm::Pbkdf2 => KdfAlgorithm::Scrypt,
+        KdfAlgorithm::Scrypt => KdfAlgorithm::Argon2id,
+    });
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &other).unwrap(),
+        b"kdf round trip"
+    );
+    assert!(decrypt_bytes(&encrypted, "wrong", &config).is_err());
+}
+
+fn assert_streaming_round_trip(kdf: KdfAlgorithm) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.bin");
+    let encrypted = dir.path().join("plain.bin.enc");
+    let output = dir.path().join("plain.out");
+    let data: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(&input, &data).unwrap();
+
+    let config = config_for(kdf);
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+    decrypt_file_streaming(&encrypted, &output, "password", &Config::default()).unwrap();
+
+    assert_eq!(fs::read(&output).unwrap(), data);
+}
+
+#[test]
+fn argon2id_round_trip() {
+    assert_bytes_round_trip(KdfAlgorithm::Argon2id);
+    assert_streaming_round_trip(KdfAlgorithm::Argon2id);
+}
+
+#[test]
+fn pbkdf2_round_trip() {
+    assert_bytes_round_trip(KdfAlgorithm::Pbkdf2);
+    assert_streaming_round_trip(KdfAlgorithm::Pbkdf2);
+}
+
+#[test]
+fn scrypt_round_trip() {
+    assert_bytes_round_trip(KdfAlgorithm::Scrypt);
+    assert_streaming_round_trip(KdfAlgorithm::Scrypt);
+}
+
+#[test]
+fn argon2_variants_round_trip() {
+    for (variant, id) in [
+        (Argon2Variant::Argon2id, 1),
+        (Argon2Variant::Argon2i, 4),
+        (Argon2Variant::Argon2d, 5),
+    ] {
+        let mut config = fast_config();
+        config.argon2.variant = variant;
+        let encrypted = encrypt_bytes(b"variant", "password", &config).unwrap();
+
+        // 種類はヘッダーの識別子（マジックナンバー・バージョン・フラグの後ろ）に記録される
+        assert_eq!(encrypted[9], id, "{variant}");
+        // 復号化側の設定の種類が異なっても、ヘッダーの記録に従って復号化できる
+        assert_eq!(
+            decrypt_bytes(&encrypted, "password", &fast_config()).unwrap(),
+            b"variant"
+        );
+        assert!(decrypt_bytes(&encrypted, "wrong", &config).is_err());
+        // キー導出の通知も、設定ではなくヘッダーに記録された種類になる
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&events);
+        with_kdf_observer(
+            move |event| recorded.lock().unwrap().push(event),
+            || decrypt_bytes(&encrypted, "password", &fast_config()).unwrap(),
+        );
+        assert!(
+            matches!(
+                events.lock().unwrap()[0],
+                KdfEvent::Started(params) if params.name() == variant.to_string()
+            ),
+            "{variant}"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("plain.bin");
+        let stream = dir.path().join("plain.bin.enc");
+        let output = dir.path().join("plain.out");
+        fs::write(&input, b"streaming variant").unwrap();
+        encrypt_file_streaming(&input, &stream, "password", &config).unwrap();
+        decrypt_file_streaming(&stream, &output, "password", &fast_config()).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), b"streaming variant");
+
+        assert_eq!(
+            KdfParams::from_config(&config).to_string(),
+            format!("{variant} (m=1024 KB, t=1, p=1)")
+        );
+    }
+}
+
+#[test]
+fn argon2_variants_derive_different_keys() {
+    let salt = [7u8; 16];
+    let keys: Vec<[u8; 32]> = [
+        Argon2Variant::Argon2id,
+        Argon2Variant::Argon2i,
+        Argon2Variant::Argon2d,
+    ]
+    .into_iter()
+    .map(|variant| {
+        let config = Argon2Config {
+            variant,
+            ..fast_config().argon2
+        };
+        derive_key_with_argon2("password", &salt, &config).unwrap()
+    })
+    .collect();
+
+    assert_ne!(keys[0], keys[1]);
+    assert_ne!(keys[0], keys[2]);
+    assert_ne!(keys[1], keys[2]);
+}
+
+#[test]
+fn rejects_header_requiring_more_memory_than_available() {
+    if encript_tool::key_derivation::available_memory().is_none() {
+        return;
+    }
+
+    // ヘッダーのArgon2のメモリ使用量を約4TBに書き換える（マジックナンバー・バージョン・フラグ・識別子の後ろ）
+    let mut encrypted = encrypt_bytes(b"data", "password", &fast_config()).unwrap();
+    encrypted[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let err = decrypt_bytes(&encrypted, "password", &fast_config()).unwrap_err();
+    assert!(matches!(err, CryptoError::KeyDerivation(_)));
+    assert!(err.to_string().contains("利用可能なメモリ"), "{err}");
+}
+
+#[test]
+fn last_kdf_duration_is_recorded_per_thread() {
+    std::thread::spawn(|| {
+        assert_eq!(last_kdf_duration(), None);
+        derive_key_with_argon2("password", &[0; 16], &fast_config().argon2).unwrap();
+        assert!(last_kdf_duration().is_some());
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn kdf_observer_reports_start_and_finish() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let config = config_for(KdfAlgorithm::Scrypt);
+
+    with_kdf_observer(
+        move |event| recorded.lock().unwrap().push(event),
+        || encrypt_bytes(b"data", "password", &config).unwrap(),
+    );
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            KdfEvent::Started(KdfParams::from_config(&config)),
+            KdfEvent::Finished(KdfParams::from_config(&config))
+        ]
+    );
+
+    // 登録は `run` の間だけ有効
+    encrypt_bytes(b"data", "password", &fast_config()).unwrap();
+    assert_eq!(events.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "std-fs")]
+
+//! 一括処理で失敗したファイルがあっても残りを処理する（`--keep-going`）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use encript_tool::encrypt_file_standard;
+use std::{fs, path::Path};
+
+/// a・c は正しく暗号化し、b は暗号化されていないファイルにする
+fn setup(dir: &Path) {
+    save_config(&fast_config(), &dir.join("config.toml")).unwrap();
+    for name in ["a", "c"] {
+        let input = dir.join(format!("{name}.txt"));
+        fs::write(&input, name).unwrap();
+        encrypt_file_standard(
+            &input,
+            &dir.join(format!("{name}.txt.enc")),
+            "password",
+            &fast_config(),
+        )
+        .unwrap();
+        fs::remove_file(input).unwrap();
+    }
+    fs::write(dir.join("b.txt.enc"), "not encrypted").unwrap();
+}
+
+fn decrypt_all(dir: &Path, extra: &[&str]) -> std::process::Output {
+    cli(&dir.join("config.toml"))
+        .current_dir(dir)
+        .args(["decrypt-file", "a.txt.enc", "b.txt.enc", "c.txt.enc"])
+        .args(["-p", "password", "--threads", "1"])
+        .args(extra)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn stops_at_first_failure_by_default() {
+    let dir = tempfile::tempdir().unwrap();
+    setup(dir.path());
+
+    let output = decrypt_all(dir.path(), &[]);
+    assert!(!output.status.success());
+    assert!(dir.path().join("a.txt").exists());
+    assert!(!dir.path().join("c.txt").exists());
+}
+
+#[test]
+fn keep_going_processes_remaining_files() {
+    let dir = tempfile::tempdir().unwrap();
+    setup(dir.path());
+
+    let output = decrypt_all(dir.path(), &["--keep-going"]);
+    // 失敗したファイルのエラーに応じた終了コード（暗号化されていない入力）
+    assert_eq!(output.status.code(), Some(4));
+    assert_eq!(fs::read_to_string(dir.path().join("a.txt")).unwrap(), "a");
+    assert_eq!(fs::read_to_string(dir.path().join("c.txt")).unwrap(), "c");
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("2 件成功, 1 件失敗"));
+    assert!(stderr.contains("b.txt.enc"));
+}
+
+#[test]
+fn keep_going_reports_failures_as_json() {
+    let dir = tempfile::tempdir().unwrap();
+    setup(dir.path());
+
+    let output = decrypt_all(dir.path(), &["--keep-going", "--json"]);
+    assert!(!output.status.success());
+    let lines: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let failed: Vec<_> = lines
+        .iter()
+        .filter(|line| line["ok"] == false && line.get("input").is_some())
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0]["input"], "b.txt.enc");
+    assert_eq!(failed[0]["operation"], "decrypt_file");
+}
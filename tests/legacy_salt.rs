@@ -0,0 +1,78 @@
+#![cfg(feature = "legacy-compat")]
+
+mod common;
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use common::fast_config;
+use encript_tool::key_derivation::generate_key_from_password;
+use encript_tool::{decrypt_bytes, encrypt_bytes, rotate_file, Config, CryptoError};
+use std::fs;
+
+/// ヘッダーのない旧形式（ナンス(12) + 暗号文）で暗号化
+fn encrypt_headerless(data: &[u8], password: &str, config: &Config) -> Vec<u8> {
+    let key = generate_key_from_password(password, config).unwrap();
+    let nonce = [7u8; 12];
+    let ciphertext = Aes256Gcm::new(&key.into())
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .unwrap();
+    [&nonce[..], &ciphertext].concat()
+}
+
+#[test]
+fn headerless_data_requires_legacy_deterministic_salt() {
+    let config = fast_config();
+    let legacy = encrypt_headerless(b"old data", "password", &config);
+
+    assert!(matches!(
+        decrypt_bytes(&legacy, "password", &config),
+        Err(CryptoError::NotEncrypted)
+    ));
+
+    let config = Config {
+        legacy_deterministic_salt: true,
+        ..config
+    };
+    assert_eq!(
+        decrypt_bytes(&legacy, "password", &config).unwrap(),
+        b"old data"
+    );
+
+    // 新しいデータは設定にかかわらずヘッダーのランダムなソルトで暗号化・復号化される
+    let encrypted = encrypt_bytes(b"new data", "password", &config).unwrap();
+    assert_ne!(
+        encrypt_bytes(b"new data", "password", &config).unwrap()[..40],
+        encrypted[..40]
+    );
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &config).unwrap(),
+        b"new data"
+    );
+}
+
+#[test]
+fn rotate_migrates_headerless_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("old.enc");
+    let config = Config {
+        legacy_deterministic_salt: true,
+        ..fast_config()
+    };
+    fs::write(&path, encrypt_headerless(b"old file", "password", &config)).unwrap();
+
+    rotate_file(&path, "password", "password", &config).unwrap();
+
+    // 移行後は設定を無効にしても復号化できる
+    let config = Config {
+        legacy_deterministic_salt: false,
+        ..config
+    };
+    let migrated = fs::read(&path).unwrap();
+    assert!(migrated.starts_with(b"MYCRYPT"));
+    assert_eq!(
+        decrypt_bytes(&migrated, "password", &config).unwrap(),
+        b"old file"
+    );
+}
@@ -0,0 +1,129 @@
+//! 並列のキー導出のメモリ使用量の上限（`KeyCache::with_memory_budget`）
+
+mod common;
+
+use common::fast_config;
+use encript_tool::header::{KdfBlock, KeyCache};
+use encript_tool::{with_kdf_observer, CryptoError, KdfEvent, KdfParams};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::thread;
+
+/// 異なるソルトの `blocks` を並列に導出し、同時に行われたキー導出の最大数を返す
+fn max_concurrent_derivations(keys: &KeyCache, blocks: &[KdfBlock]) -> usize {
+    let running = Arc::new(AtomicUsize::new(0));
+    let peak = Arc::new(AtomicUsize::new(0));
+    thread::scope(|scope| {
+        for block in blocks {
+            let running = Arc::clone(&running);
+            let peak = Arc::clone(&peak);
+            scope.spawn(move || {
+                with_kdf_observer(
+                    move |event| match event {
+                        KdfEvent::Started(_) => {
+                            let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                            peak.fetch_max(now, Ordering::SeqCst);
+                        }
+                        KdfEvent::Finished(_) => {
+                            running.fetch_sub(1, Ordering::SeqCst);
+                        }
+                    },
+                    || keys.derive(block).unwrap(),
+                )
+            });
+        }
+    });
+    peak.load(Ordering::SeqCst)
+}
+
+#[test]
+fn budget_limits_concurrent_derivations() {
+    let config = fast_config();
+    let blocks: Vec<_> = (0..4).map(|_| KdfBlock::new(&config)).collect();
+    let per_derivation = blocks[0].params.memory_bytes();
+
+    // 2回分に満たない上限では、キー導出は1つずつ行われる
+    let keys = KeyCache::new("password").with_memory_budget(per_derivation * 3 / 2);
+    assert_eq!(max_concurrent_derivations(&keys, &blocks), 1);
+
+    // 導出したキーは上限がない場合と同じ
+    let unlimited = KeyCache::new("password");
+    for block in &blocks {
+        assert_eq!(
+            keys.derive(block).unwrap(),
+            unlimited.derive(block).unwrap()
+        );
+    }
+}
+
+#[test]
+fn derivation_larger_than_budget_still_runs() {
+    let config = fast_config();
+    let blocks: Vec<_> = (0..3).map(|_| KdfBlock::new(&config)).collect();
+
+    // 1回で上限を超えるキー導出も、他の導出がなければ単独で行う
+    let keys = KeyCache::new("password").with_memory_budget(1);
+    assert_eq!(max_concurrent_derivations(&keys, &blocks), 1);
+}
+
+#[test]
+fn invalid_header_params_are_rejected_before_budgeting() {
+    // ヘッダーから読み込んだ、メモリ使用量の計算が u64 を超える scrypt のパラメータ
+    let mut block = KdfBlock::new(&fast_config());
+    block.params = KdfParams::Scrypt {
+        log_n: 64,
+        r: u32::MAX,
+        p: 1,
+    };
+    assert_eq!(block.params.memory_bytes(), u64::MAX);
+
+    let keys = KeyCache::new("password").with_memory_budget(1 << 30);
+    assert!(matches!(
+        keys.derive(&block),
+        Err(CryptoError::InvalidFormat(_))
+    ));
+}
+
+#[cfg(feature = "std-fs")]
+#[test]
+fn crafted_scrypt_header_fails_under_max_memory() {
+    use encript_tool::config::{save_config, ScryptConfig};
+    use encript_tool::{encrypt_bytes, Config, KdfAlgorithm};
+    use std::fs;
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        kdf: KdfAlgorithm::Scrypt,
+        scrypt: ScryptConfig {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        },
+        ..fast_config()
+    };
+    let config_path = dir.path().join("config.toml");
+    save_config(&config, &config_path).unwrap();
+
+    // log_n（マジックナンバー・バージョン・フラグ・識別子の後ろ）を 64 に書き換える
+    let mut encrypted = encrypt_bytes(b"data", "password", &config).unwrap();
+    assert_eq!(encrypted[10], 10);
+    encrypted[10] = 64;
+    let input = dir.path().join("crafted.enc");
+    fs::write(&input, &encrypted).unwrap();
+
+    let output = common::cli(&config_path)
+        .args(["--max-memory", "1GB", "decrypt-file", "-p", "password"])
+        .arg(&input)
+        .arg("-o")
+        .arg(dir.path().join("out"))
+        .output()
+        .unwrap();
+    // 不正な形式（終了コード 4）として報告し、パニックしない
+    assert_eq!(output.status.code(), Some(4), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("キー導出のパラメータが不正です"),
+        "{stderr}"
+    );
+}
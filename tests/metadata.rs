@@ -0,0 +1,95 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_file_standard, decrypt_file_streaming, encrypt_file_for_passwords,
+    encrypt_file_standard, encrypt_file_streaming, Config,
+};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// 更新日時を過去に設定した入力ファイルを作成し、その更新日時を返す
+fn write_old_file(path: &Path) -> SystemTime {
+    fs::write(path, b"metadata").unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_600_000_000);
+    fs::File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    modified
+}
+
+fn modified(path: &Path) -> SystemTime {
+    fs::metadata(path).unwrap().modified().unwrap()
+}
+
+fn assert_restored(output: &Path, expected: SystemTime) {
+    assert_eq!(modified(output), expected);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(
+            fs::metadata(output).unwrap().permissions().mode() & 0o777,
+            0o640
+        );
+    }
+}
+
+#[test]
+fn restores_metadata_in_every_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let expected = write_old_file(&input);
+    let config = fast_config();
+
+    let standard = dir.path().join("standard.enc");
+    let output = dir.path().join("standard.out");
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    decrypt_file_standard(&standard, &output, "password", &config).unwrap();
+    assert_restored(&output, expected);
+
+    let streaming = dir.path().join("streaming.enc");
+    let output = dir.path().join("streaming.out");
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+    decrypt_file_streaming(&streaming, &output, "password", &config).unwrap();
+    assert_restored(&output, expected);
+
+    let envelope = dir.path().join("envelope.enc");
+    let output = dir.path().join("envelope.out");
+    encrypt_file_for_passwords(&input, &envelope, &["one", "two"], &config).unwrap();
+    decrypt_file_standard(&envelope, &output, "two", &config).unwrap();
+    assert_restored(&output, expected);
+}
+
+#[test]
+fn opt_out_skips_metadata() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let encrypted = dir.path().join("plain.enc");
+    let output = dir.path().join("plain.out");
+    let original = write_old_file(&input);
+
+    // 暗号化時に記録しなければ、復号化時に保持を有効にしても復元されない
+    let config = Config {
+        preserve_metadata: false,
+        ..fast_config()
+    };
+    encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+    decrypt_file_standard(&encrypted, &output, "password", &fast_config()).unwrap();
+    assert_ne!(modified(&output), original);
+
+    // 記録されていても、復号化時に無効なら適用しない
+    encrypt_file_standard(&input, &encrypted, "password", &fast_config()).unwrap();
+    decrypt_file_standard(&encrypted, &output, "password", &config).unwrap();
+    assert_ne!(modified(&output), original);
+}
@@ -0,0 +1,116 @@
+#![cfg(feature = "std-fs")]
+
+//! 出力先のロック（同じ出力先に書き込む複数のプロセスの排他）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use encript_tool::{with_output_lock, CryptoError};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+/// `with_output_lock` がロックするロックファイル
+fn lock_file(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".lock");
+    PathBuf::from(name)
+}
+
+#[test]
+fn locked_output_is_rejected_without_writing() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("out.enc");
+    fs::write(&output, b"in progress").unwrap();
+
+    // 他のプロセスの代わりに、別に開いたロックファイルでロックを保持する
+    let holder = File::create(lock_file(&output)).unwrap();
+    holder.lock().unwrap();
+
+    let mut ran = false;
+    let result = with_output_lock(&output, || -> Result<(), CryptoError> {
+        ran = true;
+        Ok(())
+    });
+    match result {
+        Err(CryptoError::Locked(path)) => assert_eq!(path, output),
+        other => panic!("{other:?}"),
+    }
+    assert!(!ran);
+    assert_eq!(fs::read(&output).unwrap(), b"in progress");
+
+    // 解放された後は処理できる
+    drop(holder);
+    with_output_lock(&output, || {
+        fs::write(&output, b"done").map_err(|_| CryptoError::Encryption)
+    })
+    .unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"done");
+}
+
+#[test]
+fn lock_is_released_after_completion_or_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("out.enc");
+
+    // 終了後はロックファイルを残さず、出力先も作成しない
+    with_output_lock(&output, || Ok::<_, CryptoError>(())).unwrap();
+    assert!(!lock_file(&output).exists());
+    assert!(!output.exists());
+
+    let result = with_output_lock(&output, || {
+        Err::<(), _>(CryptoError::WrongPasswordOrCorrupt)
+    });
+    assert!(matches!(result, Err(CryptoError::WrongPasswordOrCorrupt)));
+    assert!(!lock_file(&output).exists());
+    with_output_lock(&output, || Ok::<_, CryptoError>(())).unwrap();
+}
+
+#[test]
+fn lock_survives_replacing_the_output() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = dir.path().join("out.enc");
+    fs::write(&output, b"old").unwrap();
+
+    with_output_lock(&output, || {
+        // 一時ファイルからの置き換えで出力先が別のファイルになっても、ロックは保持される
+        let temp = dir.path().join("out.enc.tmp");
+        fs::write(&temp, b"new").unwrap();
+        fs::rename(&temp, &output).unwrap();
+
+        let result = with_output_lock(&output, || Ok::<_, CryptoError>(()));
+        assert!(matches!(result, Err(CryptoError::Locked(_))), "{result:?}");
+        Ok::<_, CryptoError>(())
+    })
+    .unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"new");
+}
+
+#[test]
+fn cli_reports_output_locked_by_another_process() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    let input = dir.path().join("data.txt");
+    fs::write(&input, b"data").unwrap();
+    let output = dir.path().join("data.enc");
+    fs::write(&output, b"in progress").unwrap();
+
+    let holder = File::create(lock_file(&output)).unwrap();
+    holder.lock().unwrap();
+
+    let result = cli(&config)
+        .args(["--allow-weak-password", "encrypt-file", "-p", "password"])
+        .arg(&input)
+        .arg("-o")
+        .arg(&output)
+        .output()
+        .unwrap();
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(
+        stderr.contains("他のプロセスがこのファイルを処理中です"),
+        "{stderr}"
+    );
+    assert_eq!(fs::read(&output).unwrap(), b"in progress");
+}
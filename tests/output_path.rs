@@ -0,0 +1,313 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use encript_tool::file_ops::{determine_output_path, determine_output_path_excluding};
+use encript_tool::{decrypt_file_auto, Config};
+use std::collections::HashSet;
+use std::fs;
+
+#[test]
+fn uses_natural_name_without_collision() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("report.txt");
+
+    assert_eq!(
+        determine_output_path(&input, &None, &Config::default(), true, false).unwrap(),
+        dir.path().join("report.txt.enc")
+    );
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("report.txt.enc"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("report.txt")
+    );
+}
+
+#[test]
+fn numbers_name_on_single_collision() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("report.txt");
+    fs::write(dir.path().join("report.txt.enc"), b"").unwrap();
+
+    assert_eq!(
+        determine_output_path(&input, &None, &Config::default(), true, false).unwrap(),
+        dir.path().join("report.txt (1).enc")
+    );
+
+    // 復号化では拡張子を除いた名前に番号を付ける
+    fs::write(&input, b"").unwrap();
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("report.txt.enc"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("report (1).txt")
+    );
+}
+
+#[test]
+fn numbers_name_on_multiple_collisions() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data");
+    for name in ["data.enc", "data (1).enc", "data (2).enc"] {
+        fs::write(dir.path().join(name), b"").unwrap();
+    }
+
+    assert_eq!(
+        determine_output_path(&input, &None, &Config::default(), true, false).unwrap(),
+        dir.path().join("data (3).enc")
+    );
+
+    // 拡張子のない名前は末尾に番号を付ける
+    for name in ["data", "data (1)"] {
+        fs::write(dir.path().join(name), b"").unwrap();
+    }
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("data.enc"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("data (2)")
+    );
+}
+
+#[test]
+fn skips_names_taken_in_the_same_batch() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("report.txt");
+    fs::write(dir.path().join("report.txt (1).enc"), b"").unwrap();
+
+    // まだ書き込まれていなくても、同じ一括処理で割り当てた名前は使わない
+    let taken = HashSet::from([dir.path().join("report.txt.enc")]);
+    assert_eq!(
+        determine_output_path_excluding(&input, &None, &Config::default(), true, false, &taken)
+            .unwrap(),
+        dir.path().join("report.txt (2).enc")
+    );
+
+    // --force でも割り当て済みの名前は上書きしない
+    assert_eq!(
+        determine_output_path_excluding(&input, &None, &Config::default(), true, true, &taken)
+            .unwrap(),
+        dir.path().join("report.txt (2).enc")
+    );
+}
+
+#[test]
+fn force_and_explicit_output_keep_the_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("report.txt");
+    fs::write(dir.path().join("report.txt.enc"), b"").unwrap();
+
+    assert_eq!(
+        determine_output_path(&input, &None, &Config::default(), true, true).unwrap(),
+        dir.path().join("report.txt.enc")
+    );
+
+    let explicit = Some(dir.path().join("report.txt.enc"));
+    assert_eq!(
+        determine_output_path(&input, &explicit, &Config::default(), true, false).unwrap(),
+        dir.path().join("report.txt.enc")
+    );
+}
+
+#[test]
+fn decrypt_without_extension_appends_dec() {
+    let dir = tempfile::tempdir().unwrap();
+
+    // 拡張子を除去しても入力と同じ名前になる場合は `.dec` を付ける
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("secret"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("secret.dec")
+    );
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join(".env"),
+            &None,
+            &Config::default(),
+            false,
+            true
+        )
+        .unwrap(),
+        dir.path().join(".env.dec")
+    );
+
+    // ドットファイルは先頭の `.` を拡張子として扱わない
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join(".env.enc"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join(".env")
+    );
+    fs::write(dir.path().join(".env"), b"").unwrap();
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join(".env.enc"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join(".env (1)")
+    );
+
+    fs::write(dir.path().join("secret.dec"), b"").unwrap();
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("secret"),
+            &None,
+            &Config::default(),
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("secret (1).dec")
+    );
+}
+
+#[test]
+fn output_dir_collects_auto_named_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("docs").join("report.txt");
+    let output_dir = dir.path().join("encrypted");
+    let config = Config {
+        default_output_dir: Some(output_dir.clone()),
+        ..Config::default()
+    };
+
+    let path = determine_output_path(&input, &None, &config, true, false).unwrap();
+    assert_eq!(path.parent(), Some(output_dir.as_path()));
+    assert_eq!(path.file_name().unwrap(), "report.txt.enc");
+
+    let decrypted = determine_output_path(&path, &None, &config, false, false).unwrap();
+    assert_eq!(decrypted, output_dir.join("report.txt"));
+
+    // 衝突の判定は出力先のディレクトリで行う
+    fs::create_dir(&output_dir).unwrap();
+    fs::write(output_dir.join("report.txt.enc"), b"").unwrap();
+    assert_eq!(
+        determine_output_path(&input, &None, &config, true, false).unwrap(),
+        output_dir.join("report.txt (1).enc")
+    );
+
+    // 明示的な出力先が優先される
+    let explicit = Some(dir.path().join("other.enc"));
+    assert_eq!(
+        determine_output_path(&input, &explicit, &config, true, false).unwrap(),
+        dir.path().join("other.enc")
+    );
+}
+
+#[test]
+fn batch_with_output_dir_numbers_duplicate_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    for (name, data) in [("a", "first"), ("b", "second")] {
+        fs::create_dir(dir.path().join(name)).unwrap();
+        fs::write(dir.path().join(name).join("x.txt"), data).unwrap();
+    }
+
+    // 同じ名前の出力先が重なると、後のファイルが先の出力を上書きし、削除した元ファイルを復元できなくなる
+    let output = cli(&config)
+        .arg("--allow-weak-password")
+        .args([
+            "encrypt-file",
+            "a/x.txt",
+            "b/x.txt",
+            "--output-dir",
+            "out",
+            "--threads",
+            "1",
+            "--delete-original",
+            "-p",
+            "password",
+        ])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    assert!(!dir.path().join("a/x.txt").exists());
+    assert!(!dir.path().join("b/x.txt").exists());
+
+    let out = dir.path().join("out");
+    for (name, data) in [("x.txt.enc", "first"), ("x.txt (1).enc", "second")] {
+        let decrypted = dir.path().join(format!("{name}.out"));
+        decrypt_file_auto(&out.join(name), &decrypted, "password", &fast_config()).unwrap();
+        assert_eq!(fs::read_to_string(&decrypted).unwrap(), data);
+    }
+}
+
+#[test]
+fn custom_extension_is_appended_and_stripped() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        encrypted_extension: "aes".to_string(),
+        ..Config::default()
+    };
+
+    let encrypted =
+        determine_output_path(&dir.path().join("photo.jpg"), &None, &config, true, false).unwrap();
+    assert_eq!(encrypted, dir.path().join("photo.jpg.aes"));
+    assert_eq!(
+        determine_output_path(&encrypted, &None, &config, false, false).unwrap(),
+        dir.path().join("photo.jpg")
+    );
+
+    // 複数の `.` を含む拡張子も丸ごと除く
+    let config = Config {
+        encrypted_extension: "enc.v2".to_string(),
+        ..Config::default()
+    };
+    assert_eq!(
+        determine_output_path(
+            &dir.path().join("notes.txt.enc.v2"),
+            &None,
+            &config,
+            false,
+            false
+        )
+        .unwrap(),
+        dir.path().join("notes.txt")
+    );
+}
+
+#[test]
+fn extension_setting_is_validated() {
+    let mut config = Config::default();
+    assert!(config.set_value("encrypted_extension", ".aes").is_ok());
+    assert_eq!(config.encrypted_extension, "aes");
+
+    for invalid in ["", "a/b", "a\\b"] {
+        assert!(config.set_value("encrypted_extension", invalid).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::fast_config;
+use encript_tool::{base64_decode, decrypt_string, encrypt_string, Config};
+
+const BLOCK: usize = 16;
+
+fn padded_config() -> Config {
+    Config {
+        pad_block: Some(BLOCK),
+        ..fast_config()
+    }
+}
+
+/// 暗号化してバイト長を返し、元の文字列に正確に戻ることを確認
+fn encrypted_len(text: &str) -> usize {
+    let encrypted = encrypt_string(text, "password", &padded_config()).unwrap();
+    // 復号化側の設定にパディングがなくても、ヘッダーの記録に従って取り除かれる
+    assert_eq!(
+        decrypt_string(&encrypted, "password", &fast_config()).unwrap(),
+        text
+    );
+    base64_decode(&encrypted).unwrap().len()
+}
+
+#[test]
+fn empty_string_pads_to_one_block() {
+    let unpadded = base64_decode(&encrypt_string("", "password", &fast_config()).unwrap())
+        .unwrap()
+        .len();
+    assert_eq!(encrypted_len(""), unpadded + BLOCK);
+}
+
+#[test]
+fn one_byte_under_a_block_fills_the_block() {
+    let under = "a".repeat(BLOCK - 1);
+    assert_eq!(encrypted_len(&under), encrypted_len(""));
+    assert_eq!(encrypted_len("1234"), encrypted_len(""));
+}
+
+#[test]
+fn exactly_one_block_adds_a_full_block() {
+    let exact = "a".repeat(BLOCK);
+    assert_eq!(encrypted_len(&exact), encrypted_len("") + BLOCK);
+}
+
+#[test]
+fn trailing_zero_bytes_survive_padding() {
+    let text = "pin\0\0";
+    assert_eq!(encrypted_len(text), encrypted_len(""));
+}
+
+#[test]
+fn rejects_invalid_block_size() {
+    let mut config = fast_config();
+    assert!(config.set_value("pad_block", "1").is_err());
+    assert!(config.set_value("pad_block", "32").is_ok());
+    assert_eq!(config.pad_block, Some(32));
+    assert!(config.set_value("pad_block", "").is_ok());
+    assert_eq!(config.pad_block, None);
+}
@@ -0,0 +1,34 @@
+use encript_tool::config::DEFAULT_MIN_PASSWORD_ENTROPY;
+use encript_tool::{check_password, Config, PasswordIssue};
+
+#[test]
+fn empty_password_is_always_rejected() {
+    assert_eq!(check_password("", 0.0), Err(PasswordIssue::Empty));
+    assert_eq!(
+        check_password("", DEFAULT_MIN_PASSWORD_ENTROPY),
+        Err(PasswordIssue::Empty)
+    );
+}
+
+#[test]
+fn weak_password_is_rejected_below_minimum() {
+    let err = check_password("1", DEFAULT_MIN_PASSWORD_ENTROPY).unwrap_err();
+    assert!(matches!(err, PasswordIssue::Weak { entropy, .. } if entropy < 4.0));
+
+    // 下限が0なら空でない限り受け付ける
+    assert_eq!(check_password("1", 0.0), Ok(()));
+    assert_eq!(
+        check_password("correct horse battery staple", DEFAULT_MIN_PASSWORD_ENTROPY),
+        Ok(())
+    );
+}
+
+#[test]
+fn min_password_entropy_is_configurable() {
+    let mut config = Config::default();
+    assert_eq!(config.min_password_entropy, DEFAULT_MIN_PASSWORD_ENTROPY);
+
+    config.set_value("min_password_entropy", "60").unwrap();
+    assert_eq!(config.get_value("min_password_entropy").unwrap(), "60");
+    assert!(config.set_value("min_password_entropy", "-1").is_err());
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "std-fs")]
+
+//! ペッパー（暗号化データに記録しない、Argon2に混ぜる秘密の値）
+
+mod common;
+
+use common::{cli, fast_config, run};
+use encript_tool::config::save_config;
+use encript_tool::envelope::{decrypt_envelope, encrypt_envelope};
+use encript_tool::{
+    decrypt_bytes, decrypt_to_writer, decrypt_to_writer_with_pepper, encrypt_bytes,
+    encrypt_from_reader, inspect_bytes, Config, CryptoError, KdfAlgorithm, KdfParams, Pepper,
+};
+use std::fs;
+
+fn peppered_config(pepper: &[u8]) -> Config {
+    Config {
+        pepper: Some(Pepper::new(pepper.to_vec()).unwrap()),
+        ..fast_config()
+    }
+}
+
+#[test]
+fn pepper_is_required_to_decrypt() {
+    let config = peppered_config(b"application secret");
+    let encrypted = encrypt_bytes(b"data", "password", &config).unwrap();
+
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &config).unwrap(),
+        b"data"
+    );
+    assert!(matches!(
+        decrypt_bytes(&encrypted, "password", &fast_config()),
+        Err(CryptoError::PepperRequired)
+    ));
+    assert!(matches!(
+        decrypt_bytes(&encrypted, "password", &peppered_config(b"other secret")),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+
+    // ヘッダーにはペッパーを使ったことだけが記録される
+    match inspect_bytes(&encrypted).unwrap().kdf {
+        KdfParams::Argon2 { peppered, .. } => assert!(peppered),
+        other => panic!("{other:?}"),
+    }
+    assert!(!encrypted
+        .windows(b"application secret".len())
+        .any(|window| window == b"application secret"));
+}
+
+#[test]
+fn pepper_is_ignored_for_files_encrypted_without_it() {
+    let encrypted = encrypt_bytes(b"data", "password", &fast_config()).unwrap();
+
+    let config = peppered_config(b"application secret");
+    assert_eq!(
+        decrypt_bytes(&encrypted, "password", &config).unwrap(),
+        b"data"
+    );
+}
+
+#[test]
+fn pepper_applies_to_streaming_and_envelope_formats() {
+    let config = peppered_config(b"application secret");
+
+    let mut streamed = Vec::new();
+    encrypt_from_reader(&mut &b"streamed"[..], &mut streamed, "password", &config).unwrap();
+    assert!(matches!(
+        decrypt_to_writer(&mut streamed.as_slice(), &mut Vec::new(), "password"),
+        Err(CryptoError::PepperRequired)
+    ));
+    let mut decrypted = Vec::new();
+    decrypt_to_writer_with_pepper(
+        &mut streamed.as_slice(),
+        &mut decrypted,
+        "password",
+        config.pepper.as_ref(),
+    )
+    .unwrap();
+    assert_eq!(decrypted, b"streamed");
+
+    let envelope = encrypt_envelope(b"shared", &["alice", "bob"], &config).unwrap();
+    assert_eq!(
+        decrypt_envelope(&envelope, "bob", &config).unwrap(),
+        b"shared"
+    );
+    assert!(matches!(
+        decrypt_envelope(&envelope, "bob", &fast_config()),
+        Err(CryptoError::PepperRequired)
+    ));
+}
+
+#[test]
+fn pepper_is_redacted_and_limited_to_argon2() {
+    let pepper = Pepper::new(b"application secret".to_vec()).unwrap();
+    assert!(!format!("{pepper:?}").contains("secret"));
+    assert!(Pepper::new(Vec::new()).is_err());
+
+    let config = Config {
+        kdf: KdfAlgorithm::Pbkdf2,
+        ..peppered_config(b"application secret")
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn cli_reads_pepper_from_env_or_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    let input = dir.path().join("notes.txt");
+    let encrypted = dir.path().join("notes.txt.enc");
+    let output = dir.path().join("notes.out");
+    fs::write(&input, "ペッパー付きのデータ").unwrap();
+    let pepper_file = dir.path().join("pepper.key");
+    fs::write(&pepper_file, "application secret").unwrap();
+
+    let encrypt = [
+        "encrypt-file",
+        input.to_str().unwrap(),
+        "-o",
+        encrypted.to_str().unwrap(),
+        "-p",
+        "Correct-Horse-Battery-9",
+    ];
+    let result = cli(&config)
+        .args(encrypt)
+        .env("MYCRYPT_PEPPER", "application secret")
+        .output()
+        .unwrap();
+    assert!(result.status.success(), "{result:?}");
+
+    let decrypt = [
+        "decrypt-file",
+        encrypted.to_str().unwrap(),
+        "-o",
+        output.to_str().unwrap(),
+        "-p",
+        "Correct-Horse-Battery-9",
+    ];
+    let result = run(&config, &decrypt);
+    assert_eq!(result.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&result.stderr).contains("ペッパー"));
+
+    // 環境変数と同じ内容のファイルを --pepper-file で指定しても復号化できる
+    let mut with_file = vec!["--pepper-file", pepper_file.to_str().unwrap()];
+    with_file.extend_from_slice(&decrypt);
+    let result = run(&config, &with_file);
+    assert!(result.status.success(), "{result:?}");
+    assert_eq!(fs::read_to_string(&output).unwrap(), "ペッパー付きのデータ");
+}
@@ -0,0 +1,70 @@
+#![cfg(feature = "std-fs")]
+
+//! --progress-json で標準エラー出力に書き込まれる進捗
+
+mod common;
+
+use common::{fast_config, run};
+use encript_tool::config::save_config;
+use encript_tool::Config;
+use std::fs;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+#[test]
+fn streaming_progress_is_reported_as_json_lines() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    let config_value = Config {
+        stream_chunk_size: 4096,
+        ..fast_config()
+    };
+    save_config(&config_value, &config).unwrap();
+    let input = dir.path().join("data.bin");
+    fs::write(&input, vec![7u8; 100_000]).unwrap();
+    let input = input.to_str().unwrap();
+
+    let output = run(
+        &config,
+        &[
+            "encrypt-file",
+            input,
+            "--streaming",
+            "-p",
+            PASSWORD,
+            "--progress-json",
+        ],
+    );
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let events: Vec<serde_json::Value> = stderr
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // 最初と完了時は間隔にかかわらず出力される
+    assert!(events.len() >= 2);
+    assert_eq!(events[0]["done"], 0);
+    let last = events.last().unwrap();
+    assert_eq!(last["done"], 100_000);
+    assert_eq!(last["total"], 100_000);
+
+    // --quiet では出力しない
+    let encrypted = format!("{input}.enc");
+    let decrypted = dir.path().join("data.out");
+    let output = run(
+        &config,
+        &[
+            "decrypt-file",
+            &encrypted,
+            "-o",
+            decrypted.to_str().unwrap(),
+            "-p",
+            PASSWORD,
+            "--progress-json",
+            "--quiet",
+        ],
+    );
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    assert_eq!(fs::read(&decrypted).unwrap(), vec![7u8; 100_000]);
+}
@@ -0,0 +1,45 @@
+#![cfg(feature = "std-fs")]
+
+//! --quiet で完了メッセージを抑える（--json の出力は抑えない）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use std::fs;
+
+#[test]
+fn quiet_suppresses_messages_but_not_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    fs::write(dir.path().join("data.txt"), b"data").unwrap();
+
+    let run = |args: &[&str]| {
+        cli(&config)
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+    let password = "correct horse battery staple";
+
+    let output = run(&["-q", "encrypt-file", "data.txt", "-p", password]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(output.stdout.is_empty());
+
+    let output = run(&[
+        "-q",
+        "--json",
+        "decrypt-file",
+        "data.txt.enc",
+        "-o",
+        "out.txt",
+        "-p",
+        password,
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(json["ok"], true);
+    assert_eq!(fs::read(dir.path().join("out.txt")).unwrap(), b"data");
+}
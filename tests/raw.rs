@@ -0,0 +1,67 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    base64_decode, decrypt_bytes_with_aad, decrypt_file_standard, encrypt_file_standard,
+    encrypt_to_raw, encrypt_to_raw_with_aad, encrypt_to_text, Config, CryptoError,
+};
+use std::fs;
+
+#[test]
+fn raw_round_trip_with_padding_checksum_and_aad() {
+    let config = Config {
+        pad_block: Some(16),
+        checksum: true,
+        ..fast_config()
+    };
+    let encrypted = encrypt_to_raw_with_aad(b"PIN 1234", "password", b"user:1", &config).unwrap();
+
+    assert_eq!(
+        decrypt_bytes_with_aad(&encrypted, "password", b"user:1", &config).unwrap(),
+        b"PIN 1234"
+    );
+    assert!(matches!(
+        decrypt_bytes_with_aad(&encrypted, "password", b"user:2", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
+
+#[test]
+fn raw_output_is_the_decoded_text_format() {
+    let config = fast_config();
+    let raw = encrypt_to_raw(b"hello", "password", &config).unwrap();
+    let text = encrypt_to_text(b"hello", "password", &config).unwrap();
+
+    // ソルトとナンスは毎回異なるが、長さはエンコード前の文字列の暗号文と同じ
+    assert_eq!(raw.len(), base64_decode(&text).unwrap().len());
+    assert!(raw.len() < text.len());
+}
+
+#[test]
+fn raw_output_interoperates_with_file_commands() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+
+    // 文字列として暗号化したバイナリをファイルとして復号化できる
+    let encrypted = dir.path().join("string.enc");
+    let decrypted = dir.path().join("string.txt");
+    fs::write(
+        &encrypted,
+        encrypt_to_raw(b"from string", "password", &config).unwrap(),
+    )
+    .unwrap();
+    decrypt_file_standard(&encrypted, &decrypted, "password", &config).unwrap();
+    assert_eq!(fs::read(&decrypted).unwrap(), b"from string");
+
+    // ファイルとして暗号化したデータを文字列のコマンドと同じ処理で復号化できる
+    let input = dir.path().join("file.txt");
+    let encrypted = dir.path().join("file.txt.enc");
+    fs::write(&input, "from file").unwrap();
+    encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+    assert_eq!(
+        decrypt_bytes_with_aad(&fs::read(&encrypted).unwrap(), "password", b"", &config).unwrap(),
+        b"from file"
+    );
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::envelope::parse_recipients;
+use encript_tool::{decrypt_file_auto, encrypt_file_for_passwords, CryptoError};
+use std::fs;
+
+#[test]
+fn parse_recipients_skips_comments_and_blank_lines() {
+    let text = "# チーム全員\nalice pass\r\n\n#bob は退職\ncarol\t \n";
+
+    assert_eq!(parse_recipients(text), ["alice pass", "carol\t "]);
+    assert!(parse_recipients("# コメントのみ\n\n").is_empty());
+}
+
+#[test]
+fn every_recipient_can_decrypt() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plan.txt");
+    let encrypted = dir.path().join("plan.txt.enc");
+    let decrypted = dir.path().join("plan.out.txt");
+    fs::write(&input, "チームの計画").unwrap();
+    let config = fast_config();
+
+    let recipients = parse_recipients("# 開発チーム\nalice\nbob\n\ncarol\n");
+    let passwords: Vec<&str> = recipients.iter().map(String::as_str).collect();
+    encrypt_file_for_passwords(&input, &encrypted, &passwords, &config).unwrap();
+
+    for password in &passwords {
+        decrypt_file_auto(&encrypted, &decrypted, password, &config).unwrap();
+        assert_eq!(fs::read_to_string(&decrypted).unwrap(), "チームの計画");
+    }
+    assert!(matches!(
+        decrypt_file_auto(&encrypted, &decrypted, "# 開発チーム", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
@@ -0,0 +1,94 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::header::FileHeader;
+use encript_tool::{
+    decrypt_file_standard, file_relative_path, restore_path_into, CryptoError, FileBatch,
+};
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn relative_path_is_restored_into_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("z.txt");
+    let encrypted = dir.path().join("z.txt.enc");
+    fs::write(&input, b"nested").unwrap();
+
+    let config = fast_config();
+    let relative = Path::new("a").join("b").join("z.txt");
+    FileBatch::new("password", &config)
+        .encrypt_file_with_path(&input, &encrypted, &relative)
+        .unwrap();
+    assert_eq!(
+        file_relative_path(&encrypted).unwrap(),
+        Some(relative.clone())
+    );
+
+    let restore = dir.path().join("restore");
+    let output = restore_path_into(&encrypted, &restore, &config, false).unwrap();
+    assert_eq!(output, restore.join(&relative));
+
+    fs::create_dir_all(output.parent().unwrap()).unwrap();
+    decrypt_file_standard(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"nested");
+}
+
+#[test]
+fn file_without_relative_path_is_placed_at_top_level() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.txt");
+    let encrypted = dir.path().join("plain.txt.enc");
+    fs::write(&input, b"flat").unwrap();
+
+    let config = fast_config();
+    FileBatch::new("password", &config)
+        .encrypt_file(&input, &encrypted, false)
+        .unwrap();
+
+    assert_eq!(file_relative_path(&encrypted).unwrap(), None);
+    let restore = dir.path().join("restore");
+    assert_eq!(
+        restore_path_into(&encrypted, &restore, &config, false).unwrap(),
+        restore.join("plain.txt")
+    );
+}
+
+#[test]
+fn rejects_paths_escaping_the_target_directory() {
+    let dir = tempfile::tempdir().unwrap();
+    let encrypted = dir.path().join("evil.enc");
+
+    for stored in ["../evil", "a/../../evil", "/etc/passwd", "a//b", "./a", ""] {
+        // ヘッダーだけを読むため、暗号文は不要
+        let header = FileHeader {
+            path: Some(stored.to_string()),
+            ..FileHeader::new(&fast_config())
+        };
+        let mut data = header.to_bytes();
+        data.extend_from_slice(&[0u8; 28]);
+        fs::write(&encrypted, &data).unwrap();
+
+        let err = file_relative_path(&encrypted).unwrap_err();
+        assert!(matches!(err, CryptoError::InvalidFormat(_)), "{stored}");
+        assert!(restore_path_into(&encrypted, dir.path(), &fast_config(), false).is_err());
+    }
+}
+
+#[test]
+fn rejects_non_relative_paths_when_encrypting() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.txt");
+    fs::write(&input, b"data").unwrap();
+
+    let config = fast_config();
+    let batch = FileBatch::new("password", &config);
+    for relative in ["../data.txt", "/abs/data.txt", ""] {
+        let err = batch
+            .encrypt_file_with_path(&input, &dir.path().join("out.enc"), Path::new(relative))
+            .unwrap_err();
+        assert!(matches!(err, CryptoError::InvalidInput(_)), "{relative}");
+    }
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "std-fs")]
+
+//! 外部で管理するソルト（`--salt-file` / `Config::salt`）での暗号化
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_file_standard, decrypt_file_streaming, encrypt_file_standard, encrypt_file_streaming,
+    inspect_file, read_salt_file, Config, CryptoError,
+};
+use std::fs;
+
+#[test]
+fn external_salt_is_recorded_in_header() {
+    let dir = tempfile::tempdir().unwrap();
+    let salt_path = dir.path().join("user.salt");
+    let salt = *b"0123456789abcdef";
+    fs::write(&salt_path, salt).unwrap();
+    let input = dir.path().join("data.txt");
+    let decrypted = dir.path().join("data.out");
+    fs::write(&input, "外部のソルトで暗号化").unwrap();
+
+    let config = Config {
+        salt: Some(read_salt_file(&salt_path).unwrap()),
+        ..fast_config()
+    };
+    let standard = dir.path().join("standard.enc");
+    let streaming = dir.path().join("streaming.enc");
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+
+    for path in [&standard, &streaming] {
+        assert_eq!(inspect_file(path).unwrap().salt, Some(salt));
+    }
+
+    // ソルトはヘッダーに記録されるため、復号化にはソルトの指定は不要
+    decrypt_file_standard(&standard, &decrypted, "password", &fast_config()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&decrypted).unwrap(),
+        "外部のソルトで暗号化"
+    );
+    decrypt_file_streaming(&streaming, &decrypted, "password", &fast_config()).unwrap();
+    assert_eq!(
+        fs::read_to_string(&decrypted).unwrap(),
+        "外部のソルトで暗号化"
+    );
+
+    // 同じソルトでもナンスはランダムなため、暗号文は毎回異なる
+    let again = dir.path().join("again.enc");
+    encrypt_file_standard(&input, &again, "password", &config).unwrap();
+    assert_ne!(fs::read(&standard).unwrap(), fs::read(&again).unwrap());
+}
+
+#[test]
+fn salt_file_must_be_16_bytes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("short.salt");
+
+    for len in [0, 15, 17, 32] {
+        fs::write(&path, vec![0u8; len]).unwrap();
+        assert!(matches!(
+            read_salt_file(&path),
+            Err(CryptoError::InvalidInput(_))
+        ));
+    }
+    assert!(read_salt_file(&dir.path().join("missing.salt")).is_err());
+}
@@ -0,0 +1,48 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{encrypt_file_standard, encrypt_file_streaming, CryptoError};
+use std::fs;
+
+#[test]
+fn standard_rejects_output_onto_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    fs::write(&input, b"important").unwrap();
+
+    // 相対パス要素を含む同じファイル
+    let output = dir.path().join(".").join("data.bin");
+    let err = encrypt_file_standard(&input, &output, "password", &fast_config()).unwrap_err();
+
+    assert!(matches!(err, CryptoError::InvalidInput(_)));
+    assert_eq!(fs::read(&input).unwrap(), b"important");
+}
+
+#[test]
+fn streaming_rejects_output_onto_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    fs::write(&input, b"important").unwrap();
+
+    let err = encrypt_file_streaming(&input, &input, "password", &fast_config()).unwrap_err();
+
+    assert!(matches!(err, CryptoError::InvalidInput(_)));
+    assert_eq!(fs::read(&input).unwrap(), b"important");
+}
+
+#[cfg(unix)]
+#[test]
+fn rejects_output_symlink_to_input() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("data.bin");
+    let link = dir.path().join("link.bin");
+    fs::write(&input, b"important").unwrap();
+    std::os::unix::fs::symlink(&input, &link).unwrap();
+
+    let err = encrypt_file_streaming(&input, &link, "password", &fast_config()).unwrap_err();
+
+    assert!(matches!(err, CryptoError::InvalidInput(_)));
+    assert_eq!(fs::read(&input).unwrap(), b"important");
+}
@@ -0,0 +1,24 @@
+#![cfg(feature = "std-fs")]
+
+//! selftest サブコマンド
+
+mod common;
+
+use common::run;
+
+#[test]
+fn selftest_reports_passing_checks_as_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let output = run(&dir.path().join("config.toml"), &["selftest", "--json"]);
+    assert!(output.status.success());
+
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["operation"], "selftest");
+    assert_eq!(report["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(report["cipher"], "AES-256-GCM");
+    assert!(report["kdf"].as_str().unwrap().starts_with("argon2id"));
+    let checks = report["checks"].as_array().unwrap();
+    assert_eq!(checks.len(), 4);
+    assert!(checks.iter().all(|check| check["ok"] == true));
+    assert_eq!(report["ok"], true);
+}
@@ -0,0 +1,42 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{decrypt_file_standard, encrypt_file_standard, Config};
+use std::fs;
+
+/// ヘッダー(9 + キー導出ブロック29 + メタデータブロック1) + ナンス(12) + GCMタグ(16)
+const OVERHEAD: usize = 9 + 29 + 1 + 12 + 16;
+
+fn round_trip(data: &[u8]) {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.bin");
+    let encrypted = dir.path().join("plain.bin.enc");
+    let output = dir.path().join("plain.out");
+    fs::write(&input, data).unwrap();
+
+    let config = Config {
+        preserve_metadata: false,
+        ..fast_config()
+    };
+    encrypt_file_standard(&input, &encrypted, "password", &config).unwrap();
+    assert_eq!(
+        fs::metadata(&encrypted).unwrap().len() as usize,
+        data.len() + OVERHEAD
+    );
+
+    decrypt_file_standard(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), data);
+}
+
+#[test]
+fn standard_round_trip() {
+    let data: Vec<u8> = (0..300_000u32).map(|i| (i % 251) as u8).collect();
+    round_trip(&data);
+}
+
+#[test]
+fn standard_round_trip_empty_file() {
+    round_trip(b"");
+}
@@ -0,0 +1,103 @@
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use encript_tool::config::MIN_STREAM_CHUNK_SIZE;
+use encript_tool::{
+    decrypt_to_writer, derive_key_with_pbkdf2, encrypt_from_reader_with_nonce_prefix, Config,
+    KdfAlgorithm, Pbkdf2Config,
+};
+use std::io::Cursor;
+
+const PASSWORD: &str = "correct horse battery staple";
+const SALT: [u8; 16] = [0x11; 16];
+const NONCE_PREFIX: [u8; 8] = [0x22; 8];
+const CHUNK: usize = MIN_STREAM_CHUNK_SIZE;
+
+/// 形式の検証用の設定（PBKDF2・反復回数1000回・最小チャンクサイズ・固定ソルト）
+fn layout_config() -> Config {
+    Config {
+        kdf: KdfAlgorithm::Pbkdf2,
+        pbkdf2: Pbkdf2Config { iterations: 1000 },
+        stream_chunk_size: CHUNK,
+        salt: Some(SALT),
+        ..Config::default()
+    }
+}
+
+fn encrypt(plaintext: &[u8]) -> Vec<u8> {
+    let mut output = Cursor::new(Vec::new());
+    encrypt_from_reader_with_nonce_prefix(
+        &mut Cursor::new(plaintext),
+        &mut output,
+        PASSWORD,
+        &layout_config(),
+        &NONCE_PREFIX,
+    )
+    .unwrap();
+    output.into_inner()
+}
+
+/// 2チャンク半の平文（チャンクごとに異なる値で埋める）
+fn plaintext() -> Vec<u8> {
+    (0..CHUNK * 5 / 2).map(|i| (i / CHUNK) as u8 + 1).collect()
+}
+
+#[test]
+fn header_layout() {
+    let encrypted = encrypt(b"");
+
+    let mut expected = b"GCMSTREAM".to_vec();
+    expected.extend_from_slice(&(CHUNK as u32).to_le_bytes()); // チャンクサイズ
+    expected.extend_from_slice(&[0x02, 0xe8, 0x03, 0x00, 0x00]); // PBKDF2(1000回)
+    expected.extend_from_slice(&SALT);
+    expected.push(0x00); // メタデータなし
+    assert!(encrypted.starts_with(&expected));
+
+    // 空の入力はチャンクを持たず、ヘッダーの直後にフッター（目印 + HMAC）が続く
+    let footer = &encrypted[expected.len()..];
+    assert_eq!(footer.len(), 12 + 32);
+    assert!(footer.starts_with(b"GCMSTREAMEND"));
+}
+
+#[test]
+fn chunk_layout() {
+    let data = plaintext();
+    let encrypted = encrypt(&data);
+    let key = derive_key_with_pbkdf2(PASSWORD, &SALT, 1000);
+    let cipher = Aes256Gcm::new(&key.into());
+
+    // ヘッダー（マジックナンバー9 + チャンクサイズ4 + キー導出ブロック5 + ソルト16 + メタデータ1）を飛ばす
+    let mut rest = &encrypted[9 + 4 + 5 + 16 + 1..];
+    for (counter, plain_chunk) in data.chunks(CHUNK).enumerate() {
+        let (nonce, tail) = rest.split_at(12);
+        assert_eq!(&nonce[..8], NONCE_PREFIX);
+        assert_eq!(nonce[8..], (counter as u32).to_le_bytes());
+
+        let (len, tail) = tail.split_at(4);
+        let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+        assert_eq!(len, plain_chunk.len() + 16);
+
+        let (ciphertext, tail) = tail.split_at(len);
+        assert_eq!(
+            cipher
+                .decrypt(Nonce::from_slice(nonce), ciphertext)
+                .unwrap(),
+            plain_chunk
+        );
+        rest = tail;
+    }
+    assert_eq!(rest.len(), 12 + 32);
+    assert!(rest.starts_with(b"GCMSTREAMEND"));
+}
+
+#[test]
+fn fixed_nonce_prefix_is_deterministic_and_decryptable() {
+    let data = plaintext();
+    let encrypted = encrypt(&data);
+    assert_eq!(encrypted, encrypt(&data));
+
+    let mut decrypted = Vec::new();
+    decrypt_to_writer(&mut Cursor::new(&encrypted), &mut decrypted, PASSWORD).unwrap();
+    assert_eq!(decrypted, data);
+}
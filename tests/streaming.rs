@@ -0,0 +1,306 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_file_streaming, decrypt_file_streaming_with_progress, decrypt_to_writer,
+    encrypt_file_streaming, encrypt_from_reader, inspect_file, Config, CryptoError, DecryptReader,
+    FormatInfo, KdfAlgorithm, Pbkdf2Config,
+};
+use std::fs::{self, File};
+use std::io::{self, Read};
+
+#[test]
+fn streaming_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.bin");
+    let encrypted = dir.path().join("plain.bin.enc");
+    let output = dir.path().join("plain.out");
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(&input, &data).unwrap();
+
+    let config = fast_config();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+    decrypt_file_streaming(&encrypted, &output, "password", &config).unwrap();
+
+    assert_eq!(fs::read(&output).unwrap(), data);
+}
+
+#[test]
+fn streaming_rejects_oversized_chunk_length() {
+    let dir = tempfile::tempdir().unwrap();
+    let crafted = dir.path().join("crafted.enc");
+    let output = dir.path().join("crafted.out");
+
+    // ヘッダー(64KBチャンク、軽量なArgon2id) + ナンス + 4GB近いチャンク長
+    let mut data = b"GCMSTREAM".to_vec();
+    data.extend_from_slice(&(64 * 1024u32).to_le_bytes());
+    data.push(1); // Argon2id
+    for param in [1024u32, 1, 1] {
+        data.extend_from_slice(&param.to_le_bytes());
+    }
+    data.extend_from_slice(&[0u8; 16]); // ソルト
+    data.push(0); // メタデータなし
+    data.extend_from_slice(&[0u8; 12]);
+    data.extend_from_slice(&u32::MAX.to_le_bytes());
+    data.extend_from_slice(&[0u8; 32]);
+    fs::write(&crafted, &data).unwrap();
+
+    let err = decrypt_file_streaming(&crafted, &output, "password", &fast_config()).unwrap_err();
+    assert!(err.to_string().contains("チャンク長が不正です"), "{err}");
+}
+
+#[test]
+fn streaming_detects_truncation_at_chunk_boundary() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.bin");
+    let encrypted = dir.path().join("plain.bin.enc");
+    let output = dir.path().join("plain.out");
+    fs::write(&input, vec![7u8; 200_000]).unwrap();
+
+    let config = fast_config();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+
+    // ヘッダー(13 + Argon2idのキー導出ブロック29 + メタデータブロック17)
+    // + 先頭の1チャンク(ナンス12 + 長さ4 + 64KB + タグ16)だけを残す
+    let data = fs::read(&encrypted).unwrap();
+    let header_len = 13 + 29 + 17;
+    let boundary = header_len + 12 + 4 + 64 * 1024 + 16;
+    let truncated = dir.path().join("truncated.enc");
+    fs::write(&truncated, &data[..boundary]).unwrap();
+    let err = decrypt_file_streaming(&truncated, &output, "password", &config).unwrap_err();
+    assert!(
+        err.to_string().contains("ファイルが途中で切れています"),
+        "{err}"
+    );
+
+    // チャンクを1つ抜いてフッターを残しても、ファイル全体の検証で検出される
+    let mut spliced = data[..header_len].to_vec();
+    spliced.extend_from_slice(&data[boundary..]);
+    let spliced_path = dir.path().join("spliced.enc");
+    fs::write(&spliced_path, &spliced).unwrap();
+    let err = decrypt_file_streaming(&spliced_path, &output, "password", &config).unwrap_err();
+    assert!(
+        err.to_string().contains("ファイルが途中で切れています"),
+        "{err}"
+    );
+}
+
+#[test]
+fn reader_writer_round_trip_matches_file_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let encrypted = dir.path().join("pipe.enc");
+    let output = dir.path().join("pipe.out");
+    // UTF-8として不正なバイト列を含む入力
+    let data: Vec<u8> = (0..300_000u32).map(|i| (i * 13 % 256) as u8).collect();
+
+    let config = fast_config();
+    let mut piped = Vec::new();
+    encrypt_from_reader(&mut data.as_slice(), &mut piped, "password", &config).unwrap();
+
+    let mut decrypted = Vec::new();
+    decrypt_to_writer(&mut piped.as_slice(), &mut decrypted, "password").unwrap();
+    assert_eq!(decrypted, data);
+
+    // 出力はストリーミング形式のファイルとしても復号化できる
+    fs::write(&encrypted, &piped).unwrap();
+    decrypt_file_streaming(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), data);
+
+    let truncated = &piped[..piped.len() - 10];
+    assert!(matches!(
+        decrypt_to_writer(&mut &truncated[..], &mut Vec::new(), "password"),
+        Err(CryptoError::Truncated(_))
+    ));
+}
+
+#[test]
+fn decrypt_progress_reaches_file_size() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("plain.bin");
+    let encrypted = dir.path().join("plain.bin.enc");
+    let output = dir.path().join("plain.out");
+    fs::write(&input, vec![3u8; 4096 * 5 + 123]).unwrap();
+
+    let config = Config {
+        stream_chunk_size: 4096,
+        ..fast_config()
+    };
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+
+    let mut updates = Vec::new();
+    decrypt_file_streaming_with_progress(
+        &encrypted,
+        &output,
+        "password",
+        &config,
+        &mut |done, total| updates.push((done, total)),
+    )
+    .unwrap();
+
+    let file_size = fs::metadata(&encrypted).unwrap().len();
+    assert!(updates.iter().all(|&(_, total)| total == file_size));
+    assert!(updates.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert_eq!(updates.last(), Some(&(file_size, file_size)));
+}
+
+fn small_chunk_stream(data: &[u8]) -> Vec<u8> {
+    let config = Config {
+        stream_chunk_size: 4096,
+        ..fast_config()
+    };
+    let mut encrypted = Vec::new();
+    encrypt_from_reader(&mut &data[..], &mut encrypted, "password", &config).unwrap();
+    encrypted
+}
+
+#[test]
+fn decrypt_reader_yields_plaintext_on_demand() {
+    let data: Vec<u8> = (0..4096 * 3 + 100u32).map(|i| (i % 251) as u8).collect();
+    let encrypted = small_chunk_stream(&data);
+
+    let mut reader = DecryptReader::new(encrypted.as_slice(), "password").unwrap();
+    // チャンク境界をまたぐ小さな読み込みでも、元のデータが順に得られる
+    let mut decrypted = Vec::new();
+    let mut buf = [0u8; 1000];
+    loop {
+        let read = reader.read(&mut buf).unwrap();
+        if read == 0 {
+            break;
+        }
+        decrypted.extend_from_slice(&buf[..read]);
+    }
+    assert_eq!(decrypted, data);
+}
+
+#[test]
+fn decrypt_reader_reports_tampered_chunk_as_io_error() {
+    let data = vec![7u8; 4096 * 2];
+    let mut encrypted = small_chunk_stream(&data);
+    // 2つ目のチャンクの暗号文を1バイト書き換える
+    let index = encrypted.len() - 44 - 100;
+    encrypted[index] ^= 1;
+
+    let mut reader = DecryptReader::new(encrypted.as_slice(), "password").unwrap();
+    let mut first = vec![0u8; 4096];
+    reader.read_exact(&mut first).unwrap();
+    assert_eq!(first, data[..4096]);
+
+    let err = reader.read(&mut [0u8; 16]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let source = err.get_ref().and_then(|e| e.downcast_ref::<CryptoError>());
+    assert!(matches!(source, Some(CryptoError::WrongPasswordOrCorrupt)));
+    // 失敗した後は読み込みを続けられない
+    assert!(reader.read(&mut [0u8; 16]).is_err());
+}
+
+#[test]
+fn decrypt_reader_detects_truncation_at_end() {
+    let data = vec![1u8; 4096 * 2];
+    let encrypted = small_chunk_stream(&data);
+    // フッターを取り除く
+    let truncated = &encrypted[..encrypted.len() - 44];
+
+    let mut decrypted = Vec::new();
+    let err = DecryptReader::new(truncated, "password")
+        .unwrap()
+        .read_to_end(&mut decrypted)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn streaming_round_trips_empty_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("empty.bin");
+    let encrypted = dir.path().join("empty.bin.enc");
+    let output = dir.path().join("empty.out");
+    fs::write(&input, b"").unwrap();
+
+    let config = fast_config();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+
+    // チャンクがなく、ヘッダーの直後にフッターが続く
+    let info = inspect_file(&encrypted).unwrap();
+    assert_eq!(
+        info.format,
+        FormatInfo::Streaming {
+            chunk_size: config.stream_chunk_size,
+            chunk_count: 0,
+            complete: true,
+            volumes: None,
+        }
+    );
+
+    decrypt_file_streaming(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"");
+
+    // チャンクがなくても、パスワードの誤りはフッターの検証で検出される
+    assert!(matches!(
+        decrypt_file_streaming(&encrypted, &output, "wrong", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+
+    let mut reader = DecryptReader::new(File::open(&encrypted).unwrap(), "password").unwrap();
+    let mut decrypted = Vec::new();
+    reader.read_to_end(&mut decrypted).unwrap();
+    assert!(decrypted.is_empty());
+}
+
+#[test]
+fn streaming_round_trips_partial_chunks() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = Config {
+        stream_chunk_size: 4096,
+        ..fast_config()
+    };
+
+    for (len, chunks) in [(1, 1), (4095, 1), (4096, 1), (4097, 2)] {
+        let input = dir.path().join(format!("{len}.bin"));
+        let encrypted = dir.path().join(format!("{len}.bin.enc"));
+        let output = dir.path().join(format!("{len}.out"));
+        let data: Vec<u8> = (0..len as u32).map(|i| (i % 251) as u8).collect();
+        fs::write(&input, &data).unwrap();
+
+        encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+        match inspect_file(&encrypted).unwrap().format {
+            FormatInfo::Streaming { chunk_count, .. } => assert_eq!(chunk_count, chunks, "{len}"),
+            other => panic!("{other:?}"),
+        }
+
+        decrypt_file_streaming(&encrypted, &output, "password", &config).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), data, "{len}");
+    }
+}
+
+#[test]
+fn smallest_header_only_stream_is_not_too_small() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("empty.bin");
+    let encrypted = dir.path().join("empty.bin.enc");
+    let output = dir.path().join("empty.out");
+    fs::write(&input, b"").unwrap();
+
+    // キー導出ブロックが最も短いPBKDF2で、メタデータを記録しない場合が最小のファイルになる
+    let config = Config {
+        kdf: KdfAlgorithm::Pbkdf2,
+        pbkdf2: Pbkdf2Config { iterations: 1000 },
+        preserve_metadata: false,
+        ..fast_config()
+    };
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+    let data = fs::read(&encrypted).unwrap();
+    assert_eq!(data.len(), 79);
+
+    decrypt_file_streaming(&encrypted, &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), b"");
+
+    // 1バイトでも欠けていれば途中で切れたファイルとして拒否する
+    let truncated = dir.path().join("truncated.enc");
+    fs::write(&truncated, &data[..data.len() - 1]).unwrap();
+    assert!(matches!(
+        decrypt_file_streaming(&truncated, &output, "password", &config),
+        Err(CryptoError::Truncated(_))
+    ));
+}
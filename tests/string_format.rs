@@ -0,0 +1,89 @@
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    decrypt_from_text, decrypt_string, detect_encoding, encrypt_string, encrypt_to_text,
+    hex_decode, Config, CryptoError, OutputFormat,
+};
+
+fn hex_config() -> Config {
+    Config {
+        default_format: OutputFormat::Hex,
+        ..fast_config()
+    }
+}
+
+#[test]
+fn hex_output_round_trip() {
+    let config = hex_config();
+    let encrypted = encrypt_string("こんにちは", "password", &config).unwrap();
+
+    assert_eq!(detect_encoding(&encrypted), OutputFormat::Hex);
+    // 復号側は出力形式の設定に関係なく自動判別する
+    let decrypted = decrypt_string(&encrypted, "password", &fast_config()).unwrap();
+    assert_eq!(decrypted, "こんにちは");
+}
+
+#[test]
+fn base64_output_round_trip() {
+    let config = fast_config();
+    let encrypted = encrypt_string("hello", "password", &config).unwrap();
+
+    let decrypted = decrypt_string(&encrypted, "password", &hex_config()).unwrap();
+    assert_eq!(decrypted, "hello");
+}
+
+#[test]
+fn detect_encoding_prefers_hex_for_even_length_hex_digits() {
+    // Base64としても有効だが、16進数として先に試す
+    assert_eq!(detect_encoding("deadBEEF00112233"), OutputFormat::Hex);
+    // 奇数長や16進数以外の文字を含む場合は Base64
+    assert_eq!(detect_encoding("deadbeef0"), OutputFormat::Base64);
+    assert_eq!(detect_encoding("3q2+7w=="), OutputFormat::Base64);
+    assert_eq!(detect_encoding(""), OutputFormat::Base64);
+}
+
+#[test]
+#[cfg(not(feature = "legacy-compat"))]
+fn all_hex_looking_base64_falls_back_to_base64() {
+    // 32文字の16進数: Hexでは16バイト、Base64では24バイトとして解釈できる。
+    // どちらの候補もデコードには成功し、マジックナンバーがないため暗号文ではないと判定される。
+    let text = "0123456789abcdef0123456789abcdef";
+    let err = decrypt_string(text, "password", &fast_config()).unwrap_err();
+    assert!(matches!(err, CryptoError::NotEncrypted), "{err:?}");
+}
+
+#[test]
+fn hex_decode_rejects_signs_and_non_hex_digits() {
+    assert_eq!(hex_decode("0aFf").unwrap(), [0x0a, 0xff]);
+    for text in ["+f", "-f", "0x", " f", "ｆｆ"] {
+        assert!(
+            matches!(hex_decode(text), Err(CryptoError::InvalidFormat(_))),
+            "{text}"
+        );
+    }
+}
+
+#[test]
+fn wrong_password_on_hex_input_reports_authentication_error() {
+    let encrypted = encrypt_string("secret", "password", &hex_config()).unwrap();
+    let err = decrypt_string(&encrypted, "wrong", &fast_config()).unwrap_err();
+    assert!(
+        matches!(err, CryptoError::WrongPasswordOrCorrupt),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn binary_round_trip_through_text() {
+    let data = [0x00, 0xff, 0xfe, b'\n', 0x80, b' '];
+    let encrypted = encrypt_to_text(&data, "password", &fast_config()).unwrap();
+
+    assert_eq!(
+        decrypt_from_text(&encrypted, "password", &fast_config()).unwrap(),
+        data
+    );
+    // UTF-8でないため文字列としては復号化できない
+    let err = decrypt_string(&encrypted, "password", &fast_config()).unwrap_err();
+    assert!(matches!(err, CryptoError::Utf8(_)));
+}
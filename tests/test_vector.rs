@@ -0,0 +1,49 @@
+use encript_tool::{
+    decrypt_bytes, encrypt_bytes, encrypt_with_salt_and_nonce, hex_encode, Config, KdfAlgorithm,
+    Pbkdf2Config,
+};
+
+const PASSWORD: &str = "correct horse battery staple";
+const PLAINTEXT: &[u8] = b"Hello, AES-GCM!";
+const SALT: [u8; 16] = [0x11; 16];
+const NONCE: [u8; 12] = [0x22; 12];
+
+/// テストベクター用の設定（PBKDF2・反復回数1000回）
+fn vector_config() -> Config {
+    Config {
+        kdf: KdfAlgorithm::Pbkdf2,
+        pbkdf2: Pbkdf2Config { iterations: 1000 },
+        ..Config::default()
+    }
+}
+
+/// PBKDF2-HMAC-SHA256 と AES-256-GCM の独立した実装（Python の cryptography）で計算した期待値
+const EXPECTED: &str = concat!(
+    "4d594352595054030002e8030000", // マジックナンバー・バージョン・フラグ・PBKDF2(1000回)
+    "11111111111111111111111111111111", // ソルト
+    "00",                           // メタデータなし
+    "222222222222222222222222",     // ナンス
+    "debb125c0481846a73206105cd398c", // 暗号文
+    "0b500eb0078d3769f67f0c24003abee2", // 認証タグ
+);
+
+#[test]
+fn known_answer() {
+    let config = vector_config();
+    let encrypted =
+        encrypt_with_salt_and_nonce(PLAINTEXT, PASSWORD, &config, &SALT, &NONCE).unwrap();
+    assert_eq!(hex_encode(&encrypted), EXPECTED);
+    assert_eq!(
+        decrypt_bytes(&encrypted, PASSWORD, &config).unwrap(),
+        PLAINTEXT
+    );
+}
+
+#[test]
+fn public_api_stays_randomized() {
+    let config = vector_config();
+    let first = encrypt_bytes(PLAINTEXT, PASSWORD, &config).unwrap();
+    let second = encrypt_bytes(PLAINTEXT, PASSWORD, &config).unwrap();
+    assert_ne!(first, second);
+    assert_ne!(hex_encode(&first), EXPECTED);
+}
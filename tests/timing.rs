@@ -0,0 +1,85 @@
+#![cfg(feature = "std-fs")]
+
+//! 処理段階ごとの所要時間の計測（`--time`）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::save_config;
+use encript_tool::{
+    decrypt_file_streaming, enable_phase_timing, encrypt_file_streaming, phase_times,
+};
+use std::fs;
+use std::time::Duration;
+
+#[test]
+fn phase_times_cover_each_phase() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = fast_config();
+    let input = dir.path().join("data.bin");
+    fs::write(&input, vec![7u8; 100_000]).unwrap();
+    let encrypted = dir.path().join("data.enc");
+
+    enable_phase_timing();
+    encrypt_file_streaming(&input, &encrypted, "password", &config).unwrap();
+    let times = phase_times();
+    assert!(times.key_derivation > Duration::ZERO, "{times:?}");
+    assert!(times.cipher > Duration::ZERO, "{times:?}");
+    assert!(times.read > Duration::ZERO, "{times:?}");
+    assert!(times.write > Duration::ZERO, "{times:?}");
+
+    // 有効にし直すと合計はリセットされ、その後の処理だけが加算される
+    enable_phase_timing();
+    assert_eq!(phase_times().cipher, Duration::ZERO);
+    decrypt_file_streaming(&encrypted, &dir.path().join("out"), "password", &config).unwrap();
+    assert!(phase_times().cipher > Duration::ZERO);
+}
+
+#[test]
+fn cli_prints_times_unless_quiet() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = dir.path().join("config.toml");
+    save_config(&fast_config(), &config).unwrap();
+    let input = dir.path().join("data.txt");
+    fs::write(&input, b"data").unwrap();
+
+    let run = |args: &[&str]| {
+        cli(&config)
+            .args(["--allow-weak-password", "--time"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&["encrypt-file", "data.txt", "-p", "password"]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("鍵導出: "), "{stderr}");
+    assert!(stderr.contains("暗号化: "), "{stderr}");
+    assert!(stderr.contains("書き込み: "), "{stderr}");
+
+    let output = run(&[
+        "decrypt-file",
+        "data.txt.enc",
+        "-o",
+        "out",
+        "-p",
+        "password",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("復号化: "), "{stderr}");
+
+    let output = run(&[
+        "-q",
+        "decrypt-file",
+        "data.txt.enc",
+        "-o",
+        "out2",
+        "-p",
+        "password",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("鍵導出"));
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "std-fs")]
+
+mod common;
+
+use common::fast_config;
+use encript_tool::{
+    armor_file, encrypt_file_for_passwords, encrypt_file_standard, encrypt_file_streaming,
+    verify_encrypted_file, CryptoError, FileBatch,
+};
+use std::fs;
+
+#[test]
+fn verify_accepts_every_format() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.bin");
+    let data: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+    fs::write(&input, &data).unwrap();
+    let config = fast_config();
+
+    let standard = dir.path().join("standard.enc");
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    verify_encrypted_file(&standard, &input, "password", &config).unwrap();
+
+    let streaming = dir.path().join("streaming.enc");
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+    verify_encrypted_file(&streaming, &input, "password", &config).unwrap();
+
+    let armored = dir.path().join("armored.asc");
+    encrypt_file_standard(&input, &armored, "password", &config).unwrap();
+    armor_file(&armored).unwrap();
+    verify_encrypted_file(&armored, &input, "password", &config).unwrap();
+
+    let envelope = dir.path().join("envelope.enc");
+    encrypt_file_for_passwords(&input, &envelope, &["one", "two"], &config).unwrap();
+    verify_encrypted_file(&envelope, &input, "two", &config).unwrap();
+
+    // 一括処理では暗号化と同じキーで検証する
+    let batch = FileBatch::new("password", &config);
+    let batched = dir.path().join("batched.enc");
+    batch.encrypt_file(&input, &batched, true).unwrap();
+    batch.verify_file(&batched, &input).unwrap();
+}
+
+#[test]
+fn verify_detects_differences() {
+    let dir = tempfile::tempdir().unwrap();
+    let input = dir.path().join("input.txt");
+    let standard = dir.path().join("standard.enc");
+    let streaming = dir.path().join("streaming.enc");
+    fs::write(&input, "original contents").unwrap();
+    let config = fast_config();
+    encrypt_file_standard(&input, &standard, "password", &config).unwrap();
+    encrypt_file_streaming(&input, &streaming, "password", &config).unwrap();
+
+    for contents in [
+        "original content!",
+        "original content",
+        "original contents!",
+    ] {
+        fs::write(&input, contents).unwrap();
+        for encrypted in [&standard, &streaming] {
+            assert!(matches!(
+                verify_encrypted_file(encrypted, &input, "password", &config),
+                Err(CryptoError::VerifyMismatch)
+            ));
+        }
+    }
+
+    fs::write(&input, "original contents").unwrap();
+    assert!(matches!(
+        verify_encrypted_file(&standard, &input, "wrong", &config),
+        Err(CryptoError::WrongPasswordOrCorrupt)
+    ));
+}
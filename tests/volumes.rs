@@ -0,0 +1,172 @@
+#![cfg(feature = "std-fs")]
+
+//! ストリーミング形式を決まったサイズのボリュームに分割する（`encrypt-file --split`）
+
+mod common;
+
+use common::{cli, fast_config};
+use encript_tool::config::{save_config, MIN_STREAM_CHUNK_SIZE};
+use encript_tool::volume::{volume_base_path, volume_path, MIN_VOLUME_SIZE};
+use encript_tool::{
+    decrypt_file_auto, detect_format, inspect_file, Config, CryptoError, EncFormat, FileBatch,
+    FormatInfo,
+};
+use std::fs;
+use std::path::Path;
+
+fn split_config() -> Config {
+    Config {
+        stream_chunk_size: MIN_STREAM_CHUNK_SIZE,
+        ..fast_config()
+    }
+}
+
+fn sample_data() -> Vec<u8> {
+    (0..20_000u32).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+/// `dir/big.bin` を暗号化して `dir/big.bin.enc.*` に分割し、ボリュームのパスを返す
+fn split_sample(dir: &Path, config: &Config) -> Vec<std::path::PathBuf> {
+    let input = dir.join("big.bin");
+    fs::write(&input, sample_data()).unwrap();
+    FileBatch::new("password", config)
+        .without_progress()
+        .encrypt_file_split(&input, &dir.join("big.bin.enc"), MIN_VOLUME_SIZE)
+        .unwrap()
+}
+
+#[test]
+fn split_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = split_config();
+    let volumes = split_sample(dir.path(), &config);
+
+    assert!(volumes.len() > 1, "{volumes:?}");
+    for (i, volume) in volumes.iter().enumerate() {
+        assert_eq!(
+            *volume,
+            volume_path(&dir.path().join("big.bin.enc"), i as u32 + 1)
+        );
+        assert!(fs::metadata(volume).unwrap().len() <= MIN_VOLUME_SIZE);
+        // どのボリュームからも分割全体を識別できる
+        assert_eq!(detect_format(volume).unwrap(), Some(EncFormat::Streaming));
+        assert_eq!(
+            volume_base_path(volume).unwrap(),
+            Some(dir.path().join("big.bin.enc"))
+        );
+    }
+
+    let info = inspect_file(&volumes[0]).unwrap();
+    assert!(matches!(
+        info.format,
+        FormatInfo::Streaming {
+            complete: true,
+            volumes: Some(count),
+            ..
+        } if count as usize == volumes.len()
+    ));
+
+    let output = dir.path().join("big.out");
+    decrypt_file_auto(&volumes[0], &output, "password", &config).unwrap();
+    assert_eq!(fs::read(&output).unwrap(), sample_data());
+}
+
+#[test]
+fn missing_volume_is_reported() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = split_config();
+    let volumes = split_sample(dir.path(), &config);
+    fs::remove_file(&volumes[1]).unwrap();
+
+    let result = decrypt_file_auto(&volumes[0], &dir.path().join("out"), "password", &config);
+    match result {
+        Err(CryptoError::Truncated(message)) => {
+            assert!(message.contains("big.bin.enc.002"), "{message}");
+        }
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn swapped_or_foreign_volumes_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = split_config();
+    let volumes = split_sample(dir.path(), &config);
+    let output = dir.path().join("out");
+
+    // 順序の入れ替え（サイズが揃うよう、いっぱいまで書き込んだ1個目と2個目を入れ替える）
+    let first = fs::read(&volumes[0]).unwrap();
+    let second = fs::read(&volumes[1]).unwrap();
+    fs::write(&volumes[0], &second).unwrap();
+    fs::write(&volumes[1], &first).unwrap();
+    assert!(matches!(
+        decrypt_file_auto(&volumes[0], &output, "password", &config),
+        Err(CryptoError::InvalidFormat(_))
+    ));
+    fs::write(&volumes[0], &first).unwrap();
+    fs::write(&volumes[1], &second).unwrap();
+
+    // 別の分割の同じ番号のボリューム
+    let other = tempfile::tempdir().unwrap();
+    let other_volumes = split_sample(other.path(), &config);
+    fs::copy(&other_volumes[1], &volumes[1]).unwrap();
+    assert!(matches!(
+        decrypt_file_auto(&volumes[0], &output, "password", &config),
+        Err(CryptoError::InvalidFormat(_))
+    ));
+}
+
+#[test]
+fn cli_split_and_decrypt_from_first_volume() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    save_config(&split_config(), &config_path).unwrap();
+    let input = dir.path().join("big.bin");
+    fs::write(&input, sample_data()).unwrap();
+
+    let run = |args: &[&str]| {
+        cli(&config_path)
+            .args(["--allow-weak-password", "--no-progress"])
+            .args(args)
+            .current_dir(dir.path())
+            .output()
+            .unwrap()
+    };
+
+    let output = run(&[
+        "encrypt-file",
+        "big.bin",
+        "-p",
+        "password",
+        "--split",
+        "4KB",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert!(dir.path().join("big.bin.enc.001").exists());
+    assert!(dir.path().join("big.bin.enc.002").exists());
+    assert!(!dir.path().join("big.bin.enc").exists());
+
+    fs::remove_file(&input).unwrap();
+    let output = run(&[
+        "decrypt-file",
+        "big.bin.enc.001",
+        "-p",
+        "password",
+        "--delete-encrypted",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(fs::read(&input).unwrap(), sample_data());
+    // 全ボリュームを削除する
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+
+    // 小さすぎる分割サイズは使い方の誤り
+    let output = run(&[
+        "encrypt-file",
+        "big.bin",
+        "-p",
+        "password",
+        "--split",
+        "1KB",
+    ]);
+    assert_eq!(output.status.code(), Some(64));
+}